@@ -0,0 +1,98 @@
+//! Saves the attachments of the newest INBOX message to a directory.
+//!
+//! `OUT_DIR` (default `mailux-attachments` in the system temp directory) is where files
+//! go; see `common` for the connection variables. Only the text and attachment parts are
+//! downloaded, the attachments with BINARY when the server has it.
+
+mod common;
+
+use anyhow::Result;
+use bindings::SelectedState;
+use bindings::async_impl::Client;
+use common::Script;
+use std::env;
+use std::path::{Path, PathBuf};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let out_dir = env::var("OUT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir().join("mailux-attachments"));
+
+    let session = common::login("IMAP", script()).await?;
+    let (mut session, _) = session.select("INBOX").await?;
+    let saved = run(&mut session, &out_dir).await?;
+    println!("Saved {} attachments to {}", saved.len(), out_dir.display());
+    session.logout().await
+}
+
+/// Downloads the attachments of the newest INBOX message into `out_dir` and returns the
+/// paths written.
+async fn run(session: &mut Client<SelectedState>, out_dir: &Path) -> Result<Vec<PathBuf>> {
+    let Some(&uid) = session.uid_search(Vec::new()).await?.iter().max() else {
+        println!("INBOX is empty");
+        return Ok(Vec::new());
+    };
+
+    let Some(message) = session.fetch_readable(uid).await? else {
+        anyhow::bail!("UID {} disappeared", uid);
+    };
+    if let Some(text) = message.text_body() {
+        println!("{}", text.lines().next().unwrap_or_default());
+    }
+    tokio::fs::create_dir_all(out_dir).await?;
+
+    let mut saved = Vec::new();
+    for attachment in message.attachments() {
+        let Some(data) = session.fetch_attachment(uid, &attachment).await? else {
+            continue;
+        };
+        // Never trust a sender-supplied name with a directory in it.
+        let name = attachment
+            .filename()
+            .and_then(|f| Path::new(f).file_name())
+            .map(|f| f.to_string_lossy().into_owned())
+            .unwrap_or_else(|| format!("part-{}", attachment.section));
+        let path = out_dir.join(name);
+        tokio::fs::write(&path, &data).await?;
+        println!(
+            "  {} ({}, {} bytes)",
+            path.display(),
+            attachment.content_type(),
+            data.len()
+        );
+        saved.push(path);
+    }
+    Ok(saved)
+}
+
+fn script() -> Script {
+    Script::new("IMAP4rev1")
+        .reply("SELECT", "* 1 EXISTS\r\n* OK [UIDVALIDITY 3] UIDs valid\r\n")
+        .reply("UID SEARCH", "* SEARCH 7\r\n")
+        .reply(
+            "UID FETCH 7 (UID FLAGS ENVELOPE BODYSTRUCTURE)",
+            "* 1 FETCH (UID 7 FLAGS () BODYSTRUCTURE ((\"TEXT\" \"PLAIN\" (\"CHARSET\" \"utf-8\") NIL NIL \"7BIT\" 12 1 NIL NIL NIL NIL)(\"APPLICATION\" \"PDF\" (\"NAME\" \"report.pdf\") NIL NIL \"BASE64\" 12 NIL (\"ATTACHMENT\" (\"FILENAME\" \"../report.pdf\")) NIL NIL) \"MIXED\" (\"BOUNDARY\" \"b1\") NIL NIL NIL))\r\n",
+        )
+        .reply(
+            "UID FETCH 7 (UID BODY.PEEK[1])",
+            "* 1 FETCH (UID 7 BODY[1] {12}\r\nHello there!)\r\n",
+        )
+        .reply(
+            "UID FETCH 7 (UID BODY.PEEK[2])",
+            "* 1 FETCH (UID 7 BODY[2] {12}\r\nJVBERi0xLjQK)\r\n",
+        )
+}
+
+#[tokio::test]
+async fn saves_against_mock() {
+    let dir = env::temp_dir().join(format!("mailux-attachments-{}", std::process::id()));
+    let session = common::login_mock(script()).await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    let saved = run(&mut session, &dir).await.unwrap();
+    assert_eq!(saved, vec![dir.join("report.pdf")]);
+    assert_eq!(std::fs::read(&saved[0]).unwrap(), b"%PDF-1.4\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -16,10 +16,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let mut session = client.login(&email, &password)?;
 
-    let mut msgs = session.fetch("INBOX", 2)?;
+    let envelopes = session.fetch("INBOX", 1..=2)?;
 
-    while let Some(msg) = msgs.try_next()? {
-        println!("Subject: {}", msg.subject());
+    for env in envelopes {
+        println!(
+            "Subject: {}",
+            env.subject.as_deref().unwrap_or("(no subject)")
+        );
     }
 
     Ok(())
@@ -0,0 +1,168 @@
+//! Keeps a local cache of INBOX's UIDs and flags, for reading mail offline.
+//!
+//! `CACHE_FILE` (default `mailux-cache.txt` in the system temp directory) holds the cache;
+//! see `common` for the connection variables. Each run fetches the current flags, reports
+//! what changed since the last run and rewrites the cache. A new UIDVALIDITY discards it.
+
+mod common;
+
+use anyhow::{Context as _, Result};
+use bindings::{AuthenticatedState, SelectedState};
+use bindings::async_impl::Client;
+use common::Script;
+use imap::types::command::{SequenceBound, SequenceSet};
+use imap::types::common::Uid;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let path = env::var("CACHE_FILE")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir().join("mailux-cache.txt"));
+
+    let session = common::login("IMAP", script()).await?;
+    let (session, report) = run(session, &path).await?;
+    println!(
+        "{}: {} new, {} removed, {} with changed flags",
+        path.display(),
+        report.added.len(),
+        report.removed.len(),
+        report.changed.len()
+    );
+    session.logout().await
+}
+
+/// What changed in INBOX since the cache was written.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct SyncReport {
+    added: Vec<Uid>,
+    removed: Vec<Uid>,
+    changed: Vec<Uid>,
+}
+
+/// The cache file: a `UIDVALIDITY <n>` line, then one `<uid> <flags>` line per message.
+#[derive(Debug, Default)]
+struct Cache {
+    uid_validity: u32,
+    flags: BTreeMap<Uid, String>,
+}
+
+impl Cache {
+    async fn load(path: &Path) -> Result<Self> {
+        let text = match tokio::fs::read_to_string(path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let mut lines = text.lines();
+        let uid_validity = lines
+            .next()
+            .and_then(|l| l.strip_prefix("UIDVALIDITY "))
+            .and_then(|v| v.parse().ok())
+            .context("Cache file has no UIDVALIDITY line")?;
+        let mut flags = BTreeMap::new();
+        for line in lines {
+            let (uid, rest) = line.split_once(' ').unwrap_or((line, ""));
+            flags.insert(
+                Uid(uid.parse().context("Bad UID in cache file")?),
+                rest.to_string(),
+            );
+        }
+        Ok(Self {
+            uid_validity,
+            flags,
+        })
+    }
+
+    async fn save(&self, path: &Path) -> Result<()> {
+        let mut text = format!("UIDVALIDITY {}\n", self.uid_validity);
+        for (uid, flags) in &self.flags {
+            text.push_str(&format!("{} {}\n", uid, flags));
+        }
+        tokio::fs::write(path, text)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Brings the cache at `path` up to date with INBOX.
+async fn run(
+    session: Client<AuthenticatedState>,
+    path: &Path,
+) -> Result<(Client<SelectedState>, SyncReport)> {
+    let mut cache = Cache::load(path).await?;
+    let (mut session, status) = session.select("INBOX").await?;
+    let uid_validity = status.uid_validity.unwrap_or(0);
+    if cache.uid_validity != uid_validity {
+        if !cache.flags.is_empty() {
+            println!("UIDVALIDITY changed, rebuilding the cache");
+        }
+        cache = Cache {
+            uid_validity,
+            flags: BTreeMap::new(),
+        };
+    }
+
+    let mut current = BTreeMap::new();
+    if status.exists > 0 {
+        let all = SequenceSet::new().add_range(SequenceBound::Number(1), SequenceBound::Star);
+        for (uid, flags) in session.fetch_flags("INBOX", all).await? {
+            let flags: Vec<String> = flags.to_vec().iter().map(|f| f.to_string()).collect();
+            current.insert(uid, flags.join(" "));
+        }
+    }
+
+    let mut report = SyncReport::default();
+    for (uid, flags) in &current {
+        match cache.flags.get(uid) {
+            None => report.added.push(*uid),
+            Some(cached) if cached != flags => report.changed.push(*uid),
+            Some(_) => {}
+        }
+    }
+    report.removed = cache
+        .flags
+        .keys()
+        .filter(|uid| !current.contains_key(uid))
+        .copied()
+        .collect();
+
+    cache.flags = current;
+    cache.save(path).await?;
+    Ok((session, report))
+}
+
+fn script() -> Script {
+    Script::new("IMAP4rev1")
+        .reply("SELECT", "* 3 EXISTS\r\n* OK [UIDVALIDITY 5] UIDs valid\r\n")
+        .reply(
+            "FETCH",
+            "* 1 FETCH (UID 10 FLAGS (\\Seen))\r\n* 2 FETCH (UID 12 FLAGS (\\Seen \\Flagged))\r\n* 3 FETCH (UID 13 FLAGS ())\r\n",
+        )
+}
+
+#[tokio::test]
+async fn syncs_against_mock() {
+    let path = env::temp_dir().join(format!("mailux-cache-{}.txt", std::process::id()));
+    std::fs::write(&path, "UIDVALIDITY 5\n10 \\Seen\n11 \n12 \\Seen\n").unwrap();
+
+    let session = common::login_mock(script()).await.unwrap();
+    let (_, report) = run(session, &path).await.unwrap();
+    assert_eq!(
+        report,
+        SyncReport {
+            added: vec![Uid(13)],
+            removed: vec![Uid(11)],
+            changed: vec![Uid(12)],
+        }
+    );
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "UIDVALIDITY 5\n10 \\Seen\n12 \\Seen \\Flagged\n13 \n"
+    );
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,146 @@
+//! Connection setup shared by the examples.
+//!
+//! With `<PREFIX>_SERVER`, `<PREFIX>_EMAIL` and `<PREFIX>_PASSWORD` set, an example logs in
+//! to that server over TLS. Without them it talks to a scripted [`MockServer`], which is
+//! how the examples run as tests.
+
+#![allow(dead_code)]
+
+use std::env;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+use bindings::{AuthenticatedState, Builder};
+
+/// Logs in to the server named by the `<prefix>_*` variables, or to `script` if they are
+/// unset.
+pub async fn login(prefix: &str, script: Script) -> Result<Client<AuthenticatedState>> {
+    let Ok(server) = env::var(format!("{}_SERVER", prefix)) else {
+        println!(
+            "{}_SERVER is not set, using the built-in mock server",
+            prefix
+        );
+        return login_mock(script).await;
+    };
+    let email = env::var(format!("{}_EMAIL", prefix))?;
+    let password = env::var(format!("{}_PASSWORD", prefix))?;
+    println!("Connecting to {} as {} ...", server, email);
+    Builder::new(&server)
+        .tls()
+        .build()
+        .connect()
+        .await?
+        .login(&email, &password)
+        .await
+}
+
+pub async fn login_mock(script: Script) -> Result<Client<AuthenticatedState>> {
+    Builder::new("mock:143")
+        .build()
+        .connect_stream(script.into_server().spawn())
+        .await?
+        .login("demo", "demo")
+        .await
+}
+
+/// Canned replies for a [`MockServer`].
+///
+/// Each command is answered with the untagged lines of the first entry whose prefix it
+/// starts with (case-insensitively, e.g. `"UID SEARCH"`) and a tagged OK. Literals are
+/// read before the command completes, IDLE runs until DONE. LOGIN, CAPABILITY and LOGOUT
+/// are answered without an entry.
+#[derive(Debug, Clone)]
+pub struct Script {
+    capabilities: String,
+    replies: Vec<(String, String)>,
+}
+
+struct Pending {
+    tag: String,
+    untagged: String,
+    remaining: usize,
+}
+
+impl Script {
+    pub fn new(capabilities: &str) -> Self {
+        Self {
+            capabilities: capabilities.to_string(),
+            replies: Vec::new(),
+        }
+    }
+
+    /// Answers commands starting with `prefix` with `untagged`, each line ending in CRLF.
+    pub fn reply(mut self, prefix: &str, untagged: impl Into<String>) -> Self {
+        self.replies
+            .push((prefix.to_ascii_uppercase(), untagged.into()));
+        self
+    }
+
+    pub fn into_server(self) -> MockServer {
+        let pending: Mutex<Option<Pending>> = Mutex::new(None);
+        let idle_tag: Mutex<Option<String>> = Mutex::new(None);
+        let script = Arc::new(self);
+        MockServer::new(move |tag, cmd| {
+            let mut pending = pending.lock().unwrap();
+            if let Some(literal) = pending.as_mut() {
+                let line = if cmd.is_empty() {
+                    tag.len()
+                } else {
+                    tag.len() + 1 + cmd.len()
+                };
+                literal.remaining = literal.remaining.saturating_sub(line + 2);
+                if literal.remaining > 0 {
+                    return Vec::new();
+                }
+                let done = pending.take().unwrap();
+                return format!("{}{} OK completed\r\n", done.untagged, done.tag).into_bytes();
+            }
+            if tag == "DONE" {
+                let tag = idle_tag.lock().unwrap().take().unwrap_or_default();
+                return format!("{} OK IDLE terminated\r\n", tag).into_bytes();
+            }
+
+            let upper = cmd.to_ascii_uppercase();
+            let untagged = match upper.split(' ').next().unwrap_or("") {
+                "LOGIN" => String::new(),
+                "CAPABILITY" => format!("* CAPABILITY {}\r\n", script.capabilities),
+                "LOGOUT" => "* BYE logging out\r\n".to_string(),
+                _ => match script.replies.iter().find(|(p, _)| upper.starts_with(p)) {
+                    Some((_, untagged)) => untagged.clone(),
+                    None => return format!("{} BAD unexpected command\r\n", tag).into_bytes(),
+                },
+            };
+            if upper == "IDLE" {
+                *idle_tag.lock().unwrap() = Some(tag.to_string());
+                return format!("+ idling\r\n{}", untagged).into_bytes();
+            }
+            if let Some((len, synchronizing)) = literal_length(cmd) {
+                *pending = Some(Pending {
+                    tag: tag.to_string(),
+                    untagged,
+                    // The literal plus the CRLF ending the command.
+                    remaining: len + 2,
+                });
+                return if synchronizing {
+                    b"+ Ready for literal\r\n".to_vec()
+                } else {
+                    Vec::new()
+                };
+            }
+            format!("{}{} OK completed\r\n", untagged, tag).into_bytes()
+        })
+    }
+}
+
+/// The length of a literal announced at the end of `cmd`, and whether it is
+/// synchronizing.
+fn literal_length(cmd: &str) -> Option<(usize, bool)> {
+    let open = cmd.strip_suffix('}')?.rfind('{')?;
+    let spec = &cmd[open + 1..cmd.len() - 1];
+    match spec.strip_suffix('+') {
+        Some(n) => Some((n.parse().ok()?, false)),
+        None => Some((spec.parse().ok()?, true)),
+    }
+}
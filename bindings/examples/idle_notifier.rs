@@ -0,0 +1,89 @@
+//! Prints a line for every change to INBOX while idling, like a new-mail notifier.
+//!
+//! `IDLE_EVENTS` stops after that many events (default: never against a real server, 3
+//! against the mock); see `common` for the connection variables.
+
+mod common;
+
+use anyhow::Result;
+use bindings::{AuthenticatedState, SelectedState};
+use bindings::async_impl::Client;
+use common::Script;
+use imap::types::response::IdleEvent;
+use std::env;
+use tokio_stream::StreamExt;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let limit = match env::var("IDLE_EVENTS") {
+        Ok(n) => Some(n.parse()?),
+        Err(_) if env::var("IMAP_SERVER").is_err() => Some(3),
+        Err(_) => None,
+    };
+
+    let session = common::login("IMAP", script()).await?;
+    let (session, seen) = run(session, limit).await?;
+    println!("Stopped after {} events", seen.len());
+    session.logout().await
+}
+
+/// Idles on INBOX until `limit` events have arrived, printing and returning them.
+async fn run(
+    session: Client<AuthenticatedState>,
+    limit: Option<usize>,
+) -> Result<(Client<SelectedState>, Vec<IdleEvent>)> {
+    let (mut session, status) = session.select("INBOX").await?;
+    println!(
+        "INBOX has {} messages, waiting for changes ...",
+        status.exists
+    );
+
+    let mut seen = Vec::new();
+    let mut idle = session.idle().await?;
+    while limit.is_none_or(|n| seen.len() < n) {
+        let Some(event) = idle.next().await else {
+            break;
+        };
+        let event = event?;
+        match &event {
+            IdleEvent::Exists(n) => println!("New mail: INBOX now has {} messages", n),
+            IdleEvent::Expunge(seq) => println!("Message {} was removed", seq),
+            IdleEvent::Vanished(uids) => println!("Messages {:?} were removed", uids),
+            IdleEvent::FlagsChanged { seq, flags, .. } => {
+                println!("Message {} is now {:?}", seq, flags.to_vec())
+            }
+            other => println!("Other event: {:?}", other),
+        }
+        seen.push(event);
+    }
+    idle.done().await?;
+    Ok((session, seen))
+}
+
+fn script() -> Script {
+    Script::new("IMAP4rev1 IDLE")
+        .reply(
+            "SELECT",
+            "* 4 EXISTS\r\n* OK [UIDVALIDITY 9] UIDs valid\r\n",
+        )
+        .reply(
+            "IDLE",
+            "* 5 EXISTS\r\n* 2 FETCH (FLAGS (\\Seen))\r\n* 1 EXPUNGE\r\n",
+        )
+}
+
+#[tokio::test]
+async fn notifies_against_mock() {
+    use imap::types::common::Seq;
+
+    let session = common::login_mock(script()).await.unwrap();
+    let (_, events) = run(session, Some(3)).await.unwrap();
+    assert_eq!(events[0], IdleEvent::Exists(5));
+    assert!(matches!(
+        events[1],
+        IdleEvent::FlagsChanged { seq: Seq(2), .. }
+    ));
+    assert_eq!(events[2], IdleEvent::Expunge(Seq(1)));
+}
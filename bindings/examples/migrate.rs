@@ -0,0 +1,95 @@
+//! Copies every folder of one account to another, resuming where a previous run stopped.
+//!
+//! The accounts come from the `SOURCE_*` and `DEST_*` variables (see `common`), and
+//! progress is kept in `MIGRATE_STATE` (default `mailux-migrate.state`).
+
+mod common;
+
+use anyhow::Result;
+use bindings::AuthenticatedState;
+use bindings::async_impl::{Client, Migration, MigrationReport};
+use common::Script;
+use std::env;
+use std::time::Instant;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let state_file =
+        env::var("MIGRATE_STATE").unwrap_or_else(|_| "mailux-migrate.state".to_string());
+
+    let mut source = common::login("SOURCE", source_script()).await?;
+    let mut dest = common::login("DEST", dest_script()).await?;
+
+    let t0 = Instant::now();
+    let report = run(&mut source, &mut dest, &state_file).await?;
+    println!(
+        "Copied {} messages in {:.2?}",
+        report.copied(),
+        t0.elapsed()
+    );
+
+    source.logout().await?;
+    dest.logout().await
+}
+
+async fn run(
+    source: &mut Client<AuthenticatedState>,
+    dest: &mut Client<AuthenticatedState>,
+    state_file: &str,
+) -> Result<MigrationReport> {
+    let report = Migration::new(source, dest, state_file).run().await?;
+    for folder in &report.folders {
+        println!(
+            "{} -> {}: {} copied, {} already done{}",
+            folder.source,
+            folder.destination,
+            folder.copied,
+            folder.skipped,
+            if folder.created { " (created)" } else { "" }
+        );
+    }
+    Ok(report)
+}
+
+fn source_script() -> Script {
+    Script::new("IMAP4rev1")
+        .reply(
+            "LIST \"\" \"*\"",
+            "* LIST () \"/\" INBOX\r\n* LIST () \"/\" Projects\r\n",
+        )
+        .reply("EXAMINE", "* 2 EXISTS\r\n* OK [UIDVALIDITY 4] UIDs valid\r\n")
+        .reply("UID SEARCH", "* SEARCH 1 2\r\n")
+        .reply(
+            "UID FETCH",
+            "* 1 FETCH (UID 1 FLAGS (\\Seen) INTERNALDATE \"17-Jul-1996 02:44:25 -0700\" BODY[] {20}\r\nSubject: hi\r\n\r\nHello)\r\n\
+             * 2 FETCH (UID 2 FLAGS () INTERNALDATE \"18-Jul-1996 09:00:00 -0700\" BODY[] {21}\r\nSubject: re\r\n\r\nThanks)\r\n",
+        )
+}
+
+fn dest_script() -> Script {
+    Script::new("IMAP4rev1")
+        .reply("LIST \"\" \"\"", "* LIST (\\Noselect) \"/\" \"\"\r\n")
+        .reply("LIST \"\" \"*\"", "* LIST () \"/\" INBOX\r\n")
+        .reply("CREATE", "")
+        .reply("APPEND", "")
+}
+
+#[tokio::test]
+async fn migrates_against_mock() {
+    let state = env::temp_dir().join(format!("mailux-migrate-{}.state", std::process::id()));
+    let state = state.to_str().unwrap();
+    let mut source = common::login_mock(source_script()).await.unwrap();
+    let mut dest = common::login_mock(dest_script()).await.unwrap();
+
+    let report = run(&mut source, &mut dest, state).await.unwrap();
+    assert_eq!(report.copied(), 4);
+    assert!(!report.folders[0].created);
+    assert!(report.folders[1].created);
+
+    // A second run finds everything already copied.
+    let report = run(&mut source, &mut dest, state).await.unwrap();
+    assert_eq!(report.copied(), 0);
+    std::fs::remove_file(state).unwrap();
+}
@@ -0,0 +1,76 @@
+//! Moves every message from one sender out of INBOX, e.g. to file newsletters away.
+//!
+//! `SEARCH_FROM` (default `newsletter@example.com`) picks the sender and `MOVE_TO`
+//! (default `Archive`) the destination; see `common` for the connection variables.
+
+mod common;
+
+use anyhow::Result;
+use bindings::{AuthenticatedState, SelectedState};
+use bindings::async_impl::Client;
+use common::Script;
+use imap::types::command::{SearchKey, SequenceSet};
+use imap::types::common::Uid;
+use std::env;
+
+const MAX_SET_LEN: usize = 4 * 1024;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt::init();
+
+    let from = env::var("SEARCH_FROM").unwrap_or_else(|_| "newsletter@example.com".to_string());
+    let target = env::var("MOVE_TO").unwrap_or_else(|_| "Archive".to_string());
+
+    let session = common::login("IMAP", script()).await?;
+    let (session, moved) = run(session, &from, &target).await?;
+    println!("Moved {} messages from {} to {}", moved.len(), from, target);
+    session.logout().await
+}
+
+/// Moves the INBOX messages from `from` to `target` and returns their new UIDs, when the
+/// server reports them.
+async fn run(
+    session: Client<AuthenticatedState>,
+    from: &str,
+    target: &str,
+) -> Result<(Client<SelectedState>, Vec<Uid>)> {
+    let (mut session, _) = session.select("INBOX").await?;
+    let uids = session
+        .uid_search(vec![SearchKey::From(from.to_string())])
+        .await?;
+    println!("{} messages from {}", uids.len(), from);
+
+    let mut moved = Vec::new();
+    for set in SequenceSet::batched(&uids, MAX_SET_LEN) {
+        if let Some(copy) = session.uid_mv(set, target).await? {
+            for (old, new) in copy.source.iter().zip(&copy.destination) {
+                println!("  UID {} -> {} UID {}", old, target, new);
+            }
+            moved.extend(copy.destination);
+        }
+    }
+    Ok((session, moved))
+}
+
+fn script() -> Script {
+    Script::new("IMAP4rev1 MOVE UIDPLUS")
+        .reply(
+            "SELECT",
+            "* 5 EXISTS\r\n* OK [UIDVALIDITY 7] UIDs valid\r\n* OK [UIDNEXT 12] Predicted next UID\r\n",
+        )
+        .reply("UID SEARCH", "* SEARCH 3 8\r\n")
+        .reply(
+            "UID MOVE",
+            "* OK [COPYUID 21 3,8 40:41] Moved\r\n* 4 EXPUNGE\r\n* 2 EXPUNGE\r\n",
+        )
+}
+
+#[tokio::test]
+async fn moves_against_mock() {
+    let session = common::login_mock(script()).await.unwrap();
+    let (_, moved) = run(session, "newsletter@example.com", "Archive")
+        .await
+        .unwrap();
+    assert_eq!(moved, vec![Uid(40), Uid(41)]);
+}
@@ -30,7 +30,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         count, mailbox
     );
     let t2 = Instant::now();
-    let envelopes = session.fetch(&mailbox, count).await?;
+    let envelopes = session.fetch(&mailbox, 1..=count).await?;
     for (idx, env) in envelopes.into_iter().enumerate() {
         println!(
             "#{:02}  {}",
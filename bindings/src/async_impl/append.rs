@@ -0,0 +1,266 @@
+use anyhow::{Context as _, Result};
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinSet;
+
+use super::Client;
+use super::connector::{
+    Literal, Request, Response, await_response, ensure_ok, join_lines, queue_literal_command,
+};
+use crate::{AuthenticatedState, next_tag};
+
+use imap::commands::CommandBuilder;
+use imap::parser::mailbox::parse_append_uid;
+use imap::types::common::{Capability, DateTime, Flag, Uid};
+
+/// A message to upload with [`AppendPipeline::upload`].
+#[derive(Debug, Clone)]
+pub struct AppendMessage {
+    pub flags: Vec<Flag>,
+    /// Internal date; the server's current time if `None`.
+    pub internal_date: Option<DateTime>,
+    pub body: Bytes,
+}
+
+/// Bulk upload over one or more authenticated sessions, for migration tools.
+///
+/// Messages are shared out to the sessions as each one has room. Every session pipelines up
+/// to `max_in_flight` APPENDs. When the server advertises LITERAL+ the literals are sent
+/// without waiting for continuation requests; when it also advertises MULTIAPPEND,
+/// messages are grouped into multi-message APPENDs. A rejected MULTIAPPEND stores none of
+/// its messages, so all of them report the error.
+pub struct AppendPipeline {
+    sessions: Vec<Client<AuthenticatedState>>,
+    max_in_flight: usize,
+    batch_size: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Mode {
+    literal_plus: bool,
+    batch_size: usize,
+    max_in_flight: usize,
+}
+
+type Outcome = (usize, Result<Option<Uid>>);
+
+/// The error for an APPEND refused before sending because the message exceeds the
+/// server's APPENDLIMIT (RFC 7889).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageTooLarge {
+    pub size: u64,
+    pub limit: u64,
+}
+
+impl std::fmt::Display for MessageTooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "message of {} octets exceeds the server's APPENDLIMIT of {}",
+            self.size, self.limit
+        )
+    }
+}
+
+impl std::error::Error for MessageTooLarge {}
+
+/// Fails with [`MessageTooLarge`] if `body` is over `limit`.
+pub(super) fn check_append_limit(body: &[u8], limit: Option<u64>) -> Result<()> {
+    let size = body.len() as u64;
+    match limit {
+        Some(limit) if size > limit => Err(MessageTooLarge { size, limit }.into()),
+        _ => Ok(()),
+    }
+}
+
+impl AppendPipeline {
+    pub fn new(sessions: Vec<Client<AuthenticatedState>>) -> Self {
+        Self {
+            sessions,
+            max_in_flight: 8,
+            batch_size: 16,
+        }
+    }
+
+    /// APPEND commands outstanding per session (default 8).
+    pub fn max_in_flight(mut self, n: usize) -> Self {
+        self.max_in_flight = n.max(1);
+        self
+    }
+
+    /// Messages per MULTIAPPEND command (default 16); 1 disables MULTIAPPEND.
+    pub fn multiappend_batch(mut self, n: usize) -> Self {
+        self.batch_size = n.max(1);
+        self
+    }
+
+    pub fn into_sessions(self) -> Vec<Client<AuthenticatedState>> {
+        self.sessions
+    }
+
+    /// Appends `messages` to `mailbox` and returns one result per message, in input order:
+    /// the assigned UID when the server reports APPENDUID (RFC 4315), or the error.
+    /// Messages over the server's APPENDLIMIT fail with [`MessageTooLarge`] without being
+    /// sent.
+    pub async fn upload(
+        &mut self,
+        mailbox: &str,
+        messages: Vec<AppendMessage>,
+    ) -> Result<Vec<Result<Option<Uid>>>> {
+        if self.sessions.iter().any(|s| s.is_read_only()) {
+            anyhow::bail!("APPEND is not allowed on a read-only session");
+        }
+        let total = messages.len();
+        let mut results: Vec<Option<Result<Option<Uid>>>> = (0..total).map(|_| None).collect();
+        let limit = match self.sessions.first_mut() {
+            Some(session) => session.append_limit().await?,
+            None => None,
+        };
+        let mut accepted = VecDeque::new();
+        for (index, message) in messages.into_iter().enumerate() {
+            match check_append_limit(&message.body, limit) {
+                Ok(()) => accepted.push_back((index, message)),
+                Err(e) => results[index] = Some(Err(e)),
+            }
+        }
+        let queue = Arc::new(Mutex::new(accepted));
+
+        let mut workers = JoinSet::new();
+        for session in &mut self.sessions {
+            let caps = session.capabilities().await?;
+            let literal_plus = caps.contains(&Capability::LiteralPlus);
+            let mode = Mode {
+                literal_plus,
+                batch_size: if literal_plus && caps.contains(&Capability::MultiAppend) {
+                    self.batch_size
+                } else {
+                    1
+                },
+                max_in_flight: self.max_in_flight,
+            };
+            workers.spawn(upload_worker(
+                session.command_sender(),
+                mailbox.to_string(),
+                queue.clone(),
+                mode,
+            ));
+        }
+
+        while let Some(outcomes) = workers.join_next().await {
+            for (index, result) in outcomes.context("APPEND worker panicked")? {
+                results[index] = Some(result);
+            }
+        }
+        Ok(results
+            .into_iter()
+            .map(|r| r.unwrap_or_else(|| Err(anyhow::anyhow!("No connection left to upload on"))))
+            .collect())
+    }
+}
+
+async fn upload_worker(
+    cmd_tx: mpsc::Sender<Request>,
+    mailbox: String,
+    queue: Arc<Mutex<VecDeque<(usize, AppendMessage)>>>,
+    mode: Mode,
+) -> Vec<Outcome> {
+    let mut outcomes = Vec::new();
+    let mut pending: VecDeque<(String, Vec<usize>, oneshot::Receiver<Response>)> = VecDeque::new();
+    let mut connected = true;
+
+    loop {
+        while connected && pending.len() < mode.max_in_flight {
+            let batch: Vec<(usize, AppendMessage)> = {
+                let mut queue = queue.lock().expect("append queue poisoned");
+                (0..mode.batch_size)
+                    .map_while(|_| queue.pop_front())
+                    .collect()
+            };
+            if batch.is_empty() {
+                break;
+            }
+            let tag = next_tag();
+            let (command, literal) = build_append(&tag, &mailbox, &batch, mode.literal_plus);
+            match queue_literal_command(&cmd_tx, &tag, command, Some(literal)).await {
+                Ok(rx) => pending.push_back((tag, batch.into_iter().map(|(i, _)| i).collect(), rx)),
+                Err(_) => {
+                    // Leave the messages for the other sessions.
+                    let mut queue = queue.lock().expect("append queue poisoned");
+                    for item in batch.into_iter().rev() {
+                        queue.push_front(item);
+                    }
+                    connected = false;
+                }
+            }
+        }
+
+        let Some((tag, indices, rx)) = pending.pop_front() else {
+            break;
+        };
+        let result = match await_response(rx, "APPEND").await {
+            Ok(lines) => ensure_ok(&lines, &tag, "APPEND")
+                .map(|()| parse_append_uid(&join_lines(&lines), &tag).map(|(_, uids)| uids)),
+            Err(e) => {
+                connected = false;
+                Err(e)
+            }
+        };
+        match result {
+            Ok(uids) => {
+                for (k, index) in indices.into_iter().enumerate() {
+                    let uid = uids.as_ref().and_then(|u| u.get(k).copied());
+                    outcomes.push((index, Ok(uid)));
+                }
+            }
+            Err(e) => {
+                for index in indices {
+                    outcomes.push((index, Err(anyhow::anyhow!("{:#}", e))));
+                }
+            }
+        }
+    }
+    outcomes
+}
+
+fn build_append(
+    tag: &str,
+    mailbox: &str,
+    batch: &[(usize, AppendMessage)],
+    literal_plus: bool,
+) -> (String, Literal) {
+    if let [(_, message)] = batch {
+        let mut builder = CommandBuilder::new(tag)
+            .append(mailbox)
+            .flags(message.flags.clone())
+            .literal(message.body.to_vec());
+        if let Some(date) = message.internal_date {
+            builder = builder.internal_date(date);
+        }
+        if literal_plus {
+            builder = builder.literal_plus();
+            return (
+                builder.as_string(),
+                Literal::NonSynchronizing(message.body.clone()),
+            );
+        }
+        return (
+            builder.as_string(),
+            Literal::Synchronizing(message.body.clone()),
+        );
+    }
+
+    let mut builder = CommandBuilder::new(tag).multiappend(mailbox);
+    for (_, message) in batch {
+        builder = builder.message(
+            message.flags.clone(),
+            message.internal_date,
+            message.body.to_vec(),
+        );
+    }
+    (
+        builder.as_string(),
+        Literal::NonSynchronizing(Bytes::from(builder.literal_bytes())),
+    )
+}
@@ -1,10 +1,27 @@
 use anyhow::Result;
-use crate::async_impl::{Connector, Client};
+use rustls::ClientConfig;
+use rustls::client::Resumption;
+use std::sync::Arc;
+use std::net::IpAddr;
+use std::time::Duration;
+use imap::tls;
+use crate::async_impl::{AuthMechanism, CancellationToken, ConnectionMetrics, Connector, Client, ReconnectPolicy};
+use crate::async_impl::connector::{CommandEvent, CommandHook, Options};
+use crate::async_impl::metrics::MetricsHook;
 use crate::ConnectedState;
 
 pub struct Builder {
     addr: String,
     conn_type: crate::ConnectionType,
+    tls_config: Option<Arc<ClientConfig>>,
+    resumption: Option<Resumption>,
+    #[cfg(feature = "system-roots")]
+    system_roots: bool,
+    #[cfg(feature = "dangerous-tls")]
+    accept_invalid_certs: bool,
+    #[cfg(feature = "dangerous-tls")]
+    accept_invalid_hostnames: bool,
+    opts: Options,
 }
 
 impl Builder {
@@ -12,6 +29,15 @@ impl Builder {
         Self {
             addr: addr.to_string(),
             conn_type: crate::ConnectionType::Tls,
+            tls_config: None,
+            resumption: None,
+            #[cfg(feature = "system-roots")]
+            system_roots: false,
+            #[cfg(feature = "dangerous-tls")]
+            accept_invalid_certs: false,
+            #[cfg(feature = "dangerous-tls")]
+            accept_invalid_hostnames: false,
+            opts: Options::default(),
         }
     }
 
@@ -30,8 +56,203 @@ impl Builder {
         self
     }
 
+    /// Present and verify `name` in the TLS handshake instead of the host in the address,
+    /// e.g. to connect to an IP address or a tunnel endpoint while still checking the
+    /// real server's certificate.
+    pub fn server_name(mut self, name: &str) -> Self {
+        self.opts.server_name = Some(name.to_string());
+        self
+    }
+
+    /// Connect from `address`, e.g. to pick an interface on a multi-homed host. Only server
+    /// addresses of the same family (IPv4 or IPv6) are tried.
+    pub fn local_address(mut self, address: IpAddr) -> Self {
+        self.opts.local_address = Some(address);
+        self
+    }
+
+    /// Tolerate up to `max_lines` non-IMAP lines before the server greeting.
+    ///
+    /// Some gateways and middleboxes inject banner lines ahead of the `* OK` greeting.
+    /// Skipped lines are reported as `tracing` warnings.
+    pub fn greeting_skip_lines(mut self, max_lines: usize) -> Self {
+        self.opts.greeting_skip_lines = max_lines;
+        self
+    }
+
+    /// TLS session resumption policy for connections made by the built [`Connector`].
+    ///
+    /// Defaults to an in-memory cache; clones of the connector share it, so reconnects
+    /// resume the previous session. Use `Resumption::disabled()` to force full handshakes.
+    pub fn tls_resumption(mut self, resumption: Resumption) -> Self {
+        self.resumption = Some(resumption);
+        self
+    }
+
+    /// Use a caller-provided TLS configuration, e.g. to share one session cache between
+    /// several builders. Overrides [`Builder::tls_resumption`].
+    pub fn tls_config(mut self, config: Arc<ClientConfig>) -> Self {
+        self.tls_config = Some(config);
+        self
+    }
+
+    /// Trust the Unix PEM trust bundle as well as the bundled Mozilla roots, e.g. for a
+    /// corporate proxy CA an administrator added to it. Needs the `system-roots` feature;
+    /// see [`tls::add_system_roots`] for where it looks. The macOS keychain and the Windows
+    /// certificate store are not read. If no bundle can be read only the bundled roots are
+    /// trusted, with a warning.
+    #[cfg(feature = "system-roots")]
+    pub fn system_roots(mut self) -> Self {
+        self.system_roots = true;
+        self
+    }
+
+    /// Accept any server certificate, including expired, self-signed and mismatched ones.
+    ///
+    /// Only for test servers, such as a Dovecot container with a self-signed certificate:
+    /// anyone on the path can impersonate the server. Needs the `dangerous-tls` feature and
+    /// has no effect with [`Builder::tls_config`].
+    #[cfg(feature = "dangerous-tls")]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Accept a trusted certificate issued for a different host name, e.g. when reaching a
+    /// test server by IP address. Needs the `dangerous-tls` feature.
+    #[cfg(feature = "dangerous-tls")]
+    pub fn danger_accept_invalid_hostnames(mut self, accept: bool) -> Self {
+        self.accept_invalid_hostnames = accept;
+        self
+    }
+
+    /// Call `hook` once for every command when it completes, or when the connection
+    /// closes before it does.
+    ///
+    /// The hook runs on the connection task and should return quickly.
+    pub fn on_command<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&CommandEvent) + Send + Sync + 'static,
+    {
+        self.opts.on_command = Some(CommandHook(Arc::new(hook)));
+        self
+    }
+
+    /// Report bytes, commands, latencies and pipeline depth to `metrics`; see
+    /// [`ConnectionMetrics`](crate::async_impl::ConnectionMetrics).
+    pub fn metrics(mut self, metrics: impl ConnectionMetrics + 'static) -> Self {
+        self.opts.metrics = Some(MetricsHook(Arc::new(metrics)));
+        self
+    }
+
+    /// Like [`Builder::metrics`], for an observer shared with other connections.
+    pub fn shared_metrics(mut self, metrics: Arc<dyn ConnectionMetrics>) -> Self {
+        self.opts.metrics = Some(MetricsHook(metrics));
+        self
+    }
+
+    /// Shut the connection down when `token` is cancelled.
+    ///
+    /// Pending commands fail with [`Cancelled`](crate::async_impl::Cancelled), LOGOUT is
+    /// sent and the transport is closed. One token can be shared by many connections.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.opts.cancel = Some(token);
+        self
+    }
+
+    /// Mechanisms [`Client::authenticate`] may use, most preferred first. Mechanisms the
+    /// server does not offer are skipped. Defaults to PLAIN, then LOGIN.
+    pub fn preferred_auth(mut self, mechanisms: Vec<AuthMechanism>) -> Self {
+        self.opts.preferred_auth = mechanisms;
+        self
+    }
+
+    /// Never change mailbox state, for monitoring and compliance tools.
+    ///
+    /// Mailboxes are always opened with EXAMINE, body fetches use their `.PEEK` forms, and
+    /// commands that would change anything (APPEND, COPY, MOVE, STORE, EXPUNGE, CREATE,
+    /// SETACL, SETQUOTA, ...) fail without being sent. Raw commands sent with
+    /// [`RawClient::execute`](crate::async_impl::RawClient::execute) are not checked.
+    pub fn read_only(mut self) -> Self {
+        self.opts.read_only = true;
+        self
+    }
+
+    /// Give up on the TCP connection and TLS handshake after `timeout`, failing with
+    /// [`TimedOut`](crate::async_impl::TimedOut). No limit by default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.opts.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Give up if the server's greeting has not arrived `timeout` after connecting.
+    pub fn greeting_timeout(mut self, timeout: Duration) -> Self {
+        self.opts.greeting_timeout = Some(timeout);
+        self
+    }
+
+    /// Close the connection if the server sends nothing for `timeout` while a command is
+    /// waiting for its response. IDLE is exempt, since silence is normal there. Pending
+    /// commands then fail.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.opts.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Close the connection if a write makes no progress for `timeout`, e.g. because the
+    /// server stopped reading.
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.opts.write_timeout = Some(timeout);
+        self
+    }
+
+    /// Close the connection if the server announces a literal larger than `bytes`, rather
+    /// than buffering it; pending commands then fail. 64 MiB by default
+    /// ([`DEFAULT_MAX_LITERAL`](imap::framing::DEFAULT_MAX_LITERAL)).
+    pub fn max_literal_size(mut self, bytes: usize) -> Self {
+        self.opts.max_literal = Some(bytes);
+        self
+    }
+
+    /// Send NOOP once the connection has been quiet for `interval` with no command running,
+    /// so NAT gateways and firewalls do not drop a long-lived session between user actions.
+    /// During IDLE the IDLE is ended and re-issued instead. Off by default.
+    pub fn keepalive(mut self, interval: Duration) -> Self {
+        self.opts.keepalive = Some(interval);
+        self
+    }
+
+    /// Reconnect transparently when the connection fails, as described by `policy`.
+    /// Off by default: pending commands then fail and the client is unusable.
+    pub fn reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.opts.reconnect = Some(policy);
+        self
+    }
+
     pub fn build(self) -> Connector {
-        Connector::new(&self.addr, self.conn_type)
+        let tls_config = self.tls_config.unwrap_or_else(|| {
+            let resumption = self.resumption.unwrap_or_default();
+            #[allow(unused_mut)]
+            let mut roots = tls::webpki_root_store();
+            #[cfg(feature = "system-roots")]
+            if self.system_roots {
+                match tls::add_system_roots(&mut roots) {
+                    Ok(added) => tracing::debug!(added, "Loaded system root certificates"),
+                    Err(e) => tracing::warn!("Using only the bundled roots: {}", e),
+                }
+            }
+            #[cfg(feature = "dangerous-tls")]
+            let config = tls::create_tls_config_dangerous(
+                roots,
+                resumption,
+                self.accept_invalid_certs,
+                self.accept_invalid_hostnames,
+            );
+            #[cfg(not(feature = "dangerous-tls"))]
+            let config = tls::create_tls_config_with_roots(roots, resumption);
+            config
+        });
+        Connector::from_parts(&self.addr, self.conn_type, tls_config, self.opts)
     }
 
     pub async fn connect(
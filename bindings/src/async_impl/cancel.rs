@@ -0,0 +1,54 @@
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Signals connections built with
+/// [`Builder::cancellation_token`](crate::async_impl::Builder::cancellation_token) to shut
+/// down.
+///
+/// Clones share one flag; cancelling any of them cancels all. On cancellation the run loop
+/// fails every pending command with [`Cancelled`], sends LOGOUT and closes the transport.
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    flag: Arc<watch::Sender<bool>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self {
+            flag: Arc::new(watch::Sender::new(false)),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.flag.borrow()
+    }
+
+    /// Completes once [`CancellationToken::cancel`] has been called.
+    pub async fn cancelled(&self) {
+        let mut rx = self.flag.subscribe();
+        // The sender lives in `self`, so the channel cannot close while we wait.
+        let _ = rx.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The error for a command that was still pending when its connection was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
@@ -1,47 +1,327 @@
 use anyhow::{Context, Result};
 use bytes::{BufMut, Bytes, BytesMut};
 use memchr::memmem;
-use std::collections::VecDeque;
+use rustls::ClientConfig;
+use rustls::pki_types::ServerName;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpSocket, TcpStream};
 use tokio::sync::{broadcast, mpsc, oneshot};
 use tokio_rustls::TlsConnector;
 use tokio_rustls::client::TlsStream;
 
-use crate::{AuthenticatedState, ConnectedState, next_tag};
+use super::append::check_append_limit;
+use super::cancel::{CancellationToken, Cancelled};
+use super::events::Events;
+use super::idle::IdleHandle;
+use super::messages::Messages;
+use super::metrics::{MetricsHook, meter};
+use super::reconnect::{self, ConnectionLost, Dial, ReconnectPolicy, SessionState};
+use super::timeout::{TimedOut, WriteTimeout, within};
+use crate::{AuthenticatedState, ConnectedState, ExaminedState, LoggedIn, Selected, SelectedState, Writable, next_tag};
 
-use imap::commands::CommandBuilder;
-use imap::parser::{fetch, greeting};
-use imap::tls;
-use imap::types::command::{SequenceBound, SequenceSet};
-use imap::types::response::{Envelope, FetchData};
+use tokio_stream::Stream;
+use tokio_stream::wrappers::ReceiverStream;
+
+use imap::commands::{CommandBuilder, FetchItem, Section, redact};
+use imap::ImapError;
+use imap::framing;
+use imap::mdn::{self, MdnRequest};
+use imap::messages::{self, Message};
+use imap::mime::{self, Attachment, TextBody};
+use imap::parser::{self, acl, capability, fetch, greeting, header, id, quota, search};
+use imap::sasl;
+use imap::special_use::{self, MailboxRole};
+use imap::tls::{self, TlsInfo};
+use imap::types::command::{
+    AclChange, CatenatePart, NotifyEvent, NotifyMailboxes, SearchKey, SearchReturn, SequenceSet,
+    SortKey, StatusItem,
+};
+use imap::types::common::{
+    Capabilities, Capability, DateTime, Flag, KeywordInterner, MessageFlags, Rights, SaslMechanism,
+    Seq, Status, Uid,
+};
+use imap::types::response::{
+    AclEntry, Collation, CopyUid, Envelope, EnvelopeSummary, EsearchResult, FetchData, HeaderMap,
+    ListEntry, ListRights, MailboxStatus, MailboxStatusSummary, Quota, QuotaRoot, ResponseCode,
+    UntaggedResponse,
+};
 
 const LINE_CAP: usize = 8 * 1024;
 const GROW_STEP: usize = 2 * 1024; // 2 KiB increments (one TLS record fragment)
+const MAX_IN_FLIGHT: usize = 16;
+const MAX_COMMAND_LEN: usize = 8 * 1024;
+const LITERAL_STEP: usize = 64 * 1024; // read buffer growth while a literal is outstanding
+const STREAM_CHUNKS: usize = 4; // literal reads queued for a `fetch_body_to` writer
+const LOGOUT_GRACE: Duration = Duration::from_secs(2);
 
+/// Connection settings. Cloning a connector shares its TLS configuration, so later
+/// connections can resume the TLS session of earlier ones.
+#[derive(Clone)]
 pub struct Connector {
     addr: String,
     conn_type: crate::ConnectionType,
+    tls_config: Arc<ClientConfig>,
+    opts: Options,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Options {
+    /// Number of non-IMAP lines (e.g. middlebox banners) tolerated before the greeting.
+    pub(crate) greeting_skip_lines: usize,
+    pub(crate) on_command: Option<CommandHook>,
+    pub(crate) metrics: Option<MetricsHook>,
+    pub(crate) cancel: Option<CancellationToken>,
+    /// Mechanisms for [`Client::authenticate`], most preferred first.
+    pub(crate) preferred_auth: Vec<AuthMechanism>,
+    pub(crate) read_only: bool,
+    /// TCP connection plus TLS handshake.
+    pub(crate) connect_timeout: Option<Duration>,
+    pub(crate) greeting_timeout: Option<Duration>,
+    /// Longest silence while a command awaits its response, outside IDLE.
+    pub(crate) read_timeout: Option<Duration>,
+    /// Longest a write may make no progress.
+    pub(crate) write_timeout: Option<Duration>,
+    /// Quiet period after which the run loop sends NOOP, or restarts IDLE.
+    pub(crate) keepalive: Option<Duration>,
+    pub(crate) reconnect: Option<ReconnectPolicy>,
+    /// Name to present (SNI) and verify in TLS, instead of the host in the address.
+    pub(crate) server_name: Option<String>,
+    /// Source address for the TCP connection.
+    pub(crate) local_address: Option<IpAddr>,
+    /// Largest literal accepted from the server; `None` for the framing default.
+    pub(crate) max_literal: Option<usize>,
+}
+
+/// A way to authenticate with a user name and password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthMechanism {
+    /// `AUTHENTICATE PLAIN` (RFC 4616); needs `AUTH=PLAIN`.
+    Plain,
+    /// The LOGIN command; unavailable when the server advertises LOGINDISABLED.
+    Login,
+}
+
+impl AuthMechanism {
+    fn offered(self, caps: &Capabilities) -> bool {
+        match self {
+            AuthMechanism::Plain => caps.contains(&Capability::Auth(SaslMechanism::Plain)),
+            AuthMechanism::Login => !caps.contains(&Capability::LoginDisabled),
+        }
+    }
+}
+
+/// The lifecycle of one command, reported to the hook set with
+/// [`Builder::on_command`](crate::async_impl::Builder::on_command).
+#[derive(Debug, Clone)]
+pub struct CommandEvent {
+    pub tag: String,
+    /// Command name, e.g. `SELECT` or `UID FETCH`.
+    pub name: String,
+    pub queued_at: Instant,
+    /// `None` if the connection closed before the command was written.
+    pub sent_at: Option<Instant>,
+    pub completed_at: Instant,
+    /// The tagged completion status, or `None` if the connection closed first.
+    pub status: Option<Status>,
+}
+
+#[derive(Clone)]
+pub(crate) struct CommandHook(pub(crate) Arc<dyn Fn(&CommandEvent) + Send + Sync>);
+
+impl std::fmt::Debug for CommandHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CommandHook")
+    }
 }
 
 pub struct Client<State> {
-    cmd_tx: mpsc::Sender<CommandMessage>,
+    cmd_tx: mpsc::Sender<Request>,
     unsol_rx: broadcast::Receiver<Bytes>,
+    selected: Option<String>,
+    /// Keyword flags seen in the selected mailbox.
+    keywords: KeywordInterner,
+    /// Set while [`Client::examine_then_fetch`] runs; mailbox switches are refused.
+    snapshot: bool,
+    /// Capabilities from the last CAPABILITY response or login completion.
+    capabilities: Option<Capabilities>,
+    /// Items requested by the convenience fetch methods; see [`Client::set_fetch_profile`].
+    fetch_profile: Vec<FetchItem>,
+    watermarks: Arc<Watermarks>,
+    mailbox: Arc<MailboxTracker>,
+    preferred_auth: Vec<AuthMechanism>,
+    /// Set by [`Builder::read_only`](crate::async_impl::Builder::read_only).
+    read_only: bool,
+    tls_info: Option<Arc<TlsInfo>>,
     _state: PhantomData<State>,
 }
 
-struct CommandMessage {
+/// High-watermarks of a connection's buffers, from [`Client::buffer_stats`].
+///
+/// Useful for sizing limits: compare `max_line_len` with the 8 KiB line cap and
+/// `max_queue_depth` with how far callers run ahead of the server.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferStats {
+    /// Longest response line received, in bytes, not counting literal data.
+    pub max_line_len: usize,
+    /// Most commands waiting in the run loop to be written.
+    pub max_queue_depth: usize,
+    /// Most commands written but not yet completed.
+    pub max_in_flight: usize,
+}
+
+/// Shared between the run loop, which records, and its clients, which read.
+#[derive(Debug, Default)]
+struct Watermarks {
+    max_line_len: AtomicUsize,
+    max_queue_depth: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl Watermarks {
+    fn snapshot(&self) -> BufferStats {
+        BufferStats {
+            max_line_len: self.max_line_len.load(Ordering::Relaxed),
+            max_queue_depth: self.max_queue_depth.load(Ordering::Relaxed),
+            max_in_flight: self.max_in_flight.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The selected mailbox as the run loop last saw it, for [`Client::mailbox_state`].
+#[derive(Debug, Default)]
+pub(super) struct MailboxTracker(Mutex<Option<MailboxStatus>>);
+
+impl MailboxTracker {
+    fn snapshot(&self) -> Option<MailboxStatus> {
+        self.0.lock().expect("mailbox state poisoned").clone()
+    }
+
+    /// Applies an untagged response to the selected mailbox, if there is one.
+    fn observe_line(&self, line: &[u8]) {
+        if let Some(status) = self.0.lock().expect("mailbox state poisoned").as_mut() {
+            parser::mailbox::update_mailbox_status(status, line);
+        }
+    }
+
+    /// Notes the outcome of a completed command, given all its response lines.
+    pub(super) fn observe(&self, name: &str, tag: &str, lines: &[Bytes], ok: bool) {
+        let mut state = self.0.lock().expect("mailbox state poisoned");
+        match name {
+            "SELECT" | "EXAMINE" => {
+                *state = ok
+                    .then(|| parser::mailbox::parse_select_response(&join_lines(lines), tag));
+            }
+            "CLOSE" | "UNSELECT" if ok => *state = None,
+            _ => {}
+        }
+    }
+}
+
+impl std::fmt::Display for BufferStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "longest line {} bytes, deepest queue {}, most in flight {}",
+            self.max_line_len, self.max_queue_depth, self.max_in_flight
+        )
+    }
+}
+
+/// A transport the run loop can drive.
+///
+/// The run loop uses tokio's IO traits, timers and `spawn`, so it needs a tokio runtime.
+/// Streams from other runtimes (async-std, smol) implement `futures::io` traits instead;
+/// wrap them with an adapter such as `tokio_util::compat` and pass them to
+/// [`Connector::connect_stream`]. A connector for another runtime can reuse the framing
+/// and parsing in the `imap` crate, which does no IO.
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Transport for T {}
+
+/// The transport handed back by [`Client::into_inner`], plus any bytes the run loop
+/// had already read but not yet consumed.
+pub struct RawStream {
+    pub stream: Box<dyn Transport>,
+    pub buffered: BytesMut,
+}
+
+/// Raw access to a running connection: commands are sent verbatim (only the tag is added)
+/// and every response line is returned unparsed.
+///
+/// Commands that need continuation requests (literals, AUTHENTICATE, IDLE) are not
+/// supported; use [`Client::into_inner`] to take over the stream for those.
+pub struct RawClient {
+    cmd_tx: mpsc::Sender<Request>,
+    unsol_rx: broadcast::Receiver<Bytes>,
+}
+
+pub(super) enum Request {
+    Command(CommandMessage),
+    Detach(oneshot::Sender<RawStream>),
+    /// Ends the IDLE command in progress, if any.
+    IdleDone,
+    /// Closes the transport at once, failing whatever is still in flight.
+    Close,
+}
+
+pub(super) enum Literal {
+    /// Sent after the server's `+` continuation request.
+    Synchronizing(Bytes),
+    /// Sent straight after the command line (`{n+}`, RFC 7888).
+    NonSynchronizing(Bytes),
+}
+
+/// Where parts of a command's responses go as they arrive, instead of being collected.
+#[derive(Debug)]
+pub(super) enum Sink {
+    /// The literal data; the responses are collected without it.
+    Literals(mpsc::Sender<Bytes>),
+    /// Every untagged response. Only the tagged completion is collected.
+    Responses(mpsc::Sender<Bytes>),
+}
+
+pub(super) struct CommandMessage {
     tag: String,
     command: String,
-    responder: oneshot::Sender<Vec<Bytes>>, // all lines collected for this command (untagged + completion)
+    /// Data that follows the command line: an APPEND literal or a SASL response.
+    literal: Option<Literal>,
+    sink: Option<Sink>,
+    queued_at: Instant,
+    responder: oneshot::Sender<Response>, // all lines collected for this command (untagged + completion)
 }
 
+/// What a command's responder receives: its lines, [`Cancelled`] on shutdown, or
+/// [`ConnectionLost`] if it was sent before a reconnect.
+pub(super) type Response = Result<Vec<Bytes>>;
+
 impl Connector {
     pub fn new(addr: &str, conn_type: crate::ConnectionType) -> Self {
+        Self::from_parts(
+            addr,
+            conn_type,
+            tls::create_tls_config(),
+            Options::default(),
+        )
+    }
+
+    pub(crate) fn from_parts(
+        addr: &str,
+        conn_type: crate::ConnectionType,
+        tls_config: Arc<ClientConfig>,
+        opts: Options,
+    ) -> Self {
         Self {
             addr: addr.to_owned(),
             conn_type,
+            tls_config,
+            opts,
         }
     }
 
@@ -49,96 +329,263 @@ impl Connector {
     pub async fn connect(self) -> Result<Client<ConnectedState>> {
         tracing::info!("Connecting to IMAP server");
 
+        let (stream, greet) = self.dial().await?;
+        let info = TlsInfo::from_connection(stream.get_ref().1);
+        let redial = self.opts.reconnect.is_some().then(|| self.redialer());
+        Self::spawn(self.opts, Box::new(stream), greet, Some(info), redial).await
+    }
+
+    /// Opens the TLS transport. The flag says whether the greeting is still to be read
+    /// (it was read in plaintext for STARTTLS).
+    async fn dial(&self) -> Result<(TlsStream<TcpStream>, bool)> {
         match self.conn_type {
             crate::ConnectionType::Tls => {
-                let config = tls::create_tls_config();
-                let server_name = tls::parse_server_name(&self.addr).with_context(|| {
-                    format!("Failed to parse server name from address: {}", self.addr)
-                })?;
-
-                let connector = TlsConnector::from(config);
-                let sock = TcpStream::connect(&self.addr).await.with_context(|| {
-                    format!("Failed to establish TCP connection to {}", self.addr)
-                })?;
-                let stream = connector
-                    .connect(server_name, sock)
-                    .await
-                    .with_context(|| {
-                        format!("Failed to establish TLS connection to {}", self.addr)
-                    })?;
-
-                let (cmd_tx, cmd_rx) = mpsc::channel::<CommandMessage>(32);
-                let (unsol_tx, unsol_rx) = broadcast::channel::<Bytes>(64);
-                let (greeting_tx, greeting_rx) = oneshot::channel::<Result<()>>();
+                let stream = within(self.opts.connect_timeout, "connect", async {
+                    let sock = self.tcp_connect().await?;
+                    self.tls_handshake(sock).await
+                })
+                .await?;
+                Ok((stream, true))
+            }
+            crate::ConnectionType::StartTls => {
+                let connect_timeout = self.opts.connect_timeout;
+                let mut sock = within(connect_timeout, "connect", self.tcp_connect()).await?;
+                self.negotiate_starttls(&mut sock).await?;
+                let stream = within(connect_timeout, "connect", self.tls_handshake(sock)).await?;
+                tracing::info!("STARTTLS negotiation complete");
+                Ok((stream, false))
+            }
+            _ => anyhow::bail!("Connection type {:?} not implemented", self.conn_type),
+        }
+    }
 
-                tokio::spawn(async move {
-                    if let Err(e) = Self::run_imap_loop(stream, cmd_rx, unsol_tx, greeting_tx).await
-                    {
-                        tracing::error!("Error handling messages: {}", e);
-                    }
-                });
+    /// Dials the same server again for a reconnect, resuming the TLS session if it can.
+    fn redialer(&self) -> Dial {
+        let connector = self.clone();
+        Arc::new(move || {
+            let connector = connector.clone();
+            Box::pin(async move {
+                let (stream, greet) = connector.dial().await?;
+                Ok((Box::new(stream) as Box<dyn Transport>, greet))
+            })
+        })
+    }
 
-                greeting_rx
-                    .await
-                    .context("Greeting handler task panicked or was cancelled")?
-                    .context("Failed to process IMAP greeting")?;
+    async fn tcp_connect(&self) -> Result<TcpStream> {
+        let Some(local) = self.opts.local_address else {
+            return TcpStream::connect(&self.addr)
+                .await
+                .with_context(|| format!("Failed to establish TCP connection to {}", self.addr));
+        };
 
-                Ok(Client::<ConnectedState> {
-                    cmd_tx,
-                    unsol_rx,
-                    _state: PhantomData,
-                })
+        // Like TcpStream::connect, try each resolved address in turn, but only those of the
+        // local address's family.
+        let mut last_err = None;
+        let addrs = tokio::net::lookup_host(&self.addr)
+            .await
+            .with_context(|| format!("Failed to resolve {}", self.addr))?;
+        for addr in addrs.filter(|a| a.is_ipv4() == local.is_ipv4()) {
+            let socket = if local.is_ipv4() {
+                TcpSocket::new_v4()?
+            } else {
+                TcpSocket::new_v6()?
+            };
+            socket
+                .bind(SocketAddr::new(local, 0))
+                .with_context(|| format!("Failed to bind to local address {}", local))?;
+            match socket.connect(addr).await {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_err = Some(e),
             }
-            _ => anyhow::bail!("Connection type {:?} not implemented", self.conn_type),
+        }
+        match last_err {
+            Some(e) => Err(e).with_context(|| {
+                format!(
+                    "Failed to establish TCP connection to {} from {}",
+                    self.addr, local
+                )
+            }),
+            None => anyhow::bail!("{} has no address reachable from {}", self.addr, local),
         }
     }
 
-    async fn run_imap_loop(
-        mut stream: TlsStream<TcpStream>,
-        mut cmd_rx: mpsc::Receiver<CommandMessage>,
-        unsol_tx: broadcast::Sender<Bytes>,
-        greeting_tx: oneshot::Sender<Result<()>>,
-    ) -> Result<()> {
+    async fn tls_handshake(&self, sock: TcpStream) -> Result<TlsStream<TcpStream>> {
+        let server_name = match &self.opts.server_name {
+            Some(name) => ServerName::try_from(name.clone())
+                .with_context(|| format!("Invalid TLS server name: {}", name))?,
+            None => tls::parse_server_name(&self.addr).with_context(|| {
+                format!("Failed to parse server name from address: {}", self.addr)
+            })?,
+        };
+
+        let stream = TlsConnector::from(self.tls_config.clone())
+            .connect(server_name, sock)
+            .await
+            .with_context(|| format!("Failed to establish TLS connection to {}", self.addr))?;
+        tracing::debug!(handshake = ?stream.get_ref().1.handshake_kind(), "TLS established");
+        Ok(stream)
+    }
+
+    /// Reads the plaintext greeting and asks the server to start TLS.
+    async fn negotiate_starttls(&self, sock: &mut TcpStream) -> Result<()> {
         let mut buf = BytesMut::with_capacity(1024);
+        within(
+            self.opts.greeting_timeout,
+            "greeting",
+            read_greeting(sock, &mut buf, &self.opts),
+        )
+        .await
+        .context("Failed to process IMAP greeting")?;
 
-        // Handle greeting
-        loop {
-            // Check spare capacity before reading
-            if buf.remaining_mut() == 0 {
-                if buf.capacity() >= LINE_CAP {
-                    anyhow::bail!(
-                        "IMAP greeting exceeded maximum line length of {} bytes",
-                        LINE_CAP
-                    );
+        let tag = next_tag();
+        let command = CommandBuilder::new(&tag).starttls().as_string();
+        within(
+            self.opts.write_timeout,
+            "write",
+            write_command(sock, &command),
+        )
+        .await?;
+        let completion = within(self.opts.read_timeout, "read", async {
+            loop {
+                let line = read_line(sock, &mut buf)
+                    .await
+                    .context("Failed to read STARTTLS response")?;
+                if is_tagged_completion(&line, &tag) {
+                    break Ok(line);
                 }
-                let add = GROW_STEP.min(LINE_CAP - buf.capacity());
-                buf.reserve(add);
             }
+        })
+        .await?;
+        ensure_ok(&[completion], &tag, "STARTTLS")?;
 
-            let n = stream
-                .read_buf(&mut buf)
-                .await
-                .context("Failed to read data while waiting for IMAP greeting")?;
-            if n == 0 {
-                anyhow::bail!("Server closed connection before sending greeting");
+        // Anything already buffered arrived in plaintext and must not be treated as
+        // part of the protected session.
+        if !buf.is_empty() {
+            anyhow::bail!("Server sent unexpected data after accepting STARTTLS");
+        }
+        Ok(())
+    }
+
+    /// Runs the IMAP session over an already established transport, starting with the
+    /// server greeting: a Unix socket, an SSH tunnel, a TLS stream from another TLS stack,
+    /// or an in-memory stream in tests.
+    ///
+    /// The connector's address, TLS settings and connect timeout are not used, and
+    /// [`Builder::reconnect`](crate::async_impl::Builder::reconnect) needs
+    /// [`Connector::connect_with`] to open new transports.
+    pub async fn connect_stream<S>(self, stream: S) -> Result<Client<ConnectedState>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        Self::spawn(self.opts, Box::new(stream), true, None, None).await
+    }
+
+    /// Like [`Connector::connect_stream`], with transports opened by `dial`: once for the
+    /// first connection, then again for each reconnect allowed by
+    /// [`Builder::reconnect`](crate::async_impl::Builder::reconnect).
+    pub async fn connect_with<F, Fut, S>(self, dial: F) -> Result<Client<ConnectedState>>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: std::future::Future<Output = Result<S>> + Send + 'static,
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let dial: Dial = Arc::new(move || {
+            let stream = dial();
+            Box::pin(async move { Ok((Box::new(stream.await?) as Box<dyn Transport>, true)) })
+        });
+        let (stream, greet) = dial().await?;
+        Self::spawn(self.opts, stream, greet, None, Some(dial)).await
+    }
+
+    async fn spawn(
+        opts: Options,
+        stream: Box<dyn Transport>,
+        greet: bool,
+        tls_info: Option<TlsInfo>,
+        redial: Option<Dial>,
+    ) -> Result<Client<ConnectedState>> {
+        let (cmd_tx, cmd_rx) = mpsc::channel::<Request>(32);
+        let (unsol_tx, unsol_rx) = broadcast::channel::<Bytes>(64);
+        let (greeting_tx, greeting_rx) = oneshot::channel::<Result<Option<Capabilities>>>();
+        let watermarks = Arc::new(Watermarks::default());
+        let mailbox = Arc::new(MailboxTracker::default());
+
+        let loop_watermarks = watermarks.clone();
+        let loop_mailbox = mailbox.clone();
+        let preferred_auth = opts.preferred_auth.clone();
+        let read_only = opts.read_only;
+        let stream: Box<dyn Transport> = Box::new(WriteTimeout::new(stream, opts.write_timeout));
+        let stream = meter(stream, opts.metrics.as_ref());
+        tokio::spawn(async move {
+            if let Err(e) = Self::run_imap_loop(
+                stream,
+                opts,
+                greet,
+                cmd_rx,
+                unsol_tx,
+                greeting_tx,
+                loop_watermarks,
+                loop_mailbox,
+                redial,
+            )
+            .await
+            {
+                tracing::error!("Error handling messages: {}", e);
             }
+        });
 
-            if let Some(pos) = memmem::find(&buf, b"\r\n") {
-                let line = buf.split_to(pos + 2).freeze();
-                match greeting::try_parse(&line) {
-                    Ok(Some(_greeting)) => {
-                        let _ = greeting_tx.send(Ok(()));
-                        break;
-                    }
-                    Ok(None) | Err(imap::parser::ParserError::Incomplete) => continue,
-                    Err(e) => {
-                        let err = e.to_string();
-                        let _ = greeting_tx.send(Err(e.into()));
-                        anyhow::bail!("Failed to parse IMAP greeting: {}", err);
-                    }
+        let capabilities = greeting_rx
+            .await
+            .context("Greeting handler task panicked or was cancelled")?
+            .context("Failed to process IMAP greeting")?;
+
+        Ok(Client::<ConnectedState> {
+            cmd_tx,
+            unsol_rx,
+            selected: None,
+            keywords: KeywordInterner::new(),
+            snapshot: false,
+            capabilities,
+            fetch_profile: default_fetch_profile(),
+            watermarks,
+            mailbox,
+            preferred_auth,
+            read_only,
+            tls_info: tls_info.map(Arc::new),
+            _state: PhantomData,
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn run_imap_loop(
+        mut stream: Box<dyn Transport>,
+        opts: Options,
+        greet: bool,
+        mut cmd_rx: mpsc::Receiver<Request>,
+        unsol_tx: broadcast::Sender<Bytes>,
+        greeting_tx: oneshot::Sender<Result<Option<Capabilities>>>,
+        watermarks: Arc<Watermarks>,
+        mailbox: Arc<MailboxTracker>,
+        redial: Option<Dial>,
+    ) -> Result<()> {
+        let mut buf = BytesMut::with_capacity(1024);
+
+        // Handle greeting (already done on the plaintext stream for STARTTLS, whose
+        // capabilities must not be trusted once TLS is up)
+        let capabilities = if greet {
+            let greeting = read_greeting(&mut stream, &mut buf, &opts);
+            match within(opts.greeting_timeout, "greeting", greeting).await {
+                Ok(caps) => caps,
+                Err(e) => {
+                    let err = format!("{:#}", e);
+                    let _ = greeting_tx.send(Err(e));
+                    anyhow::bail!("Failed to handle IMAP greeting: {}", err);
                 }
             }
-        }
+        } else {
+            None
+        };
+        let _ = greeting_tx.send(Ok(capabilities));
 
         // Ensure we have spare capacity before entering main loop
         if buf.remaining_mut() == 0 {
@@ -149,186 +596,2975 @@ impl Connector {
         #[derive(Debug)]
         struct ActiveCommand {
             tag: String,
-            responder: oneshot::Sender<Vec<Bytes>>,
+            name: String,
+            command: String,
+            queued_at: Instant,
+            sent_at: Instant,
+            sink: Option<Sink>,
+            responder: oneshot::Sender<Response>,
             collected: Vec<Bytes>,
         }
 
-        let mut active: Option<ActiveCommand> = None;
+        impl ActiveCommand {
+            fn sent(msg: CommandMessage) -> Self {
+                Self {
+                    name: command_name(&msg.command),
+                    tag: msg.tag,
+                    command: msg.command,
+                    queued_at: msg.queued_at,
+                    sent_at: Instant::now(),
+                    sink: msg.sink,
+                    responder: msg.responder,
+                    collected: Vec::new(),
+                }
+            }
+        }
+
+        let report = |tag: String, name: String, queued_at, sent_at, status| {
+            if opts.on_command.is_none() && opts.metrics.is_none() {
+                return;
+            }
+            let event = CommandEvent {
+                tag,
+                name,
+                queued_at,
+                sent_at,
+                completed_at: Instant::now(),
+                status,
+            };
+            if let Some(hook) = &opts.on_command {
+                (hook.0)(&event);
+            }
+            if let Some(metrics) = &opts.metrics {
+                metrics.0.command_completed(&event);
+            }
+        };
+        let sent = |command: &ActiveCommand| {
+            if let Some(metrics) = &opts.metrics {
+                metrics.0.command_sent(&command.name);
+            }
+        };
+        // The pipeline depth last given to the metrics observer.
+        let mut depth = (0, 0);
+
+        // Commands are pipelined: up to MAX_IN_FLIGHT are written before their completions
+        // arrive. Untagged lines are attributed to the oldest in-flight command.
+        let mut in_flight: VecDeque<ActiveCommand> = VecDeque::new();
         let mut queue: VecDeque<CommandMessage> = VecDeque::new();
+        let mut detach: Option<oneshot::Sender<RawStream>> = None;
+        // What a reconnect has to restore.
+        let mut session = SessionState::default();
 
-        // Main IMAP loop
-        loop {
-            tokio::select! {
-                result = stream.read_buf(&mut buf) => {
-                    let n = result.context("Failed to read data from IMAP server")?;
-                    if n == 0 {
-                        anyhow::bail!("IMAP server closed connection unexpectedly")
+        // After a tagged BAD the server may still be waiting for the remainder of a
+        // command it misparsed. Once in-flight commands drain we send a bare CRLF and a
+        // NOOP probe, and hold queued commands until the probe's tag completes.
+        let mut needs_resync = false;
+        let mut probe_tag: Option<String> = None;
+
+        // A command whose literal waits for a `+` continuation request. Nothing else may
+        // be written until it is sent, or the server would read it as literal data.
+        let mut continuation: Option<(String, Bytes)> = None;
+
+        // An IDLE in progress. Like a pending literal it blocks further writes; DONE is
+        // sent once the server has accepted the IDLE and the client asked to stop.
+        struct IdleState {
+            tag: String,
+            accepted: bool,
+            done_requested: bool,
+        }
+        let mut idle: Option<IdleState> = None;
+
+        let mut framer = Framer {
+            watermarks: watermarks.clone(),
+            ..Framer::new(&opts)
+        };
+        let mut shutting_down = false;
+        // Set once LOGOUT completes; the server closing the connection then is expected.
+        let mut logged_out = false;
+        // Set once every handle for sending commands has been dropped.
+        let mut abandoned = false;
+        // When the server last sent data, or a command arrived with nothing in flight; the
+        // read timeout counts from here.
+        let mut last_progress = Instant::now();
+        // When anything was last read or written; the keepalive counts from here.
+        let mut last_activity = Instant::now();
+
+        // How a pass of the main loop ended without an error.
+        enum Exit {
+            Closed,
+            /// The server sent BYE outside LOGOUT.
+            Bye(String),
+            Detach(oneshot::Sender<RawStream>),
+        }
+
+        // The text of a BYE that ended the session, for the commands it leaves unanswered.
+        let mut bye: Option<String> = None;
+
+        let result: Result<()> = loop {
+            // Main IMAP loop
+            let pass: Result<Exit> = async {
+                loop {
+                    // Write queued commands while the pipeline has room.
+                    while in_flight.len() < MAX_IN_FLIGHT && !needs_resync && probe_tag.is_none() && continuation.is_none() && idle.is_none() {
+                        let Some(mut next) = queue.pop_front() else { break };
+                        write_command(&mut stream, &next.command).await?;
+                        last_activity = Instant::now();
+                        match next.literal.take() {
+                            Some(Literal::Synchronizing(l)) => continuation = Some((next.tag.clone(), l)),
+                            Some(Literal::NonSynchronizing(l)) => write_literal(&mut stream, &l).await?,
+                            None => {}
+                        }
+                        if command_name(&next.command) == "IDLE" {
+                            idle = Some(IdleState { tag: next.tag.clone(), accepted: false, done_requested: false });
+                        }
+                        let active = ActiveCommand::sent(next);
+                        sent(&active);
+                        in_flight.push_back(active);
+                        watermarks.max_in_flight.fetch_max(in_flight.len(), Ordering::Relaxed);
                     }
 
-                    while let Some(pos) = memmem::find(&buf, b"\r\n") {
-                        let line = buf.split_to(pos + 2).freeze();
-
-                        // Broadcast raw line
-                        let _ = unsol_tx.send(line.clone());
-
-                        // If active command and this is a continuation request, we may need to write literal content.
-                        // For now, we just collect lines and detect completion; literal handling is covered by
-                        // parsing ENVELOPE which can include literals on the server side, but they arrive inline.
-
-                        if let Some(active_cmd) = &mut active {
-                            active_cmd.collected.push(line.clone());
-                            if is_tagged_completion(&line, &active_cmd.tag) {
-                                let collected = std::mem::take(&mut active_cmd.collected);
-                                let responder = std::mem::replace(&mut active_cmd.responder, oneshot::channel().0);
-                                let _ = responder.send(collected);
-                                active = None;
-
-                                if let Some(next) = queue.pop_front() {
-                                    stream.write_all(next.command.as_bytes()).await
-                                        .with_context(|| format!("Failed to send IMAP command: {}", next.command))?;
-                                    stream.flush().await
-                                        .with_context(|| format!("Failed to flush IMAP command: {}", next.command))?;
-                                    active = Some(ActiveCommand { tag: next.tag, responder: next.responder, collected: Vec::new() });
-                                }
-                            }
+                    if let Some(metrics) = &opts.metrics
+                        && depth != (queue.len(), in_flight.len())
+                    {
+                        depth = (queue.len(), in_flight.len());
+                        metrics.0.pipeline_depth(depth.0, depth.1);
+                    }
+
+                    // Hand the stream back once every outstanding command has completed.
+                    if in_flight.is_empty()
+                        && queue.is_empty()
+                        && let Some(tx) = detach.take()
+                    {
+                        return Ok(Exit::Detach(tx));
+                    }
+                    // Nobody can send another command, so the session is over.
+                    if abandoned && in_flight.is_empty() && queue.is_empty() {
+                        if !logged_out {
+                            tracing::debug!("Every client handle dropped; logging out");
+                            logout(&mut stream, &mut buf, &mut framer).await;
                         }
+                        return Ok(Exit::Closed);
                     }
 
-                    if buf.remaining_mut() == 0 {
-                        if buf.capacity() >= LINE_CAP {
-                            anyhow::bail!("IMAP response line exceeded maximum length of {} bytes", LINE_CAP);
+                    tokio::select! {
+                        result = stream.read_buf(&mut buf) => {
+                            let n = result.context("Failed to read data from IMAP server")?;
+                            if n == 0 {
+                                if logged_out {
+                                    return Ok(Exit::Closed);
+                                }
+                                anyhow::bail!("IMAP server closed connection unexpectedly")
+                            }
+                            last_progress = Instant::now();
+                            last_activity = last_progress;
+
+                            loop {
+                                // Literals in the oldest command's responses go to its sink.
+                                let sink = match in_flight.front().and_then(|c| c.sink.as_ref()) {
+                                    Some(Sink::Literals(tx)) => Some(tx),
+                                    _ => None,
+                                };
+                                framer.stream_literals(sink.is_some());
+                                let Some(line) = framer.next(&mut buf)? else {
+                                    let Some(sink) = sink else { break };
+                                    let data = framer.take_literal(&mut buf);
+                                    if data.is_empty() {
+                                        break;
+                                    }
+                                    // A caller that stopped listening gets nothing more.
+                                    let _ = sink.send(data).await;
+                                    continue;
+                                };
+                                // Broadcast raw line
+                                let _ = unsol_tx.send(line.clone());
+                                mailbox.observe_line(&line);
+                                if let Some(text) = server_bye(&line)
+                                    && !in_flight.iter().any(|c| c.name == "LOGOUT")
+                                {
+                                    return Ok(Exit::Bye(text));
+                                }
+
+                                if line.starts_with(b"+")
+                                    && let Some((_, literal)) = continuation.take()
+                                {
+                                    write_literal(&mut stream, &literal).await?;
+                                    continue;
+                                }
+                                if line.starts_with(b"+")
+                                    && let Some(state) = idle.as_mut().filter(|s| !s.accepted)
+                                {
+                                    state.accepted = true;
+                                    if state.done_requested {
+                                        write_command(&mut stream, "DONE\r\n").await?;
+                                    }
+                                    continue;
+                                }
+
+                                if let Some(idx) = in_flight.iter().position(|c| is_tagged_completion(&line, &c.tag)) {
+                                    let mut done = in_flight.remove(idx).expect("index from position");
+                                    let status = completion_status(&line, &done.tag);
+                                    if continuation.as_ref().is_some_and(|(tag, _)| *tag == done.tag) {
+                                        // Rejected before the literal was requested.
+                                        continuation = None;
+                                    }
+                                    if idle.as_ref().is_some_and(|s| s.tag == done.tag) {
+                                        idle = None;
+                                    }
+                                    if probe_tag.as_deref() == Some(done.tag.as_str()) {
+                                        tracing::debug!("Connection resynchronized after BAD response");
+                                        probe_tag = None;
+                                    } else if matches!(status, Some(Status::Bad)) {
+                                        tracing::warn!(
+                                            line = %String::from_utf8_lossy(&line).trim_end(),
+                                            "Server rejected command; resynchronizing"
+                                        );
+                                        needs_resync = true;
+                                    }
+                                    logged_out |= done.name == "LOGOUT";
+                                    let ok = matches!(status, Some(Status::Ok));
+                                    session.observe(&done.name, &done.command, ok);
+                                    done.collected.push(line);
+                                    mailbox.observe(&done.name, &done.tag, &done.collected, ok);
+                                    report(done.tag, done.name, done.queued_at, Some(done.sent_at), status);
+                                    let _ = done.responder.send(Ok(done.collected));
+                                } else if let Some(oldest) = in_flight.front_mut() {
+                                    match &oldest.sink {
+                                        Some(Sink::Responses(tx)) => {
+                                            let _ = tx.send(line).await;
+                                        }
+                                        _ => oldest.collected.push(line),
+                                    }
+                                }
+                            }
+
+                            if needs_resync && in_flight.is_empty() {
+                                let tag = next_tag();
+                                let probe = format!("\r\n{}", CommandBuilder::new(&tag).noop().as_string());
+                                write_command(&mut stream, &probe).await?;
+                                let now = Instant::now();
+                                let active = ActiveCommand { tag: tag.clone(), name: "NOOP".to_string(), command: probe, queued_at: now, sent_at: now, sink: None, responder: oneshot::channel().0, collected: Vec::new() };
+                                sent(&active);
+                                in_flight.push_back(active);
+                                probe_tag = Some(tag);
+                                needs_resync = false;
+                            }
+
+                            framer.reserve(&mut buf)?;
+                        }
+                        req = cmd_rx.recv(), if detach.is_none() && !abandoned => {
+                            let Some(req) = req else {
+                                abandoned = true;
+                                continue;
+                            };
+                            if in_flight.is_empty() {
+                                last_progress = Instant::now();
+                            }
+                            let mut msg = match req {
+                                Request::Command(msg) => msg,
+                                Request::Detach(tx) => {
+                                    detach = Some(tx);
+                                    continue;
+                                }
+                                Request::Close => {
+                                    shutting_down = true;
+                                    return Ok(Exit::Closed);
+                                }
+                                Request::IdleDone => {
+                                    if let Some(state) = idle.as_mut().filter(|s| !s.done_requested) {
+                                        state.done_requested = true;
+                                        if state.accepted {
+                                            write_command(&mut stream, "DONE\r\n").await?;
+                                        }
+                                    }
+                                    continue;
+                                }
+                            };
+                            if in_flight.len() < MAX_IN_FLIGHT && queue.is_empty() && !needs_resync && probe_tag.is_none() && continuation.is_none() && idle.is_none() {
+                                write_command(&mut stream, &msg.command).await?;
+                                last_activity = Instant::now();
+                                match msg.literal.take() {
+                                    Some(Literal::Synchronizing(l)) => continuation = Some((msg.tag.clone(), l)),
+                                    Some(Literal::NonSynchronizing(l)) => write_literal(&mut stream, &l).await?,
+                                    None => {}
+                                }
+                                if command_name(&msg.command) == "IDLE" {
+                                    idle = Some(IdleState { tag: msg.tag.clone(), accepted: false, done_requested: false });
+                                }
+                                let active = ActiveCommand::sent(msg);
+                                sent(&active);
+                                in_flight.push_back(active);
+                                watermarks.max_in_flight.fetch_max(in_flight.len(), Ordering::Relaxed);
+                            } else {
+                                queue.push_back(msg);
+                                watermarks.max_queue_depth.fetch_max(queue.len(), Ordering::Relaxed);
+                            }
+                        }
+                        _ = cancelled(opts.cancel.as_ref()) => {
+                            tracing::info!("Connection cancelled; logging out");
+                            shutting_down = true;
+                            logout(&mut stream, &mut buf, &mut framer).await;
+                            return Ok(Exit::Closed);
+                        }
+                        _ = deadline(opts.keepalive, last_activity), if !logged_out && !abandoned && detach.is_none() && ((in_flight.is_empty() && queue.is_empty()) || idle.as_ref().is_some_and(|s| s.accepted && !s.done_requested)) => {
+                            last_activity = Instant::now();
+                            if let Some(state) = idle.as_mut() {
+                                // The IDLE task re-issues IDLE once this one completes.
+                                tracing::debug!("Restarting IDLE to keep the connection alive");
+                                state.done_requested = true;
+                                write_command(&mut stream, "DONE\r\n").await?;
+                                continue;
+                            }
+                            let tag = next_tag();
+                            let noop = CommandBuilder::new(&tag).noop().as_string();
+                            write_command(&mut stream, &noop).await?;
+                            last_progress = last_activity;
+                            let active = ActiveCommand { tag, name: "NOOP".to_string(), command: noop, queued_at: last_activity, sent_at: last_activity, sink: None, responder: oneshot::channel().0, collected: Vec::new() };
+                            sent(&active);
+                            in_flight.push_back(active);
                         }
-                        let add = GROW_STEP.min(LINE_CAP - buf.capacity());
-                        buf.reserve(add);
+                        _ = deadline(opts.read_timeout, last_progress), if !in_flight.is_empty() && idle.is_none() => {
+                            let after = opts.read_timeout.unwrap_or_default();
+                            return Err(TimedOut { operation: "read", after }.into());
+                        }
+                        else => break,
                     }
                 }
-                Some(msg) = cmd_rx.recv() => {
-                    if active.is_none() {
-                        stream.write_all(msg.command.as_bytes()).await
-                            .with_context(|| format!("Failed to send IMAP command: {}", msg.command))?;
-                        stream.flush().await
-                            .with_context(|| format!("Failed to flush IMAP command: {}", msg.command))?;
-                        active = Some(ActiveCommand { tag: msg.tag, responder: msg.responder, collected: Vec::new() });
-                    } else {
-                        queue.push_back(msg);
-                    }
+                Ok(Exit::Closed)
+            }
+            .await;
+
+            let err = match pass {
+                Ok(Exit::Closed) => break Ok(()),
+                Ok(Exit::Detach(tx)) => {
+                    let _ = tx.send(RawStream {
+                        stream,
+                        buffered: buf,
+                    });
+                    break Ok(());
                 }
-                else => break,
+                // Without a reconnect policy the session is over; the transport closes next.
+                Ok(Exit::Bye(text)) if opts.reconnect.is_none() || redial.is_none() => {
+                    tracing::info!(text, "Server closed the session");
+                    bye = Some(text);
+                    break Ok(());
+                }
+                Ok(Exit::Bye(text)) => ImapError::ServerBye(text).into(),
+                Err(e) => e,
+            };
+            let (Some(policy), Some(dial)) = (&opts.reconnect, &redial) else {
+                break Err(err);
+            };
+            if shutting_down || logged_out {
+                break Err(err);
+            }
+
+            // Commands already written may or may not have been carried out; their callers
+            // decide whether to retry. Queued commands go out on the new connection.
+            tracing::warn!("Connection lost, reconnecting: {:#}", err);
+            for cmd in in_flight.drain(..) {
+                report(cmd.tag, cmd.name, cmd.queued_at, Some(cmd.sent_at), None);
+                let _ = cmd.responder.send(Err(ConnectionLost.into()));
+            }
+            continuation = None;
+            idle = None;
+            needs_resync = false;
+            probe_tag = None;
+            let resumed = tokio::select! {
+                resumed = reconnect::resume(policy, dial, &session, &mailbox, &opts) => resumed,
+                _ = cancelled(opts.cancel.as_ref()) => {
+                    shutting_down = true;
+                    break Ok(());
+                }
+            };
+            match resumed {
+                Ok((resumed, buffered)) => {
+                    stream = resumed;
+                    buf = buffered;
+                    framer = Framer {
+                        watermarks: watermarks.clone(),
+                        ..Framer::new(&opts)
+                    };
+                    last_progress = Instant::now();
+                    last_activity = last_progress;
+                }
+                Err(e) => break Err(e.context(format!("Connection lost: {:#}", err))),
+            }
+        };
+
+        // Whatever never completed is reported without a status. The callers still waiting
+        // are told why: a cancellation, a BYE, a timeout or a failed connection.
+        let failure = || -> Option<anyhow::Error> {
+            if shutting_down {
+                Some(Cancelled.into())
+            } else if let Some(text) = &bye {
+                Some(ImapError::ServerBye(text.clone()).into())
+            } else if let Err(e) = &result {
+                Some(match e.downcast_ref::<TimedOut>() {
+                    Some(timed_out) => (*timed_out).into(),
+                    None => ImapError::ConnectionFailed(format!("{:#}", e)).into(),
+                })
+            } else {
+                None
+            }
+        };
+        for cmd in in_flight.drain(..) {
+            report(cmd.tag, cmd.name, cmd.queued_at, Some(cmd.sent_at), None);
+            if let Some(e) = failure() {
+                let _ = cmd.responder.send(Err(e));
             }
         }
-        Ok(())
+        cmd_rx.close();
+        while let Ok(req) = cmd_rx.try_recv() {
+            if let Request::Command(msg) = req {
+                queue.push_back(msg);
+            }
+        }
+        for msg in queue.drain(..) {
+            report(
+                msg.tag,
+                command_name(&msg.command),
+                msg.queued_at,
+                None,
+                None,
+            );
+            if let Some(e) = failure() {
+                let _ = msg.responder.send(Err(e));
+            }
+        }
+        result
     }
 }
 
-fn is_tagged_completion(line: &Bytes, tag: &str) -> bool {
-    // Tagged completion is: <tag> SP (OK|NO|BAD) ... CRLF
-    if line.len() < tag.len() + 4 {
-        return false;
-    }
-    if &line[..tag.len()] != tag.as_bytes() {
-        return false;
+/// [`framing::Framer`] plus this crate's buffer policy, recording line lengths in the
+/// connection's watermarks.
+#[derive(Debug, Default)]
+pub(super) struct Framer {
+    inner: framing::Framer,
+    watermarks: Arc<Watermarks>,
+}
+
+impl Framer {
+    pub(super) fn new(opts: &Options) -> Self {
+        let mut inner = framing::Framer::default();
+        if let Some(max) = opts.max_literal {
+            inner.max_literal(max);
+        }
+        Self {
+            inner,
+            watermarks: Arc::default(),
+        }
     }
-    if line.get(tag.len()) != Some(&b' ') {
-        return false;
+
+    pub(super) fn next(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>> {
+        let response = self.inner.next(buf)?;
+        self.watermarks
+            .max_line_len
+            .fetch_max(self.inner.longest_line(), Ordering::Relaxed);
+        if let Some(line) = &response {
+            trace_received(line);
+        }
+        Ok(response)
     }
-    // We accept any status for completion detection
-    true
-}
 
-impl Client<ConnectedState> {
-    #[tracing::instrument(skip(self, pass))]
-    pub async fn login(self, user: &str, pass: &str) -> Result<Client<AuthenticatedState>> {
-        tracing::info!("Attempting IMAP login");
+    fn stream_literals(&mut self, on: bool) {
+        self.inner.stream_literals(on);
+    }
 
-        let tag = next_tag();
-        let cmd = CommandBuilder::new(&tag)
-            .login()
-            .username(user)
-            .password(pass)
-            .as_string();
+    fn take_literal(&mut self, buf: &mut BytesMut) -> Bytes {
+        self.inner.take_literal(buf)
+    }
 
-        let (tx, rx) = oneshot::channel::<Vec<Bytes>>();
-        self.cmd_tx
-            .send(CommandMessage {
-                tag: tag.clone(),
-                command: cmd,
-                responder: tx,
-            })
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to send login command: {}", e))?;
+    /// Makes room for the next read. Lines are capped at `LINE_CAP`; literals, at the
+    /// framer's maximum literal size, grow the buffer `LITERAL_STEP` at a time.
+    pub(super) fn reserve(&self, buf: &mut BytesMut) -> Result<()> {
+        let missing = self.inner.literal_missing(buf);
+        // Checked before every read: growing the buffer may add more than `GROW_STEP`.
+        if missing == 0 && self.inner.partial_line_len(buf) >= LINE_CAP {
+            anyhow::bail!(
+                "IMAP response line exceeded maximum length of {} bytes ({})",
+                LINE_CAP,
+                self.watermarks.snapshot()
+            );
+        }
+        if buf.remaining_mut() > 0 {
+            return Ok(());
+        }
+        if missing > 0 {
+            buf.reserve(missing.min(LITERAL_STEP));
+            return Ok(());
+        }
+        buf.reserve(GROW_STEP);
+        Ok(())
+    }
+}
 
-        let lines = rx.await.context("Login command timed out")?;
-        tracing::debug!("Login response lines: {}", lines.len());
+/// Reads one CRLF-terminated line, growing `buf` up to `LINE_CAP`.
+async fn read_line<S: AsyncRead + Unpin>(stream: &mut S, buf: &mut BytesMut) -> Result<Bytes> {
+    loop {
+        if let Some(pos) = memmem::find(buf, b"\r\n") {
+            let line = buf.split_to(pos + 2).freeze();
+            trace_received(&line);
+            return Ok(line);
+        }
 
-        // Basic status check: last tagged completion should be OK
-        if let Some(last) = lines.iter().rev().find(|l| l.starts_with(tag.as_bytes())) {
-            if !last.windows(3).any(|w| w == b" OK") {
-                anyhow::bail!("Login failed: {}", String::from_utf8_lossy(last));
+        // Check spare capacity before reading
+        if buf.remaining_mut() == 0 {
+            if buf.capacity() >= LINE_CAP {
+                anyhow::bail!(
+                    "IMAP response line exceeded maximum length of {} bytes",
+                    LINE_CAP
+                );
             }
+            let add = GROW_STEP.min(LINE_CAP - buf.capacity());
+            buf.reserve(add);
         }
 
-        Ok(Client::<AuthenticatedState> {
-            cmd_tx: self.cmd_tx,
-            unsol_rx: self.unsol_rx,
-            _state: PhantomData,
-        })
+        let n = stream
+            .read_buf(buf)
+            .await
+            .context("Failed to read data from IMAP server")?;
+        if n == 0 {
+            anyhow::bail!("IMAP server closed connection unexpectedly");
+        }
     }
 }
 
-impl Client<AuthenticatedState> {
-    pub async fn fetch(&mut self, mailbox: &str, id: u32) -> Result<Vec<Envelope>> {
-        // Select mailbox (simple approach; future: cache selected mailbox)
-        let sel_tag = next_tag();
-        let select_cmd = CommandBuilder::new(&sel_tag).select(mailbox).as_string();
-        let (sel_tx, sel_rx) = oneshot::channel::<Vec<Bytes>>();
-        self.cmd_tx
-            .send(CommandMessage {
-                tag: sel_tag.clone(),
-                command: select_cmd,
-                responder: sel_tx,
-            })
+/// Reads the server greeting, skipping up to `opts.greeting_skip_lines` non-IMAP lines.
+///
+/// Returns the capabilities from a `[CAPABILITY ...]` code in the greeting, if any.
+pub(super) async fn read_greeting<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    buf: &mut BytesMut,
+    opts: &Options,
+) -> Result<Option<Capabilities>> {
+    let mut skipped = 0usize;
+    loop {
+        let line = read_line(stream, buf)
             .await
-            .context("Failed to send SELECT command")?;
-        let sel_lines = sel_rx.await.context("SELECT timed out")?;
-        if let Some(last) = sel_lines
-            .iter()
-            .rev()
-            .find(|l| l.starts_with(sel_tag.as_bytes()))
-        {
-            if !last.windows(3).any(|w| w == b" OK") {
-                anyhow::bail!("SELECT failed: {}", String::from_utf8_lossy(last));
+            .context("Failed to read IMAP greeting")?;
+        match greeting::try_parse(&line) {
+            Ok(Some(_greeting)) => return Ok(capability::harvest_capabilities(&line)),
+            Ok(None) | Err(imap::parser::ParserError::Incomplete) => continue,
+            Err(_) if skipped < opts.greeting_skip_lines => {
+                skipped += 1;
+                tracing::warn!(
+                    line = %String::from_utf8_lossy(&line).trim_end(),
+                    skipped,
+                    "Skipping non-IMAP line before greeting"
+                );
             }
+            Err(e) => return Err(e).context("Failed to parse IMAP greeting"),
         }
+    }
+}
 
-        // Build FETCH 1:id ENVELOPE for subjects
-        let set = SequenceSet::new().add_range(SequenceBound::Number(1), SequenceBound::Number(id));
-        let fetch_tag = next_tag();
-        let fetch_cmd = CommandBuilder::new(&fetch_tag)
-            .fetch(set)
-            .add_item(imap::commands::FetchItem::Envelope)
-            .as_string();
-        let (tx, rx) = oneshot::channel::<Vec<Bytes>>();
-        self.cmd_tx
-            .send(CommandMessage {
-                tag: fetch_tag.clone(),
-                command: fetch_cmd,
-                responder: tx,
-            })
-            .await
-            .context("Failed to send FETCH command")?;
-        let lines = rx.await.context("FETCH timed out")?;
+/// Completes when `token` is cancelled; never, without a token.
+async fn cancelled(token: Option<&CancellationToken>) {
+    match token {
+        Some(token) => token.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
 
-        let mut joined = BytesMut::new();
-        for l in &lines {
-            joined.extend_from_slice(l);
-        }
-        let mut envelopes = Vec::new();
-        for (_num, data) in fetch::fetch_envelopes(&joined) {
-            if let FetchData::Envelope(env) = data {
-                envelopes.push(env);
-            }
-        }
+/// Completes `limit` after `since`; never, without a limit.
+async fn deadline(limit: Option<Duration>, since: Instant) {
+    match limit {
+        Some(limit) => tokio::time::sleep_until((since + limit).into()).await,
+        None => std::future::pending().await,
+    }
+}
 
-        Ok(envelopes)
+/// The `tracing` target of the lines logged with the `trace-wire` feature.
+#[cfg(feature = "trace-wire")]
+const WIRE_TARGET: &str = "mailux_imap::wire";
+
+/// Logs a command at TRACE level with the `trace-wire` feature, credentials redacted.
+fn trace_sent(command: &str) {
+    #[cfg(feature = "trace-wire")]
+    tracing::trace!(target: WIRE_TARGET, "C: {}", redact(command).trim_end());
+    #[cfg(not(feature = "trace-wire"))]
+    let _ = command;
+}
+
+/// Logs a line from the server at TRACE level with the `trace-wire` feature.
+fn trace_received(line: &[u8]) {
+    #[cfg(feature = "trace-wire")]
+    tracing::trace!(target: WIRE_TARGET, "S: {}", String::from_utf8_lossy(line).trim_end());
+    #[cfg(not(feature = "trace-wire"))]
+    let _ = line;
+}
+
+/// The text of an untagged BYE.
+fn server_bye(line: &[u8]) -> Option<String> {
+    if !line.get(..5)?.eq_ignore_ascii_case(b"* BYE") {
+        return None;
+    }
+    match parser::mailbox::parse_untagged(line, &mut KeywordInterner::new()).pop()? {
+        UntaggedResponse::Bye(text) => Some(text),
+        _ => None,
+    }
+}
+
+/// Best-effort LOGOUT on shutdown: waits up to `LOGOUT_GRACE` for the tagged reply, then
+/// closes the transport (sending TLS close_notify). Errors are only logged.
+async fn logout<S: Transport>(stream: &mut S, buf: &mut BytesMut, framer: &mut Framer) {
+    let tag = next_tag();
+    let command = CommandBuilder::new(&tag).logout().as_string();
+    let exchange = async {
+        write_command(stream, &command).await?;
+        loop {
+            while let Some(line) = framer.next(buf)? {
+                if is_tagged_completion(&line, &tag) {
+                    return Ok::<(), anyhow::Error>(());
+                }
+            }
+            framer.reserve(buf)?;
+            if stream.read_buf(buf).await? == 0 {
+                return Ok(());
+            }
+        }
+    };
+    match tokio::time::timeout(LOGOUT_GRACE, exchange).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::debug!("LOGOUT on cancellation failed: {:#}", e),
+        Err(_) => tracing::debug!("No LOGOUT reply before closing"),
+    }
+    if let Err(e) = stream.shutdown().await {
+        tracing::debug!("Failed to close IMAP transport: {}", e);
+    }
+}
+
+pub(super) async fn write_command<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    command: &str,
+) -> Result<()> {
+    trace_sent(command);
+    stream
+        .write_all(command.as_bytes())
+        .await
+        .with_context(|| format!("Failed to send IMAP command: {}", redact(command)))?;
+    stream
+        .flush()
+        .await
+        .with_context(|| format!("Failed to flush IMAP command: {}", redact(command)))?;
+    Ok(())
+}
+
+/// Writes a literal and the CRLF that ends its command line.
+pub(super) async fn write_literal<S: AsyncWrite + Unpin>(
+    stream: &mut S,
+    literal: &[u8],
+) -> Result<()> {
+    #[cfg(feature = "trace-wire")]
+    tracing::trace!(target: WIRE_TARGET, "C: <literal of {} octets>", literal.len());
+    stream
+        .write_all(literal)
+        .await
+        .context("Failed to send IMAP literal")?;
+    stream
+        .write_all(b"\r\n")
+        .await
+        .context("Failed to send IMAP literal")?;
+    stream
+        .flush()
+        .await
+        .context("Failed to flush IMAP literal")?;
+    Ok(())
+}
+
+fn completion_status(line: &Bytes, tag: &str) -> Option<Status> {
+    let rest = line.get(tag.len() + 1..)?;
+    let word = rest.split(|&b| b == b' ' || b == b'\r').next()?;
+    if word.eq_ignore_ascii_case(b"OK") {
+        Some(Status::Ok)
+    } else if word.eq_ignore_ascii_case(b"NO") {
+        Some(Status::No)
+    } else if word.eq_ignore_ascii_case(b"BAD") {
+        Some(Status::Bad)
+    } else {
+        None
+    }
+}
+
+/// Whether a tagged completion carries a response code saying the credentials, rather
+/// than the mechanism, were refused.
+fn credentials_rejected(line: &[u8]) -> bool {
+    let upper = line.to_ascii_uppercase();
+    [
+        &b"[AUTHENTICATIONFAILED]"[..],
+        b"[AUTHORIZATIONFAILED]",
+        b"[EXPIRED]",
+    ]
+    .iter()
+    .any(|code| memmem::find(&upper, code).is_some())
+}
+
+/// `UID FETCH` for `A0001 UID FETCH 1:* (FLAGS)`.
+fn command_name(command: &str) -> String {
+    let mut words = command.split_ascii_whitespace().skip(1);
+    let first = words.next().unwrap_or_default().to_ascii_uppercase();
+    match words.next() {
+        Some(second) if first == "UID" => format!("UID {}", second.to_ascii_uppercase()),
+        _ => first,
+    }
+}
+
+pub(super) fn is_tagged_completion(line: &Bytes, tag: &str) -> bool {
+    // Tagged completion is: <tag> SP (OK|NO|BAD) ... CRLF
+    if line.len() < tag.len() + 4 {
+        return false;
+    }
+    if &line[..tag.len()] != tag.as_bytes() {
+        return false;
+    }
+    if line.get(tag.len()) != Some(&b' ') {
+        return false;
+    }
+    // We accept any status for completion detection
+    true
+}
+
+/// Checks that the tagged completion for `tag` in `lines` is OK.
+pub(super) fn ensure_ok(lines: &[Bytes], tag: &str, what: &str) -> Result<()> {
+    if let Some(last) = lines.iter().rev().find(|l| is_tagged_completion(l, tag))
+        && !matches!(completion_status(last, tag), Some(Status::Ok))
+    {
+        let error = parser::mailbox::parse_completion_error(last, tag).unwrap_or_else(|| {
+            ImapError::CommandFailed(String::from_utf8_lossy(last).trim_end().to_string())
+        });
+        return Err(anyhow::Error::new(error).context(format!("{} failed", what)));
+    }
+    Ok(())
+}
+
+/// Why CREATE, DELETE or RENAME was refused, from the response code of the server's NO
+/// (RFC 5530). Refusals without one of these codes fail with a plain error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MailboxError {
+    /// The mailbox to create, or the new name of a rename, is taken.
+    AlreadyExists(String),
+    /// The mailbox to delete or rename does not exist.
+    NonExistent(String),
+}
+
+impl std::fmt::Display for MailboxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MailboxError::AlreadyExists(name) => write!(f, "mailbox {} already exists", name),
+            MailboxError::NonExistent(name) => write!(f, "mailbox {} does not exist", name),
+        }
+    }
+}
+
+impl std::error::Error for MailboxError {}
+
+fn default_fetch_profile() -> Vec<FetchItem> {
+    vec![FetchItem::Uid, FetchItem::Envelope]
+}
+
+pub(super) fn join_lines(lines: &[Bytes]) -> BytesMut {
+    let mut joined = BytesMut::new();
+    for l in lines {
+        joined.extend_from_slice(l);
+    }
+    joined
+}
+
+impl<State> Client<State> {
+    /// Queues a command on the connection and returns the receiver for its response lines.
+    async fn send_command(
+        &self,
+        tag: &str,
+        command: String,
+    ) -> Result<oneshot::Receiver<Response>> {
+        queue_command(&self.cmd_tx, tag, command).await
+    }
+
+    /// Runs a command to completion and checks that it finished with OK.
+    async fn run_command(&self, tag: &str, command: String, what: &str) -> Result<Vec<Bytes>> {
+        let rx = self
+            .send_command(tag, command)
+            .await
+            .with_context(|| format!("Failed to send {} command", what))?;
+        let lines = await_response(rx, what).await?;
+        ensure_ok(&lines, tag, what)?;
+        Ok(lines)
+    }
+
+    /// Logs out and closes the connection.
+    ///
+    /// Commands already queued on the connection, e.g. by an
+    /// [`AppendPipeline`](super::AppendPipeline), are answered first, since LOGOUT is
+    /// queued after them. See [`logout_all`](super::shutdown::logout_all) to stop many
+    /// connections with a deadline.
+    pub async fn logout(self) -> Result<()> {
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).logout().as_string();
+        self.run_command(&tag, cmd, "LOGOUT").await?;
+        Ok(())
+    }
+
+    /// Returns the capabilities the server currently advertises.
+    ///
+    /// Uses the list sent with the greeting or authentication response if there was one;
+    /// otherwise asks once and remembers the answer until the connection state changes.
+    pub async fn capabilities(&mut self) -> Result<Capabilities> {
+        if let Some(caps) = &self.capabilities {
+            return Ok(caps.clone());
+        }
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).capability().as_string();
+        let lines = self.run_command(&tag, cmd, "CAPABILITY").await?;
+        let caps = capability::parse_capabilities(&join_lines(&lines));
+        self.capabilities = Some(caps.clone());
+        Ok(caps)
+    }
+
+    /// The negotiated TLS protocol, cipher suite and server certificates, or `None` for a
+    /// connection that is not using TLS.
+    pub fn tls_info(&self) -> Option<&TlsInfo> {
+        self.tls_info.as_deref()
+    }
+
+    /// High-watermarks of this connection's read buffer and command queue so far.
+    pub fn buffer_stats(&self) -> BufferStats {
+        self.watermarks.snapshot()
+    }
+
+    /// Typed events parsed from the untagged responses received from now on, such as
+    /// new messages, expunges, flag changes, BYE and `[ALERT]` notices; see [`Events`].
+    pub fn events(&self) -> Events {
+        Events::new(self.unsol_rx.resubscribe())
+    }
+
+    /// Identifies the client to the server with ID (RFC 2971) and returns what the server
+    /// says about itself, keyed by lowercased field name.
+    ///
+    /// Some providers refuse mailbox access until an ID has been sent. `client_info` holds
+    /// pairs like `("name", "mailux")`; an empty slice sends `ID NIL`.
+    pub async fn id(
+        &mut self,
+        client_info: &[(&str, &str)],
+    ) -> Result<HashMap<String, Option<String>>> {
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).id(client_info).as_string();
+        let lines = self.run_command(&tag, cmd, "ID").await?;
+        Ok(id::parse_id(&join_lines(&lines)))
+    }
+
+    pub(super) fn command_sender(&self) -> mpsc::Sender<Request> {
+        self.cmd_tx.clone()
+    }
+
+    /// The same session in another typestate.
+    fn with_state<T>(self) -> Client<T> {
+        Client::<T> {
+            cmd_tx: self.cmd_tx,
+            unsol_rx: self.unsol_rx,
+            selected: self.selected,
+            keywords: self.keywords,
+            snapshot: self.snapshot,
+            capabilities: self.capabilities,
+            fetch_profile: self.fetch_profile,
+            watermarks: self.watermarks,
+            mailbox: self.mailbox,
+            preferred_auth: self.preferred_auth,
+            read_only: self.read_only,
+            tls_info: self.tls_info,
+            _state: PhantomData,
+        }
+    }
+
+    /// Moves the session into a `Client<T>` that shares the connection, for code that
+    /// only holds `&mut self` but has just selected a mailbox. Hand it back with
+    /// [`Client::restore`] before using `self` again.
+    pub(super) fn lend<T>(&mut self) -> Client<T> {
+        Client::<T> {
+            cmd_tx: self.cmd_tx.clone(),
+            unsol_rx: self.unsol_rx.resubscribe(),
+            selected: self.selected.take(),
+            keywords: std::mem::take(&mut self.keywords),
+            snapshot: self.snapshot,
+            capabilities: self.capabilities.take(),
+            fetch_profile: std::mem::take(&mut self.fetch_profile),
+            watermarks: self.watermarks.clone(),
+            mailbox: self.mailbox.clone(),
+            preferred_auth: std::mem::take(&mut self.preferred_auth),
+            read_only: self.read_only,
+            tls_info: self.tls_info.clone(),
+            _state: PhantomData,
+        }
+    }
+
+    /// Takes back what [`Client::lend`] moved out.
+    pub(super) fn restore<T>(&mut self, lent: Client<T>) {
+        self.selected = lent.selected;
+        self.keywords = lent.keywords;
+        self.snapshot = lent.snapshot;
+        self.capabilities = lent.capabilities;
+        self.fetch_profile = lent.fetch_profile;
+        self.preferred_auth = lent.preferred_auth;
+    }
+
+    /// Converts the client into a raw command channel, keeping the run loop (and any
+    /// authentication already performed) intact.
+    pub fn into_raw(self) -> RawClient {
+        RawClient {
+            cmd_tx: self.cmd_tx,
+            unsol_rx: self.unsol_rx,
+        }
+    }
+
+    /// Stops the run loop once outstanding commands complete and returns the underlying
+    /// transport, so custom protocol extensions can take over the connection.
+    pub async fn into_inner(self) -> Result<RawStream> {
+        let (tx, rx) = oneshot::channel();
+        self.cmd_tx
+            .send(Request::Detach(tx))
+            .await
+            .map_err(|_| anyhow::anyhow!("IMAP connection is closed"))?;
+        rx.await.context("IMAP connection closed before detaching")
+    }
+}
+
+impl RawClient {
+    /// Sends `command` (without tag or CRLF) and returns all lines up to its tagged completion.
+    pub async fn execute(&self, command: &str) -> Result<Vec<Bytes>> {
+        let tag = next_tag();
+        let rx = queue_command(&self.cmd_tx, &tag, format!("{} {}\r\n", tag, command)).await?;
+        match rx.await {
+            Ok(response) => Ok(response?),
+            Err(_) => anyhow::bail!("IMAP connection closed before the command completed"),
+        }
+    }
+
+    /// Every line received from the server, including responses to commands.
+    pub fn lines(&mut self) -> &mut broadcast::Receiver<Bytes> {
+        &mut self.unsol_rx
+    }
+
+    /// Detaches the underlying transport; see [`Client::into_inner`].
+    pub async fn into_inner(self) -> Result<RawStream> {
+        Client::<ConnectedState> {
+            cmd_tx: self.cmd_tx,
+            unsol_rx: self.unsol_rx,
+            selected: None,
+            keywords: KeywordInterner::new(),
+            snapshot: false,
+            capabilities: None,
+            fetch_profile: default_fetch_profile(),
+            watermarks: Arc::default(),
+            mailbox: Arc::default(),
+            preferred_auth: Vec::new(),
+            read_only: false,
+            tls_info: None,
+            _state: PhantomData,
+        }
+        .into_inner()
+        .await
+    }
+}
+
+pub(super) async fn queue_command(
+    cmd_tx: &mpsc::Sender<Request>,
+    tag: &str,
+    command: String,
+) -> Result<oneshot::Receiver<Response>> {
+    queue_literal_command(cmd_tx, tag, command, None).await
+}
+
+/// Like [`queue_command`], for a command line ending in a literal announcement.
+pub(super) async fn queue_literal_command(
+    cmd_tx: &mpsc::Sender<Request>,
+    tag: &str,
+    command: String,
+    literal: Option<Literal>,
+) -> Result<oneshot::Receiver<Response>> {
+    queue_message(cmd_tx, tag, command, literal, None).await
+}
+
+/// Like [`queue_command`], sending what `sink` asks for as it arrives instead of
+/// collecting it. The sink closes once the command completes.
+pub(super) async fn queue_streaming_command(
+    cmd_tx: &mpsc::Sender<Request>,
+    tag: &str,
+    command: String,
+    sink: Sink,
+) -> Result<oneshot::Receiver<Response>> {
+    queue_message(cmd_tx, tag, command, None, Some(sink)).await
+}
+
+async fn queue_message(
+    cmd_tx: &mpsc::Sender<Request>,
+    tag: &str,
+    command: String,
+    literal: Option<Literal>,
+    sink: Option<Sink>,
+) -> Result<oneshot::Receiver<Response>> {
+    let (tx, rx) = oneshot::channel::<Response>();
+    cmd_tx
+        .send(Request::Command(CommandMessage {
+            tag: tag.to_string(),
+            command,
+            literal,
+            sink,
+            queued_at: Instant::now(),
+            responder: tx,
+        }))
+        .await
+        .map_err(|_| anyhow::anyhow!("IMAP connection is closed"))?;
+    Ok(rx)
+}
+
+/// Waits for the lines of a queued command. A cancelled connection yields [`Cancelled`],
+/// a reconnect [`ConnectionLost`].
+pub(super) async fn await_response(
+    rx: oneshot::Receiver<Response>,
+    what: &str,
+) -> Result<Vec<Bytes>> {
+    match rx.await {
+        Ok(response) => Ok(response?),
+        Err(_) => anyhow::bail!("{} timed out", what),
+    }
+}
+
+impl Client<ConnectedState> {
+    #[tracing::instrument(skip(self, pass))]
+    pub async fn login(self, user: &str, pass: &str) -> Result<Client<AuthenticatedState>> {
+        tracing::info!("Attempting IMAP login");
+
+        let (tag, lines) = self.send_login(user, pass).await?;
+        ensure_ok(&lines, &tag, "Login")?;
+        tracing::debug!("Login response lines: {}", lines.len());
+
+        Ok(self.authenticated(&lines))
+    }
+
+    async fn send_login(&self, user: &str, pass: &str) -> Result<(String, Vec<Bytes>)> {
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .login()
+            .username(user)
+            .password(pass)
+            .as_string();
+        let rx = self
+            .send_command(&tag, cmd)
+            .await
+            .context("Failed to send Login command")?;
+        let lines = await_response(rx, "Login").await?;
+        Ok((tag, lines))
+    }
+
+    /// Authenticates with SASL PLAIN (RFC 4616), for servers that disable LOGIN.
+    ///
+    /// The credentials are sent in response to the server's continuation request, so this
+    /// should only be used over TLS.
+    #[tracing::instrument(skip(self, pass))]
+    pub async fn authenticate_plain(
+        self,
+        user: &str,
+        pass: &str,
+    ) -> Result<Client<AuthenticatedState>> {
+        tracing::info!("Attempting AUTHENTICATE PLAIN");
+
+        let (tag, lines) = self.send_authenticate_plain(user, pass).await?;
+        ensure_ok(&lines, &tag, "AUTHENTICATE")?;
+
+        tracing::info!("IMAP authentication successful");
+        Ok(self.authenticated(&lines))
+    }
+
+    async fn send_authenticate_plain(
+        &self,
+        user: &str,
+        pass: &str,
+    ) -> Result<(String, Vec<Bytes>)> {
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).authenticate("PLAIN").as_string();
+        let response = Literal::Synchronizing(Bytes::from(sasl::plain_response(user, pass)));
+        let rx = queue_literal_command(&self.cmd_tx, &tag, cmd, Some(response))
+            .await
+            .context("Failed to send AUTHENTICATE command")?;
+        let lines = await_response(rx, "AUTHENTICATE").await?;
+        Ok((tag, lines))
+    }
+
+    /// Authenticates with the first mechanism from
+    /// [`Builder::preferred_auth`](crate::async_impl::Builder::preferred_auth) that the
+    /// server offers (PLAIN, then LOGIN, by default) and reports which one succeeded.
+    ///
+    /// A failed mechanism moves on to the next one, except when the server says the
+    /// credentials themselves were wrong (`AUTHENTICATIONFAILED`, `AUTHORIZATIONFAILED`
+    /// or `EXPIRED`, RFC 5530): repeating them would only count towards an account
+    /// lockout.
+    #[tracing::instrument(skip(self, pass))]
+    pub async fn authenticate(
+        mut self,
+        user: &str,
+        pass: &str,
+    ) -> Result<(Client<AuthenticatedState>, AuthMechanism)> {
+        let caps = self.capabilities().await?;
+        let preferred = match self.preferred_auth.as_slice() {
+            [] => &[AuthMechanism::Plain, AuthMechanism::Login][..],
+            list => list,
+        };
+        let candidates: Vec<AuthMechanism> = preferred
+            .iter()
+            .copied()
+            .filter(|m| m.offered(&caps))
+            .collect();
+        if candidates.is_empty() {
+            anyhow::bail!(
+                "None of the preferred mechanisms {:?} is offered by the server",
+                preferred
+            );
+        }
+
+        let mut last_error = None;
+        for mechanism in candidates {
+            tracing::info!(?mechanism, "Attempting authentication");
+            let (tag, lines) = match mechanism {
+                AuthMechanism::Plain => self.send_authenticate_plain(user, pass).await?,
+                AuthMechanism::Login => self.send_login(user, pass).await?,
+            };
+            let Err(e) = ensure_ok(&lines, &tag, "Authentication") else {
+                tracing::info!(?mechanism, "IMAP authentication successful");
+                return Ok((self.authenticated(&lines), mechanism));
+            };
+            if lines.last().is_some_and(|l| credentials_rejected(l)) {
+                return Err(e.context(format!("{:?} authentication rejected", mechanism)));
+            }
+            tracing::warn!(
+                ?mechanism,
+                "Authentication failed, trying next mechanism: {:#}",
+                e
+            );
+            last_error = Some(e);
+        }
+        Err(last_error.expect("at least one mechanism was tried"))
+    }
+
+    /// Switches to the authenticated state, keeping any capabilities the server sent with
+    /// the authentication response; pre-login capabilities no longer apply (RFC 3501
+    /// section 6.2), so without them the list is fetched again on first use.
+    fn authenticated(self, response: &[Bytes]) -> Client<AuthenticatedState> {
+        Client::<AuthenticatedState> {
+            cmd_tx: self.cmd_tx,
+            unsol_rx: self.unsol_rx,
+            selected: None,
+            keywords: KeywordInterner::new(),
+            snapshot: false,
+            capabilities: capability::harvest_capabilities(&join_lines(response)),
+            fetch_profile: self.fetch_profile,
+            watermarks: self.watermarks,
+            mailbox: self.mailbox,
+            preferred_auth: Vec::new(),
+            read_only: self.read_only,
+            tls_info: self.tls_info,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S: LoggedIn> Client<S> {
+    /// Selects `mailbox`, moving the session to the selected state, and returns the state
+    /// the server reported for the mailbox.
+    ///
+    /// A [read-only](crate::async_impl::Builder::read_only) session sends EXAMINE instead.
+    /// From the selected state this switches to `mailbox`.
+    pub async fn select(
+        mut self,
+        mailbox: &str,
+    ) -> Result<(Client<SelectedState>, MailboxStatus)> {
+        let status = self.open(mailbox, false, None).await?;
+        Ok((self.with_state(), status))
+    }
+
+    /// Opens `mailbox` read-only (EXAMINE). Fetching bodies does not set `\Seen`.
+    ///
+    /// The returned session searches and fetches like a selected one, but has no STORE,
+    /// COPY, MOVE or EXPUNGE methods; [`Client::select`] switches it to read-write.
+    pub async fn examine(
+        mut self,
+        mailbox: &str,
+    ) -> Result<(Client<ExaminedState>, MailboxStatus)> {
+        let status = self.open(mailbox, true, None).await?;
+        Ok((self.with_state(), status))
+    }
+
+    /// Turns on server extensions (RFC 5161) and returns those the server enabled.
+    ///
+    /// Must run before any mailbox is selected.
+    pub async fn enable(&mut self, extensions: &[&str]) -> Result<Vec<String>> {
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).enable(extensions).as_string();
+        let lines = self.run_command(&tag, cmd, "ENABLE").await?;
+        Ok(capability::parse_enabled(&join_lines(&lines)))
+    }
+
+    /// Selects `mailbox` with QRESYNC (RFC 7162), resuming from the `uid_validity` and
+    /// `highest_modseq` saved after the previous sync.
+    ///
+    /// The returned status lists the UIDs expunged since then in
+    /// [`MailboxStatus::vanished`]; save its `uid_validity` and `highest_modseq` for the
+    /// next call. If UIDVALIDITY changed the server ignores the old state and the whole
+    /// mailbox must be resynchronized. Enables QRESYNC first, so it must be the first
+    /// mailbox selected on this connection.
+    pub async fn select_qresync(
+        mut self,
+        mailbox: &str,
+        uid_validity: u32,
+        highest_modseq: u64,
+    ) -> Result<(Client<SelectedState>, MailboxStatus)> {
+        if !self.capabilities().await?.contains(&Capability::Qresync) {
+            anyhow::bail!("Server does not support QRESYNC");
+        }
+        if self.selected.is_none()
+            && !self
+                .enable(&["QRESYNC"])
+                .await?
+                .iter()
+                .any(|e| e.eq_ignore_ascii_case("QRESYNC"))
+        {
+            anyhow::bail!("Server did not enable QRESYNC");
+        }
+        let status = self
+            .open(mailbox, false, Some((uid_validity, highest_modseq)))
+            .await?;
+        Ok((self.with_state(), status))
+    }
+
+    /// SELECTs or EXAMINEs `mailbox` without changing the session's type, for commands
+    /// that take a mailbox name.
+    pub(super) async fn open(
+        &mut self,
+        mailbox: &str,
+        read_only: bool,
+        qresync: Option<(u32, u64)>,
+    ) -> Result<MailboxStatus> {
+        if self.snapshot {
+            anyhow::bail!("Cannot open {} during a read-only snapshot", mailbox);
+        }
+        if self.selected.as_deref() != Some(mailbox) {
+            self.keywords = KeywordInterner::new();
+        }
+        // A failed SELECT leaves no mailbox selected.
+        self.selected = None;
+        let tag = next_tag();
+        let (mut cmd, what) = if read_only || self.read_only {
+            (CommandBuilder::new(&tag).examine(mailbox), "EXAMINE")
+        } else {
+            (CommandBuilder::new(&tag).select(mailbox), "SELECT")
+        };
+        if let Some((uid_validity, modseq)) = qresync {
+            cmd = cmd.qresync(uid_validity, modseq, None);
+        }
+        let cmd = cmd.as_string();
+        let lines = self.run_command(&tag, cmd, what).await?;
+        self.selected = Some(mailbox.to_string());
+        Ok(parser::mailbox::parse_select_response(
+            &join_lines(&lines),
+            &tag,
+        ))
+    }
+
+    /// The selected mailbox as of the last response read, or `None` if none is selected.
+    ///
+    /// Starts from what SELECT or EXAMINE reported and follows the untagged EXISTS,
+    /// RECENT, EXPUNGE, VANISHED and FLAGS responses (and response codes such as UIDNEXT)
+    /// the server sends afterwards, whichever command they arrive with. `unseen` is moved
+    /// down as earlier messages are expunged.
+    pub fn mailbox_state(&self) -> Option<MailboxStatus> {
+        self.mailbox.snapshot()
+    }
+
+    /// Whether this session was built with
+    /// [`Builder::read_only`](crate::async_impl::Builder::read_only).
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Fails on a read-only session; called first by every command that changes state.
+    fn ensure_writable(&self, what: &str) -> Result<()> {
+        if self.read_only {
+            anyhow::bail!("{} is not allowed on a read-only session", what);
+        }
+        Ok(())
+    }
+
+    /// Body fetches as sent: `.PEEK` forms on a read-only session, see [`FetchItem::peek`].
+    fn fetch_items_for(&self, items: Vec<FetchItem>) -> Vec<FetchItem> {
+        if self.read_only {
+            items.into_iter().map(FetchItem::peek).collect()
+        } else {
+            items
+        }
+    }
+
+    /// Selects `mailbox` unless it is already selected; an examined session EXAMINEs it.
+    pub(super) async fn ensure_selected(&mut self, mailbox: &str) -> Result<()> {
+        if self.selected.as_deref() != Some(mailbox) {
+            self.open(mailbox, S::EXAMINED, None).await?;
+        }
+        Ok(())
+    }
+
+    /// UNSELECTs (or CLOSEs) the selected mailbox; see [`Client::unselect`].
+    async fn leave(&mut self) -> Result<()> {
+        if self.selected.is_none() {
+            anyhow::bail!("UNSELECT requires a selected mailbox");
+        }
+        let caps = self.capabilities().await?;
+        let tag = next_tag();
+        let (cmd, what) = if caps.contains(&Capability::Unselect) {
+            (CommandBuilder::new(&tag).unselect().as_string(), "UNSELECT")
+        } else {
+            (CommandBuilder::new(&tag).close().as_string(), "CLOSE")
+        };
+        self.run_command(&tag, cmd, what).await?;
+        self.selected = None;
+        Ok(())
+    }
+
+    /// EXAMINEs `mailbox`, runs `read` against it, then unselects it.
+    ///
+    /// Because the mailbox is read-only, nothing `read` fetches changes its flags; opening
+    /// any other mailbox from inside `read` fails. The mailbox is unselected even if `read`
+    /// returns an error.
+    pub async fn examine_then_fetch<T, F>(&mut self, mailbox: &str, read: F) -> Result<T>
+    where
+        F: AsyncFnOnce(&mut Client<ExaminedState>) -> Result<T>,
+    {
+        self.open(mailbox, true, None).await?;
+        let mut selected = self.lend::<ExaminedState>();
+        selected.snapshot = true;
+        let result = read(&mut selected).await;
+        selected.snapshot = false;
+        self.restore(selected);
+        let unselected = self.leave().await;
+        let value = result?;
+        unselected?;
+        Ok(value)
+    }
+
+    /// Sets the items fetched by [`Client::fetch`], [`Client::fetch_messages`] and
+    /// [`Client::messages`], e.g. `UID FLAGS ENVELOPE RFC822.SIZE`. Defaults to UID and
+    /// ENVELOPE.
+    pub fn set_fetch_profile(&mut self, items: Vec<FetchItem>) {
+        self.fetch_profile = items;
+    }
+
+    pub fn fetch_profile(&self) -> &[FetchItem] {
+        &self.fetch_profile
+    }
+
+    /// Fetches the envelopes of the messages in `set` (sequence numbers) in `mailbox`, along
+    /// with the rest of the fetch profile (ENVELOPE is always requested).
+    ///
+    /// `set` is anything that converts into a [`SequenceSet`]: a number, a range such as
+    /// `1..=10` or a list such as `&[1, 2, 4, 9]`.
+    pub async fn fetch(
+        &mut self,
+        mailbox: &str,
+        set: impl Into<SequenceSet>,
+    ) -> Result<Vec<Envelope>> {
+        self.ensure_selected(mailbox).await?;
+
+        let set = set.into();
+        let mut items = self.fetch_items_for(self.fetch_profile.clone());
+        if !items.iter().any(|i| matches!(i, FetchItem::Envelope)) {
+            items.push(FetchItem::Envelope);
+        }
+        let fetch_tag = next_tag();
+        let fetch_cmd = items
+            .into_iter()
+            .fold(CommandBuilder::new(&fetch_tag).fetch(set), |b, item| {
+                b.add_item(item)
+            })
+            .as_string();
+        let rx = self
+            .send_command(&fetch_tag, fetch_cmd)
+            .await
+            .context("Failed to send FETCH command")?;
+        let lines = await_response(rx, "FETCH").await?;
+
+        // Other profile items may carry literals, so parse whole FETCH responses.
+        let joined = join_lines(&lines);
+        let mut envelopes = Vec::new();
+        for (_num, items) in fetch::parse_fetch_responses_with(&joined, &mut self.keywords) {
+            envelopes.extend(items.into_iter().filter_map(|item| match item {
+                FetchData::Envelope(env) => Some(env),
+                _ => None,
+            }));
+        }
+
+        Ok(envelopes)
+    }
+
+    /// Fetches an [`EnvelopeSummary`] for each message in `set` (sequence numbers).
+    pub async fn fetch_summaries(
+        &mut self,
+        mailbox: &str,
+        set: impl Into<SequenceSet>,
+    ) -> Result<Vec<EnvelopeSummary>> {
+        self.ensure_selected(mailbox).await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .fetch(set.into())
+            .add_item(FetchItem::Uid)
+            .add_item(FetchItem::InternalDate)
+            .add_item(FetchItem::Rfc822Size)
+            .add_item(FetchItem::Flags)
+            .add_item(FetchItem::Envelope)
+            .as_string();
+        let lines = self.run_command(&tag, cmd, "FETCH").await?;
+        Ok(fetch::parse_envelope_summaries(&join_lines(&lines)))
+    }
+
+    /// Fetches the flags of each message in `set` (sequence numbers), keyed by UID.
+    ///
+    /// Keywords are interned per mailbox, so repeated fetches share their allocations.
+    pub async fn fetch_flags(
+        &mut self,
+        mailbox: &str,
+        set: impl Into<SequenceSet>,
+    ) -> Result<Vec<(Uid, MessageFlags)>> {
+        self.ensure_selected(mailbox).await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .fetch(set.into())
+            .add_item(FetchItem::Uid)
+            .add_item(FetchItem::Flags)
+            .as_string();
+        let lines = self.run_command(&tag, cmd, "FETCH").await?;
+        let mut res = Vec::new();
+        for (_seq, items) in
+            fetch::parse_fetch_responses_with(&join_lines(&lines), &mut self.keywords)
+        {
+            let mut uid = None;
+            let mut flags = MessageFlags::default();
+            for item in items {
+                match item {
+                    FetchData::Uid(u) => uid = Some(u),
+                    FetchData::Flags(f) => flags = f,
+                    _ => {}
+                }
+            }
+            if let Some(uid) = uid {
+                res.push((uid, flags));
+            }
+        }
+        Ok(res)
+    }
+
+    pub(super) async fn run_search(
+        &mut self,
+        uid: bool,
+        keys: Vec<SearchKey>,
+    ) -> Result<Vec<u32>> {
+        let (_tag, lines) = self.search_lines(uid, keys, None).await?;
+        Ok(search::parse_search(&join_lines(&lines)))
+    }
+
+    async fn run_esearch(
+        &mut self,
+        uid: bool,
+        keys: Vec<SearchKey>,
+        returns: Vec<SearchReturn>,
+    ) -> Result<EsearchResult> {
+        if !self.capabilities().await?.contains(&Capability::Esearch) {
+            anyhow::bail!("Server does not support ESEARCH");
+        }
+        let (tag, lines) = self.search_lines(uid, keys, Some(returns)).await?;
+        Ok(search::parse_esearch(&join_lines(&lines), &tag))
+    }
+
+    async fn search_lines(
+        &mut self,
+        uid: bool,
+        keys: Vec<SearchKey>,
+        returns: Option<Vec<SearchReturn>>,
+    ) -> Result<(String, Vec<Bytes>)> {
+        if self.selected.is_none() {
+            anyhow::bail!("SEARCH requires a selected mailbox");
+        }
+        self.require_search_keys(&keys).await?;
+        let literal_plus = self.search_literals(&keys).await?;
+        let keys = if keys.is_empty() {
+            vec![SearchKey::All]
+        } else {
+            keys
+        };
+        let tag = next_tag();
+        let mut builder = if uid {
+            CommandBuilder::new(&tag).uid().search()
+        } else {
+            CommandBuilder::new(&tag).search()
+        };
+        if let Some(returns) = returns {
+            builder = builder.returning(returns);
+        }
+        if literal_plus {
+            builder = builder.literal_plus();
+        }
+        let builder = builder.keys(keys);
+        let what = if uid { "UID SEARCH" } else { "SEARCH" };
+        let literal = builder.literal_bytes();
+        let lines = self
+            .run_search_command(&tag, builder.as_string(), literal, literal_plus, what)
+            .await?;
+        Ok((tag, lines))
+    }
+
+    /// Fails if a string value in `keys` cannot be sent, or if more than one must go as a
+    /// literal without LITERAL+. Returns whether literals are sent non-synchronizing.
+    async fn search_literals(&mut self, keys: &[SearchKey]) -> Result<bool> {
+        if let Some(value) = keys.iter().find_map(SearchKey::unsendable_value) {
+            anyhow::bail!("Search value {:?} cannot be sent to the server", value);
+        }
+        let literals: usize = keys.iter().map(SearchKey::literals).sum();
+        if literals == 0 {
+            return Ok(false);
+        }
+        let literal_plus = self
+            .capabilities()
+            .await?
+            .contains(&Capability::LiteralPlus);
+        if literals > 1 && !literal_plus {
+            anyhow::bail!(
+                "More than one search value with a line break or non-ASCII text needs LITERAL+"
+            );
+        }
+        Ok(literal_plus)
+    }
+
+    /// Runs a SEARCH or SORT command whose literal data, if any, follows `command`.
+    async fn run_search_command(
+        &self,
+        tag: &str,
+        command: String,
+        literal: Option<Vec<u8>>,
+        literal_plus: bool,
+        what: &str,
+    ) -> Result<Vec<Bytes>> {
+        let literal = literal.map(|b| {
+            if literal_plus {
+                Literal::NonSynchronizing(Bytes::from(b))
+            } else {
+                Literal::Synchronizing(Bytes::from(b))
+            }
+        });
+        let rx = queue_literal_command(&self.cmd_tx, tag, command, literal)
+            .await
+            .with_context(|| format!("Failed to send {} command", what))?;
+        let lines = await_response(rx, what).await?;
+        ensure_ok(&lines, tag, what)?;
+        Ok(lines)
+    }
+
+    /// Fails if a key needs an extension the server does not advertise.
+    async fn require_search_keys(&mut self, keys: &[SearchKey]) -> Result<()> {
+        let mut required = keys
+            .iter()
+            .filter_map(SearchKey::required_capability)
+            .peekable();
+        if required.peek().is_none() {
+            return Ok(());
+        }
+        let caps = self.capabilities().await?;
+        for capability in required {
+            if !caps.contains(&capability) {
+                anyhow::bail!("Server does not support {}", capability);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports how the server compares strings in SEARCH and SORT (RFC 5255).
+    ///
+    /// Without an I18NLEVEL capability results are octet-wise only, and applications that
+    /// need locale-aware ordering should sort client-side.
+    pub async fn collation(&mut self) -> Result<Collation> {
+        let caps = self.capabilities().await?;
+        Ok(Collation::from_capabilities(caps.iter()))
+    }
+
+    /// Appends a message to `mailbox` and returns its UID, if the server reports it with
+    /// APPENDUID (UIDPLUS, RFC 4315).
+    ///
+    /// Fails with [`MessageTooLarge`](super::MessageTooLarge) before sending anything if `body` is over the
+    /// server's [`append_limit`](Self::append_limit).
+    ///
+    /// `date` sets the internal date; the server's current time is used otherwise.
+    pub async fn append(
+        &mut self,
+        mailbox: &str,
+        flags: Vec<Flag>,
+        date: Option<DateTime>,
+        body: &[u8],
+    ) -> Result<Option<Uid>> {
+        self.append_literal(mailbox, flags, date, body, false).await
+    }
+
+    /// The largest message the server accepts for APPEND, if it advertises a server-wide
+    /// APPENDLIMIT (RFC 7889).
+    pub async fn append_limit(&mut self) -> Result<Option<u64>> {
+        Ok(self.capabilities().await?.append_limit())
+    }
+
+    /// Like [`append`](Self::append), but sends `body` as a `literal8` (BINARY, RFC 3516),
+    /// so it may hold raw 8-bit or binary parts without a content transfer encoding.
+    pub async fn append_binary(
+        &mut self,
+        mailbox: &str,
+        flags: Vec<Flag>,
+        date: Option<DateTime>,
+        body: &[u8],
+    ) -> Result<Option<Uid>> {
+        if !self.capabilities().await?.contains(&Capability::Binary) {
+            anyhow::bail!("Server does not support BINARY");
+        }
+        self.append_literal(mailbox, flags, date, body, true).await
+    }
+
+    async fn append_literal(
+        &mut self,
+        mailbox: &str,
+        flags: Vec<Flag>,
+        date: Option<DateTime>,
+        body: &[u8],
+        binary: bool,
+    ) -> Result<Option<Uid>> {
+        self.ensure_writable("APPEND")?;
+        check_append_limit(body, self.append_limit().await?)?;
+        let tag = next_tag();
+        let mut builder = CommandBuilder::new(&tag)
+            .append(mailbox)
+            .flags(flags)
+            .literal(body.to_vec());
+        if let Some(date) = date {
+            builder = builder.internal_date(date);
+        }
+        if binary {
+            builder = builder.binary();
+        }
+        let literal = builder
+            .literal_bytes()
+            .map(|b| Literal::Synchronizing(Bytes::copy_from_slice(b)));
+        let rx = queue_literal_command(&self.cmd_tx, &tag, builder.as_string(), literal)
+            .await
+            .context("Failed to send APPEND command")?;
+        let lines = await_response(rx, "APPEND").await?;
+        ensure_ok(&lines, &tag, "APPEND")?;
+        Ok(parser::mailbox::parse_append_uid(&join_lines(&lines), &tag)
+            .and_then(|(_, uids)| uids.first().copied()))
+    }
+
+    /// Appends a message the server assembles from `parts` (CATENATE, RFC 4469) and returns
+    /// its UID if the server reports APPENDUID.
+    ///
+    /// URL parts reference messages or sections already on the server, see
+    /// [`CatenatePart::message`], so forwarding or building a draft does not download and
+    /// re-upload their content. More than one TEXT part needs LITERAL+.
+    pub async fn append_catenate(
+        &mut self,
+        mailbox: &str,
+        flags: Vec<Flag>,
+        date: Option<DateTime>,
+        parts: Vec<CatenatePart>,
+    ) -> Result<Option<Uid>> {
+        self.ensure_writable("APPEND")?;
+        let caps = self.capabilities().await?;
+        if !caps.contains(&Capability::Catenate) {
+            anyhow::bail!("Server does not support CATENATE");
+        }
+        let literal_plus = caps.contains(&Capability::LiteralPlus);
+        let texts = parts
+            .iter()
+            .filter(|p| matches!(p, CatenatePart::Text(_)))
+            .count();
+        if texts > 1 && !literal_plus {
+            anyhow::bail!("CATENATE with more than one TEXT part needs LITERAL+");
+        }
+
+        let tag = next_tag();
+        let mut builder = parts
+            .into_iter()
+            .fold(CommandBuilder::new(&tag).catenate(mailbox), |b, part| {
+                b.part(part)
+            })
+            .flags(flags);
+        if let Some(date) = date {
+            builder = builder.internal_date(date);
+        }
+        if literal_plus {
+            builder = builder.literal_plus();
+        }
+        let literal = builder.literal_bytes().map(|b| {
+            if literal_plus {
+                Literal::NonSynchronizing(Bytes::from(b))
+            } else {
+                Literal::Synchronizing(Bytes::from(b))
+            }
+        });
+        let rx = queue_literal_command(&self.cmd_tx, &tag, builder.as_string(), literal)
+            .await
+            .context("Failed to send APPEND command")?;
+        let lines = await_response(rx, "APPEND").await?;
+        ensure_ok(&lines, &tag, "APPEND")?;
+        Ok(parser::mailbox::parse_append_uid(&join_lines(&lines), &tag)
+            .and_then(|(_, uids)| uids.first().copied()))
+    }
+
+    /// Lists the mailboxes matching `pattern` under `reference` (`*` and `%` wildcards).
+    pub async fn list(&mut self, reference: &str, pattern: &str) -> Result<Vec<ListEntry>> {
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .list(reference, pattern)
+            .as_string();
+        let lines = self.run_command(&tag, cmd, "LIST").await?;
+        Ok(parser::mailbox::parse_list(&join_lines(&lines)))
+    }
+
+    /// Like [`Client::list`], with [`ListEntry::is_subscribed`] set for every entry.
+    ///
+    /// Uses `RETURN (SUBSCRIBED)` (LIST-EXTENDED, RFC 5258) when available; otherwise
+    /// matches the LIST output against LSUB.
+    pub async fn list_with_subscriptions(
+        &mut self,
+        reference: &str,
+        pattern: &str,
+    ) -> Result<Vec<ListEntry>> {
+        if self.capabilities().await?.contains(&Capability::ListExtended) {
+            let tag = next_tag();
+            let cmd = CommandBuilder::new(&tag)
+                .list(reference, pattern)
+                .return_option("SUBSCRIBED")
+                .as_string();
+            let lines = self.run_command(&tag, cmd, "LIST").await?;
+            return Ok(parser::mailbox::parse_list(&join_lines(&lines)));
+        }
+        let subscribed = self.lsub(reference, pattern).await?;
+        let mut entries = self.list(reference, pattern).await?;
+        for entry in &mut entries {
+            if !entry.is_subscribed() && subscribed.iter().any(|s| s.name == entry.name) {
+                entry.attributes.push("\\Subscribed".to_string());
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Lists only the subscribed mailboxes matching `pattern`, using the SUBSCRIBED
+    /// selection option when LIST-EXTENDED is available and LSUB otherwise.
+    ///
+    /// With LIST-EXTENDED, subscribed mailboxes that no longer exist are included with the
+    /// `\NonExistent` attribute.
+    pub async fn list_subscribed(
+        &mut self,
+        reference: &str,
+        pattern: &str,
+    ) -> Result<Vec<ListEntry>> {
+        if self.capabilities().await?.contains(&Capability::ListExtended) {
+            let tag = next_tag();
+            let cmd = CommandBuilder::new(&tag)
+                .list(reference, pattern)
+                .select_option("SUBSCRIBED")
+                .as_string();
+            let lines = self.run_command(&tag, cmd, "LIST").await?;
+            return Ok(parser::mailbox::parse_list(&join_lines(&lines)));
+        }
+        self.lsub(reference, pattern).await
+    }
+
+    /// Lists the subscribed mailboxes matching `pattern` (LSUB), each marked
+    /// `\Subscribed`.
+    ///
+    /// Unlike [`Client::list_subscribed`] this always sends LSUB, which servers may drop
+    /// in IMAP4rev2.
+    pub async fn lsub(&mut self, reference: &str, pattern: &str) -> Result<Vec<ListEntry>> {
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .lsub(reference, pattern)
+            .as_string();
+        let lines = self.run_command(&tag, cmd, "LSUB").await?;
+        let mut entries = parser::mailbox::parse_list(&join_lines(&lines));
+        for entry in &mut entries {
+            if !entry.is_subscribed() {
+                entry.attributes.push("\\Subscribed".to_string());
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Lists only the mailboxes with a SPECIAL-USE attribute (RFC 6154) such as `\Sent`;
+    /// [`ListEntry::special_use`] gives each one's role.
+    ///
+    /// Uses the SPECIAL-USE selection option when the server has it and filters a full
+    /// LIST otherwise. Servers without the attributes list nothing here; see
+    /// [`Client::mailbox_roles`] for a name-based fallback.
+    pub async fn list_special_use(&mut self) -> Result<Vec<ListEntry>> {
+        let caps = self.capabilities().await?;
+        if caps.contains(&Capability::SpecialUse) && caps.contains(&Capability::ListExtended) {
+            let tag = next_tag();
+            let cmd = CommandBuilder::new(&tag)
+                .list("", "*")
+                .select_option("SPECIAL-USE")
+                .as_string();
+            let lines = self.run_command(&tag, cmd, "LIST").await?;
+            return Ok(parser::mailbox::parse_list(&join_lines(&lines)));
+        }
+        let mut entries = self.list("", "*").await?;
+        entries.retain(|e| e.special_use().is_some());
+        Ok(entries)
+    }
+
+    /// Asks for the STATUS `items` of `mailbox` without selecting it.
+    ///
+    /// Only the items asked for are set in the result. STATUS on the selected mailbox
+    /// works but servers may answer it slowly; [`Client::mailbox_state`] has those
+    /// counters already.
+    pub async fn status(
+        &mut self,
+        mailbox: &str,
+        items: &[StatusItem],
+    ) -> Result<MailboxStatusSummary> {
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .status(mailbox, items.to_vec())
+            .as_string();
+        let lines = self.run_command(&tag, cmd, "STATUS").await?;
+        parser::mailbox::parse_status_responses(&join_lines(&lines))
+            .into_iter()
+            .find(|status| status.mailbox == mailbox)
+            .with_context(|| format!("Server sent no STATUS response for {}", mailbox))
+    }
+
+    /// Lists the mailboxes matching `pattern` with the STATUS `items` of each, in one
+    /// round trip when the server supports LIST-STATUS (RFC 5819).
+    ///
+    /// Otherwise STATUS is sent for each selectable mailbox in turn. Mailboxes that cannot
+    /// be selected have no status.
+    pub async fn list_with_status(
+        &mut self,
+        reference: &str,
+        pattern: &str,
+        items: &[StatusItem],
+    ) -> Result<Vec<(ListEntry, Option<MailboxStatusSummary>)>> {
+        if self.capabilities().await?.contains(&Capability::ListStatus) {
+            let tag = next_tag();
+            let cmd = CommandBuilder::new(&tag)
+                .list(reference, pattern)
+                .return_status(items)
+                .as_string();
+            let lines = self.run_command(&tag, cmd, "LIST").await?;
+            let buf = join_lines(&lines);
+            let mut statuses = parser::mailbox::parse_status_responses(&buf);
+            return Ok(parser::mailbox::parse_list(&buf)
+                .into_iter()
+                .map(|entry| {
+                    let status = statuses
+                        .iter()
+                        .position(|s| s.mailbox == entry.name)
+                        .map(|i| statuses.swap_remove(i));
+                    (entry, status)
+                })
+                .collect());
+        }
+
+        let mut result = Vec::new();
+        for entry in self.list(reference, pattern).await? {
+            if entry.has_attribute("\\Noselect") || entry.has_attribute("\\NonExistent") {
+                result.push((entry, None));
+                continue;
+            }
+            let tag = next_tag();
+            let cmd = CommandBuilder::new(&tag)
+                .status(&entry.name, items.to_vec())
+                .as_string();
+            let lines = self.run_command(&tag, cmd, "STATUS").await?;
+            let status = parser::mailbox::parse_status_responses(&join_lines(&lines))
+                .into_iter()
+                .next();
+            result.push((entry, status));
+        }
+        Ok(result)
+    }
+
+    /// Finds the mailbox used for each role, from SPECIAL-USE attributes or, on servers
+    /// without them, from well-known localized names such as `Gesendet`.
+    pub async fn mailbox_roles(&mut self) -> Result<Vec<(MailboxRole, String)>> {
+        let entries = self.list("", "*").await?;
+        Ok(special_use::resolve_roles(&entries))
+    }
+
+    /// The mailbox used for `role`, if any; see [`Client::mailbox_roles`].
+    pub async fn mailbox_for_role(&mut self, role: MailboxRole) -> Result<Option<String>> {
+        Ok(self
+            .mailbox_roles()
+            .await?
+            .into_iter()
+            .find_map(|(r, name)| (r == role).then_some(name)))
+    }
+
+    /// Creates `mailbox`.
+    ///
+    /// Fails with [`MailboxError::AlreadyExists`] if the server says the name is taken.
+    pub async fn create(&mut self, mailbox: &str) -> Result<()> {
+        self.ensure_writable("CREATE")?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).create(mailbox).as_string();
+        self.run_mailbox_command(&tag, cmd, "CREATE", mailbox, mailbox)
+            .await
+    }
+
+    /// Deletes `mailbox` and the messages in it.
+    ///
+    /// Fails with [`MailboxError::NonExistent`] if the server says there is no such
+    /// mailbox.
+    pub async fn delete(&mut self, mailbox: &str) -> Result<()> {
+        self.ensure_writable("DELETE")?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).delete(mailbox).as_string();
+        self.run_mailbox_command(&tag, cmd, "DELETE", mailbox, mailbox)
+            .await
+    }
+
+    /// Renames `from` to `to`. Renaming INBOX moves its messages to `to` and leaves it
+    /// empty.
+    ///
+    /// Fails with [`MailboxError::NonExistent`] for a missing `from` and
+    /// [`MailboxError::AlreadyExists`] for a taken `to`.
+    pub async fn rename(&mut self, from: &str, to: &str) -> Result<()> {
+        self.ensure_writable("RENAME")?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).rename(from, to).as_string();
+        self.run_mailbox_command(&tag, cmd, "RENAME", from, to)
+            .await
+    }
+
+    /// Runs a command on the mailbox `existing`, to be created or renamed as `new`,
+    /// turning ALREADYEXISTS and NONEXISTENT refusals into [`MailboxError`].
+    async fn run_mailbox_command(
+        &self,
+        tag: &str,
+        command: String,
+        what: &str,
+        existing: &str,
+        new: &str,
+    ) -> Result<()> {
+        let rx = self
+            .send_command(tag, command)
+            .await
+            .with_context(|| format!("Failed to send {} command", what))?;
+        let lines = await_response(rx, what).await?;
+        let Err(refused) = ensure_ok(&lines, tag, what) else {
+            return Ok(());
+        };
+        match refused.downcast_ref::<ImapError>().and_then(ImapError::code) {
+            Some(ResponseCode::AlreadyExists) => {
+                Err(MailboxError::AlreadyExists(new.to_string()).into())
+            }
+            Some(ResponseCode::NonExistent) => {
+                Err(MailboxError::NonExistent(existing.to_string()).into())
+            }
+            _ => Err(refused),
+        }
+    }
+
+    /// Adds `mailbox` to the subscription list; the mailbox need not exist.
+    pub async fn subscribe(&mut self, mailbox: &str) -> Result<()> {
+        self.ensure_writable("SUBSCRIBE")?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).subscribe(mailbox).as_string();
+        self.run_command(&tag, cmd, "SUBSCRIBE").await?;
+        Ok(())
+    }
+
+    /// Removes `mailbox` from the subscription list.
+    pub async fn unsubscribe(&mut self, mailbox: &str) -> Result<()> {
+        self.ensure_writable("UNSUBSCRIBE")?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).unsubscribe(mailbox).as_string();
+        self.run_command(&tag, cmd, "UNSUBSCRIBE").await?;
+        Ok(())
+    }
+
+    /// Lists who has which rights on `mailbox` (GETACL, RFC 4314).
+    ///
+    /// Requires the administer (`a`) right on the mailbox.
+    pub async fn get_acl(&mut self, mailbox: &str) -> Result<Vec<AclEntry>> {
+        self.require_acl().await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).getacl(mailbox).as_string();
+        let lines = self.run_command(&tag, cmd, "GETACL").await?;
+        Ok(acl::parse_acl(&join_lines(&lines)))
+    }
+
+    /// Replaces, extends or reduces the rights of `identifier` on `mailbox`.
+    pub async fn set_acl(
+        &mut self,
+        mailbox: &str,
+        identifier: &str,
+        change: AclChange,
+    ) -> Result<()> {
+        self.ensure_writable("SETACL")?;
+        self.require_acl().await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .setacl(mailbox, identifier, change)
+            .as_string();
+        self.run_command(&tag, cmd, "SETACL").await?;
+        Ok(())
+    }
+
+    /// Removes every right `identifier` has on `mailbox`.
+    pub async fn delete_acl(&mut self, mailbox: &str, identifier: &str) -> Result<()> {
+        self.ensure_writable("DELETEACL")?;
+        self.require_acl().await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .deleteacl(mailbox, identifier)
+            .as_string();
+        self.run_command(&tag, cmd, "DELETEACL").await?;
+        Ok(())
+    }
+
+    /// The rights the server allows granting to `identifier` on `mailbox`.
+    pub async fn list_rights(&mut self, mailbox: &str, identifier: &str) -> Result<ListRights> {
+        self.require_acl().await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .listrights(mailbox, identifier)
+            .as_string();
+        let lines = self.run_command(&tag, cmd, "LISTRIGHTS").await?;
+        acl::parse_listrights(&join_lines(&lines)).context("No LISTRIGHTS response")
+    }
+
+    /// The logged-in user's rights on `mailbox`.
+    pub async fn my_rights(&mut self, mailbox: &str) -> Result<Rights> {
+        self.require_acl().await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).myrights(mailbox).as_string();
+        let lines = self.run_command(&tag, cmd, "MYRIGHTS").await?;
+        acl::parse_myrights(&join_lines(&lines)).context("No MYRIGHTS response")
+    }
+
+    async fn require_acl(&mut self) -> Result<()> {
+        if !self.capabilities().await?.contains(&Capability::Acl) {
+            anyhow::bail!("Server does not support ACL");
+        }
+        Ok(())
+    }
+
+    /// The quota roots `mailbox` counts against and their usage and limits (QUOTA,
+    /// RFC 9208).
+    pub async fn quota_root(&mut self, mailbox: &str) -> Result<QuotaRoot> {
+        self.require_quota().await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).getquotaroot(mailbox).as_string();
+        let lines = self.run_command(&tag, cmd, "GETQUOTAROOT").await?;
+        Ok(quota::parse_quota_root(&join_lines(&lines)))
+    }
+
+    /// Usage and limits of the quota root `root`, as named by [`Client::quota_root`].
+    pub async fn quota(&mut self, root: &str) -> Result<Quota> {
+        self.require_quota().await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).getquota(root).as_string();
+        let lines = self.run_command(&tag, cmd, "GETQUOTA").await?;
+        quota::parse_quotas(&join_lines(&lines))
+            .into_iter()
+            .next()
+            .with_context(|| format!("No QUOTA response for root {:?}", root))
+    }
+
+    /// Sets the limits of `root`, e.g. `&[("STORAGE", 512)]`, and returns the new quota.
+    ///
+    /// Usually reserved for administrators.
+    pub async fn set_quota(&mut self, root: &str, limits: &[(&str, u64)]) -> Result<Quota> {
+        self.ensure_writable("SETQUOTA")?;
+        self.require_quota().await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).setquota(root, limits).as_string();
+        let lines = self.run_command(&tag, cmd, "SETQUOTA").await?;
+        Ok(quota::parse_quotas(&join_lines(&lines))
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| Quota {
+                root: root.to_string(),
+                resources: Vec::new(),
+            }))
+    }
+
+    async fn require_quota(&mut self) -> Result<()> {
+        if !self.capabilities().await?.contains(&Capability::Quota) {
+            anyhow::bail!("Server does not support QUOTA");
+        }
+        Ok(())
+    }
+
+    pub(super) async fn run_fetch(
+        &mut self,
+        uid: bool,
+        set: SequenceSet,
+        items: Vec<FetchItem>,
+    ) -> Result<Vec<(Seq, Vec<FetchData>)>> {
+        let what = if uid { "UID FETCH" } else { "FETCH" };
+        let (tag, cmd) = self.fetch_command(uid, set, items)?;
+        let lines = self.run_command(&tag, cmd, what).await?;
+        Ok(self.parse_fetch(&join_lines(&lines)))
+    }
+
+    /// The tag and command line of a FETCH (or UID FETCH) of `items` for `set`.
+    pub(super) fn fetch_command(
+        &self,
+        uid: bool,
+        set: SequenceSet,
+        items: Vec<FetchItem>,
+    ) -> Result<(String, String)> {
+        if self.selected.is_none() {
+            let what = if uid { "UID FETCH" } else { "FETCH" };
+            anyhow::bail!("{} requires a selected mailbox", what);
+        }
+        let tag = next_tag();
+        let builder = if uid {
+            CommandBuilder::new(&tag).uid().fetch(set)
+        } else {
+            CommandBuilder::new(&tag).fetch(set)
+        };
+        let cmd = self
+            .fetch_items_for(items)
+            .into_iter()
+            .fold(builder, |b, item| b.add_item(item))
+            .as_string();
+        Ok((tag, cmd))
+    }
+
+    /// Parses FETCH responses, interning keywords with the session's other fetches.
+    pub(super) fn parse_fetch(&mut self, buf: &[u8]) -> Vec<(Seq, Vec<FetchData>)> {
+        fetch::parse_fetch_responses_with(buf, &mut self.keywords)
+    }
+
+    /// Fetches the fetch profile for the messages in `set` (sequence numbers).
+    pub async fn fetch_messages(
+        &mut self,
+        mailbox: &str,
+        set: impl Into<SequenceSet>,
+    ) -> Result<Vec<Message>> {
+        let items = self.fetch_profile.clone();
+        self.fetch_messages_with(mailbox, set, items).await
+    }
+
+    /// Like [`Client::fetch_messages`], but fetches `items` instead of the profile.
+    pub async fn fetch_messages_with(
+        &mut self,
+        mailbox: &str,
+        set: impl Into<SequenceSet>,
+        items: Vec<FetchItem>,
+    ) -> Result<Vec<Message>> {
+        self.ensure_selected(mailbox).await?;
+        let fetched = self.run_fetch(false, set.into(), items).await?;
+        Ok(messages::assemble(fetched))
+    }
+
+    /// Iterates over every message in `mailbox`, fetching the fetch profile a page at a
+    /// time as the [`Messages`] stream is polled.
+    ///
+    /// Covers the messages that existed when the mailbox was selected, or, if it already
+    /// is, the messages it holds now.
+    pub async fn messages(&mut self, mailbox: &str) -> Result<Messages<'_, S>> {
+        let status = match self.mailbox.snapshot() {
+            Some(status) if self.selected.as_deref() == Some(mailbox) => status,
+            _ => self.open(mailbox, S::EXAMINED, None).await?,
+        };
+        let items = self.fetch_profile.clone();
+        Ok(Messages::new(self, status.exists, items))
+    }
+
+    /// Selects the comparator used by SEARCH and SORT, in order of preference.
+    ///
+    /// Returns the comparator the server made active. Fails if COMPARATOR is not supported.
+    pub async fn set_comparator(&mut self, comparators: &[&str]) -> Result<Option<String>> {
+        if !self.collation().await?.comparator {
+            anyhow::bail!("Server does not support the COMPARATOR command");
+        }
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .comparator(comparators)
+            .as_string();
+        let lines = self.run_command(&tag, cmd, "COMPARATOR").await?;
+        Ok(search::parse_active_comparator(&join_lines(&lines)))
+    }
+
+    /// Sorts the messages in `mailbox` matching `keys` (all if empty) by `criteria`,
+    /// returning their sequence numbers.
+    pub async fn sort(
+        &mut self,
+        mailbox: &str,
+        criteria: Vec<SortKey>,
+        keys: Vec<SearchKey>,
+    ) -> Result<Vec<Seq>> {
+        self.ensure_selected(mailbox).await?;
+        self.require_search_keys(&keys).await?;
+        let literal_plus = self.search_literals(&keys).await?;
+        let tag = next_tag();
+        let mut builder = CommandBuilder::new(&tag).sort(criteria).keys(keys);
+        if literal_plus {
+            builder = builder.literal_plus();
+        }
+        let literal = builder.literal_bytes();
+        let lines = self
+            .run_search_command(&tag, builder.as_string(), literal, literal_plus, "SORT")
+            .await?;
+        Ok(search::parse_sort(&join_lines(&lines))
+            .into_iter()
+            .map(Seq)
+            .collect())
+    }
+
+    /// Fetches the given header fields for `uids` in `mailbox`.
+    ///
+    /// The UIDs are split into batches that keep every UID FETCH under the common 8 KiB
+    /// command line limit, all batches are pipelined, and `(uid, headers)` pairs are
+    /// yielded as each batch completes.
+    pub async fn fetch_headers(
+        &mut self,
+        mailbox: &str,
+        uids: &[Uid],
+        fields: &[&str],
+    ) -> Result<impl Stream<Item = Result<(Uid, HeaderMap)>> + use<S>> {
+        self.ensure_selected(mailbox).await?;
+
+        let section = Section::full().header_fields(fields).to_string();
+        let overhead = CommandBuilder::new(&next_tag())
+            .uid()
+            .fetch(SequenceSet::new())
+            .add_item(FetchItem::Uid)
+            .add_item(FetchItem::BodyPeekSection(section.clone()))
+            .as_string()
+            .len();
+        let batches = SequenceSet::batched(uids, MAX_COMMAND_LEN.saturating_sub(overhead).max(16));
+
+        let cmd_tx = self.cmd_tx.clone();
+        let (tx, rx) = mpsc::channel::<Result<(Uid, HeaderMap)>>(64);
+        tokio::spawn(async move {
+            // Queue every batch up front so the run loop can pipeline them.
+            let mut pending = VecDeque::with_capacity(batches.len());
+            for set in batches {
+                let tag = next_tag();
+                let cmd = CommandBuilder::new(&tag)
+                    .uid()
+                    .fetch(set)
+                    .add_item(FetchItem::Uid)
+                    .add_item(FetchItem::BodyPeekSection(section.clone()))
+                    .as_string();
+                match queue_command(&cmd_tx, &tag, cmd).await {
+                    Ok(rx) => pending.push_back((tag, rx)),
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                }
+            }
+
+            for (tag, rx) in pending {
+                let lines = match await_response(rx, "UID FETCH").await {
+                    Ok(lines) => lines,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
+                if let Err(e) = ensure_ok(&lines, &tag, "UID FETCH") {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+                for (_seq, items) in fetch::parse_fetch_responses(&join_lines(&lines)) {
+                    let mut uid = None;
+                    let mut headers = HeaderMap::new();
+                    for item in items {
+                        match item {
+                            FetchData::Uid(u) => uid = Some(u),
+                            FetchData::BodySection {
+                                data: Some(raw), ..
+                            } => {
+                                headers = header::parse_header_block(&raw);
+                            }
+                            _ => {}
+                        }
+                    }
+                    if let Some(uid) = uid
+                        && tx.send(Ok((uid, headers))).await.is_err()
+                    {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(ReceiverStream::new(rx))
+    }
+
+    /// Finds the messages among `uids` in `mailbox` whose sender asked for a read receipt.
+    ///
+    /// Requests with [`MdnRequest::sent`] set were already handled and must not be
+    /// answered again. After sending (or declining) a receipt, record it with
+    /// [`Client::mark_mdn_sent`]; before sending, check [`mdn::can_mark_sent`] against the
+    /// mailbox's PERMANENTFLAGS.
+    pub async fn mdn_requests(&mut self, mailbox: &str, uids: &[Uid]) -> Result<Vec<MdnRequest>> {
+        self.ensure_selected(mailbox).await?;
+        let section = Section::full().header_fields(&[mdn::NOTIFICATION_HEADER]);
+        let mut requests = Vec::new();
+        for set in SequenceSet::batched(uids, MAX_COMMAND_LEN / 2) {
+            let items = vec![
+                FetchItem::Uid,
+                FetchItem::Flags,
+                FetchItem::body_peek(section.clone()),
+            ];
+            for (_seq, items) in self.run_fetch(true, set, items).await? {
+                let mut uid = None;
+                let mut sent = false;
+                let mut notify_to = None;
+                for item in items {
+                    match item {
+                        FetchData::Uid(u) => uid = Some(u),
+                        FetchData::Flags(flags) => sent = mdn::is_sent(&flags),
+                        FetchData::BodySection {
+                            data: Some(raw), ..
+                        } => {
+                            let headers = header::parse_header_block(&raw);
+                            notify_to = mdn::requested_by(&headers).map(str::to_string);
+                        }
+                        _ => {}
+                    }
+                }
+                if let (Some(uid), Some(notify_to)) = (uid, notify_to) {
+                    requests.push(MdnRequest {
+                        uid,
+                        notify_to,
+                        sent,
+                    });
+                }
+            }
+        }
+        Ok(requests)
+    }
+
+    /// Subscribes to events for other mailboxes as well as the selected one (NOTIFY,
+    /// RFC 5465), so one connection can watch many folders.
+    ///
+    /// Each group names some mailboxes and the events wanted for them; an empty event
+    /// list turns a group off. The events arrive through [`Client::idle`] (which needs some
+    /// mailbox selected) as `IdleEvent::MailboxStatus` and `IdleEvent::MailboxName`, next
+    /// to the usual events for the selected mailbox. With `status` the server first reports the current
+    /// STATUS of every mailbox named. For the selected mailbox, MessageNew and
+    /// MessageExpunge have to be requested together.
+    pub async fn notify(
+        &mut self,
+        groups: Vec<(NotifyMailboxes, Vec<NotifyEvent>)>,
+        status: bool,
+    ) -> Result<()> {
+        self.require_notify().await?;
+        let tag = next_tag();
+        let mut builder = groups.into_iter().fold(
+            CommandBuilder::new(&tag).notify_set(),
+            |b, (mailboxes, events)| b.group(mailboxes, events),
+        );
+        if status {
+            builder = builder.status();
+        }
+        self.run_command(&tag, builder.as_string(), "NOTIFY")
+            .await?;
+        Ok(())
+    }
+
+    /// Turns off all NOTIFY events.
+    pub async fn notify_none(&mut self) -> Result<()> {
+        self.require_notify().await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).notify_none().as_string();
+        self.run_command(&tag, cmd, "NOTIFY").await?;
+        Ok(())
+    }
+
+    async fn require_notify(&mut self) -> Result<()> {
+        if !self.capabilities().await?.contains(&Capability::Notify) {
+            anyhow::bail!("Server does not support NOTIFY");
+        }
+        Ok(())
+    }
+
+    /// Fetches the full raw message with UID `uid` from `mailbox`, byte for byte.
+    ///
+    /// Does not set `\Seen`. Returns `None` if no such message exists.
+    pub async fn fetch_body(&mut self, mailbox: &str, uid: Uid) -> Result<Option<Bytes>> {
+        self.ensure_selected(mailbox).await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .uid()
+            .fetch(SequenceSet::from(uid))
+            .add_item(FetchItem::Uid)
+            .add_item(FetchItem::BodyPeekSection(String::new()))
+            .as_string();
+        let lines = self.run_command(&tag, cmd, "UID FETCH").await?;
+        for (_seq, items) in fetch::parse_fetch_responses(&join_lines(&lines)) {
+            if !items
+                .iter()
+                .any(|item| matches!(item, FetchData::Uid(u) if *u == uid))
+            {
+                continue;
+            }
+            for item in items {
+                if let FetchData::BodySection { section, data, .. } = item
+                    && section.is_empty()
+                {
+                    return Ok(data);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fetches and decodes the readable body of the message with UID `uid` in the selected
+    /// mailbox.
+    ///
+    /// Reads BODYSTRUCTURE first and downloads only the chosen text part, picked by
+    /// [`mime::find_text_part`]; `prefer_html` selects which alternative wins. Does not set
+    /// `\Seen`. Returns `None` if the message does not exist or has no inline text part.
+    pub async fn fetch_best_body(
+        &mut self,
+        uid: Uid,
+        prefer_html: bool,
+    ) -> Result<Option<TextBody>> {
+        let set = || SequenceSet::from(uid);
+        let structure = self
+            .run_fetch(true, set(), vec![FetchItem::Uid, FetchItem::BodyStructure])
+            .await?
+            .into_iter()
+            .filter(|(_seq, items)| {
+                items
+                    .iter()
+                    .any(|item| matches!(item, FetchData::Uid(u) if *u == uid))
+            })
+            .flat_map(|(_seq, items)| items)
+            .find_map(|item| match item {
+                FetchData::BodyStructure(structure) => Some(structure),
+                _ => None,
+            });
+        let Some(structure) = structure else {
+            return Ok(None);
+        };
+        let Some((path, part)) = mime::find_text_part(&structure, prefer_html) else {
+            return Ok(None);
+        };
+
+        let items = vec![FetchItem::Uid, FetchItem::BodyPeekSection(path.clone())];
+        for (_seq, items) in self.run_fetch(true, set(), items).await? {
+            for item in items {
+                if let FetchData::BodySection {
+                    section,
+                    data: Some(data),
+                    ..
+                } = item
+                    && section == path
+                {
+                    return Ok(Some(TextBody {
+                        html: part.is("text", "html"),
+                        text: mime::decode_text(&data, part),
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<S: Selected> Client<S> {
+    /// Searches the selected mailbox, returning the sequence numbers of matching messages.
+    ///
+    /// An empty `keys` list matches every message. String values with a line break or
+    /// non-ASCII text are sent as literals, the latter with `CHARSET UTF-8`; more than one
+    /// such value needs LITERAL+. A value with a NUL, or a keyword that is not an atom, is
+    /// refused before anything is sent.
+    pub async fn search(&mut self, keys: Vec<SearchKey>) -> Result<Vec<Seq>> {
+        let numbers = self.run_search(false, keys).await?;
+        Ok(numbers.into_iter().map(Seq).collect())
+    }
+
+    /// Like [`Client::search`], but returns UIDs.
+    pub async fn uid_search(&mut self, keys: Vec<SearchKey>) -> Result<Vec<Uid>> {
+        let numbers = self.run_search(true, keys).await?;
+        Ok(numbers.into_iter().map(Uid).collect())
+    }
+
+    /// Searches the selected mailbox and returns only the requested summary of the matches
+    /// (ESEARCH, RFC 4731), e.g. their count, or all of them as a compact sequence set.
+    ///
+    /// An empty `returns` list asks for `ALL`.
+    pub async fn esearch(
+        &mut self,
+        keys: Vec<SearchKey>,
+        returns: Vec<SearchReturn>,
+    ) -> Result<EsearchResult> {
+        self.run_esearch(false, keys, returns).await
+    }
+
+    /// Like [`Client::esearch`], but the results are UIDs.
+    pub async fn uid_esearch(
+        &mut self,
+        keys: Vec<SearchKey>,
+        returns: Vec<SearchReturn>,
+    ) -> Result<EsearchResult> {
+        self.run_esearch(true, keys, returns).await
+    }
+
+    /// Closes the selected mailbox, silently expunging messages flagged `\Deleted`, and
+    /// returns to the authenticated state.
+    pub async fn close(mut self) -> Result<Client<AuthenticatedState>> {
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).close().as_string();
+        self.run_command(&tag, cmd, "CLOSE").await?;
+        self.selected = None;
+        Ok(self.with_state())
+    }
+
+    /// Requests a server-side checkpoint of the selected mailbox.
+    pub async fn check(&mut self) -> Result<()> {
+        if self.selected.is_none() {
+            anyhow::bail!("CHECK requires a selected mailbox");
+        }
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).check().as_string();
+        self.run_command(&tag, cmd, "CHECK").await?;
+        Ok(())
+    }
+
+    /// Leaves the selected mailbox without expunging (UNSELECT, RFC 3691) and returns to
+    /// the authenticated state.
+    ///
+    /// Falls back to CLOSE, which only expunges if the mailbox was opened read-write.
+    pub async fn unselect(mut self) -> Result<Client<AuthenticatedState>> {
+        self.leave().await?;
+        Ok(self.with_state())
+    }
+
+    /// Fetches `items` for the messages with sequence numbers in `set` from the selected
+    /// mailbox.
+    ///
+    /// Returns each message's sequence number with its data items.
+    pub async fn fetch_items(
+        &mut self,
+        set: impl Into<SequenceSet>,
+        items: Vec<FetchItem>,
+    ) -> Result<Vec<(Seq, Vec<FetchData>)>> {
+        self.run_fetch(false, set.into(), items).await
+    }
+
+    /// Like [`Client::fetch_items`], but `set` holds UIDs.
+    pub async fn uid_fetch(
+        &mut self,
+        set: impl Into<SequenceSet>,
+        items: Vec<FetchItem>,
+    ) -> Result<Vec<(Seq, Vec<FetchData>)>> {
+        self.run_fetch(true, set.into(), items).await
+    }
+
+    /// Starts IDLE (RFC 2177) on the selected mailbox.
+    ///
+    /// The returned handle yields mailbox changes as they arrive and re-issues IDLE before
+    /// the server's inactivity timeout. The session is borrowed until [`IdleHandle::done`].
+    pub async fn idle(&mut self) -> Result<IdleHandle<'_>> {
+        if self.selected.is_none() {
+            anyhow::bail!("IDLE requires a selected mailbox");
+        }
+        Ok(IdleHandle::start(
+            self.cmd_tx.clone(),
+            self.unsol_rx.resubscribe(),
+        ))
+    }
+
+    /// Fetches the message with UID `uid` in the selected mailbox for reading: its flags,
+    /// envelope and BODYSTRUCTURE, then only its text parts, so that
+    /// [`Message::text_body`], [`Message::html_body`] and [`Message::attachments`] work
+    /// without downloading any attachment.
+    ///
+    /// Does not set `\Seen`. Returns `None` if no such message exists.
+    pub async fn fetch_readable(&mut self, uid: Uid) -> Result<Option<Message>> {
+        let set = || SequenceSet::from(uid);
+        let items = vec![
+            FetchItem::Uid,
+            FetchItem::Flags,
+            FetchItem::Envelope,
+            FetchItem::BodyStructure,
+        ];
+        let fetched = self.run_fetch(true, set(), items).await?;
+        let Some(mut message) = messages::assemble(fetched)
+            .into_iter()
+            .find(|m| m.uid() == Some(uid))
+        else {
+            return Ok(None);
+        };
+        let sections = message
+            .body_structure()
+            .map(mime::text_sections)
+            .unwrap_or_default();
+        if sections.is_empty() {
+            return Ok(Some(message));
+        }
+
+        let mut items = vec![FetchItem::Uid];
+        items.extend(
+            sections
+                .iter()
+                .filter_map(|path| Section::part_at(path))
+                .map(FetchItem::body_peek),
+        );
+        for (_seq, items) in self.run_fetch(true, set(), items).await? {
+            if items
+                .iter()
+                .any(|item| matches!(item, FetchData::Uid(u) if *u == uid))
+            {
+                message.merge(items);
+            }
+        }
+        Ok(Some(message))
+    }
+
+    /// Downloads `attachment` of the message with UID `uid` in the selected mailbox and
+    /// removes its transfer encoding, with `BINARY.PEEK` when the server supports it.
+    ///
+    /// Does not set `\Seen`. Returns `None` if the message or part does not exist.
+    pub async fn fetch_attachment(
+        &mut self,
+        uid: Uid,
+        attachment: &Attachment<'_>,
+    ) -> Result<Option<Vec<u8>>> {
+        if self.capabilities().await?.contains(&Capability::Binary) {
+            let data = self.fetch_binary(uid, &attachment.section).await?;
+            return Ok(data.map(|d| d.to_vec()));
+        }
+        let section = Section::part_at(&attachment.section)
+            .with_context(|| format!("Bad section path {:?}", attachment.section))?;
+        let items = vec![FetchItem::Uid, FetchItem::body_peek(section.clone())];
+        let fetched = self
+            .run_fetch(true, SequenceSet::from(uid), items)
+            .await?;
+        Ok(messages::assemble(fetched)
+            .iter()
+            .find(|m| m.uid() == Some(uid))
+            .and_then(|m| m.body(&section))
+            .map(|data| attachment.decode(data)))
+    }
+
+    /// Fetches `section` (e.g. `"2"` or `"1.3"`) of the message with UID `uid` in the
+    /// selected mailbox with `BINARY.PEEK` (RFC 3516): the server removes the content
+    /// transfer encoding, so attachments arrive as their raw bytes.
+    ///
+    /// Does not set `\Seen`. Returns `None` if the message or section does not exist.
+    pub async fn fetch_binary(&mut self, uid: Uid, section: &str) -> Result<Option<Bytes>> {
+        if !self.capabilities().await?.contains(&Capability::Binary) {
+            anyhow::bail!("Server does not support BINARY");
+        }
+        let items = vec![FetchItem::Uid, FetchItem::BinaryPeek(section.to_string())];
+        for (_seq, items) in self
+            .run_fetch(true, SequenceSet::from(uid), items)
+            .await?
+        {
+            for item in items {
+                if let FetchData::BinarySection {
+                    section: s, data, ..
+                } = item
+                    && s == section
+                {
+                    return Ok(data);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fetches the bytes in `range` of `section` of the message with UID `uid` in the
+    /// selected mailbox, with `BODY.PEEK[section]<offset.count>`. Large attachments can be
+    /// downloaded in chunks this way, and a download resumed from where it stopped.
+    ///
+    /// The result is shorter than the range at the end of the section, and empty past it.
+    /// Does not set `\Seen`. Returns `None` if no such message exists.
+    pub async fn fetch_body_range(
+        &mut self,
+        uid: Uid,
+        section: Section,
+        range: Range<u32>,
+    ) -> Result<Option<Bytes>> {
+        let spec = section.to_string();
+        let items = vec![FetchItem::Uid, FetchItem::body_peek_range(section, range)];
+        for (_seq, items) in self
+            .run_fetch(true, SequenceSet::from(uid), items)
+            .await?
+        {
+            if !items
+                .iter()
+                .any(|item| matches!(item, FetchData::Uid(u) if *u == uid))
+            {
+                continue;
+            }
+            for item in items {
+                if let FetchData::BodySection {
+                    section: s, data, ..
+                } = item
+                    && s.eq_ignore_ascii_case(&spec)
+                {
+                    return Ok(Some(data.unwrap_or_default()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fetches `section` of the message with UID `uid` in the selected mailbox with
+    /// `BODY.PEEK[section]`, writing the data to `writer` straight from the socket as it
+    /// arrives. A large attachment never sits in memory as a whole, unlike with
+    /// [`fetch_body`](Self::fetch_body).
+    ///
+    /// Returns the number of bytes written, or `None` if the message or section does not
+    /// exist. Other commands wait while `writer` is slow. If the connection fails partway,
+    /// part of the data has been written already. Does not set `\Seen`.
+    pub async fn fetch_body_to<W>(
+        &mut self,
+        uid: Uid,
+        section: Section,
+        mut writer: W,
+    ) -> Result<Option<u64>>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let spec = section.to_string();
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .uid()
+            .fetch(SequenceSet::from(uid))
+            .add_item(FetchItem::Uid)
+            .add_item(FetchItem::body_peek(section))
+            .as_string();
+        let (tx, mut rx) = mpsc::channel(STREAM_CHUNKS);
+        let response = queue_streaming_command(&self.cmd_tx, &tag, cmd, Sink::Literals(tx))
+            .await
+            .context("Failed to send UID FETCH command")?;
+
+        // The sink closes once the command completes.
+        let mut written = None;
+        while let Some(data) = rx.recv().await {
+            writer
+                .write_all(&data)
+                .await
+                .context("Failed to write fetched body")?;
+            *written.get_or_insert(0) += data.len() as u64;
+        }
+        let lines = await_response(response, "UID FETCH").await?;
+        ensure_ok(&lines, &tag, "UID FETCH")?;
+
+        // Short sections may come as quoted strings, which are not streamed.
+        if written.is_none() {
+            for (_seq, items) in fetch::parse_fetch_responses(&join_lines(&lines)) {
+                if !items
+                    .iter()
+                    .any(|item| matches!(item, FetchData::Uid(u) if *u == uid))
+                {
+                    continue;
+                }
+                for item in items {
+                    if let FetchData::BodySection {
+                        section: s,
+                        data: Some(data),
+                        ..
+                    } = item
+                        && s.eq_ignore_ascii_case(&spec)
+                    {
+                        writer
+                            .write_all(&data)
+                            .await
+                            .context("Failed to write fetched body")?;
+                        written = Some(data.len() as u64);
+                    }
+                }
+            }
+        }
+        writer
+            .flush()
+            .await
+            .context("Failed to write fetched body")?;
+        Ok(written)
+    }
+}
+
+impl Client<SelectedState> {
+    /// Copies the messages in `set` from the selected mailbox to `mailbox`.
+    ///
+    /// Returns the UID mapping when the server reports COPYUID (UIDPLUS, RFC 4315).
+    pub async fn copy(
+        &mut self,
+        set: impl Into<SequenceSet>,
+        mailbox: &str,
+    ) -> Result<Option<CopyUid>> {
+        let set = set.into();
+        self.ensure_writable("COPY")?;
+        if self.selected.is_none() {
+            anyhow::bail!("COPY requires a selected mailbox");
+        }
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).copy(set, mailbox).as_string();
+        let lines = self.run_command(&tag, cmd, "COPY").await?;
+        Ok(parser::mailbox::parse_copy_uid(&join_lines(&lines)))
+    }
+
+    /// Moves the messages in `set` from the selected mailbox to `mailbox`.
+    ///
+    /// Uses MOVE (RFC 6851) when the server supports it. Otherwise falls back to COPY,
+    /// flagging the originals `\Deleted` and EXPUNGE, which also removes any other
+    /// messages already flagged for deletion. Either way the COPYUID mapping is returned
+    /// if the server sent one.
+    pub async fn mv(
+        &mut self,
+        set: impl Into<SequenceSet>,
+        mailbox: &str,
+    ) -> Result<Option<CopyUid>> {
+        let set = set.into();
+        self.ensure_writable("MOVE")?;
+        if self.selected.is_none() {
+            anyhow::bail!("MOVE requires a selected mailbox");
+        }
+        let caps = self.capabilities().await?;
+        if caps.contains(&Capability::Move) {
+            let tag = next_tag();
+            let cmd = CommandBuilder::new(&tag).mv(set, mailbox).as_string();
+            let lines = self.run_command(&tag, cmd, "MOVE").await?;
+            return Ok(parser::mailbox::parse_copy_uid(&join_lines(&lines)));
+        }
+
+        let copied = self.copy(set.clone(), mailbox).await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .store(set)
+            .add()
+            .silent()
+            .flags(vec![Flag::Deleted])
+            .as_string();
+        self.run_command(&tag, cmd, "STORE").await?;
+        self.expunge().await?;
+        Ok(copied)
+    }
+
+    /// Like [`Client::mv`], but `set` holds UIDs.
+    ///
+    /// The fallback uses UID EXPUNGE when UIDPLUS is available, so only the moved
+    /// messages are removed.
+    pub async fn uid_mv(
+        &mut self,
+        set: impl Into<SequenceSet>,
+        mailbox: &str,
+    ) -> Result<Option<CopyUid>> {
+        let set = set.into();
+        self.ensure_writable("UID MOVE")?;
+        if self.selected.is_none() {
+            anyhow::bail!("UID MOVE requires a selected mailbox");
+        }
+        let caps = self.capabilities().await?;
+        if caps.contains(&Capability::Move) {
+            let tag = next_tag();
+            let cmd = CommandBuilder::new(&tag).uid().mv(set, mailbox).as_string();
+            let lines = self.run_command(&tag, cmd, "UID MOVE").await?;
+            return Ok(parser::mailbox::parse_copy_uid(&join_lines(&lines)));
+        }
+
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .uid()
+            .copy(set.clone(), mailbox)
+            .as_string();
+        let lines = self.run_command(&tag, cmd, "UID COPY").await?;
+        self.uid_delete(set).await?;
+        Ok(parser::mailbox::parse_copy_uid(&join_lines(&lines)))
+    }
+
+    /// Flags the messages with UIDs in `set` `\Deleted` and expunges them.
+    ///
+    /// Without UIDPLUS this is a plain EXPUNGE, which also removes any other messages
+    /// already flagged for deletion.
+    pub async fn uid_delete(&mut self, set: impl Into<SequenceSet>) -> Result<()> {
+        let set = set.into();
+        self.ensure_writable("UID STORE")?;
+        if self.selected.is_none() {
+            anyhow::bail!("UID STORE requires a selected mailbox");
+        }
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .uid()
+            .store(set.clone())
+            .add()
+            .silent()
+            .flags(vec![Flag::Deleted])
+            .as_string();
+        self.run_command(&tag, cmd, "UID STORE").await?;
+
+        let caps = self.capabilities().await?;
+        if caps.contains(&Capability::UidPlus) {
+            self.uid_expunge(set).await?;
+        } else {
+            self.expunge().await?;
+        }
+        Ok(())
+    }
+
+    /// Expunges only the messages flagged `\Deleted` whose UIDs are in `set` (UID EXPUNGE,
+    /// RFC 4315), leaving deletions made by other sessions alone.
+    ///
+    /// Returns the sequence numbers as [`Client::expunge`] does. Fails if the server does
+    /// not advertise UIDPLUS.
+    pub async fn uid_expunge(&mut self, set: impl Into<SequenceSet>) -> Result<Vec<Seq>> {
+        let set = set.into();
+        self.ensure_writable("UID EXPUNGE")?;
+        if self.selected.is_none() {
+            anyhow::bail!("UID EXPUNGE requires a selected mailbox");
+        }
+        if !self.capabilities().await?.contains(&Capability::UidPlus) {
+            anyhow::bail!("Server does not support UID EXPUNGE (UIDPLUS)");
+        }
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).uid().expunge(set).as_string();
+        let lines = self.run_command(&tag, cmd, "UID EXPUNGE").await?;
+        Ok(parser::mailbox::parse_expunge(&join_lines(&lines)))
+    }
+
+    /// Permanently removes messages flagged `\Deleted` from the selected mailbox.
+    ///
+    /// Returns the sequence numbers from the server's `* n EXPUNGE` responses, in the order
+    /// reported; each one is relative to the mailbox after the preceding removals.
+    pub async fn expunge(&mut self) -> Result<Vec<Seq>> {
+        self.ensure_writable("EXPUNGE")?;
+        if self.selected.is_none() {
+            anyhow::bail!("EXPUNGE requires a selected mailbox");
+        }
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).expunge().as_string();
+        let lines = self.run_command(&tag, cmd, "EXPUNGE").await?;
+        Ok(parser::mailbox::parse_expunge(&join_lines(&lines)))
+    }
+}
+
+impl<S: Writable> Client<S> {
+    /// Sets `$MDNSent` on message `uid` in `mailbox`, once the application has sent or
+    /// declined to send the receipt it requested.
+    pub async fn mark_mdn_sent(&mut self, mailbox: &str, uid: Uid) -> Result<()> {
+        self.ensure_writable("UID STORE")?;
+        self.ensure_selected(mailbox).await?;
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .uid()
+            .store(SequenceSet::from(uid))
+            .add()
+            .silent()
+            .flags(vec![Flag::Keyword(mdn::MDN_SENT.to_string())])
+            .as_string();
+        self.run_command(&tag, cmd, "UID STORE").await?;
+        Ok(())
     }
 }
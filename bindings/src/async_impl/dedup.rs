@@ -0,0 +1,198 @@
+use anyhow::Result;
+use bytes::Bytes;
+use std::collections::HashMap;
+use tokio_stream::StreamExt;
+
+use super::Client;
+use crate::{AuthenticatedState, SelectedState};
+
+use imap::commands::FetchItem;
+use imap::types::command::{SequenceBound, SequenceSet};
+use imap::types::common::{Capability, Uid};
+use imap::types::response::FetchData;
+
+const MAX_SET_LEN: usize = 4 * 1024;
+
+/// Messages in one mailbox sharing a Message-ID and size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    pub message_id: String,
+    pub size: u32,
+    /// The lowest UID, which is left in place.
+    pub keep: Uid,
+    pub duplicates: Vec<Uid>,
+}
+
+/// Scans a mailbox for duplicate messages and optionally removes them.
+///
+/// Messages are duplicates when their Message-ID and RFC822.SIZE match; messages without
+/// a Message-ID are never considered. With [`DuplicateFinder::verify_body`], candidates are
+/// downloaded and grouped again by their full content.
+pub struct DuplicateFinder<'a> {
+    client: &'a mut Client<AuthenticatedState>,
+    mailbox: String,
+    verify_body: bool,
+}
+
+impl<'a> DuplicateFinder<'a> {
+    pub fn new(client: &'a mut Client<AuthenticatedState>, mailbox: &str) -> Self {
+        Self {
+            client,
+            mailbox: mailbox.to_string(),
+            verify_body: false,
+        }
+    }
+
+    /// Confirm candidates by comparing their bodies (default off). Costs one download per
+    /// candidate message.
+    pub fn verify_body(mut self, verify: bool) -> Self {
+        self.verify_body = verify;
+        self
+    }
+
+    /// Groups the duplicates in the mailbox, which is examined (opened read-only) for the
+    /// scan.
+    pub async fn find(&mut self) -> Result<Vec<DuplicateGroup>> {
+        if self.client.open(&self.mailbox, true, None).await?.exists == 0 {
+            return Ok(Vec::new());
+        }
+        let all = SequenceSet::new().add_range(SequenceBound::Number(1), SequenceBound::Star);
+        let sizes: HashMap<Uid, u32> = self
+            .client
+            .run_fetch(true, all, vec![FetchItem::Uid, FetchItem::Rfc822Size])
+            .await?
+            .into_iter()
+            .filter_map(|(_seq, items)| {
+                let mut uid = None;
+                let mut size = None;
+                for item in items {
+                    match item {
+                        FetchData::Uid(u) => uid = Some(u),
+                        FetchData::Rfc822Size(s) => size = Some(s),
+                        _ => {}
+                    }
+                }
+                Some((uid?, size?))
+            })
+            .collect();
+
+        let uids: Vec<Uid> = sizes.keys().copied().collect();
+        let mut candidates: HashMap<(String, u32), Vec<Uid>> = HashMap::new();
+        let mut headers = self
+            .client
+            .fetch_headers(&self.mailbox, &uids, &["MESSAGE-ID"])
+            .await?;
+        while let Some(item) = headers.next().await {
+            let (uid, map) = item?;
+            let (Some(id), Some(&size)) = (map.get("Message-ID"), sizes.get(&uid)) else {
+                continue;
+            };
+            let id = id.trim();
+            if !id.is_empty() {
+                candidates
+                    .entry((id.to_string(), size))
+                    .or_default()
+                    .push(uid);
+            }
+        }
+
+        let mut groups = Vec::new();
+        for ((message_id, size), mut uids) in candidates {
+            if uids.len() < 2 {
+                continue;
+            }
+            uids.sort_unstable();
+            let sets = if self.verify_body {
+                self.split_by_body(&uids).await?
+            } else {
+                vec![uids]
+            };
+            for uids in sets.into_iter().filter(|u| u.len() > 1) {
+                groups.push(DuplicateGroup {
+                    message_id: message_id.clone(),
+                    size,
+                    keep: uids[0],
+                    duplicates: uids[1..].to_vec(),
+                });
+            }
+        }
+        groups.sort_by_key(|g| g.keep);
+        Ok(groups)
+    }
+
+    /// Regroups `uids` (sorted) by their full content, comparing each body byte for byte
+    /// with the first message of every group so far.
+    async fn split_by_body(&mut self, uids: &[Uid]) -> Result<Vec<Vec<Uid>>> {
+        let mut by_body: Vec<(Bytes, Vec<Uid>)> = Vec::new();
+        for &uid in uids {
+            let Some(body) = self.client.fetch_body(&self.mailbox, uid).await? else {
+                continue;
+            };
+            match by_body.iter_mut().find(|(first, _)| *first == body) {
+                Some((_, group)) => group.push(uid),
+                None => by_body.push((body, vec![uid])),
+            }
+        }
+        Ok(by_body.into_iter().map(|(_, uids)| uids).collect())
+    }
+
+    /// Deletes every duplicate in `groups`, keeping one copy of each message.
+    ///
+    /// Fails without UIDPLUS: a plain EXPUNGE would also remove messages that other
+    /// sessions flagged `\Deleted`.
+    pub async fn delete(&mut self, groups: &[DuplicateGroup]) -> Result<()> {
+        let sets = duplicate_sets(groups);
+        if sets.is_empty() {
+            return Ok(());
+        }
+        if !self.client.capabilities().await?.contains(&Capability::UidPlus) {
+            anyhow::bail!("Deleting duplicates needs UIDPLUS, so only they are expunged");
+        }
+        self.client.open(&self.mailbox, false, None).await?;
+        let mut selected = self.client.lend::<SelectedState>();
+        let mut result = Ok(());
+        for set in sets {
+            result = selected.uid_delete(set).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        self.client.restore(selected);
+        result
+    }
+
+    /// Moves every duplicate in `groups` to `mailbox`, keeping one copy in place.
+    ///
+    /// Fails unless the server supports MOVE or UIDPLUS: copying and then expunging
+    /// without either would also remove messages that other sessions flagged `\Deleted`.
+    pub async fn move_to(&mut self, groups: &[DuplicateGroup], mailbox: &str) -> Result<()> {
+        let sets = duplicate_sets(groups);
+        if sets.is_empty() {
+            return Ok(());
+        }
+        let caps = self.client.capabilities().await?;
+        if !caps.contains(&Capability::Move) && !caps.contains(&Capability::UidPlus) {
+            anyhow::bail!("Moving duplicates needs MOVE or UIDPLUS, so only they are expunged");
+        }
+        self.client.open(&self.mailbox, false, None).await?;
+        let mut selected = self.client.lend::<SelectedState>();
+        let mut result = Ok(());
+        for set in sets {
+            result = selected.uid_mv(set, mailbox).await.map(drop);
+            if result.is_err() {
+                break;
+            }
+        }
+        self.client.restore(selected);
+        result
+    }
+}
+
+/// The duplicates' UIDs, split into sets that fit on one command line.
+fn duplicate_sets(groups: &[DuplicateGroup]) -> Vec<SequenceSet> {
+    let uids: Vec<Uid> = groups
+        .iter()
+        .flat_map(|g| g.duplicates.iter().copied())
+        .collect();
+    SequenceSet::batched(&uids, MAX_SET_LEN)
+}
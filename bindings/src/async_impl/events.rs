@@ -0,0 +1,61 @@
+use bytes::Bytes;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_stream::Stream;
+
+use imap::parser::mailbox::parse_unsolicited_event;
+use imap::types::common::KeywordInterner;
+use imap::types::response::UnsolicitedEvent;
+
+type Receiving = Pin<Box<dyn Future<Output = (Result<Bytes, RecvError>, Lines)> + Send>>;
+type Lines = broadcast::Receiver<Bytes>;
+
+/// Untagged responses of a connection as typed events, from
+/// [`Client::events`](super::Client::events).
+///
+/// A [`Stream`] that ends when the connection closes. Only lines received after the
+/// stream was created are seen, and the server's answers to the client's own commands are
+/// included: `* 12 EXISTS` after SELECT, or `* 3 FETCH (FLAGS ...)` after a FETCH of
+/// FLAGS. A consumer that falls more than 64 lines behind misses the oldest ones.
+pub struct Events {
+    receiving: Receiving,
+    keywords: KeywordInterner,
+}
+
+impl Events {
+    pub(super) fn new(lines: Lines) -> Self {
+        Self {
+            receiving: receive(lines),
+            keywords: KeywordInterner::new(),
+        }
+    }
+}
+
+fn receive(mut lines: Lines) -> Receiving {
+    Box::pin(async move { (lines.recv().await, lines) })
+}
+
+impl Stream for Events {
+    type Item = UnsolicitedEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let (line, lines) = ready!(this.receiving.as_mut().poll(cx));
+            this.receiving = receive(lines);
+            match line {
+                Ok(line) => {
+                    if let Some(event) = parse_unsolicited_event(&line, &mut this.keywords) {
+                        return Poll::Ready(Some(event));
+                    }
+                }
+                Err(RecvError::Lagged(n)) => {
+                    tracing::warn!("Event stream fell behind; {} responses were dropped", n);
+                }
+                Err(RecvError::Closed) => return Poll::Ready(None),
+            }
+        }
+    }
+}
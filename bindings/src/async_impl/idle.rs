@@ -0,0 +1,138 @@
+use anyhow::{Context as _, Result};
+use bytes::Bytes;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_stream::Stream;
+
+use super::connector::{Request, ensure_ok, queue_command};
+use crate::next_tag;
+
+use imap::commands::CommandBuilder;
+use imap::parser::mailbox::parse_idle_event;
+use imap::types::common::KeywordInterner;
+use imap::types::response::IdleEvent;
+
+/// RFC 2177 lets servers drop a client idle for 30 minutes; IDLE is re-issued before that.
+const IDLE_REFRESH: Duration = Duration::from_secs(28 * 60);
+
+/// A running IDLE, returned by [`Client::idle`](super::Client::idle).
+///
+/// Yields mailbox changes as a [`Stream`]. Dropping the handle also ends IDLE, but only
+/// [`IdleHandle::done`] waits for the server to confirm.
+pub struct IdleHandle<'a> {
+    events: mpsc::Receiver<Result<IdleEvent>>,
+    stop: Option<oneshot::Sender<()>>,
+    task: JoinHandle<Result<()>>,
+    _session: PhantomData<&'a mut ()>,
+}
+
+impl IdleHandle<'_> {
+    pub(super) fn start(cmd_tx: mpsc::Sender<Request>, lines: broadcast::Receiver<Bytes>) -> Self {
+        let (events_tx, events) = mpsc::channel(64);
+        let (stop, stop_rx) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let result = run_idle(cmd_tx, lines, &events_tx, stop_rx).await;
+            if let Err(e) = &result {
+                let _ = events_tx.send(Err(anyhow::anyhow!("{:#}", e))).await;
+            }
+            result
+        });
+        Self {
+            events,
+            stop: Some(stop),
+            task,
+            _session: PhantomData,
+        }
+    }
+
+    /// Ends IDLE and waits for the server's tagged completion.
+    ///
+    /// Events not yet consumed from the stream are discarded.
+    pub async fn done(mut self) -> Result<()> {
+        self.events.close();
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+        (&mut self.task).await.context("IDLE task panicked")?
+    }
+}
+
+impl Stream for IdleHandle<'_> {
+    type Item = Result<IdleEvent>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().events.poll_recv(cx)
+    }
+}
+
+impl Drop for IdleHandle<'_> {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            let _ = stop.send(());
+        }
+    }
+}
+
+async fn run_idle(
+    cmd_tx: mpsc::Sender<Request>,
+    mut lines: broadcast::Receiver<Bytes>,
+    events: &mpsc::Sender<Result<IdleEvent>>,
+    mut stop: oneshot::Receiver<()>,
+) -> Result<()> {
+    let mut keywords = KeywordInterner::new();
+    let mut stopping = false;
+    while !stopping {
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).idle().as_string();
+        let mut completion = queue_command(&cmd_tx, &tag, cmd)
+            .await
+            .context("Failed to send IDLE command")?;
+
+        let refresh = tokio::time::sleep(IDLE_REFRESH);
+        tokio::pin!(refresh);
+        let mut done_sent = false;
+        let collected = loop {
+            tokio::select! {
+                _ = &mut refresh, if !done_sent => {
+                    tracing::debug!("Re-issuing IDLE");
+                    done_sent = true;
+                    let _ = cmd_tx.send(Request::IdleDone).await;
+                }
+                _ = &mut stop, if !done_sent => {
+                    stopping = true;
+                    done_sent = true;
+                    let _ = cmd_tx.send(Request::IdleDone).await;
+                }
+                line = lines.recv() => match line {
+                    Ok(line) => {
+                        if let Some(event) = parse_idle_event(&line, &mut keywords) {
+                            let _ = events.send(Ok(event)).await;
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("IDLE fell behind; {} responses were dropped", n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => {
+                        anyhow::bail!("IMAP connection is closed");
+                    }
+                },
+                result = &mut completion => {
+                    break result.context("IDLE timed out")??;
+                }
+            }
+        };
+
+        // Lines broadcast just before the completion may not have been read yet.
+        while let Ok(line) = lines.try_recv() {
+            if let Some(event) = parse_idle_event(&line, &mut keywords) {
+                let _ = events.send(Ok(event)).await;
+            }
+        }
+        ensure_ok(&collected, &tag, "IDLE")?;
+    }
+    Ok(())
+}
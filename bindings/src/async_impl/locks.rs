@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// Advisory per-mailbox locks shared by the connections of one process.
+///
+/// Sequence numbers shift under every EXPUNGE, so two connections that expunge or bulk-move
+/// in the same mailbox at once can act on the wrong messages. Taking the mailbox's lock
+/// around such operations serializes them. Nothing is enforced: connections that don't
+/// take the lock are not held back, and other processes are not affected.
+///
+/// Clones share the same locks.
+#[derive(Debug, Clone, Default)]
+pub struct MailboxLocks {
+    locks: Arc<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>>,
+}
+
+/// Holds a mailbox's lock until dropped.
+#[derive(Debug)]
+pub struct MailboxGuard {
+    mailbox: String,
+    guard: Option<OwnedMutexGuard<()>>,
+    locks: MailboxLocks,
+}
+
+impl MailboxLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits until no one else holds `mailbox`'s lock, then takes it.
+    pub async fn lock(&self, mailbox: &str) -> MailboxGuard {
+        let guard = self.entry(mailbox).lock_owned().await;
+        self.guard(mailbox, guard)
+    }
+
+    /// Takes `mailbox`'s lock if it is free.
+    pub fn try_lock(&self, mailbox: &str) -> Option<MailboxGuard> {
+        let guard = self.entry(mailbox).try_lock_owned().ok()?;
+        Some(self.guard(mailbox, guard))
+    }
+
+    pub fn is_locked(&self, mailbox: &str) -> bool {
+        self.locks
+            .lock()
+            .expect("mailbox locks poisoned")
+            .get(mailbox)
+            .is_some_and(|lock| lock.try_lock().is_err())
+    }
+
+    fn entry(&self, mailbox: &str) -> Arc<AsyncMutex<()>> {
+        self.locks
+            .lock()
+            .expect("mailbox locks poisoned")
+            .entry(mailbox.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn guard(&self, mailbox: &str, guard: OwnedMutexGuard<()>) -> MailboxGuard {
+        MailboxGuard {
+            mailbox: mailbox.to_string(),
+            guard: Some(guard),
+            locks: self.clone(),
+        }
+    }
+}
+
+impl MailboxGuard {
+    pub fn mailbox(&self) -> &str {
+        &self.mailbox
+    }
+}
+
+impl Drop for MailboxGuard {
+    fn drop(&mut self) {
+        let Some(guard) = self.guard.take() else {
+            return;
+        };
+        let mutex = OwnedMutexGuard::mutex(&guard).clone();
+        drop(guard);
+        let mut locks = self.locks.locks.lock().expect("mailbox locks poisoned");
+        // Forget the entry once only the map and `mutex` refer to it; waiters hold more.
+        if Arc::strong_count(&mutex) == 2 {
+            locks.remove(&self.mailbox);
+        }
+    }
+}
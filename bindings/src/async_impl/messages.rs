@@ -0,0 +1,175 @@
+use anyhow::{Context as _, Result};
+use bytes::Bytes;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::Stream;
+
+use super::Client;
+use super::connector::{Response, Sink, ensure_ok, queue_streaming_command};
+use crate::{AuthenticatedState, LoggedIn};
+
+use imap::commands::FetchItem;
+use imap::messages::Message;
+use imap::types::command::{SequenceBound, SequenceSet};
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+/// FETCH responses read ahead of the consumer; the connection waits once they are queued.
+const READ_AHEAD: usize = 64;
+
+/// Messages of the selected mailbox, fetched lazily in pages of sequence numbers.
+///
+/// A [`Stream`] of messages, each yielded as soon as its FETCH response has been read, in
+/// the order the server sends them. Nothing is fetched until the stream is first polled;
+/// each later page is requested only once the previous one has completed.
+pub struct Messages<'a, S = AuthenticatedState> {
+    client: &'a mut Client<S>,
+    next_seq: u32,
+    last_seq: u32,
+    page_size: u32,
+    items: Vec<FetchItem>,
+    state: State,
+}
+
+type Queueing = Pin<Box<dyn Future<Output = Result<Page>> + Send>>;
+
+enum State {
+    /// The next page is still to be requested.
+    Idle,
+    Queueing(Queueing),
+    Receiving(Page),
+    Done,
+}
+
+/// A FETCH in progress.
+struct Page {
+    tag: String,
+    responses: mpsc::Receiver<Bytes>,
+    completion: oneshot::Receiver<Response>,
+}
+
+impl<'a, S: LoggedIn> Messages<'a, S> {
+    pub(super) fn new(
+        client: &'a mut Client<S>,
+        exists: u32,
+        items: Vec<FetchItem>,
+    ) -> Self {
+        Self {
+            client,
+            next_seq: 1,
+            last_seq: exists,
+            page_size: DEFAULT_PAGE_SIZE,
+            items,
+            state: State::Idle,
+        }
+    }
+
+    /// Messages fetched per FETCH command (default 50).
+    pub fn page_size(mut self, n: u32) -> Self {
+        self.page_size = n.max(1);
+        self
+    }
+
+    /// Fetch `items` instead of the client's fetch profile.
+    pub fn items(mut self, items: Vec<FetchItem>) -> Self {
+        self.items = items;
+        self
+    }
+
+    /// Returns the next message, or `None` once every page has been read.
+    pub async fn try_next(&mut self) -> Result<Option<Message>> {
+        std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx))
+            .await
+            .transpose()
+    }
+
+    /// Queues the FETCH for the next page, or `None` if there is none.
+    fn next_page(&mut self) -> Option<Result<Queueing>> {
+        if self.next_seq > self.last_seq {
+            return None;
+        }
+        let end = self
+            .next_seq
+            .saturating_add(self.page_size - 1)
+            .min(self.last_seq);
+        let set = SequenceSet::new().add_range(
+            SequenceBound::Number(self.next_seq),
+            SequenceBound::Number(end),
+        );
+        self.next_seq = end.saturating_add(1);
+        let (tag, cmd) = match self.client.fetch_command(false, set, self.items.clone()) {
+            Ok(command) => command,
+            Err(e) => return Some(Err(e)),
+        };
+        let cmd_tx = self.client.command_sender();
+        Some(Ok(Box::pin(async move {
+            let (tx, responses) = mpsc::channel(READ_AHEAD);
+            let completion = queue_streaming_command(&cmd_tx, &tag, cmd, Sink::Responses(tx))
+                .await
+                .context("Failed to send FETCH command")?;
+            Ok(Page {
+                tag,
+                responses,
+                completion,
+            })
+        })))
+    }
+}
+
+impl<S: LoggedIn> Stream for Messages<'_, S> {
+    type Item = Result<Message>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let failed = match &mut this.state {
+                State::Idle => match this.next_page() {
+                    None => {
+                        this.state = State::Done;
+                        continue;
+                    }
+                    Some(Ok(queueing)) => {
+                        this.state = State::Queueing(queueing);
+                        continue;
+                    }
+                    Some(Err(e)) => e,
+                },
+                State::Queueing(queueing) => match ready!(queueing.as_mut().poll(cx)) {
+                    Ok(page) => {
+                        this.state = State::Receiving(page);
+                        continue;
+                    }
+                    Err(e) => e,
+                },
+                State::Receiving(page) => match ready!(page.responses.poll_recv(cx)) {
+                    Some(line) => {
+                        if let Some((seq, items)) = this.client.parse_fetch(&line).pop() {
+                            return Poll::Ready(Some(Ok(Message::new(seq, items))));
+                        }
+                        continue;
+                    }
+                    // The sink closes once the command completes.
+                    None => {
+                        let result = match ready!(Pin::new(&mut page.completion).poll(cx)) {
+                            Ok(response) => {
+                                response.and_then(|lines| ensure_ok(&lines, &page.tag, "FETCH"))
+                            }
+                            Err(_) => Err(anyhow::anyhow!("FETCH timed out")),
+                        };
+                        match result {
+                            Ok(()) => {
+                                this.state = State::Idle;
+                                continue;
+                            }
+                            Err(e) => e,
+                        }
+                    }
+                },
+                State::Done => return Poll::Ready(None),
+            };
+            this.state = State::Done;
+            return Poll::Ready(Some(Err(failed)));
+        }
+    }
+}
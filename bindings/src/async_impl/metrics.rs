@@ -0,0 +1,101 @@
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use super::connector::{CommandEvent, Transport};
+
+/// Receives counts from a connection, set with
+/// [`Builder::metrics`](crate::async_impl::Builder::metrics), e.g. to feed the counters
+/// and histograms of a monitoring system.
+///
+/// Every method does nothing by default, so an implementation picks what it needs. They
+/// are called on the connection task and should return quickly. Sharing one observer
+/// between connections gives totals for the whole fleet.
+pub trait ConnectionMetrics: Send + Sync {
+    /// `n` bytes were read from the server, after TLS decryption.
+    fn bytes_received(&self, _n: usize) {}
+
+    /// `n` bytes were written to the server, before TLS encryption.
+    fn bytes_sent(&self, _n: usize) {}
+
+    /// A command was written, e.g. `UID FETCH`. NOOPs sent by the connection itself count.
+    fn command_sent(&self, _name: &str) {}
+
+    /// A command completed, or the connection closed before it did. Its latency is
+    /// `completed_at - sent_at`.
+    fn command_completed(&self, _event: &CommandEvent) {}
+
+    /// The commands waiting to be written and those written but not yet completed,
+    /// whenever either number changes.
+    fn pipeline_depth(&self, _queued: usize, _in_flight: usize) {}
+}
+
+#[derive(Clone)]
+pub(crate) struct MetricsHook(pub(crate) Arc<dyn ConnectionMetrics>);
+
+impl std::fmt::Debug for MetricsHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MetricsHook")
+    }
+}
+
+/// Wraps `stream` to count its bytes if `hook` is set.
+pub(super) fn meter(stream: Box<dyn Transport>, hook: Option<&MetricsHook>) -> Box<dyn Transport> {
+    match hook {
+        Some(hook) => Box::new(Metered {
+            inner: stream,
+            metrics: hook.0.clone(),
+        }),
+        None => stream,
+    }
+}
+
+/// Reports the bytes read and written through it.
+struct Metered<S> {
+    inner: S,
+    metrics: Arc<dyn ConnectionMetrics>,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Metered<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut this.inner).poll_read(cx, buf);
+        let n = buf.filled().len() - before;
+        if n > 0 {
+            this.metrics.bytes_received(n);
+        }
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Metered<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = poll
+            && n > 0
+        {
+            this.metrics.bytes_sent(n);
+        }
+        poll
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,333 @@
+use anyhow::{Context as _, Result};
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::Client;
+use crate::AuthenticatedState;
+
+use imap::commands::FetchItem;
+use imap::parser::datetime;
+use imap::types::command::{SearchKey, SequenceSet};
+use imap::types::common::{DateTime, Flag, Uid};
+use imap::types::response::{FetchData, ListEntry};
+
+const STATE_HEADER: &str = "# mailux migrate state v1";
+
+/// Copies every folder and message from one account to another.
+///
+/// The folder tree is recreated on the destination, translating the hierarchy delimiter
+/// (a destination delimiter inside a source name component becomes `_`). Messages keep
+/// their flags and internal dates. Progress is recorded in a state file after every
+/// message, so an interrupted run picks up where it stopped; a folder whose UIDVALIDITY
+/// changed since the last run is copied again from the start.
+pub struct Migration<'a> {
+    source: &'a mut Client<AuthenticatedState>,
+    destination: &'a mut Client<AuthenticatedState>,
+    state_path: PathBuf,
+    batch_size: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MigrationReport {
+    pub folders: Vec<FolderReport>,
+}
+
+impl MigrationReport {
+    pub fn copied(&self) -> usize {
+        self.folders.iter().map(|f| f.copied).sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FolderReport {
+    pub source: String,
+    pub destination: String,
+    /// Whether the folder had to be created on the destination.
+    pub created: bool,
+    pub copied: usize,
+    /// Messages already copied by an earlier run.
+    pub skipped: usize,
+    /// Messages expunged from the source between the SEARCH and their FETCH.
+    pub vanished: usize,
+    /// Messages left for the next run. Copying the folder stops at the first message the
+    /// source still lists but returned without its UID or body, so that it is not lost.
+    pub remaining: usize,
+}
+
+impl<'a> Migration<'a> {
+    pub fn new(
+        source: &'a mut Client<AuthenticatedState>,
+        destination: &'a mut Client<AuthenticatedState>,
+        state_path: impl Into<PathBuf>,
+    ) -> Self {
+        Self {
+            source,
+            destination,
+            state_path: state_path.into(),
+            batch_size: 50,
+        }
+    }
+
+    /// Messages fetched from the source per UID FETCH (default 50).
+    pub fn batch_size(mut self, n: usize) -> Self {
+        self.batch_size = n.max(1);
+        self
+    }
+
+    pub async fn run(self) -> Result<MigrationReport> {
+        let mut state = MigrationState::load(&self.state_path).await?;
+        let folders = self.source.list("", "*").await?;
+        let existing = self.destination.list("", "*").await?;
+        let delimiter = match self.destination.list("", "").await?.first() {
+            Some(root) => root.delimiter,
+            None => existing.first().and_then(|e| e.delimiter),
+        };
+
+        let mut report = MigrationReport::default();
+        for folder in &folders {
+            if folder.has_attribute("\\NonExistent") {
+                continue;
+            }
+            let target = translate_name(&folder.name, folder.delimiter, delimiter);
+            let created = !existing.iter().any(|e| same_mailbox(&e.name, &target));
+            if created {
+                self.destination
+                    .create(&target)
+                    .await
+                    .with_context(|| format!("Failed to create {}", target))?;
+            }
+            let mut folder_report = FolderReport {
+                source: folder.name.clone(),
+                destination: target,
+                created,
+                copied: 0,
+                skipped: 0,
+                vanished: 0,
+                remaining: 0,
+            };
+            if !folder.has_attribute("\\Noselect") {
+                copy_folder(
+                    self.source,
+                    self.destination,
+                    folder,
+                    &mut folder_report,
+                    &mut state,
+                    &self.state_path,
+                    self.batch_size,
+                )
+                .await?;
+            }
+            tracing::info!(
+                folder = %folder_report.source,
+                copied = folder_report.copied,
+                skipped = folder_report.skipped,
+                vanished = folder_report.vanished,
+                remaining = folder_report.remaining,
+                "Folder migrated"
+            );
+            report.folders.push(folder_report);
+        }
+        Ok(report)
+    }
+}
+
+async fn copy_folder(
+    source: &mut Client<AuthenticatedState>,
+    destination: &mut Client<AuthenticatedState>,
+    folder: &ListEntry,
+    report: &mut FolderReport,
+    state: &mut MigrationState,
+    state_path: &Path,
+    batch_size: usize,
+) -> Result<()> {
+    let status = source.open(&folder.name, true, None).await?;
+    let validity = status.uid_validity.unwrap_or(0);
+    let last_uid = match state.folders.get(&folder.name) {
+        Some(&(v, last)) if v == validity => last,
+        Some(_) => {
+            tracing::warn!(folder = %folder.name, "UIDVALIDITY changed, copying folder again");
+            0
+        }
+        None => 0,
+    };
+
+    let mut uids: Vec<Uid> = source
+        .run_search(true, Vec::new())
+        .await?
+        .into_iter()
+        .map(Uid)
+        .collect();
+    uids.sort_unstable();
+    let pending: Vec<Uid> = uids
+        .iter()
+        .copied()
+        .filter(|&u| u > Uid(last_uid))
+        .collect();
+    report.skipped = uids.len() - pending.len();
+
+    for (n, chunk) in pending.chunks(batch_size).enumerate() {
+        let set = SequenceSet::from(chunk);
+        let items = vec![
+            FetchItem::Uid,
+            FetchItem::Flags,
+            FetchItem::InternalDate,
+            FetchItem::BodyPeekSection(String::new()),
+        ];
+        let mut messages: HashMap<Uid, SourceMessage> = source
+            .run_fetch(true, set, items)
+            .await?
+            .into_iter()
+            .filter_map(|(_seq, items)| SourceMessage::from_items(items))
+            .collect();
+
+        // Messages missing from the FETCH were either expunged since the search, and are
+        // passed over, or could not be read and are tried again by the next run.
+        let missing: Vec<Uid> = chunk
+            .iter()
+            .copied()
+            .filter(|uid| !messages.contains_key(uid))
+            .collect();
+        let unreadable: Vec<u32> = if missing.is_empty() {
+            Vec::new()
+        } else {
+            let keys = vec![SearchKey::Uid(SequenceSet::from(&missing[..]))];
+            source.run_search(true, keys).await?
+        };
+        for (i, &uid) in chunk.iter().enumerate() {
+            match messages.remove(&uid) {
+                Some(message) => {
+                    destination
+                        .append(
+                            &report.destination,
+                            message.flags,
+                            message.internal_date,
+                            &message.body,
+                        )
+                        .await
+                        .with_context(|| {
+                            format!("Failed to copy UID {} of {}", uid, folder.name)
+                        })?;
+                    report.copied += 1;
+                }
+                None if unreadable.contains(&uid.0) => {
+                    report.remaining = pending.len() - (n * batch_size + i);
+                    tracing::warn!(folder = %folder.name, %uid, "Could not read message; stopping the folder here");
+                    return Ok(());
+                }
+                None => report.vanished += 1,
+            }
+            state.folders.insert(folder.name.clone(), (validity, uid.0));
+            state.save(state_path).await?;
+        }
+    }
+    Ok(())
+}
+
+struct SourceMessage {
+    flags: Vec<Flag>,
+    internal_date: Option<DateTime>,
+    body: Bytes,
+}
+
+impl SourceMessage {
+    fn from_items(items: Vec<FetchData>) -> Option<(Uid, Self)> {
+        let mut uid = None;
+        let mut flags = Vec::new();
+        let mut internal_date = None;
+        let mut body = None;
+        for item in items {
+            match item {
+                FetchData::Uid(u) => uid = Some(u),
+                FetchData::Flags(f) => flags = f.into(),
+                FetchData::InternalDate(d) => internal_date = datetime::parse_date_time(&d),
+                FetchData::BodySection { section, data, .. } if section.is_empty() => body = data,
+                _ => {}
+            }
+        }
+        // \Recent is session state and cannot be set by APPEND.
+        flags.retain(|f| !matches!(f, Flag::Recent));
+        Some((
+            uid?,
+            Self {
+                flags,
+                internal_date,
+                body: body?,
+            },
+        ))
+    }
+}
+
+/// Maps a source mailbox name onto the destination hierarchy delimiter.
+fn translate_name(name: &str, from: Option<char>, to: Option<char>) -> String {
+    if name.eq_ignore_ascii_case("INBOX") {
+        return "INBOX".to_string();
+    }
+    match (from, to) {
+        (Some(from), Some(to)) if from != to => name
+            .split(from)
+            .map(|part| part.replace(to, "_"))
+            .collect::<Vec<_>>()
+            .join(&to.to_string()),
+        _ => name.to_string(),
+    }
+}
+
+/// INBOX is case-insensitive; every other name is compared exactly.
+fn same_mailbox(a: &str, b: &str) -> bool {
+    a == b || (a.eq_ignore_ascii_case("INBOX") && b.eq_ignore_ascii_case("INBOX"))
+}
+
+/// Per-folder progress: the UIDVALIDITY and the highest UID already copied.
+///
+/// Stored one folder per line as `<uidvalidity> <last uid> <folder name>`.
+#[derive(Debug, Default)]
+struct MigrationState {
+    folders: HashMap<String, (u32, u32)>,
+}
+
+impl MigrationState {
+    async fn load(path: &Path) -> Result<Self> {
+        let text = match tokio::fs::read_to_string(path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+        let mut folders = HashMap::new();
+        for line in text
+            .lines()
+            .filter(|l| !l.starts_with('#') && !l.is_empty())
+        {
+            let mut parts = line.splitn(3, ' ');
+            let (Some(validity), Some(last), Some(name)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                anyhow::bail!("Malformed migration state line: {}", line);
+            };
+            let validity = validity.parse().context("Malformed UIDVALIDITY in state")?;
+            let last = last.parse().context("Malformed UID in state")?;
+            folders.insert(name.to_string(), (validity, last));
+        }
+        Ok(Self { folders })
+    }
+
+    /// Writes the state next to `path` and renames it into place, so a crash never leaves
+    /// a truncated file.
+    async fn save(&self, path: &Path) -> Result<()> {
+        let mut text = format!("{}\n", STATE_HEADER);
+        let mut folders: Vec<_> = self.folders.iter().collect();
+        folders.sort();
+        for (name, (validity, last)) in folders {
+            text.push_str(&format!("{} {} {}\n", validity, last, name));
+        }
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, text)
+            .await
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        tokio::fs::rename(&tmp, path)
+            .await
+            .with_context(|| format!("Failed to replace {}", path.display()))
+    }
+}
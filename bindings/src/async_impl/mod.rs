@@ -1,4 +1,33 @@
+pub mod append;
+pub use append::{AppendMessage, AppendPipeline, MessageTooLarge};
 pub mod builder;
 pub use builder::Builder;
+pub mod cancel;
+pub use cancel::{CancellationToken, Cancelled};
 pub mod connector;
-pub use connector::{Connector, Client};
+pub mod dedup;
+pub mod events;
+pub mod idle;
+pub mod locks;
+pub mod messages;
+pub mod metrics;
+pub mod migrate;
+pub mod pool;
+pub mod reconnect;
+pub mod retry;
+pub mod shutdown;
+pub mod sink;
+pub mod timeout;
+pub use dedup::{DuplicateFinder, DuplicateGroup};
+pub use events::Events;
+pub use idle::IdleHandle;
+pub use locks::{MailboxGuard, MailboxLocks};
+pub use messages::Messages;
+pub use metrics::ConnectionMetrics;
+pub use migrate::{FolderReport, Migration, MigrationReport};
+pub use pool::Pool;
+pub use reconnect::{ConnectionLost, ReconnectPolicy};
+pub use retry::{error_kind, is_retryable};
+pub use sink::{EventForwarder, EventSink, ForwardStats};
+pub use timeout::TimedOut;
+pub use connector::{AuthMechanism, BufferStats, CommandEvent, Connector, Client, MailboxError, RawClient, RawStream, Transport};
@@ -0,0 +1,169 @@
+use anyhow::{Context as _, Result};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+use super::shutdown::logout_all;
+use super::{Client, Connector};
+use crate::AuthenticatedState;
+
+use imap::commands::FetchItem;
+use imap::types::command::SequenceSet;
+use imap::types::common::{Seq, Uid};
+use imap::types::response::FetchData;
+
+/// Longest sequence set in one UID FETCH, well under the usual command line limit.
+const MAX_SET_LEN: usize = 4 * 1024;
+
+/// Several authenticated connections to one account that share bulk UID FETCH work.
+///
+/// Many providers throttle each connection, so downloading a large mailbox over 3-5
+/// connections is much faster than over one. The UIDs are split into batches that idle
+/// connections take in turn, so a slow connection simply takes fewer of them.
+pub struct Pool {
+    clients: Vec<Client<AuthenticatedState>>,
+    batch_size: usize,
+}
+
+impl Pool {
+    /// Opens `size` connections with `connector` and logs each in, all at once.
+    ///
+    /// The connections share the connector's TLS configuration, so all but the first
+    /// can resume its TLS session. Fails if any connection fails.
+    pub async fn connect(
+        connector: &Connector,
+        user: &str,
+        pass: &str,
+        size: usize,
+    ) -> Result<Self> {
+        let mut tasks = JoinSet::new();
+        for index in 0..size.max(1) {
+            let connector = connector.clone();
+            let (user, pass) = (user.to_string(), pass.to_string());
+            tasks.spawn(async move {
+                let client = async { connector.connect().await?.login(&user, &pass).await };
+                (index, client.await)
+            });
+        }
+
+        let mut clients: Vec<Option<Client<AuthenticatedState>>> =
+            (0..size.max(1)).map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            let (index, client) = joined.context("Connection task panicked")?;
+            let client =
+                client.with_context(|| format!("Failed to open pool connection {}", index + 1))?;
+            clients[index] = Some(client);
+        }
+        Ok(Self::from_clients(clients.into_iter().flatten().collect()))
+    }
+
+    /// Pools connections that are already logged in.
+    pub fn from_clients(clients: Vec<Client<AuthenticatedState>>) -> Self {
+        Self {
+            clients,
+            batch_size: 500,
+        }
+    }
+
+    /// At most `n` UIDs per UID FETCH (default 500). Smaller batches spread the work more
+    /// evenly; larger ones cost fewer round trips.
+    pub fn batch_size(mut self, n: usize) -> Self {
+        self.batch_size = n.max(1);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    /// Fetches `items` for `uids` in `mailbox`, selecting it on every connection first.
+    ///
+    /// Returns each message's sequence number with its data items, ordered by sequence
+    /// number; include [`FetchItem::Uid`] to tell the messages apart by UID. The first
+    /// error stops the other connections after their current batch and is returned. A
+    /// connection whose task panicked is dropped from the pool.
+    pub async fn uid_fetch(
+        &mut self,
+        mailbox: &str,
+        uids: &[Uid],
+        items: Vec<FetchItem>,
+    ) -> Result<Vec<(Seq, Vec<FetchData>)>> {
+        if self.clients.is_empty() {
+            anyhow::bail!("Connection pool is empty");
+        }
+        let mut sorted = uids.to_vec();
+        sorted.sort_unstable();
+        sorted.dedup();
+        let batches: VecDeque<SequenceSet> = sorted
+            .chunks(self.batch_size)
+            .flat_map(|chunk| SequenceSet::batched(chunk, MAX_SET_LEN))
+            .collect();
+        let work = Arc::new(Mutex::new(batches));
+
+        let mut tasks = JoinSet::new();
+        for mut client in std::mem::take(&mut self.clients) {
+            let (work, items, mailbox) = (work.clone(), items.clone(), mailbox.to_string());
+            tasks.spawn(async move {
+                let result = async {
+                    let mut fetched = Vec::new();
+                    if work.lock().unwrap().is_empty() {
+                        return Ok(fetched);
+                    }
+                    client.ensure_selected(&mailbox).await?;
+                    loop {
+                        let Some(set) = work.lock().unwrap().pop_front() else {
+                            return Ok(fetched);
+                        };
+                        match client.run_fetch(true, set, items.clone()).await {
+                            Ok(batch) => fetched.extend(batch),
+                            Err(e) => {
+                                // Nobody else needs to keep going.
+                                work.lock().unwrap().clear();
+                                return Err(e);
+                            }
+                        }
+                    }
+                }
+                .await;
+                (client, result)
+            });
+        }
+
+        let mut fetched = Vec::new();
+        let mut first_error = None;
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((client, result)) => {
+                    self.clients.push(client);
+                    match result {
+                        Ok(batch) => fetched.extend(batch),
+                        Err(e) => {
+                            first_error.get_or_insert(e);
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Pool connection task failed: {}", e),
+            }
+        }
+        if let Some(e) = first_error {
+            return Err(e);
+        }
+        fetched.sort_by_key(|(seq, _)| *seq);
+        Ok(fetched)
+    }
+
+    /// Logs out of every connection, like [`logout_all`].
+    pub async fn logout(self, deadline: Duration) -> Vec<Result<()>> {
+        logout_all(self.clients, deadline).await
+    }
+
+    /// Hands the connections back, e.g. to use them separately.
+    pub fn into_clients(self) -> Vec<Client<AuthenticatedState>> {
+        self.clients
+    }
+}
@@ -0,0 +1,294 @@
+use anyhow::{Context as _, Result};
+use bytes::{Bytes, BytesMut};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+
+use super::connector::{
+    Framer, MailboxTracker, Options, Transport, ensure_ok, is_tagged_completion, read_greeting, write_command,
+    write_literal,
+};
+use super::metrics::meter;
+use super::timeout::{WriteTimeout, within};
+use crate::next_tag;
+
+use imap::commands::CommandBuilder;
+use imap::sasl;
+use imap::types::common::{Capabilities, Capability, SaslMechanism};
+
+/// Opens a new transport; the flag says whether the greeting is still to be read.
+pub(super) type Dial = Arc<
+    dyn Fn() -> Pin<Box<dyn Future<Output = Result<(Box<dyn Transport>, bool)>> + Send>>
+        + Send
+        + Sync,
+>;
+
+type CredentialsFn = dyn Fn() -> Result<(String, String)> + Send + Sync;
+
+/// When and how a connection built with
+/// [`Builder::reconnect`](crate::async_impl::Builder::reconnect) comes back after the
+/// transport fails.
+///
+/// On EOF, an IO error or a read timeout the run loop dials again, waits for the greeting,
+/// logs in, re-enables the extensions enabled with ENABLE and reopens the selected mailbox.
+/// Commands that were already sent fail with [`ConnectionLost`]; commands not yet sent are
+/// sent on the new connection. Sequence numbers are not stable across a reconnect, so
+/// callers that cached them should fetch them again.
+#[derive(Clone)]
+pub struct ReconnectPolicy {
+    credentials: Arc<CredentialsFn>,
+    max_attempts: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+}
+
+impl std::fmt::Debug for ReconnectPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReconnectPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_delay", &self.initial_delay)
+            .field("max_delay", &self.max_delay)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ReconnectPolicy {
+    /// Logs in again as `user` with `pass`.
+    pub fn with_login(user: &str, pass: &str) -> Self {
+        let credentials = (user.to_string(), pass.to_string());
+        Self::with_credentials(move || Ok(credentials.clone()))
+    }
+
+    /// Asks `credentials` for a user name and password (or app token) on every attempt,
+    /// e.g. to read a rotated secret.
+    pub fn with_credentials<F>(credentials: F) -> Self
+    where
+        F: Fn() -> Result<(String, String)> + Send + Sync + 'static,
+    {
+        Self {
+            credentials: Arc::new(credentials),
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+
+    /// Attempts before giving up and failing every pending command (default 5).
+    pub fn max_attempts(mut self, n: u32) -> Self {
+        self.max_attempts = n.max(1);
+        self
+    }
+
+    /// Delay before the second attempt, doubled for each further one up to `max` (default
+    /// 500 ms up to 30 s). The first attempt is made straight away.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_delay = initial;
+        self.max_delay = max.max(initial);
+        self
+    }
+}
+
+/// The error for a command that was sent before the connection failed and reconnected.
+///
+/// The server may or may not have carried it out. Retry it if doing so twice is harmless.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionLost;
+
+impl std::fmt::Display for ConnectionLost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("connection lost while the command was in flight; it may be retried")
+    }
+}
+
+impl std::error::Error for ConnectionLost {}
+
+/// What the run loop must restore on a new connection, learned from completed commands.
+#[derive(Debug, Default)]
+pub(super) struct SessionState {
+    authenticated: bool,
+    /// ENABLE commands that succeeded, without their tags.
+    enabled: Vec<String>,
+    /// The SELECT or EXAMINE that opened the current mailbox, without its tag.
+    selected: Option<String>,
+}
+
+impl SessionState {
+    /// Notes the outcome of a completed command.
+    pub(super) fn observe(&mut self, name: &str, command: &str, ok: bool) {
+        let untagged = || command.split_once(' ').map(|(_, rest)| rest.to_string());
+        match name {
+            "LOGIN" | "AUTHENTICATE" if ok => self.authenticated = true,
+            "ENABLE" if ok => self.enabled.extend(untagged()),
+            // A failed SELECT leaves no mailbox selected.
+            "SELECT" | "EXAMINE" => self.selected = if ok { untagged() } else { None },
+            "CLOSE" | "UNSELECT" if ok => self.selected = None,
+            _ => {}
+        }
+    }
+}
+
+/// A failed attempt, and whether another one could succeed.
+enum Attempt {
+    Retry(anyhow::Error),
+    GiveUp(anyhow::Error),
+}
+
+/// Dials until a connection is back in `session`'s state, following `policy`. Returns the
+/// transport and any bytes read past the last response.
+pub(super) async fn resume(
+    policy: &ReconnectPolicy,
+    dial: &Dial,
+    session: &SessionState,
+    mailbox: &MailboxTracker,
+    opts: &Options,
+) -> Result<(Box<dyn Transport>, BytesMut)> {
+    let mut delay = policy.initial_delay;
+    for attempt in 1..=policy.max_attempts {
+        if attempt > 1 {
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(policy.max_delay);
+        }
+        match restore(policy, dial, session, mailbox, opts).await {
+            Ok(restored) => {
+                tracing::info!(attempt, "Reconnected to IMAP server");
+                return Ok(restored);
+            }
+            Err(Attempt::GiveUp(e)) => return Err(e.context("Reconnecting failed")),
+            Err(Attempt::Retry(e)) => tracing::warn!(attempt, "Reconnect attempt failed: {:#}", e),
+        }
+    }
+    anyhow::bail!(
+        "Gave up reconnecting after {} attempts",
+        policy.max_attempts
+    )
+}
+
+async fn restore(
+    policy: &ReconnectPolicy,
+    dial: &Dial,
+    session: &SessionState,
+    mailbox: &MailboxTracker,
+    opts: &Options,
+) -> std::result::Result<(Box<dyn Transport>, BytesMut), Attempt> {
+    let (stream, greet) = dial().await.map_err(Attempt::Retry)?;
+    let mut conn = Conn {
+        stream: meter(
+            Box::new(WriteTimeout::new(stream, opts.write_timeout)),
+            opts.metrics.as_ref(),
+        ),
+        buf: BytesMut::with_capacity(1024),
+        framer: Framer::new(opts),
+    };
+
+    let capabilities = if greet {
+        let greeting = read_greeting(&mut conn.stream, &mut conn.buf, opts);
+        within(opts.greeting_timeout, "greeting", greeting)
+            .await
+            .map_err(Attempt::Retry)?
+    } else {
+        None
+    };
+    if !session.authenticated {
+        return Ok((conn.stream, conn.buf));
+    }
+
+    let (user, pass) = (policy.credentials)().map_err(Attempt::GiveUp)?;
+    let tag = next_tag();
+    if use_sasl_plain(capabilities.as_ref()) {
+        let command = CommandBuilder::new(&tag).authenticate("PLAIN").as_string();
+        let response = Bytes::from(sasl::plain_response(&user, &pass));
+        conn.step(opts, &tag, &command, Some(response), "AUTHENTICATE")
+            .await?;
+    } else {
+        let command = CommandBuilder::new(&tag)
+            .login()
+            .username(&user)
+            .password(&pass)
+            .as_string();
+        conn.step(opts, &tag, &command, None, "Login").await?;
+    }
+    for command in &session.enabled {
+        let tag = next_tag();
+        let command = format!("{} {}", tag, command);
+        conn.step(opts, &tag, &command, None, "ENABLE").await?;
+    }
+    if let Some(command) = &session.selected {
+        let tag = next_tag();
+        let command = format!("{} {}", tag, command);
+        let lines = conn.step(opts, &tag, &command, None, "SELECT").await?;
+        mailbox.observe("SELECT", &tag, &lines, true);
+    }
+    Ok((conn.stream, conn.buf))
+}
+
+/// A connection being restored.
+struct Conn {
+    stream: Box<dyn Transport>,
+    buf: BytesMut,
+    framer: Framer,
+}
+
+impl Conn {
+    /// Runs one command and returns its responses. A refusal gives up, since the server
+    /// would only repeat it.
+    async fn step(
+        &mut self,
+        opts: &Options,
+        tag: &str,
+        command: &str,
+        literal: Option<Bytes>,
+        what: &str,
+    ) -> std::result::Result<Vec<Bytes>, Attempt> {
+        let lines = within(opts.read_timeout, "read", self.exchange(tag, command, literal))
+            .await
+            .map_err(Attempt::Retry)?;
+        ensure_ok(&lines, tag, what).map_err(Attempt::GiveUp)?;
+        Ok(lines)
+    }
+
+    /// Sends a command and collects its responses, sending `literal` on the first
+    /// continuation request.
+    async fn exchange(
+        &mut self,
+        tag: &str,
+        command: &str,
+        mut literal: Option<Bytes>,
+    ) -> Result<Vec<Bytes>> {
+        write_command(&mut self.stream, command).await?;
+        let mut lines = Vec::new();
+        loop {
+            while let Some(line) = self.framer.next(&mut self.buf)? {
+                if line.starts_with(b"+")
+                    && let Some(literal) = literal.take()
+                {
+                    write_literal(&mut self.stream, &literal).await?;
+                    continue;
+                }
+                let done = is_tagged_completion(&line, tag);
+                lines.push(line);
+                if done {
+                    return Ok(lines);
+                }
+            }
+            self.framer.reserve(&mut self.buf)?;
+            let n = self
+                .stream
+                .read_buf(&mut self.buf)
+                .await
+                .context("Failed to read data from IMAP server")?;
+            if n == 0 {
+                anyhow::bail!("IMAP server closed connection unexpectedly");
+            }
+        }
+    }
+}
+
+/// LOGIN unless the server disabled it and offers AUTH=PLAIN instead.
+fn use_sasl_plain(capabilities: Option<&Capabilities>) -> bool {
+    capabilities.is_some_and(|caps| {
+        caps.contains(&Capability::LoginDisabled)
+            && caps.contains(&Capability::Auth(SaslMechanism::Plain))
+    })
+}
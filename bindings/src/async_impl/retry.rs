@@ -0,0 +1,35 @@
+//! Telling transient failures from permanent ones without matching on messages.
+
+use super::{ConnectionLost, MailboxError, MessageTooLarge, TimedOut};
+
+use imap::{ErrorKind, ImapError};
+
+/// The kind of the first error in `err`'s chain that this crate knows, or `None` for
+/// plain errors such as a missing server capability or a
+/// [`Cancelled`](super::Cancelled) connection.
+pub fn error_kind(err: &anyhow::Error) -> Option<ErrorKind> {
+    err.chain().find_map(|cause| {
+        if let Some(e) = cause.downcast_ref::<ImapError>() {
+            Some(e.kind())
+        } else if cause.is::<TimedOut>() {
+            Some(ErrorKind::Timeout)
+        } else if cause.is::<ConnectionLost>() {
+            Some(ErrorKind::Connection)
+        } else if cause.is::<MailboxError>() || cause.is::<MessageTooLarge>() {
+            Some(ErrorKind::Refused)
+        } else {
+            cause
+                .downcast_ref::<std::io::Error>()
+                .map(|e| match e.kind() {
+                    std::io::ErrorKind::TimedOut => ErrorKind::Timeout,
+                    _ => ErrorKind::Connection,
+                })
+        }
+    })
+}
+
+/// Whether the operation that failed with `err` may succeed if tried again later, e.g.
+/// after a timeout, a BYE or `NO [UNAVAILABLE]`; see [`ErrorKind::is_retryable`].
+pub fn is_retryable(err: &anyhow::Error) -> bool {
+    error_kind(err).is_some_and(ErrorKind::is_retryable)
+}
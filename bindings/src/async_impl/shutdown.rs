@@ -0,0 +1,49 @@
+use anyhow::Result;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+use super::Client;
+use super::connector::Request;
+
+/// Logs out of every connection at once and returns one result per client, in input order.
+///
+/// Each connection first finishes the commands already queued on it. A connection that has
+/// not completed LOGOUT within `deadline` has its transport closed and reports an error. IDLE
+/// handles and other watchers borrow their client, so they are already stopped by the time
+/// the client can be passed here.
+pub async fn logout_all<State>(clients: Vec<Client<State>>, deadline: Duration) -> Vec<Result<()>>
+where
+    State: Send + Sync + 'static,
+{
+    let mut tasks = JoinSet::new();
+    let count = clients.len();
+    for (index, client) in clients.into_iter().enumerate() {
+        tasks.spawn(async move {
+            // Dropping the LOGOUT future would leave the run loop waiting on the socket.
+            let closer = client.command_sender();
+            let result = match tokio::time::timeout(deadline, client.logout()).await {
+                Ok(result) => result,
+                Err(_) => {
+                    let _ = closer.send(Request::Close).await;
+                    Err(anyhow::anyhow!(
+                        "LOGOUT did not complete within {:?}",
+                        deadline
+                    ))
+                }
+            };
+            (index, result)
+        });
+    }
+
+    let mut results: Vec<Option<Result<()>>> = (0..count).map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, result)) => results[index] = Some(result),
+            Err(e) => tracing::warn!("LOGOUT task failed: {}", e),
+        }
+    }
+    results
+        .into_iter()
+        .map(|r| r.unwrap_or_else(|| Err(anyhow::anyhow!("LOGOUT task panicked"))))
+        .collect()
+}
@@ -0,0 +1,151 @@
+use anyhow::{Context as _, Result};
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tokio_stream::{Stream, StreamExt};
+
+use imap::types::response::IdleEvent;
+
+/// Somewhere to deliver mailbox events, such as a webhook or a message queue producer.
+///
+/// A failed delivery is retried with the same batch, so sinks should tolerate
+/// duplicates.
+pub trait EventSink: Send {
+    fn deliver(&mut self, batch: &[IdleEvent]) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Hands batches to another task, e.g. one that owns an HTTP client.
+impl EventSink for mpsc::Sender<Vec<IdleEvent>> {
+    async fn deliver(&mut self, batch: &[IdleEvent]) -> Result<()> {
+        self.send(batch.to_vec())
+            .await
+            .context("Event receiver was dropped")
+    }
+}
+
+/// Totals from [`EventForwarder::run`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ForwardStats {
+    pub batches: usize,
+    pub events: usize,
+    /// Deliveries that failed and were tried again.
+    pub retries: usize,
+}
+
+/// Forwards events from an [`IdleHandle`](super::IdleHandle), or any stream of them, to an
+/// [`EventSink`], for services that bridge IMAP into their own infrastructure.
+///
+/// Events are grouped into batches of up to `batch_size`, and a batch is sent at the
+/// latest `flush_interval` after its first event. A failed delivery is retried with
+/// exponential backoff; once `max_attempts` is used up, [`EventForwarder::run`] fails.
+pub struct EventForwarder<S> {
+    sink: S,
+    batch_size: usize,
+    flush_interval: Duration,
+    max_attempts: u32,
+    backoff: Duration,
+}
+
+impl<S: EventSink> EventForwarder<S> {
+    pub fn new(sink: S) -> Self {
+        Self {
+            sink,
+            batch_size: 100,
+            flush_interval: Duration::from_secs(1),
+            max_attempts: 5,
+            backoff: Duration::from_millis(500),
+        }
+    }
+
+    /// Most events per delivery (default 100).
+    pub fn batch_size(mut self, n: usize) -> Self {
+        self.batch_size = n.max(1);
+        self
+    }
+
+    /// Longest an event waits for its batch to fill (default 1 s).
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Delivery attempts per batch (default 5) and the wait before the first retry
+    /// (default 500 ms), doubled after each failure.
+    pub fn retry(mut self, max_attempts: u32, backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.backoff = backoff;
+        self
+    }
+
+    /// Forwards events until `events` ends or yields an error, then delivers what is left.
+    ///
+    /// An error from the stream is returned after the final delivery.
+    pub async fn run<E>(&mut self, mut events: E) -> Result<ForwardStats>
+    where
+        E: Stream<Item = Result<IdleEvent>> + Unpin,
+    {
+        let mut stats = ForwardStats::default();
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut deadline: Option<Instant> = None;
+        let outcome = loop {
+            let flush_at = deadline.unwrap_or_else(Instant::now);
+            tokio::select! {
+                event = events.next() => match event {
+                    Some(Ok(event)) => {
+                        deadline.get_or_insert_with(|| Instant::now() + self.flush_interval);
+                        batch.push(event);
+                        if batch.len() >= self.batch_size {
+                            self.flush(&mut batch, &mut stats).await?;
+                            deadline = None;
+                        }
+                    }
+                    Some(Err(e)) => break Err(e),
+                    None => break Ok(()),
+                },
+                _ = tokio::time::sleep_until(flush_at), if deadline.is_some() => {
+                    self.flush(&mut batch, &mut stats).await?;
+                    deadline = None;
+                }
+            }
+        };
+        self.flush(&mut batch, &mut stats).await?;
+        outcome.map(|()| stats)
+    }
+
+    async fn flush(&mut self, batch: &mut Vec<IdleEvent>, stats: &mut ForwardStats) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match self.sink.deliver(batch).await {
+                Ok(()) => break,
+                Err(e) if attempt < self.max_attempts => {
+                    tracing::warn!(
+                        "Event delivery failed (attempt {}/{}): {:#}",
+                        attempt,
+                        self.max_attempts,
+                        e
+                    );
+                    stats.retries += 1;
+                    attempt += 1;
+                    tokio::time::sleep(backoff).await;
+                    backoff = backoff.saturating_mul(2);
+                }
+                Err(e) => {
+                    return Err(e.context(format!(
+                        "Dropping {} events after {} delivery attempts",
+                        batch.len(),
+                        attempt
+                    )));
+                }
+            }
+        }
+        stats.batches += 1;
+        stats.events += batch.len();
+        batch.clear();
+        Ok(())
+    }
+}
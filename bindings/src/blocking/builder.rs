@@ -1,25 +1,56 @@
 use rustls::StreamOwned;
-use std::io::BufRead;
-use std::io::Write;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::marker::PhantomData;
-use std::net::TcpStream;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
 
-use crate::{AuthenticatedStateState, ConnectedStateState};
-use imap::types::Envelope;
+use crate::{AuthenticatedState, ConnectedState, next_tag};
+use imap::commands::{CommandBuilder, FetchItem};
+use imap::framing::DEFAULT_MAX_LITERAL;
+use imap::parser::{self, capability, fetch, literal_announcement};
+use imap::types::command::SequenceSet;
+use imap::types::common::Capabilities;
+use imap::types::response::{Envelope, FetchData};
 use imap::{ImapError, tls};
 
+type TlsStream = StreamOwned<rustls::ClientConnection, TcpStream>;
+
+/// Longest response line accepted, not counting literal data, as in the async client.
+const LINE_CAP: usize = 8 * 1024;
+
 pub struct Builder {
     addr: String,
     conn_type: crate::ConnectionType,
+    server_name: Option<String>,
+    tls_config: Option<Arc<rustls::ClientConfig>>,
+    timeouts: Timeouts,
+    max_literal: usize,
 }
 
 pub struct Connector {
     addr: String,
     conn_type: crate::ConnectionType,
+    server_name: Option<String>,
+    tls_config: Option<Arc<rustls::ClientConfig>>,
+    timeouts: Timeouts,
+    max_literal: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Timeouts {
+    connect: Option<Duration>,
+    greeting: Option<Duration>,
+    read: Option<Duration>,
+    write: Option<Duration>,
 }
 
 pub struct Client<State> {
-    stream: StreamOwned<rustls::ClientConnection, TcpStream>,
+    stream: BufReader<TlsStream>,
+    /// Capabilities from the last CAPABILITY response or login completion.
+    capabilities: Option<Capabilities>,
+    /// Largest literal accepted from the server.
+    max_literal: usize,
     _state: PhantomData<State>,
 }
 
@@ -28,6 +59,10 @@ impl Builder {
         Self {
             addr: addr.to_string(),
             conn_type: crate::ConnectionType::Tls,
+            server_name: None,
+            tls_config: None,
+            timeouts: Timeouts::default(),
+            max_literal: DEFAULT_MAX_LITERAL,
         }
     }
 
@@ -46,10 +81,59 @@ impl Builder {
         self
     }
 
+    /// Present and verify `name` in the TLS handshake instead of the host in the address.
+    pub fn server_name(mut self, name: &str) -> Self {
+        self.server_name = Some(name.to_string());
+        self
+    }
+
+    /// Use a caller-provided TLS configuration, e.g. one trusting a private CA, instead of
+    /// the bundled web PKI roots.
+    pub fn tls_config(mut self, config: Arc<rustls::ClientConfig>) -> Self {
+        self.tls_config = Some(config);
+        self
+    }
+
+    /// Give up on each TCP connection attempt after `timeout`. No limit by default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.connect = Some(timeout);
+        self
+    }
+
+    /// Give up if the TLS handshake and greeting take longer than `timeout` to arrive.
+    /// Defaults to the read timeout.
+    pub fn greeting_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.greeting = Some(timeout);
+        self
+    }
+
+    /// Fail a read that waits longer than `timeout` for the server.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.read = Some(timeout);
+        self
+    }
+
+    /// Fail a write that blocks longer than `timeout`.
+    pub fn write_timeout(mut self, timeout: Duration) -> Self {
+        self.timeouts.write = Some(timeout);
+        self
+    }
+
+    /// Fail with [`ImapError::LiteralTooLarge`] if the server announces a literal larger
+    /// than `bytes`, rather than reading it. 64 MiB by default.
+    pub fn max_literal_size(mut self, bytes: usize) -> Self {
+        self.max_literal = bytes;
+        self
+    }
+
     pub fn build(self) -> Connector {
         Connector {
             addr: self.addr,
             conn_type: self.conn_type,
+            server_name: self.server_name,
+            tls_config: self.tls_config,
+            timeouts: self.timeouts,
+            max_literal: self.max_literal,
         }
     }
 
@@ -65,22 +149,31 @@ impl Connector {
 
         match self.conn_type {
             crate::ConnectionType::Tls => {
-                let config = tls::create_tls_config();
-                let server_name = tls::parse_server_name(&self.addr)?;
+                let config = self.tls_config.clone().unwrap_or_else(tls::create_tls_config);
+                let server_name = match &self.server_name {
+                    Some(name) => rustls::pki_types::ServerName::try_from(name.clone())?,
+                    None => tls::parse_server_name(&self.addr)?,
+                };
 
-                let conn = rustls::ClientConnection::new(config, server_name)?;
-                let sock = TcpStream::connect(&self.addr)?;
-                let mut stream = rustls::StreamOwned::new(conn, sock);
+                let conn = rustls::ClientConnection::new(config, server_name)
+                    .map_err(|e| ImapError::ConnectionFailed(e.to_string()))?;
+                let sock = self.tcp_connect()?;
+                sock.set_read_timeout(self.timeouts.greeting.or(self.timeouts.read))?;
+                sock.set_write_timeout(self.timeouts.write)?;
+                let mut stream = BufReader::new(rustls::StreamOwned::new(conn, sock));
 
                 // Since we have to read the greeting, we don't have to derive the TLS handshake
                 // manually. The first read will derive the TLS handshake implicitly.
-                Self::handle_greeting(&mut stream)?;
+                let capabilities = Self::handle_greeting(&mut stream)?;
+                stream.get_ref().sock.set_read_timeout(self.timeouts.read)?;
 
                 tracing::info!("TLS connection established");
 
                 Ok(Client {
                     stream,
-                    state: PhantomData,
+                    capabilities,
+                    max_literal: self.max_literal,
+                    _state: PhantomData,
                 })
             }
             _ => Err(ImapError::ConnectionFailed(
@@ -89,76 +182,212 @@ impl Connector {
         }
     }
 
+    /// Connects to the first address that accepts, within the connect timeout if set.
+    fn tcp_connect(&self) -> Result<TcpStream, ImapError> {
+        let Some(timeout) = self.timeouts.connect else {
+            return Ok(TcpStream::connect(&self.addr)?);
+        };
+        let mut last_err = None;
+        for addr in self.addr.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(sock) => return Ok(sock),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(match last_err {
+            Some(e) => e.into(),
+            None => ImapError::InvalidAddressFormat(self.addr.clone()),
+        })
+    }
+
+    /// Checks the greeting and returns the capabilities it announced, if any.
     fn handle_greeting(
-        stream: &mut StreamOwned<rustls::ClientConnection, TcpStream>,
-    ) -> Result<(), ImapError> {
-        let mut line = String::new();
-        stream.read_line(&mut line)?;
+        stream: &mut BufReader<TlsStream>,
+    ) -> Result<Option<Capabilities>, ImapError> {
+        let mut line = Vec::new();
+        read_line(stream, &mut line)?;
 
-        if !line.starts_with("* OK") {
-            return Err(ImapError::ConnectionFailed(line));
+        if !line.starts_with(b"* OK") {
+            return Err(ImapError::ConnectionFailed(
+                String::from_utf8_lossy(&line).into_owned(),
+            ));
         }
 
+        Ok(capability::harvest_capabilities(&line))
+    }
+}
+
+/// Appends one line, with its LF, to `out`; fails beyond `LINE_CAP` bytes or at the end of
+/// the stream.
+fn read_line(stream: &mut impl BufRead, out: &mut Vec<u8>) -> Result<(), ImapError> {
+    let start = out.len();
+    let n = stream.take(LINE_CAP as u64).read_until(b'\n', out)?;
+    if out[start..].ends_with(b"\n") {
         Ok(())
+    } else if n == LINE_CAP {
+        Err(ImapError::ConnectionFailed(format!(
+            "IMAP response line exceeded maximum length of {} bytes",
+            LINE_CAP
+        )))
+    } else {
+        Err(ImapError::ConnectionFailed(
+            "IMAP server closed connection unexpectedly".to_string(),
+        ))
     }
 }
 
-pub fn connect_tls(addr: &str) -> Result<Client<ConnectedStateState>, ImapError> {
+pub fn connect_tls(addr: &str) -> Result<Client<ConnectedState>, ImapError> {
     Builder::new(addr).tls().build().connect()
 }
 
-pub fn connect_starttls(addr: &str) -> Result<Client<ConnectedStateState>, ImapError> {
+pub fn connect_starttls(addr: &str) -> Result<Client<ConnectedState>, ImapError> {
     Builder::new(addr).starttls().build().connect()
 }
 
-pub fn connect_plain(addr: &str) -> Result<Client<ConnectedStateState>, ImapError> {
+pub fn connect_plain(addr: &str) -> Result<Client<ConnectedState>, ImapError> {
     Builder::new(addr).plain().build().connect()
 }
 
-impl Client<ConnectedStateState> {
+impl<State> Client<State> {
+    /// Reads one response line, including the payload of any literals it announces.
+    ///
+    /// Lines are capped at `LINE_CAP` bytes and literals at the configured maximum.
+    fn read_response(&mut self) -> Result<Vec<u8>, ImapError> {
+        let mut response = Vec::new();
+        loop {
+            let start = response.len();
+            read_line(&mut self.stream, &mut response)?;
+            match literal_announcement(&response[start..]) {
+                Some(n) => {
+                    let at = response.len();
+                    let end = at
+                        .checked_add(n)
+                        .filter(|_| n <= self.max_literal)
+                        .ok_or(ImapError::LiteralTooLarge {
+                            size: n,
+                            max: self.max_literal,
+                        })?;
+                    response.resize(end, 0);
+                    self.stream.read_exact(&mut response[at..])?;
+                }
+                None => return Ok(response),
+            }
+        }
+    }
+
+    /// Returns the capabilities the server currently advertises.
+    ///
+    /// Uses the list sent with the greeting or login response if there was one; otherwise
+    /// asks once and remembers the answer until the connection state changes.
+    pub fn capabilities(&mut self) -> Result<Capabilities, ImapError> {
+        if let Some(caps) = &self.capabilities {
+            return Ok(caps.clone());
+        }
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag).capability().as_string();
+        let lines = self.run_command(&tag, &cmd, "CAPABILITY")?;
+        let caps = capability::parse_capabilities(&lines.concat());
+        self.capabilities = Some(caps.clone());
+        Ok(caps)
+    }
+
+    /// Sends a command and collects every response up to and including its tagged
+    /// completion, failing unless the completion is OK.
+    fn run_command(
+        &mut self,
+        tag: &str,
+        command: &str,
+        what: &str,
+    ) -> Result<Vec<Vec<u8>>, ImapError> {
+        let stream = self.stream.get_mut();
+        stream.write_all(command.as_bytes())?;
+        stream.flush()?;
+
+        let mut lines = Vec::new();
+        loop {
+            let line = self.read_response()?;
+            let done = line.starts_with(tag.as_bytes()) && line.get(tag.len()) == Some(&b' ');
+            lines.push(line);
+            if done {
+                break;
+            }
+        }
+
+        let last = lines.last().map(Vec::as_slice).unwrap_or_default();
+        let ok = last
+            .get(tag.len() + 1..)
+            .and_then(|rest| rest.split(|&b| b == b' ' || b == b'\r').next())
+            .is_some_and(|word| word.eq_ignore_ascii_case(b"OK"));
+        if !ok {
+            return Err(
+                parser::mailbox::parse_completion_error(last, tag).unwrap_or_else(|| {
+                    ImapError::CommandFailed(format!(
+                        "{} failed: {}",
+                        what,
+                        String::from_utf8_lossy(last).trim_end()
+                    ))
+                }),
+            );
+        }
+        Ok(lines)
+    }
+}
+
+impl Client<ConnectedState> {
     #[tracing::instrument(skip(self, pass))]
     pub fn login(
         mut self,
         user: &str,
         pass: &str,
-    ) -> Result<Client<AuthenticatedStateState>, ImapError> {
+    ) -> Result<Client<AuthenticatedState>, ImapError> {
         tracing::info!("Attempting IMAP login");
 
-        self.stream
-            .write_all(format!("a001 LOGIN {} {}\r\n", user, pass).as_bytes())?;
-
-        let mut line = String::new();
-        self.stream.read_line(&mut line)?;
-
-        if !line.starts_with("* CAPABILITY") {
-            return Err(ImapError::Connection(line));
-        }
-
-        line.clear();
-        self.stream.read_line(&mut line)?;
-
-        if !line.starts_with("a001 OK") {
-            return Err(ImapError::ConnectionFailed(line));
-        }
+        let tag = next_tag();
+        let cmd = CommandBuilder::new(&tag)
+            .login()
+            .username(user)
+            .password(pass)
+            .as_string();
+        let lines = self.run_command(&tag, &cmd, "Login")?;
 
         tracing::info!("IMAP login successful");
 
         Ok(Client {
             stream: self.stream,
-            state: PhantomData,
+            capabilities: capability::harvest_capabilities(&lines.concat()),
+            max_literal: self.max_literal,
+            _state: PhantomData,
         })
     }
 }
 
-impl Client<AuthenticatedStateState> {
-    pub fn fetch(&mut self, _mailbox: &str, _id: u32) -> Result<Vec<Envelope>, ImapError> {
-        Ok(vec![
-            Envelope {
-                subject: Some("Subject1".to_string()),
-            },
-            Envelope {
-                subject: Some("Subject2".to_string()),
-            },
-        ])
+impl Client<AuthenticatedState> {
+    /// Fetches the envelopes of the messages in `set` (sequence numbers) in `mailbox`.
+    pub fn fetch(
+        &mut self,
+        mailbox: &str,
+        set: impl Into<SequenceSet>,
+    ) -> Result<Vec<Envelope>, ImapError> {
+        let sel_tag = next_tag();
+        let select_cmd = CommandBuilder::new(&sel_tag).select(mailbox).as_string();
+        self.run_command(&sel_tag, &select_cmd, "SELECT")?;
+
+        let fetch_tag = next_tag();
+        let fetch_cmd = CommandBuilder::new(&fetch_tag)
+            .fetch(set.into())
+            .add_item(FetchItem::Envelope)
+            .as_string();
+        let lines = self.run_command(&fetch_tag, &fetch_cmd, "FETCH")?;
+
+        let joined = lines.concat();
+        let envelopes = fetch::fetch_envelopes(&joined)
+            .into_iter()
+            .filter_map(|(_num, data)| match data {
+                FetchData::Envelope(env) => Some(env),
+                _ => None,
+            })
+            .collect();
+
+        Ok(envelopes)
     }
 }
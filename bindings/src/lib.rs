@@ -5,12 +5,15 @@ pub use async_impl::Builder;
 
 #[cfg(feature = "blocking")]
 pub mod blocking;
+
+#[cfg(feature = "test-util")]
+pub mod test_util;
 #[cfg(feature = "blocking")]
 pub use blocking::Builder;
 
 use std::sync::atomic::{AtomicU32, Ordering};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum ConnectionType {
     Tls,
     StartTls,
@@ -19,6 +22,51 @@ pub enum ConnectionType {
 
 pub struct ConnectedState;
 pub struct AuthenticatedState;
+/// Logged in with a mailbox selected read-write, so message commands such as SEARCH,
+/// STORE and EXPUNGE are allowed.
+pub struct SelectedState;
+/// Logged in with a mailbox opened read-only by EXAMINE: SEARCH and FETCH are allowed, but
+/// nothing that changes flags or removes messages.
+pub struct ExaminedState;
+
+/// The states of a logged-in session, [`AuthenticatedState`], [`SelectedState`] and
+/// [`ExaminedState`].
+///
+/// Sealed: commands available in all of them are implemented once for
+/// `Client<S: LoggedIn>`.
+pub trait LoggedIn: sealed::Sealed {
+    /// Whether commands that take a mailbox name open it with EXAMINE, so an examined
+    /// session never turns read-write behind the caller's back.
+    #[doc(hidden)]
+    const EXAMINED: bool = false;
+}
+
+impl LoggedIn for AuthenticatedState {}
+impl LoggedIn for SelectedState {}
+impl LoggedIn for ExaminedState {
+    const EXAMINED: bool = true;
+}
+
+/// The logged-in states that may change messages, [`AuthenticatedState`] and
+/// [`SelectedState`]; an [`ExaminedState`] session has no such methods.
+pub trait Writable: LoggedIn {}
+
+impl Writable for AuthenticatedState {}
+impl Writable for SelectedState {}
+
+/// The states with a mailbox open, [`SelectedState`] and [`ExaminedState`].
+pub trait Selected: LoggedIn {}
+
+impl Selected for SelectedState {}
+impl Selected for ExaminedState {}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::AuthenticatedState {}
+    impl Sealed for super::SelectedState {}
+    impl Sealed for super::ExaminedState {}
+}
 
 static TAG_COUNTER: AtomicU32 = AtomicU32::new(1);
 
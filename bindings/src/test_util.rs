@@ -0,0 +1,332 @@
+//! Test support: a fault-injecting transport and a scripted in-memory IMAP server.
+//!
+//! Only compiled with the `test-util` feature.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, ready};
+use std::time::Duration;
+
+use tokio::io::{
+    AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader, DuplexStream, ReadBuf,
+};
+use tokio::time::{Instant, Sleep};
+
+/// Faults injected by a [`FaultyStream`].
+#[derive(Debug, Clone, Default)]
+pub struct Faults {
+    read_splits: Vec<usize>,
+    max_read_chunk: Option<usize>,
+    max_write_chunk: Option<usize>,
+    read_delay: Option<Duration>,
+    disconnect_after: Option<usize>,
+}
+
+impl Faults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// End reads exactly at these offsets of the inbound byte stream.
+    pub fn split_reads_at(mut self, offsets: impl IntoIterator<Item = usize>) -> Self {
+        self.read_splits.extend(offsets);
+        self.read_splits.sort_unstable();
+        self
+    }
+
+    /// Never return more than `n` bytes from a single read.
+    pub fn max_read_chunk(mut self, n: usize) -> Self {
+        self.max_read_chunk = Some(n.max(1));
+        self
+    }
+
+    /// Accept at most `n` bytes per write, forcing partial writes.
+    pub fn max_write_chunk(mut self, n: usize) -> Self {
+        self.max_write_chunk = Some(n.max(1));
+        self
+    }
+
+    /// Sleep before every read.
+    pub fn read_delay(mut self, delay: Duration) -> Self {
+        self.read_delay = Some(delay);
+        self
+    }
+
+    /// Report EOF once `n` inbound bytes have been delivered; later writes fail.
+    pub fn disconnect_after(mut self, n: usize) -> Self {
+        self.disconnect_after = Some(n);
+        self
+    }
+}
+
+/// Wraps a transport and applies [`Faults`] to it.
+pub struct FaultyStream<S> {
+    inner: S,
+    faults: Faults,
+    splits: VecDeque<usize>,
+    read_pos: usize,
+    delay: Option<Pin<Box<Sleep>>>,
+    delay_elapsed: bool,
+}
+
+impl<S> FaultyStream<S> {
+    pub fn new(inner: S, faults: Faults) -> Self {
+        Self {
+            inner,
+            splits: faults.read_splits.iter().copied().collect(),
+            faults,
+            read_pos: 0,
+            delay: None,
+            delay_elapsed: false,
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn disconnected(&self) -> bool {
+        self.faults
+            .disconnect_after
+            .is_some_and(|limit| self.read_pos >= limit)
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for FaultyStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.disconnected() {
+            return Poll::Ready(Ok(()));
+        }
+
+        if let Some(delay) = this.faults.read_delay
+            && !this.delay_elapsed
+        {
+            let sleep = this
+                .delay
+                .get_or_insert_with(|| Box::pin(tokio::time::sleep(delay)));
+            ready!(sleep.as_mut().poll(cx));
+            this.delay = None;
+            this.delay_elapsed = true;
+        }
+
+        let mut limit = buf.remaining();
+        if let Some(n) = this.faults.max_read_chunk {
+            limit = limit.min(n);
+        }
+        while this.splits.front().is_some_and(|&at| at <= this.read_pos) {
+            this.splits.pop_front();
+        }
+        if let Some(&at) = this.splits.front() {
+            limit = limit.min(at - this.read_pos);
+        }
+        if let Some(at) = this.faults.disconnect_after {
+            limit = limit.min(at - this.read_pos);
+        }
+
+        let mut tmp = vec![0u8; limit];
+        let mut limited = ReadBuf::new(&mut tmp);
+        ready!(Pin::new(&mut this.inner).poll_read(cx, &mut limited))?;
+        let n = limited.filled().len();
+        buf.put_slice(limited.filled());
+        this.read_pos += n;
+        this.delay_elapsed = false;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for FaultyStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.disconnected() {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        let n = this
+            .faults
+            .max_write_chunk
+            .map_or(buf.len(), |n| n.min(buf.len()));
+        Pin::new(&mut this.inner).poll_write(cx, &buf[..n])
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+type Handler = dyn Fn(&str, &str) -> Vec<u8> + Send + Sync;
+
+/// Network conditions simulated by a [`MockServer`].
+#[derive(Debug, Clone, Copy)]
+struct Shaping {
+    latency: Duration,
+    jitter: Duration,
+    bytes_per_sec: Option<u64>,
+    seed: u64,
+}
+
+impl Default for Shaping {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            jitter: Duration::ZERO,
+            bytes_per_sec: None,
+            seed: 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+}
+
+/// Scripted in-memory IMAP server.
+///
+/// Every command line received is split into its tag and the remainder, and passed to the
+/// handler, whose return value is written back verbatim (it must include the tagged completion).
+///
+/// Responses can be delayed and throttled to mimic a real link, see
+/// [`MockServer::latency`]. Delays overlap like on a network, so pipelined commands pay the
+/// latency once rather than per command.
+pub struct MockServer {
+    greeting: Vec<u8>,
+    handler: Arc<Handler>,
+    shaping: Shaping,
+}
+
+impl MockServer {
+    pub fn new<F>(handler: F) -> Self
+    where
+        F: Fn(&str, &str) -> Vec<u8> + Send + Sync + 'static,
+    {
+        Self {
+            greeting: b"* OK IMAP4rev1 mock server ready\r\n".to_vec(),
+            handler: Arc::new(handler),
+            shaping: Shaping::default(),
+        }
+    }
+
+    /// Deliver each response (and the greeting) `latency` after the command arrived.
+    pub fn latency(mut self, latency: Duration) -> Self {
+        self.shaping.latency = latency;
+        self
+    }
+
+    /// Add a random delay of up to `jitter` to each response. Responses still arrive in
+    /// order.
+    pub fn jitter(mut self, jitter: Duration) -> Self {
+        self.shaping.jitter = jitter;
+        self
+    }
+
+    /// Limit the server-to-client direction to `bytes_per_sec`.
+    pub fn bandwidth(mut self, bytes_per_sec: u64) -> Self {
+        self.shaping.bytes_per_sec = Some(bytes_per_sec.max(1));
+        self
+    }
+
+    /// Seed for the jitter, so runs are reproducible.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.shaping.seed = seed.max(1);
+        self
+    }
+
+    /// Replace the default `* OK` greeting; anything may be sent, including banners.
+    pub fn greeting(mut self, greeting: impl Into<Vec<u8>>) -> Self {
+        self.greeting = greeting.into();
+        self
+    }
+
+    /// Spawn the server and return the client end of the connection.
+    pub fn spawn(self) -> DuplexStream {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(async move {
+            let _ = self.serve(server).await;
+        });
+        client
+    }
+
+    async fn serve(self, stream: DuplexStream) -> io::Result<()> {
+        let (read, write) = tokio::io::split(stream);
+        let mut reader = BufReader::new(read);
+        let (out_tx, out_rx) = tokio::sync::mpsc::unbounded_channel();
+        let writer = tokio::spawn(write_shaped(write, out_rx, self.shaping));
+        let mut jitter = Jitter(self.shaping.seed);
+        let mut last_due = Instant::now();
+        let mut send = |out: Vec<u8>| {
+            let due = Instant::now() + self.shaping.latency + jitter.next(self.shaping.jitter);
+            // A stream never reorders, so a response cannot overtake the previous one.
+            last_due = last_due.max(due);
+            out_tx.send((last_due, out)).is_ok()
+        };
+        send(self.greeting.clone());
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await? == 0 {
+                break;
+            }
+            let trimmed = line.trim_end_matches(['\r', '\n']);
+            let (tag, rest) = trimmed.split_once(' ').unwrap_or((trimmed, ""));
+            let out = (self.handler)(tag, rest);
+            if !send(out) || rest.eq_ignore_ascii_case("LOGOUT") {
+                break;
+            }
+        }
+        drop(out_tx);
+        writer.await.unwrap_or(Ok(()))
+    }
+}
+
+/// Writes each response once it is due, at no more than the configured bandwidth.
+async fn write_shaped<W: AsyncWrite + Unpin>(
+    mut write: W,
+    mut responses: tokio::sync::mpsc::UnboundedReceiver<(Instant, Vec<u8>)>,
+    shaping: Shaping,
+) -> io::Result<()> {
+    while let Some((due, out)) = responses.recv().await {
+        tokio::time::sleep_until(due).await;
+        match shaping.bytes_per_sec {
+            None => write.write_all(&out).await?,
+            Some(rate) => {
+                // About 100 chunks per second keeps the pacing smooth.
+                let chunk = usize::try_from(rate / 100).unwrap_or(usize::MAX).max(1);
+                for piece in out.chunks(chunk) {
+                    write.write_all(piece).await?;
+                    write.flush().await?;
+                    let secs = piece.len() as f64 / rate as f64;
+                    tokio::time::sleep(Duration::from_secs_f64(secs)).await;
+                }
+            }
+        }
+        write.flush().await?;
+    }
+    Ok(())
+}
+
+/// xorshift64, enough for reproducible jitter.
+struct Jitter(u64);
+
+impl Jitter {
+    fn next(&mut self, max: Duration) -> Duration {
+        if max.is_zero() {
+            return Duration::ZERO;
+        }
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        let nanos = u64::try_from(max.as_nanos()).unwrap_or(u64::MAX);
+        Duration::from_nanos(self.0 % nanos.saturating_add(1))
+    }
+}
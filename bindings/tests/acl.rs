@@ -0,0 +1,118 @@
+//! Access control lists (RFC 4314).
+
+use std::sync::{Arc, Mutex};
+
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+use bindings::{AuthenticatedState, Builder};
+
+use imap::types::command::AclChange;
+use imap::types::common::Rights;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+async fn connect(capabilities: &'static str) -> (Client<AuthenticatedState>, Log) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = match cmd {
+            "CAPABILITY" => format!("* CAPABILITY {}\r\n", capabilities),
+            "GETACL \"Shared\"" => {
+                "* ACL Shared owner lrswipkxtea \"Fred Smith\" lrs -bob w\r\n".to_string()
+            }
+            "LISTRIGHTS \"Shared\" \"fred\"" => {
+                "* LISTRIGHTS Shared fred la r s w i p k x t e\r\n".to_string()
+            }
+            // RFC 2086 servers still report the obsolete `c` and `d` rights.
+            "MYRIGHTS \"Shared\"" => "* MYRIGHTS Shared lrscd\r\n".to_string(),
+            _ => String::new(),
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    (client.login("user", "pass").await.unwrap(), received)
+}
+
+#[tokio::test]
+async fn reads_acls_and_rights() {
+    let (mut session, _) = connect("IMAP4rev1 ACL").await;
+
+    let acl = session.get_acl("Shared").await.unwrap();
+    let entries: Vec<_> = acl
+        .iter()
+        .map(|e| (e.identifier.as_str(), e.rights.to_string()))
+        .collect();
+    assert_eq!(
+        entries,
+        [
+            ("owner", "lrswipkxtea".to_string()),
+            ("Fred Smith", "lrs".to_string()),
+            ("-bob", "w".to_string()),
+        ]
+    );
+
+    let rights = session.list_rights("Shared", "fred").await.unwrap();
+    assert_eq!(rights.identifier, "fred");
+    assert_eq!(rights.required, Rights::LOOKUP | Rights::ADMINISTER);
+    assert_eq!(rights.optional.len(), 9);
+    assert_eq!(rights.optional[0], Rights::READ);
+
+    let mine = session.my_rights("Shared").await.unwrap();
+    assert_eq!(mine.to_string(), "lrskxte");
+
+    // A reply without the untagged response is an error.
+    let err = session.my_rights("Other").await.unwrap_err();
+    assert!(err.to_string().contains("No MYRIGHTS response"));
+}
+
+#[tokio::test]
+async fn changes_rights() {
+    let (mut session, received) = connect("IMAP4rev1 ACL").await;
+
+    let read = Rights::LOOKUP | Rights::READ;
+    session
+        .set_acl("Shared", "fred", AclChange::Replace(read))
+        .await
+        .unwrap();
+    session
+        .set_acl("Shared", "fred", AclChange::Add(Rights::WRITE))
+        .await
+        .unwrap();
+    session
+        .set_acl("Shared", "Fred Smith", AclChange::Remove(Rights::SEEN))
+        .await
+        .unwrap();
+    session.delete_acl("Shared", "bob").await.unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[received.len() - 4..],
+        [
+            "SETACL \"Shared\" \"fred\" \"lr\"",
+            "SETACL \"Shared\" \"fred\" \"+w\"",
+            "SETACL \"Shared\" \"Fred Smith\" \"-s\"",
+            "DELETEACL \"Shared\" \"bob\"",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn requires_the_capability() {
+    let (mut session, received) = connect("IMAP4rev1").await;
+
+    let err = session.get_acl("Shared").await.unwrap_err();
+    assert!(err.to_string().contains("ACL"));
+    assert!(session.delete_acl("Shared", "bob").await.is_err());
+    assert!(
+        !received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|cmd| cmd.contains("ACL"))
+    );
+}
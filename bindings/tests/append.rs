@@ -0,0 +1,116 @@
+//! APPEND waits for the server's continuation before sending the message literal.
+
+use std::time::Duration;
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::task::JoinHandle;
+
+use bindings::Builder;
+use imap::types::common::{DateTime, Flag};
+
+const MESSAGE: &[u8] = b"Subject: hi\r\n\r\nline one\r\n\x00 binary-safe";
+
+/// Answers LOGIN, then each APPEND with a continuation (or, if `refuse`, a tagged NO).
+/// Returns every APPEND line with the literal that followed it.
+fn serve(stream: DuplexStream, refuse: bool) -> JoinHandle<Vec<(String, Vec<u8>)>> {
+    tokio::spawn(async move {
+        let (read, mut write) = tokio::io::split(stream);
+        let mut reader = BufReader::new(read);
+        write.write_all(b"* OK ready\r\n").await.unwrap();
+        let mut appends = Vec::new();
+        let mut line = String::new();
+        while reader.read_line(&mut line).await.unwrap() > 0 {
+            let (tag, cmd) = line.trim_end().split_once(' ').unwrap();
+            let tag = tag.to_string();
+            if !cmd.starts_with("APPEND") {
+                let reply = format!("{} OK done\r\n", tag);
+                write.write_all(reply.as_bytes()).await.unwrap();
+                line.clear();
+                continue;
+            }
+            let command = cmd.to_string();
+            let size: usize = command
+                .rsplit_once('{')
+                .and_then(|(_, n)| n.strip_suffix('}'))
+                .unwrap()
+                .parse()
+                .unwrap();
+            // Nothing may follow until the server asks for the literal.
+            let mut byte = [0u8];
+            let early = tokio::time::timeout(Duration::from_millis(30), reader.read(&mut byte));
+            assert!(early.await.is_err(), "literal sent before the continuation");
+
+            if refuse {
+                let reply = format!("{} NO [TRYCREATE] No such mailbox\r\n", tag);
+                write.write_all(reply.as_bytes()).await.unwrap();
+                appends.push((command, Vec::new()));
+            } else {
+                write
+                    .write_all(b"+ Ready for literal data\r\n")
+                    .await
+                    .unwrap();
+                let mut literal = vec![0u8; size + 2];
+                reader.read_exact(&mut literal).await.unwrap();
+                assert!(literal.ends_with(b"\r\n"));
+                literal.truncate(size);
+                appends.push((command, literal));
+                let reply = format!("{} OK APPEND completed\r\n", tag);
+                write.write_all(reply.as_bytes()).await.unwrap();
+            }
+            line.clear();
+        }
+        appends
+    })
+}
+
+#[tokio::test]
+async fn literal_follows_the_continuation() {
+    let (client_end, server_end) = tokio::io::duplex(4096);
+    let server = serve(server_end, false);
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(client_end)
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let date = DateTime::new(1_700_000_000, 3600);
+    let uid = session
+        .append("Sent", vec![Flag::Seen], Some(date), MESSAGE)
+        .await
+        .unwrap();
+    assert_eq!(uid, None);
+    session.logout().await.unwrap();
+
+    let appends = server.await.unwrap();
+    assert_eq!(
+        appends[0].0,
+        format!(
+            "APPEND \"Sent\" (\\Seen) \"14-Nov-2023 23:13:20 +0100\" {{{}}}",
+            MESSAGE.len()
+        )
+    );
+    assert_eq!(appends[0].1, MESSAGE);
+}
+
+#[tokio::test]
+async fn refusal_skips_the_literal() {
+    let (client_end, server_end) = tokio::io::duplex(4096);
+    let server = serve(server_end, true);
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(client_end)
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let err = session
+        .append("Missing", Vec::new(), None, MESSAGE)
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("TRYCREATE"));
+    // The connection is still usable: the literal was never sent.
+    session.create("Missing").await.unwrap();
+    session.logout().await.unwrap();
+    assert_eq!(server.await.unwrap().len(), 1);
+}
@@ -0,0 +1,67 @@
+//! APPENDLIMIT (RFC 7889) is enforced before any bytes are sent.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::async_impl::{AppendMessage, AppendPipeline, MessageTooLarge};
+use bindings::test_util::MockServer;
+use bytes::Bytes;
+
+#[tokio::test]
+async fn oversized_append_is_rejected_locally() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        match verb.as_str() {
+            "CAPABILITY" => format!(
+                "* CAPABILITY IMAP4rev1 APPENDLIMIT=10\r\n{} OK CAPABILITY completed\r\n",
+                tag
+            )
+            .into_bytes(),
+            "LOGIN" | "APPEND" => format!("{} OK {} completed\r\n", tag, verb).into_bytes(),
+            _ => format!("{} BAD unknown\r\n", tag).into_bytes(),
+        }
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    assert_eq!(session.append_limit().await.unwrap(), Some(10));
+
+    let err = session
+        .append("INBOX", Vec::new(), None, b"eleven byte")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<MessageTooLarge>(),
+        Some(&MessageTooLarge {
+            size: 11,
+            limit: 10
+        })
+    );
+
+    let mut pipeline = AppendPipeline::new(vec![session]);
+    let results = pipeline
+        .upload(
+            "INBOX",
+            vec![AppendMessage {
+                flags: Vec::new(),
+                internal_date: None,
+                body: Bytes::from_static(b"much too long"),
+            }],
+        )
+        .await
+        .unwrap();
+    assert!(results[0].as_ref().unwrap_err().is::<MessageTooLarge>());
+    assert!(
+        !received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c.starts_with("APPEND"))
+    );
+}
@@ -0,0 +1,185 @@
+//! AppendPipeline: bulk uploads with LITERAL+ and MULTIAPPEND.
+
+use bytes::Bytes;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::task::JoinHandle;
+
+use bindings::async_impl::{AppendMessage, AppendPipeline, Client};
+use bindings::{AuthenticatedState, Builder};
+use imap::types::common::{Flag, Uid};
+
+/// One APPEND as received: the command with its literals cut out, the literals, and
+/// whether the server had to send a continuation for them.
+#[derive(Debug)]
+struct Received {
+    command: String,
+    literals: Vec<Vec<u8>>,
+    continuations: usize,
+}
+
+/// Advertises `capabilities`, sends a continuation for each synchronizing literal, and
+/// assigns UIDs from 11 on. An APPEND with a `reject` literal is refused.
+fn serve(stream: DuplexStream, capabilities: &'static str) -> JoinHandle<Vec<Received>> {
+    tokio::spawn(async move {
+        let (read, mut write) = tokio::io::split(stream);
+        let mut reader = BufReader::new(read);
+        write.write_all(b"* OK ready\r\n").await.unwrap();
+        let mut appends = Vec::new();
+        let mut next_uid = 11;
+        let mut line = String::new();
+        while reader.read_line(&mut line).await.unwrap() > 0 {
+            let (tag, first) = line.trim_end().split_once(' ').unwrap();
+            let tag = tag.to_string();
+            let mut received = Received {
+                command: first.to_string(),
+                literals: Vec::new(),
+                continuations: 0,
+            };
+            // Each line ending in a literal is continued after it.
+            let mut tail = first.to_string();
+            while let Some((_, size)) = tail.rsplit_once('{') {
+                let Some(size) = size.strip_suffix('}') else {
+                    break;
+                };
+                let (size, sync) = match size.strip_suffix('+') {
+                    Some(size) => (size.parse().unwrap(), false),
+                    None => (size.parse().unwrap(), true),
+                };
+                if sync {
+                    received.continuations += 1;
+                    write.write_all(b"+ go ahead\r\n").await.unwrap();
+                }
+                let mut literal = vec![0u8; size];
+                reader.read_exact(&mut literal).await.unwrap();
+                received.literals.push(literal);
+                line.clear();
+                reader.read_line(&mut line).await.unwrap();
+                tail = line.trim_end().to_string();
+                received.command.push_str(&tail);
+            }
+            let reply = if received.command.starts_with("APPEND") {
+                let count = received.literals.len();
+                let reply = if received.literals.iter().any(|l| l == b"reject") {
+                    format!("{} NO [OVERQUOTA] Over quota\r\n", tag)
+                } else {
+                    let first = next_uid;
+                    next_uid += count as u32;
+                    format!(
+                        "{} OK [APPENDUID 9 {}:{}] done\r\n",
+                        tag,
+                        first,
+                        next_uid - 1
+                    )
+                };
+                appends.push(received);
+                reply
+            } else if received.command == "CAPABILITY" {
+                format!("* CAPABILITY {}\r\n{} OK done\r\n", capabilities, tag)
+            } else {
+                format!("{} OK done\r\n", tag)
+            };
+            write.write_all(reply.as_bytes()).await.unwrap();
+            line.clear();
+        }
+        appends
+    })
+}
+
+async fn connect(
+    capabilities: &'static str,
+) -> (Client<AuthenticatedState>, JoinHandle<Vec<Received>>) {
+    let (client_end, server_end) = tokio::io::duplex(64 * 1024);
+    let server = serve(server_end, capabilities);
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(client_end)
+        .await
+        .unwrap();
+    (client.login("user", "pass").await.unwrap(), server)
+}
+
+fn messages(bodies: &[&'static str]) -> Vec<AppendMessage> {
+    bodies
+        .iter()
+        .map(|body| AppendMessage {
+            flags: vec![Flag::Seen],
+            internal_date: None,
+            body: Bytes::from_static(body.as_bytes()),
+        })
+        .collect()
+}
+
+async fn finish(
+    pipeline: AppendPipeline,
+    servers: Vec<JoinHandle<Vec<Received>>>,
+) -> Vec<Vec<Received>> {
+    for session in pipeline.into_sessions() {
+        session.logout().await.unwrap();
+    }
+    let mut received = Vec::new();
+    for server in servers {
+        received.push(server.await.unwrap());
+    }
+    received
+}
+
+#[tokio::test]
+async fn multiappend_groups_messages_without_continuations() {
+    let (session, server) = connect("IMAP4rev1 LITERAL+ MULTIAPPEND UIDPLUS").await;
+    let mut pipeline = AppendPipeline::new(vec![session]).multiappend_batch(2);
+    let results = pipeline
+        .upload("Archive", messages(&["one", "two", "three"]))
+        .await
+        .unwrap();
+    let uids: Vec<_> = results.into_iter().map(|r| r.unwrap()).collect();
+    assert_eq!(uids, [Some(Uid(11)), Some(Uid(12)), Some(Uid(13))]);
+
+    let received = finish(pipeline, vec![server]).await.remove(0);
+    assert_eq!(received.len(), 2);
+    assert_eq!(
+        received[0].command,
+        "APPEND \"Archive\" (\\Seen) {3+} (\\Seen) {3+}"
+    );
+    assert_eq!(received[0].literals, [b"one".to_vec(), b"two".to_vec()]);
+    assert_eq!(received[1].command, "APPEND \"Archive\" (\\Seen) {5+}");
+    assert!(received.iter().all(|r| r.continuations == 0));
+}
+
+#[tokio::test]
+async fn a_rejected_multiappend_fails_all_its_messages() {
+    let (session, server) = connect("IMAP4rev1 LITERAL+ MULTIAPPEND").await;
+    let mut pipeline = AppendPipeline::new(vec![session]).multiappend_batch(2);
+    let results = pipeline
+        .upload("Archive", messages(&["ok", "reject", "fine"]))
+        .await
+        .unwrap();
+    for result in &results[..2] {
+        let err = result.as_ref().unwrap_err();
+        assert!(format!("{:#}", err).contains("Over quota"), "{:#}", err);
+    }
+    assert_eq!(results[2].as_ref().unwrap(), &Some(Uid(11)));
+    finish(pipeline, vec![server]).await;
+}
+
+#[tokio::test]
+async fn plain_servers_get_one_synchronizing_append_per_message() {
+    let (first, first_server) = connect("IMAP4rev1").await;
+    let (second, second_server) = connect("IMAP4rev1").await;
+    let mut pipeline = AppendPipeline::new(vec![first, second]);
+    let bodies = ["a", "b", "c", "d", "e"];
+    let results = pipeline.upload("INBOX", messages(&bodies)).await.unwrap();
+    assert_eq!(results.len(), bodies.len());
+    assert!(results.iter().all(|r| r.is_ok()));
+
+    // Shared between the sessions, each message sent exactly once.
+    let mut sent: Vec<Vec<u8>> = Vec::new();
+    for received in finish(pipeline, vec![first_server, second_server]).await {
+        for append in received {
+            assert_eq!(append.literals.len(), 1);
+            assert_eq!(append.continuations, 1);
+            sent.extend(append.literals);
+        }
+    }
+    sent.sort();
+    assert_eq!(sent, bodies.map(|b| b.as_bytes().to_vec()));
+}
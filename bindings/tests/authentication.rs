@@ -0,0 +1,146 @@
+//! Logging in with AUTHENTICATE PLAIN and with negotiated mechanisms.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::async_impl::AuthMechanism;
+use bindings::test_util::MockServer;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+/// A server advertising `caps` that completes the PLAIN exchange with `plain` and LOGIN
+/// with `login`, logging each command and SASL response it receives.
+fn server(caps: &str, plain: &'static str, login: &'static str) -> (MockServer, Log) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let pending = Mutex::new(None::<String>);
+    let server = MockServer::new(move |tag, cmd| {
+        let mut pending = pending.lock().unwrap();
+        if let Some(auth_tag) = pending.take() {
+            // The base64 response to the continuation, alone on its line.
+            log.lock().unwrap().push(format!("response {}", tag));
+            return format!("{} {}\r\n", auth_tag, plain).into_bytes();
+        }
+        let name = cmd.split(' ').next().unwrap_or_default();
+        log.lock().unwrap().push(name.to_string());
+        match name {
+            "AUTHENTICATE" => {
+                *pending = Some(tag.to_string());
+                b"+ \r\n".to_vec()
+            }
+            "LOGIN" => format!("{} {}\r\n", tag, login).into_bytes(),
+            _ => format!("{} OK done\r\n", tag).into_bytes(),
+        }
+    })
+    .greeting(format!("* OK [CAPABILITY IMAP4rev1 {}] ready\r\n", caps));
+    (server, received)
+}
+
+#[tokio::test]
+async fn authenticate_plain_answers_the_continuation() {
+    let (server, log) = server("AUTH=PLAIN LOGINDISABLED", "OK done", "BAD Not expected");
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.authenticate_plain("user", "pass").await.unwrap();
+    session.logout().await.unwrap();
+    assert_eq!(
+        log.lock().unwrap()[..2],
+        ["AUTHENTICATE", "response AHVzZXIAcGFzcw=="]
+    );
+}
+
+#[tokio::test]
+async fn authenticate_plain_reports_a_refusal() {
+    let (server, _) = server(
+        "AUTH=PLAIN",
+        "NO [AUTHENTICATIONFAILED] Invalid",
+        "BAD Not expected",
+    );
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let err = client
+        .authenticate_plain("user", "wrong")
+        .await
+        .err()
+        .unwrap();
+    assert!(format!("{:#}", err).contains("AUTHENTICATIONFAILED"));
+}
+
+#[tokio::test]
+async fn tries_only_offered_mechanisms_in_preferred_order() {
+    let (mock, log) = server("AUTH=PLAIN", "OK done", "OK done");
+    let client = Builder::new("mock:143")
+        .preferred_auth(vec![AuthMechanism::Login, AuthMechanism::Plain])
+        .build()
+        .connect_stream(mock.spawn())
+        .await
+        .unwrap();
+    let (session, mechanism) = client.authenticate("user", "pass").await.unwrap();
+    assert_eq!(mechanism, AuthMechanism::Login);
+    session.logout().await.unwrap();
+    assert_eq!(log.lock().unwrap()[0], "LOGIN");
+
+    // LOGINDISABLED takes LOGIN out of the list.
+    let (mock, log) = server("AUTH=PLAIN LOGINDISABLED", "OK done", "BAD Not expected");
+    let client = Builder::new("mock:143")
+        .preferred_auth(vec![AuthMechanism::Login, AuthMechanism::Plain])
+        .build()
+        .connect_stream(mock.spawn())
+        .await
+        .unwrap();
+    let (_, mechanism) = client.authenticate("user", "pass").await.unwrap();
+    assert_eq!(mechanism, AuthMechanism::Plain);
+    assert!(!log.lock().unwrap().iter().any(|c| c == "LOGIN"));
+}
+
+#[tokio::test]
+async fn fails_when_no_preferred_mechanism_is_offered() {
+    let (server, log) = server("LOGINDISABLED", "BAD Not expected", "BAD Not expected");
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let err = client.authenticate("user", "pass").await.err().unwrap();
+    assert!(err.to_string().contains("None of the preferred mechanisms"));
+    assert!(log.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn falls_back_when_a_mechanism_fails() {
+    let (server, log) = server("AUTH=PLAIN", "NO Mechanism unavailable", "OK done");
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let (_, mechanism) = client.authenticate("user", "pass").await.unwrap();
+    assert_eq!(mechanism, AuthMechanism::Login);
+    assert_eq!(
+        log.lock().unwrap()[..],
+        ["AUTHENTICATE", "response AHVzZXIAcGFzcw==", "LOGIN"]
+    );
+}
+
+#[tokio::test]
+async fn does_not_retry_rejected_credentials() {
+    let (server, log) = server(
+        "AUTH=PLAIN",
+        "NO [AUTHENTICATIONFAILED] Invalid credentials",
+        "OK done",
+    );
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let err = client.authenticate("user", "wrong").await.err().unwrap();
+    assert!(format!("{:#}", err).contains("Plain authentication rejected"));
+    assert!(!log.lock().unwrap().iter().any(|c| c == "LOGIN"));
+}
@@ -0,0 +1,106 @@
+//! Fetching only the readable text part of a message, chosen from its BODYSTRUCTURE.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::common::Uid;
+
+/// multipart/mixed: an alternative (quoted-printable plain, base64 html) and a PDF.
+const STRUCTURE: &str = concat!(
+    "* 1 FETCH (UID 3 BODYSTRUCTURE (",
+    "((\"TEXT\" \"PLAIN\" (\"CHARSET\" \"iso-8859-1\") NIL NIL \"QUOTED-PRINTABLE\" 12 1 NIL NIL NIL NIL)",
+    "(\"TEXT\" \"HTML\" (\"CHARSET\" \"utf-8\") NIL NIL \"BASE64\" 16 1 NIL NIL NIL NIL) ",
+    "\"ALTERNATIVE\" (\"BOUNDARY\" \"b2\") NIL NIL NIL)",
+    "(\"APPLICATION\" \"PDF\" NIL NIL NIL \"BASE64\" 12 NIL (\"ATTACHMENT\" (\"FILENAME\" \"report.pdf\")) NIL NIL) ",
+    "\"MIXED\" (\"BOUNDARY\" \"b1\") NIL NIL NIL))\r\n",
+);
+
+fn reply(cmd: &str) -> &'static str {
+    if !cmd.starts_with("UID FETCH 3 ") {
+        ""
+    } else if cmd.contains("BODYSTRUCTURE") {
+        STRUCTURE
+    } else if cmd.contains("BODY.PEEK[1.1]") {
+        "* 1 FETCH (UID 3 BODY[1.1] {13}\r\nGr=FC=DFe =\r\n)\r\n"
+    } else if cmd.contains("BODY.PEEK[1.2]") {
+        "* 1 FETCH (UID 3 BODY[1.2] {16}\r\nPGI+SGk8L2I+Cg==)\r\n"
+    } else {
+        ""
+    }
+}
+
+#[tokio::test]
+async fn downloads_only_the_chosen_part() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        format!("{}{} OK done\r\n", reply(cmd), tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let plain = session
+        .fetch_best_body(Uid(3), false)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!plain.html);
+    assert_eq!(plain.text, "Grüße ");
+
+    let html = session
+        .fetch_best_body(Uid(3), true)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(html.html);
+    assert_eq!(html.text, "<b>Hi</b>\n");
+
+    // The attachment is never downloaded, and peeking leaves `\Seen` alone.
+    let received = received.lock().unwrap();
+    let fetches: Vec<_> = received.iter().filter(|c| c.contains("FETCH")).collect();
+    assert_eq!(
+        fetches,
+        [
+            "UID FETCH 3 (UID BODYSTRUCTURE)",
+            "UID FETCH 3 (UID BODY.PEEK[1.1])",
+            "UID FETCH 3 (UID BODYSTRUCTURE)",
+            "UID FETCH 3 (UID BODY.PEEK[1.2])",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn missing_messages_have_no_body() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        format!("{}{} OK done\r\n", reply(cmd), tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    assert!(
+        session
+            .fetch_best_body(Uid(9), false)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    // No structure came back, so no section is requested.
+    let received = received.lock().unwrap();
+    assert_eq!(received.last().unwrap(), "UID FETCH 9 (UID BODYSTRUCTURE)");
+}
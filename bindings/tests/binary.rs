@@ -0,0 +1,66 @@
+//! BINARY (RFC 3516): `literal8` uploads and decoded section fetches.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::AuthenticatedState;
+use bindings::Builder;
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+use imap::types::common::Uid;
+
+const BODY: &[u8] = b"nul \0 and \x7f bytes";
+
+async fn session(received: Arc<Mutex<Vec<String>>>) -> Client<AuthenticatedState> {
+    let append_tag = Arc::new(Mutex::new(None::<String>));
+    let server = MockServer::new(move |tag, cmd| {
+        received.lock().unwrap().push(format!("{} {}", tag, cmd));
+        if let Some(tag) = append_tag.lock().unwrap().take() {
+            // The literal, ended by the CRLF that completes the command.
+            return format!("{} OK [APPENDUID 1 7] APPEND completed\r\n", tag).into_bytes();
+        }
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body: &[u8] = match verb.as_str() {
+            "LOGIN" => b"",
+            "CAPABILITY" => b"* CAPABILITY IMAP4rev1 BINARY UIDPLUS\r\n",
+            "SELECT" => b"* 1 EXISTS\r\n",
+            "APPEND" => {
+                *append_tag.lock().unwrap() = Some(tag.to_string());
+                return b"+ Ready for literal\r\n".to_vec();
+            }
+            "UID" => b"* 1 FETCH (UID 7 BINARY[2] ~{5}\r\n\x00\x01\xff\r\n)\r\n",
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        let mut out = body.to_vec();
+        out.extend_from_slice(format!("{} OK {} completed\r\n", tag, verb).as_bytes());
+        out
+    })
+    .greeting("* OK [CAPABILITY IMAP4rev1 BINARY UIDPLUS] ready\r\n");
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    client.login("user", "pass").await.unwrap()
+}
+
+#[tokio::test]
+async fn append_binary_sends_literal8() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let mut session = session(received.clone()).await;
+    let uid = session
+        .append_binary("INBOX", Vec::new(), None, BODY)
+        .await
+        .unwrap();
+    assert_eq!(uid, Some(Uid(7)));
+    let received = received.lock().unwrap();
+    let append = received.iter().find(|l| l.contains("APPEND")).unwrap();
+    assert!(append.ends_with(&format!("APPEND \"INBOX\" ~{{{}}}", BODY.len())));
+}
+
+#[tokio::test]
+async fn fetch_binary_returns_decoded_section() {
+    let session = session(Arc::new(Mutex::new(Vec::new()))).await;
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    let data = session.fetch_binary(Uid(7), "2").await.unwrap().unwrap();
+    assert_eq!(&data[..], b"\x00\x01\xff\r\n");
+}
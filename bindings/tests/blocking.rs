@@ -0,0 +1,140 @@
+//! The blocking client against a local TLS server on a thread.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+
+use imap::ImapError;
+
+const CERT: &[u8] = include_bytes!("tls/localhost.der");
+const KEY: &[u8] = include_bytes!("tls/localhost.key.der");
+
+fn trusting_ca() -> Arc<rustls::ClientConfig> {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in CertificateDer::pem_slice_iter(include_bytes!("tls/ca.pem")) {
+        roots.add(cert.unwrap()).unwrap();
+    }
+    let config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Arc::new(config)
+}
+
+fn reply(tag: &str, command: &str) -> String {
+    let body = match command {
+        "LOGIN \"user\" \"pass\"" => {
+            return format!("{} OK [CAPABILITY IMAP4rev1 IDLE] Logged in\r\n", tag);
+        }
+        "SELECT \"Missing\"" => return format!("{} NO [NONEXISTENT] No such mailbox\r\n", tag),
+        "SELECT \"Huge\"" => "* 1 FETCH (BODY[] {18446744073709551615}\r\n".to_string(),
+        "SELECT \"Big\"" => "* 1 FETCH (BODY[] {17}\r\n".to_string(),
+        "SELECT \"Long\"" => format!("* OK {}\r\n", "x".repeat(9000)),
+        "SELECT \"INBOX\"" => "* 2 EXISTS\r\n* OK [UIDVALIDITY 7] ok\r\n".to_string(),
+        "FETCH 1:2 (ENVELOPE)" => concat!(
+            "* 1 FETCH (ENVELOPE (NIL \"Hello\" ((NIL NIL \"ann\" \"example.org\")) ",
+            "NIL NIL NIL NIL NIL NIL \"<1@example.org>\"))\r\n",
+            // A literal subject, with a CRLF inside it.
+            "* 2 FETCH (ENVELOPE (NIL {14}\r\nline 1\r\nline 2 NIL NIL NIL NIL NIL NIL NIL NIL))\r\n",
+        )
+        .to_string(),
+        _ => String::new(),
+    };
+    format!("{}{} OK done\r\n", body, tag)
+}
+
+/// Serves one TLS connection on a thread, answering with [`reply`]. Returns the port and
+/// the commands received.
+fn serve() -> (u16, Arc<Mutex<Vec<String>>>) {
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![CertificateDer::from(CERT.to_vec())],
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(KEY.to_vec())),
+        )
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let seen = log.clone();
+    std::thread::spawn(move || {
+        let (sock, _) = listener.accept()?;
+        let conn = rustls::ServerConnection::new(Arc::new(config)).unwrap();
+        let mut stream = BufReader::new(rustls::StreamOwned::new(conn, sock));
+        stream.get_mut().write_all(b"* OK test server ready\r\n")?;
+        let mut line = String::new();
+        while stream.read_line(&mut line)? > 0 {
+            let (tag, command) = line.trim_end().split_once(' ').unwrap_or(("*", ""));
+            seen.lock().unwrap().push(command.to_string());
+            let response = reply(tag, command);
+            stream.get_mut().write_all(response.as_bytes())?;
+            line.clear();
+        }
+        Ok::<_, std::io::Error>(())
+    });
+    (port, log)
+}
+
+#[test]
+fn logs_in_and_fetches_envelopes() {
+    let (port, received) = serve();
+    let client = Builder::new(&format!("localhost:{}", port))
+        .tls_config(trusting_ca())
+        .connect()
+        .unwrap();
+    let mut session = client.login("user", "pass").unwrap();
+    // Harvested from the login response, so no CAPABILITY is sent.
+    assert!(session.capabilities().unwrap().has("IDLE"));
+
+    let envelopes = session.fetch("INBOX", 1..=2).unwrap();
+    assert_eq!(envelopes.len(), 2);
+    assert_eq!(envelopes[0].subject.as_deref(), Some("Hello"));
+    assert_eq!(envelopes[0].from[0].to_string(), "ann@example.org");
+    assert_eq!(envelopes[0].message_id.as_deref(), Some("<1@example.org>"));
+    assert_eq!(envelopes[1].subject.as_deref(), Some("line 1\r\nline 2"));
+
+    let err = session.fetch("Missing", 1).unwrap_err();
+    assert!(matches!(err, ImapError::No { .. }), "{:?}", err);
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        [
+            "LOGIN \"user\" \"pass\"",
+            "SELECT \"INBOX\"",
+            "FETCH 1:2 (ENVELOPE)",
+            "SELECT \"Missing\"",
+        ]
+    );
+}
+
+#[test]
+fn refuses_an_untrusted_certificate() {
+    let (port, _) = serve();
+    // The bundled web PKI roots do not include the test CA.
+    let result = Builder::new(&format!("localhost:{}", port)).connect();
+    assert!(result.is_err());
+}
+
+#[test]
+fn refuses_oversized_literals_and_lines() {
+    for (mailbox, max) in [("Huge", None), ("Big", Some(16)), ("Long", None)] {
+        let (port, _) = serve();
+        let mut builder = Builder::new(&format!("localhost:{}", port)).tls_config(trusting_ca());
+        if let Some(max) = max {
+            builder = builder.max_literal_size(max);
+        }
+        let mut session = builder.connect().unwrap().login("user", "pass").unwrap();
+        let err = session.fetch(mailbox, 1).unwrap_err();
+        match mailbox {
+            "Long" => assert!(err.to_string().contains("maximum length"), "{}", err),
+            _ => assert!(
+                matches!(err, ImapError::LiteralTooLarge { .. }),
+                "{:?}",
+                err
+            ),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Buffer and pipeline high-watermarks, and their use in overflow errors.
+
+use std::time::Duration;
+
+use tokio_stream::StreamExt;
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::common::Uid;
+
+fn server(line_len: usize) -> MockServer {
+    let long = format!("* OK {}\r\n", "x".repeat(line_len - 7));
+    MockServer::new(move |tag, cmd| {
+        let body = if cmd == "CHECK" { long.as_str() } else { "" };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    })
+    // Keeps every fetch batch in flight until the last one has been written.
+    .latency(Duration::from_millis(20))
+}
+
+#[tokio::test]
+async fn records_the_longest_line_and_pipeline_depth() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(500).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    let before = session.buffer_stats();
+    assert!(before.max_line_len < 100);
+    assert_eq!(before.max_in_flight, 1);
+
+    session.check().await.unwrap();
+    assert_eq!(session.buffer_stats().max_line_len, 500);
+
+    // Every other UID, so the set cannot be compressed and is split into batches.
+    let uids: Vec<Uid> = (1..6000).step_by(2).map(Uid).collect();
+    let mut headers = session
+        .fetch_headers("INBOX", &uids, &["SUBJECT"])
+        .await
+        .unwrap();
+    while let Some(item) = headers.next().await {
+        item.unwrap();
+    }
+    let stats = session.buffer_stats();
+    assert!(stats.max_in_flight >= 2, "{}", stats);
+    assert_eq!(stats.max_line_len, 500);
+}
+
+#[tokio::test]
+async fn overflow_errors_include_the_watermarks() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(9000).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    let err = session.check().await.unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(
+        message.contains("exceeded maximum length of 8192 bytes"),
+        "{}",
+        message
+    );
+    assert!(message.contains("most in flight 1"), "{}", message);
+}
@@ -0,0 +1,44 @@
+//! Shutting a connection down with a CancellationToken.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bindings::Builder;
+use bindings::async_impl::{CancellationToken, Cancelled};
+use bindings::test_util::MockServer;
+
+#[tokio::test]
+async fn pending_commands_fail_and_logout_is_sent() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        match cmd {
+            // Never answered, so it is still pending when the token fires.
+            "CHECK" => Vec::new(),
+            "LOGOUT" => format!("* BYE logging out\r\n{} OK done\r\n", tag).into_bytes(),
+            _ => format!("{} OK done\r\n", tag).into_bytes(),
+        }
+    });
+    let token = CancellationToken::new();
+    let client = Builder::new("mock:143")
+        .cancellation_token(token.clone())
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let raw = client.into_raw();
+
+    let pending = raw.execute("CHECK");
+    let cancel = async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        token.cancel();
+    };
+    let (result, ()) = tokio::join!(pending, cancel);
+    let err = result.unwrap_err();
+    assert!(err.is::<Cancelled>(), "unexpected error: {:#}", err);
+
+    // Commands after the shutdown fail too, without reaching the server.
+    assert!(raw.execute("NOOP").await.is_err());
+    assert_eq!(*received.lock().unwrap(), ["CHECK", "LOGOUT"]);
+}
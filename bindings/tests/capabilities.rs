@@ -0,0 +1,92 @@
+//! Capability atoms as typed values, and the client's capability cache.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::parser::capability::parse_capabilities;
+use imap::types::common::{Capability, SaslMechanism};
+
+#[test]
+fn atoms_parse_in_any_case_and_display_as_sent() {
+    let caps = parse_capabilities(
+        b"* CAPABILITY IMAP4rev1 uidplus LITERAL+ LIST-EXTENDED AUTH=PLAIN APPENDLIMIT=1000 X-ODD\r\n",
+    );
+    let parsed: Vec<_> = caps.parsed().collect();
+    assert_eq!(
+        parsed,
+        [
+            Capability::Imap4rev1,
+            Capability::UidPlus,
+            Capability::LiteralPlus,
+            Capability::ListExtended,
+            Capability::Auth(SaslMechanism::Plain),
+            Capability::AppendLimit(Some(1000)),
+            Capability::Unknown("X-ODD".into()),
+        ]
+    );
+    let shown: Vec<_> = parsed.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        shown,
+        [
+            "IMAP4rev1",
+            "UIDPLUS",
+            "LITERAL+",
+            "LIST-EXTENDED",
+            "AUTH=PLAIN",
+            "APPENDLIMIT=1000",
+            "X-ODD"
+        ]
+    );
+
+    assert!(caps.contains(&Capability::UidPlus));
+    assert!(!caps.contains(&Capability::Move));
+    assert_eq!(
+        Capability::parse("appendlimit"),
+        Capability::AppendLimit(None)
+    );
+    assert_eq!(Capability::parse("Within"), Capability::Within);
+}
+
+#[tokio::test]
+async fn asks_once_per_connection_state() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = match cmd {
+            "CAPABILITY" => "* CAPABILITY IMAP4rev1 auth=plain AUTH=xoauth2 APPENDLIMIT=25\r\n",
+            _ => "",
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let mut client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+
+    // Before login, and with nothing in the greeting.
+    let caps = client.capabilities().await.unwrap();
+    assert!(caps.has("imap4REV1"));
+    assert_eq!(
+        caps.auth_mechanisms().collect::<Vec<_>>(),
+        ["plain", "xoauth2"]
+    );
+    assert_eq!(
+        caps.sasl_mechanisms().collect::<Vec<_>>(),
+        [SaslMechanism::Plain, SaslMechanism::XOAuth2]
+    );
+    assert_eq!(caps.append_limit(), Some(25));
+    client.capabilities().await.unwrap();
+
+    // Logging in changes state, so the list is asked for again.
+    let mut session = client.login("user", "pass").await.unwrap();
+    session.capabilities().await.unwrap();
+    session.capabilities().await.unwrap();
+    assert_eq!(
+        *received.lock().unwrap(),
+        ["CAPABILITY", "LOGIN \"user\" \"pass\"", "CAPABILITY"]
+    );
+}
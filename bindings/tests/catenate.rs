@@ -0,0 +1,83 @@
+//! APPEND with CATENATE (RFC 4469).
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::commands::CommandBuilder;
+use imap::types::command::CatenatePart;
+use imap::types::common::Uid;
+
+fn parts() -> Vec<CatenatePart> {
+    vec![
+        CatenatePart::message("Sent Items", 385759045, Uid(20), Some("HEADER")),
+        CatenatePart::Text(b"--sep\r\n".to_vec()),
+        CatenatePart::message("INBOX", 385759045, Uid(21), None),
+        CatenatePart::Text(b"--sep--".to_vec()),
+    ]
+}
+
+#[test]
+fn wire_format() {
+    let builder = parts()
+        .into_iter()
+        .fold(CommandBuilder::new("A1").catenate("Drafts"), |b, p| {
+            b.part(p)
+        })
+        .literal_plus();
+    assert_eq!(
+        builder.as_string(),
+        "A1 APPEND \"Drafts\" CATENATE (URL \"/Sent%20Items;UIDVALIDITY=385759045/;UID=20/;SECTION=HEADER\" TEXT {7+}\r\n"
+    );
+    assert_eq!(
+        builder.literal_bytes().unwrap(),
+        b"--sep\r\n URL \"/INBOX;UIDVALIDITY=385759045/;UID=21\" TEXT {7+}\r\n--sep--)"
+    );
+
+    let urls_only = CommandBuilder::new("A2")
+        .catenate("Drafts")
+        .part(CatenatePart::message("INBOX", 1, Uid(2), None));
+    assert_eq!(
+        urls_only.as_string(),
+        "A2 APPEND \"Drafts\" CATENATE (URL \"/INBOX;UIDVALIDITY=1/;UID=2\")\r\n"
+    );
+    assert!(urls_only.literal_bytes().is_none());
+}
+
+#[tokio::test]
+async fn append_catenate_returns_uid() {
+    let append_tag = Arc::new(Mutex::new(None::<String>));
+    let server = MockServer::new(move |tag, cmd| {
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        match verb.as_str() {
+            "LOGIN" => format!("{} OK LOGIN completed\r\n", tag).into_bytes(),
+            "CAPABILITY" => format!(
+                "* CAPABILITY IMAP4rev1 CATENATE LITERAL+ UIDPLUS\r\n{} OK done\r\n",
+                tag
+            )
+            .into_bytes(),
+            "APPEND" => {
+                *append_tag.lock().unwrap() = Some(tag.to_string());
+                Vec::new()
+            }
+            // The last line of the CATENATE list.
+            _ if tag.ends_with(')') => {
+                let tag = append_tag.lock().unwrap().take().unwrap();
+                format!("{} OK [APPENDUID 9 3] APPEND completed\r\n", tag).into_bytes()
+            }
+            _ => Vec::new(),
+        }
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    let uid = session
+        .append_catenate("Drafts", Vec::new(), None, parts())
+        .await
+        .unwrap();
+    assert_eq!(uid, Some(Uid(3)));
+}
@@ -0,0 +1,56 @@
+//! The per-command lifecycle hook set with Builder::on_command.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bindings::Builder;
+use bindings::async_impl::{CancellationToken, CommandEvent};
+use bindings::test_util::MockServer;
+
+use imap::types::common::Status;
+
+#[tokio::test]
+async fn reports_every_command() {
+    let events = Arc::new(Mutex::new(Vec::<CommandEvent>::new()));
+    let seen = events.clone();
+    let server = MockServer::new(|tag, cmd| match cmd.split(' ').next().unwrap() {
+        "CREATE" => format!("{} NO [ALREADYEXISTS] Exists\r\n", tag).into_bytes(),
+        // Left unanswered until the connection is cancelled.
+        "CHECK" => Vec::new(),
+        _ => format!("{} OK done\r\n", tag).into_bytes(),
+    })
+    .latency(Duration::from_millis(5));
+    let token = CancellationToken::new();
+    let client = Builder::new("mock:143")
+        .on_command(move |event| seen.lock().unwrap().push(event.clone()))
+        .cancellation_token(token.clone())
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    assert!(session.create("Archive").await.is_err());
+    let raw = session.into_raw();
+    let pending = raw.execute("CHECK");
+    let cancel = async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        token.cancel();
+    };
+    let _ = tokio::join!(pending, cancel);
+
+    let events = events.lock().unwrap();
+    let names: Vec<&str> = events.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["LOGIN", "CREATE", "CHECK"]);
+    assert!(matches!(events[0].status, Some(Status::Ok)));
+    assert!(matches!(events[1].status, Some(Status::No)));
+    // Cancelled before it completed.
+    assert!(events[2].status.is_none());
+    for event in events.iter() {
+        let sent = event.sent_at.unwrap();
+        assert!(event.queued_at <= sent && sent <= event.completed_at);
+        assert!(event.tag.starts_with('A'));
+    }
+    // The latency is paid between writing a command and its completion.
+    let login = &events[0];
+    assert!(login.completed_at - login.sent_at.unwrap() >= Duration::from_millis(5));
+}
@@ -0,0 +1,81 @@
+//! COPY and MOVE, with the COPY, STORE and EXPUNGE fallback for servers without MOVE.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+use bindings::{Builder, SelectedState};
+
+use imap::types::common::{Seq, Uid};
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+async fn session(capabilities: &'static str) -> (Client<SelectedState>, Log) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = if cmd == "CAPABILITY" {
+            format!("* CAPABILITY {}\r\n", capabilities)
+        } else {
+            String::new()
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (session, _) = session.select("INBOX").await.unwrap();
+    received.lock().unwrap().clear();
+    (session, received)
+}
+
+#[tokio::test]
+async fn copy_sends_copy() {
+    let (mut session, log) = session("IMAP4rev1").await;
+    let copied = session.copy(2..=4, "Archive").await.unwrap();
+    assert!(copied.is_none());
+    assert_eq!(*log.lock().unwrap(), ["COPY 2:4 \"Archive\""]);
+}
+
+#[tokio::test]
+async fn mv_uses_move_when_offered() {
+    let (mut session, log) = session("IMAP4rev1 MOVE").await;
+    session.mv(Seq(3), "Archive").await.unwrap();
+    session.uid_mv(Uid(30), "Archive").await.unwrap();
+    assert_eq!(
+        log.lock().unwrap()[1..],
+        ["MOVE 3 \"Archive\"", "UID MOVE 30 \"Archive\""]
+    );
+}
+
+#[tokio::test]
+async fn mv_falls_back_to_copy_and_expunge() {
+    let (mut session, log) = session("IMAP4rev1").await;
+    session.mv(Seq(3), "Archive").await.unwrap();
+    assert_eq!(
+        log.lock().unwrap()[1..],
+        [
+            "COPY 3 \"Archive\"",
+            "STORE 3 +FLAGS.SILENT (\\Deleted)",
+            "EXPUNGE"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn uid_mv_expunges_only_the_moved_messages() {
+    let (mut session, log) = session("IMAP4rev1 UIDPLUS").await;
+    session.uid_mv(Uid(30), "Archive").await.unwrap();
+    assert_eq!(
+        log.lock().unwrap()[1..],
+        [
+            "UID COPY 30 \"Archive\"",
+            "UID STORE 30 +FLAGS.SILENT (\\Deleted)",
+            "UID EXPUNGE 30"
+        ]
+    );
+}
@@ -0,0 +1,37 @@
+//! Accepting invalid certificates from test servers.
+
+mod tls;
+
+use bindings::Builder;
+
+const CERT: &[u8] = include_bytes!("tls/self_signed.der");
+const KEY: &[u8] = include_bytes!("tls/self_signed.key.der");
+
+#[tokio::test]
+async fn self_signed_certificates_are_rejected_by_default() {
+    let port = tls::serve(CERT, KEY).await;
+    let result = Builder::new(&format!("localhost:{}", port)).connect().await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn accepts_a_self_signed_certificate_when_asked() {
+    let port = tls::serve(CERT, KEY).await;
+    let client = Builder::new(&format!("localhost:{}", port))
+        .danger_accept_invalid_certs(true)
+        .connect()
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    session.logout().await.unwrap();
+}
+
+#[tokio::test]
+async fn ignoring_host_names_does_not_trust_unknown_issuers() {
+    let port = tls::serve(CERT, KEY).await;
+    let result = Builder::new(&format!("localhost:{}", port))
+        .danger_accept_invalid_hostnames(true)
+        .connect()
+        .await;
+    assert!(result.is_err());
+}
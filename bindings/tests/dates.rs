@@ -0,0 +1,92 @@
+//! INTERNALDATE and RFC 2822 date parsing, and the dates commands send.
+
+use imap::commands::CommandBuilder;
+use imap::parser::datetime::{parse_date_time, parse_rfc2822_date};
+use imap::types::command::SearchKey;
+use imap::types::common::{Date, DateTime};
+
+#[test]
+fn parses_internal_dates() {
+    let date = parse_date_time("17-Jul-1996 02:44:25 -0700").unwrap();
+    assert_eq!(date.timestamp(), 837_596_665);
+    assert_eq!(date.offset(), -7 * 3600);
+    assert_eq!(date.to_string(), "17-Jul-1996 02:44:25 -0700");
+
+    // date-day-fixed pads with a space.
+    let date = parse_date_time(" 1-Feb-2024 23:05:00 +0530").unwrap();
+    assert_eq!(date.to_string(), "01-Feb-2024 23:05:00 +0530");
+    assert_eq!(
+        parse_date_time("31-Dec-1969 23:59:59 +0000")
+            .unwrap()
+            .timestamp(),
+        -1
+    );
+
+    assert_eq!(parse_date_time("17-Jul-1996 02:44:25"), None);
+    assert_eq!(parse_date_time("17-Jux-1996 02:44:25 -0700"), None);
+    assert_eq!(parse_date_time("17-Jul-1996 24:44:25 -0700"), None);
+}
+
+#[test]
+fn parses_rfc2822_dates() {
+    let expected = parse_date_time("17-Jul-1996 02:23:25 -0700").unwrap();
+    for text in [
+        "Wed, 17 Jul 1996 02:23:25 -0700",
+        "Wed, 17 Jul 1996 02:23:25 -0700 (PDT)",
+        "17 Jul 1996 02:23:25 -0700",
+        "Wed,17 Jul 96 02:23:25 PDT",
+    ] {
+        assert_eq!(parse_rfc2822_date(text), Some(expected), "{}", text);
+    }
+
+    let date = parse_rfc2822_date("Mon, 2 Jan 06 15:04 GMT").unwrap();
+    assert_eq!(date.to_string(), "02-Jan-2006 15:04:00 +0000");
+    assert_eq!(parse_rfc2822_date("yesterday"), None);
+    assert_eq!(parse_rfc2822_date("Wed, 17 Jul 1996 02:23:25 +07"), None);
+}
+
+#[test]
+fn orders_by_instant() {
+    let earlier = parse_date_time("17-Jul-1996 10:00:00 +0200").unwrap();
+    let later = parse_date_time("17-Jul-1996 09:00:00 +0000").unwrap();
+    assert!(earlier < later);
+    assert_eq!(later, DateTime::new(837_594_000, 0));
+}
+
+#[test]
+fn rejects_days_that_do_not_exist() {
+    assert!(Date::new(2024, 2, 29).is_some());
+    assert!(Date::new(2023, 2, 29).is_none());
+    assert!(Date::new(1900, 2, 29).is_none());
+    assert!(Date::new(2024, 4, 31).is_none());
+    assert!(Date::new(2024, 13, 1).is_none());
+    assert!(Date::new(2024, 1, 0).is_none());
+    assert!(Date::new(0, 1, 1).is_none());
+}
+
+#[test]
+fn search_keys_write_imap_dates() {
+    let since = Date::new(2024, 2, 1).unwrap();
+    let before = DateTime::new(837_596_665, -7 * 3600).date().unwrap();
+    let command = CommandBuilder::new("A1")
+        .search()
+        .keys(vec![SearchKey::Since(since), SearchKey::SentBefore(before)])
+        .as_string();
+    assert_eq!(
+        command,
+        "A1 SEARCH SINCE 1-Feb-2024 SENTBEFORE 17-Jul-1996\r\n"
+    );
+}
+
+#[test]
+fn append_quotes_the_internal_date() {
+    let command = CommandBuilder::new("A1")
+        .append("INBOX")
+        .internal_date(DateTime::new(837_596_665, -7 * 3600))
+        .literal(b"hi".to_vec())
+        .as_string();
+    assert_eq!(
+        command,
+        "A1 APPEND \"INBOX\" \"17-Jul-1996 02:44:25 -0700\" {2}\r\n"
+    );
+}
@@ -0,0 +1,175 @@
+//! DuplicateFinder: finding duplicates by Message-ID and size, and removing them.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::async_impl::{DuplicateFinder, DuplicateGroup};
+use bindings::test_util::MockServer;
+
+use imap::types::common::Uid;
+
+/// UIDs 1 to 3 share a Message-ID and size, but only 1 and 2 have the same body.
+const MESSAGES: [(u32, &str, &str); 5] = [
+    (1, "<a@example.com>", "hello"),
+    (2, "<a@example.com>", "hello"),
+    (3, "<a@example.com>", "hellp"),
+    (4, "<b@example.com>", "other"),
+    (5, "", "empty"),
+];
+
+/// Advertises `capabilities` after IMAP4rev1.
+fn server(received: Arc<Mutex<Vec<String>>>, capabilities: &'static str) -> MockServer {
+    MockServer::new(move |tag, cmd| {
+        received.lock().unwrap().push(cmd.to_string());
+        let mut out = String::new();
+        if cmd.contains("RFC822.SIZE") {
+            for (uid, _, body) in MESSAGES {
+                out += &format!(
+                    "* {} FETCH (UID {} RFC822.SIZE {})\r\n",
+                    uid,
+                    uid,
+                    body.len()
+                );
+            }
+        } else if cmd.contains("HEADER.FIELDS") {
+            for (uid, id, _) in MESSAGES {
+                let header = if id.is_empty() {
+                    "\r\n".to_string()
+                } else {
+                    format!("Message-ID: {}\r\n\r\n", id)
+                };
+                out += &format!(
+                    "* {} FETCH (UID {} BODY[HEADER.FIELDS (MESSAGE-ID)] {{{}}}\r\n{})\r\n",
+                    uid,
+                    uid,
+                    header.len(),
+                    header
+                );
+            }
+        } else if cmd.starts_with("UID FETCH") {
+            let uid: u32 = cmd.split(' ').nth(2).unwrap().parse().unwrap();
+            let (_, _, body) = MESSAGES[uid as usize - 1];
+            out += &format!(
+                "* {} FETCH (UID {} BODY[] {{{}}}\r\n{})\r\n",
+                uid,
+                uid,
+                body.len(),
+                body
+            );
+        } else if cmd.starts_with("SELECT") || cmd.starts_with("EXAMINE") {
+            out += "* 5 EXISTS\r\n";
+        } else if cmd == "CAPABILITY" {
+            out += &format!("* CAPABILITY IMAP4rev1 {}\r\n", capabilities);
+        }
+        out += &format!("{} OK done\r\n", tag);
+        out.into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn groups_by_message_id_and_size() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(received.clone(), "UIDPLUS").spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let groups = DuplicateFinder::new(&mut session, "INBOX")
+        .find()
+        .await
+        .unwrap();
+    assert_eq!(
+        groups,
+        [DuplicateGroup {
+            message_id: "<a@example.com>".into(),
+            size: 5,
+            keep: Uid(1),
+            duplicates: vec![Uid(2), Uid(3)],
+        }]
+    );
+    // The scan is read-only, and nothing was downloaded in full.
+    let received = received.lock().unwrap();
+    assert_eq!(received[1], "EXAMINE \"INBOX\"");
+    assert!(!received.iter().any(|c| c.starts_with("SELECT")));
+    assert!(!received.iter().any(|c| c.contains("BODY[]")));
+}
+
+#[tokio::test]
+async fn verify_body_compares_content_before_deleting() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(received.clone(), "UIDPLUS").spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let mut finder = DuplicateFinder::new(&mut session, "INBOX").verify_body(true);
+    let groups = finder.find().await.unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(
+        (groups[0].keep, &groups[0].duplicates[..]),
+        (Uid(1), &[Uid(2)][..])
+    );
+
+    received.lock().unwrap().clear();
+    finder.delete(&groups).await.unwrap();
+    let received = received.lock().unwrap();
+    // Deleting needs the mailbox read-write.
+    assert_eq!(received[..2], ["CAPABILITY", "SELECT \"INBOX\""]);
+    let stores: Vec<_> = received.iter().filter(|c| c.contains("STORE")).collect();
+    assert_eq!(stores.len(), 1);
+    assert!(stores[0].starts_with("UID STORE 2 "), "{}", stores[0]);
+    assert!(received.iter().any(|c| c.starts_with("UID EXPUNGE 2")));
+}
+
+#[tokio::test]
+async fn refuses_to_expunge_other_messages() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(received.clone(), "").spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let mut finder = DuplicateFinder::new(&mut session, "INBOX");
+    let groups = finder.find().await.unwrap();
+    let err = finder.delete(&groups).await.unwrap_err();
+    assert!(err.to_string().contains("UIDPLUS"), "{}", err);
+    let err = finder.move_to(&groups, "Duplicates").await.unwrap_err();
+    assert!(err.to_string().contains("MOVE or UIDPLUS"), "{}", err);
+    assert!(
+        !received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c.contains("STORE") || c.contains("COPY") || c.contains("EXPUNGE"))
+    );
+}
+
+#[tokio::test]
+async fn moves_with_move_alone() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(received.clone(), "MOVE").spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let mut finder = DuplicateFinder::new(&mut session, "INBOX");
+    let groups = finder.find().await.unwrap();
+    received.lock().unwrap().clear();
+    finder.move_to(&groups, "Duplicates").await.unwrap();
+    assert_eq!(
+        received.lock().unwrap()[..],
+        [
+            "CAPABILITY",
+            "SELECT \"INBOX\"",
+            "UID MOVE 2:3 \"Duplicates\""
+        ]
+    );
+}
@@ -0,0 +1,144 @@
+//! Interoperability tests against a real Dovecot server, to catch protocol details the
+//! mock server does not reproduce.
+//!
+//! Skipped unless `MAILUX_DOVECOT` names the server's plain IMAP address. To run them:
+//!
+//! ```text
+//! docker run --rm -d -p 1143:143 \
+//!     -v "$PWD/bindings/tests/dovecot/dovecot.conf:/etc/dovecot/dovecot.conf:ro" \
+//!     dovecot/dovecot:2.3.21
+//! MAILUX_DOVECOT=127.0.0.1:1143 cargo test -p bindings --test dovecot
+//! ```
+//!
+//! Every test works in a mailbox of its own, so they can run in parallel on one account.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use bindings::AuthenticatedState;
+use bindings::Builder;
+use bindings::async_impl::Client;
+use tokio_stream::StreamExt;
+
+use imap::mdn;
+use imap::types::command::{SequenceBound, SequenceSet};
+use imap::types::common::Flag;
+use imap::types::response::IdleEvent;
+
+const USER: &str = "mailux";
+const PASS: &str = "pass";
+
+fn server() -> Option<String> {
+    let addr = std::env::var("MAILUX_DOVECOT").ok();
+    if addr.is_none() {
+        eprintln!("MAILUX_DOVECOT is not set; skipping");
+    }
+    addr
+}
+
+async fn login(addr: &str) -> Client<AuthenticatedState> {
+    let client = Builder::new(addr).plain().build().connect().await.unwrap();
+    client.login(USER, PASS).await.unwrap()
+}
+
+/// Creates an empty mailbox named after the test.
+async fn scratch_mailbox(session: &mut Client<AuthenticatedState>, test: &str) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    let name = format!("{}-{}", test, nanos);
+    session.create(&name).await.unwrap();
+    name
+}
+
+fn message(subject: &str) -> Vec<u8> {
+    format!(
+        "From: Tester <tester@example.com>\r\nSubject: {}\r\n\r\nBody of {}\r\n",
+        subject, subject
+    )
+    .into_bytes()
+}
+
+#[tokio::test]
+async fn append_select_and_fetch() {
+    let Some(addr) = server() else { return };
+    let mut session = login(&addr).await;
+    let mailbox = scratch_mailbox(&mut session, "fetch").await;
+
+    let mut uids = Vec::new();
+    for subject in ["one", "two", "three"] {
+        let uid = session
+            .append(&mailbox, Vec::new(), None, &message(subject))
+            .await
+            .unwrap();
+        uids.push(uid.expect("Dovecot reports APPENDUID"));
+    }
+
+    let (mut session, status) = session.select(&mailbox).await.unwrap();
+    assert_eq!(status.exists, 3);
+    assert_eq!(status.uid_next, Some(uids[2].0 + 1));
+
+    let subjects: Vec<Option<String>> = session
+        .fetch(&mailbox, 3)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|e| e.subject)
+        .collect();
+    assert_eq!(
+        subjects,
+        ["one", "two", "three"].map(|s| Some(s.to_string()))
+    );
+
+    let body = session.fetch_body(&mailbox, uids[1]).await.unwrap();
+    assert_eq!(body.as_deref(), Some(&message("two")[..]));
+}
+
+#[tokio::test]
+async fn store_keyword() {
+    let Some(addr) = server() else { return };
+    let mut session = login(&addr).await;
+    let mailbox = scratch_mailbox(&mut session, "store").await;
+    let uid = session
+        .append(&mailbox, vec![Flag::Seen], None, &message("flags"))
+        .await
+        .unwrap()
+        .unwrap();
+
+    session.mark_mdn_sent(&mailbox, uid).await.unwrap();
+    let all = SequenceSet::new().add_range(SequenceBound::Number(1), SequenceBound::Star);
+    let flags = session.fetch_flags(&mailbox, all).await.unwrap();
+    let (_, flags) = flags.iter().find(|(u, _)| *u == uid).unwrap();
+    assert!(flags.contains(&Flag::Seen));
+    assert!(flags.contains(&Flag::Keyword(mdn::MDN_SENT.to_string())));
+}
+
+#[tokio::test]
+async fn idle_sees_new_message() {
+    let Some(addr) = server() else { return };
+    let mut watcher = login(&addr).await;
+    let mailbox = scratch_mailbox(&mut watcher, "idle").await;
+    let (mut watcher, _) = watcher.select(&mailbox).await.unwrap();
+    let mut idle = watcher.idle().await.unwrap();
+
+    let mut sender = login(&addr).await;
+    // Give the server a moment to start idling before the message arrives.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    sender
+        .append(&mailbox, Vec::new(), None, &message("pushed"))
+        .await
+        .unwrap();
+
+    let event = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            match idle.next().await.expect("IDLE ended").unwrap() {
+                IdleEvent::Exists(n) => break n,
+                _ => continue,
+            }
+        }
+    })
+    .await
+    .expect("no EXISTS within 10s");
+    assert_eq!(event, 1);
+    idle.done().await.unwrap();
+}
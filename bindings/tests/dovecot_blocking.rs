@@ -0,0 +1,27 @@
+//! The blocking client against a real Dovecot server; see `tests/dovecot.rs`.
+//!
+//! The blocking client only speaks implicit TLS, and these tests trust only the bundled
+//! web PKI roots, so they need `MAILUX_DOVECOT_TLS` to name such an endpoint, e.g. a port
+//! forwarded through a TLS proxy with a real certificate. The account's INBOX must hold at
+//! least one message. `tests/blocking.rs` covers the same calls against a local server.
+//!
+//! ```text
+//! MAILUX_DOVECOT_TLS=imap.example.com:993 \
+//!     cargo test -p bindings --no-default-features --features blocking --test dovecot_blocking
+//! ```
+
+use bindings::Builder;
+
+#[test]
+fn login_and_fetch() {
+    let Ok(addr) = std::env::var("MAILUX_DOVECOT_TLS") else {
+        eprintln!("MAILUX_DOVECOT_TLS is not set; skipping");
+        return;
+    };
+    let client = Builder::new(&addr).tls().connect().unwrap();
+    let mut session = client.login("mailux", "pass").unwrap();
+    let capabilities = session.capabilities().unwrap();
+    assert!(capabilities.has("IMAP4rev1"));
+    let envelopes = session.fetch("INBOX", 1).unwrap();
+    assert_eq!(envelopes.len(), 1);
+}
@@ -0,0 +1,54 @@
+//! Address lists in ENVELOPE: from, to, cc and bcc.
+
+use imap::parser::fetch::parse_fetch_responses;
+use imap::types::response::{Address, Envelope, FetchData};
+
+fn envelope(response: &[u8]) -> Envelope {
+    parse_fetch_responses(response)
+        .into_iter()
+        .flat_map(|(_seq, items)| items)
+        .find_map(|item| match item {
+            FetchData::Envelope(env) => Some(env),
+            _ => None,
+        })
+        .expect("an envelope")
+}
+
+fn shown(addresses: &[Address]) -> Vec<String> {
+    addresses.iter().map(|a| a.to_string()).collect()
+}
+
+#[test]
+fn parses_every_address_list() {
+    let env = envelope(
+        b"* 1 FETCH (ENVELOPE (NIL \"Plans\" \
+((\"Ann Lee\" NIL \"ann\" \"example.com\")) NIL NIL \
+((NIL NIL \"bob\" \"example.com\")) \
+((\"Cy\" NIL \"cy\" \"example.org\")(NIL NIL \"dee\" \"example.org\")) \
+((\"Eve\" NIL \"eve\" \"example.net\")) NIL \"<1@example.com>\"))\r\n",
+    );
+    assert_eq!(shown(&env.from), ["Ann Lee <ann@example.com>"]);
+    assert_eq!(shown(&env.to), ["bob@example.com"]);
+    assert_eq!(shown(&env.cc), ["Cy <cy@example.org>", "dee@example.org"]);
+    assert_eq!(
+        env.bcc,
+        [Address {
+            name: Some("Eve".to_string()),
+            mailbox: Some("eve".to_string()),
+            host: Some("example.net".to_string()),
+        }]
+    );
+}
+
+#[test]
+fn flattens_groups() {
+    // To: undisclosed-recipients:;  Cc: team: a@example.com, b@example.com;
+    let env = envelope(
+        b"* 1 FETCH (ENVELOPE (NIL NIL NIL NIL NIL \
+((NIL NIL \"undisclosed-recipients\" NIL)(NIL NIL NIL NIL)) \
+((NIL NIL \"team\" NIL)(NIL NIL \"a\" \"example.com\")(NIL NIL \"b\" \"example.com\")(NIL NIL NIL NIL)) \
+NIL NIL NIL))\r\n",
+    );
+    assert!(env.to.is_empty());
+    assert_eq!(shown(&env.cc), ["a@example.com", "b@example.com"]);
+}
@@ -0,0 +1,101 @@
+//! Tagged NO and BAD completions as structured errors.
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::ImapError;
+use imap::types::response::ResponseCode;
+
+fn server() -> MockServer {
+    MockServer::new(|tag, cmd| {
+        let reply = if cmd.contains("wrong") {
+            "NO [AUTHENTICATIONFAILED] Invalid credentials"
+        } else if cmd.starts_with("CREATE") {
+            "NO [OVERQUOTA] Mailbox limit reached"
+        } else if cmd.starts_with("STATUS") {
+            "NO [TRYCREATE] No such mailbox"
+        } else if cmd.starts_with("SUBSCRIBE") {
+            "BAD Unknown command"
+        } else if cmd.starts_with("UNSUBSCRIBE") {
+            "NO [OVERQUOTA] quota OK exceeded"
+        } else if cmd.starts_with("LSUB") {
+            "ok done"
+        } else {
+            "OK done"
+        };
+        format!("{} {}\r\n", tag, reply).into_bytes()
+    })
+}
+
+fn imap_error(err: &anyhow::Error) -> &ImapError {
+    err.downcast_ref::<ImapError>()
+        .unwrap_or_else(|| panic!("not an ImapError: {:#}", err))
+}
+
+#[tokio::test]
+async fn login_refusal_carries_its_code() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let err = client.login("user", "wrong").await.err().unwrap();
+    match imap_error(&err) {
+        ImapError::No { code, text } => {
+            assert_eq!(code, &Some(ResponseCode::AuthenticationFailed));
+            assert_eq!(text, "Invalid credentials");
+        }
+        other => panic!("expected NO, got {:?}", other),
+    }
+    assert_eq!(
+        format!("{:#}", err),
+        "Login failed: NO [AUTHENTICATIONFAILED] Invalid credentials"
+    );
+}
+
+#[tokio::test]
+async fn codes_tell_refusals_apart() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let err = session.create("Archive").await.unwrap_err();
+    assert_eq!(imap_error(&err).code(), Some(&ResponseCode::OverQuota));
+
+    let err = session.status("Missing", &[]).await.unwrap_err();
+    assert_eq!(imap_error(&err).code(), Some(&ResponseCode::TryCreate));
+
+    let err = session.subscribe("INBOX").await.unwrap_err();
+    match imap_error(&err) {
+        ImapError::Bad { code: None, text } => assert_eq!(text, "Unknown command"),
+        other => panic!("expected BAD, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn status_word_decides_the_outcome() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    // "OK" later in the text does not make a NO succeed.
+    let err = session.unsubscribe("INBOX").await.unwrap_err();
+    assert_eq!(imap_error(&err).code(), Some(&ResponseCode::OverQuota));
+    // The status word is case-insensitive.
+    assert!(session.lsub("", "*").await.unwrap().is_empty());
+}
+
+#[test]
+fn unknown_codes_are_kept() {
+    assert_eq!(
+        ResponseCode::parse("x-custom"),
+        ResponseCode::Other("X-CUSTOM".into())
+    );
+    assert_eq!(ResponseCode::parse("nonexistent").name(), "NONEXISTENT");
+}
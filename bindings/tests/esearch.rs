@@ -0,0 +1,98 @@
+//! ESEARCH (RFC 4731): summaries of the matches instead of every number.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::command::{SearchKey, SearchReturn};
+
+fn server(capabilities: &'static str, received: Arc<Mutex<Vec<String>>>) -> MockServer {
+    MockServer::new(move |tag, cmd| {
+        received.lock().unwrap().push(cmd.to_string());
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" => String::new(),
+            "CAPABILITY" => format!("* CAPABILITY {}\r\n", capabilities),
+            "SELECT" => "* 90 EXISTS\r\n".to_string(),
+            "UID" => format!(
+                "* ESEARCH (TAG \"{}\") UID MIN 2 MAX 90 COUNT 5 ALL 2,10:12,90\r\n",
+                tag
+            ),
+            // Nothing matched: the reply may be left out.
+            "SEARCH" => String::new(),
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn returns_the_requested_summary() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1 ESEARCH", received.clone()).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let result = session
+        .uid_esearch(
+            vec![SearchKey::Unseen],
+            vec![
+                SearchReturn::Min,
+                SearchReturn::Max,
+                SearchReturn::Count,
+                SearchReturn::All,
+            ],
+        )
+        .await
+        .unwrap();
+    assert!(result.uid);
+    assert_eq!(result.min, Some(2));
+    assert_eq!(result.max, Some(90));
+    assert_eq!(result.count, Some(5));
+    let all: Vec<u32> = result.all.unwrap().numbers().collect();
+    assert_eq!(all, [2, 10, 11, 12, 90]);
+    assert!(
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c == "UID SEARCH RETURN (MIN MAX COUNT ALL) UNSEEN")
+    );
+
+    let result = session
+        .esearch(vec![SearchKey::Deleted], vec![SearchReturn::Count])
+        .await
+        .unwrap();
+    assert_eq!(result.count, None);
+    assert!(result.all.is_none());
+}
+
+#[tokio::test]
+async fn esearch_requires_the_capability() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1", received.clone()).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let err = session
+        .esearch(vec![SearchKey::All], vec![SearchReturn::Count])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("ESEARCH"));
+    assert!(
+        !received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c.contains("SEARCH"))
+    );
+}
@@ -0,0 +1,125 @@
+//! EventForwarder: batching, flushing and retrying event deliveries.
+
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use bindings::async_impl::{EventForwarder, EventSink, ForwardStats};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use imap::types::common::Seq;
+use imap::types::response::IdleEvent;
+
+fn events(n: u32) -> Vec<Result<IdleEvent>> {
+    (1..=n).map(|i| Ok(IdleEvent::Exists(i))).collect()
+}
+
+/// Fails the first `failures` deliveries.
+struct Flaky {
+    failures: usize,
+}
+
+impl EventSink for Flaky {
+    async fn deliver(&mut self, _batch: &[IdleEvent]) -> Result<()> {
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(anyhow!("webhook returned 503"));
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn groups_events_into_batches() {
+    let (tx, mut rx) = mpsc::channel(8);
+    let stats = EventForwarder::new(tx)
+        .batch_size(2)
+        .run(tokio_stream::iter(events(5)))
+        .await
+        .unwrap();
+    assert_eq!(
+        stats,
+        ForwardStats {
+            batches: 3,
+            events: 5,
+            retries: 0,
+        }
+    );
+    let mut sizes = Vec::new();
+    while let Ok(batch) = rx.try_recv() {
+        sizes.push(batch.len());
+    }
+    assert_eq!(sizes, [2, 2, 1]);
+}
+
+#[tokio::test]
+async fn flushes_a_partial_batch_after_the_interval() {
+    let (events_tx, events_rx) = mpsc::channel(8);
+    let (tx, mut rx) = mpsc::channel(8);
+    let forwarder = tokio::spawn(async move {
+        EventForwarder::new(tx)
+            .flush_interval(Duration::from_millis(20))
+            .run(ReceiverStream::new(events_rx))
+            .await
+    });
+
+    events_tx.send(Ok(IdleEvent::Exists(1))).await.unwrap();
+    events_tx
+        .send(Ok(IdleEvent::Expunge(Seq(1))))
+        .await
+        .unwrap();
+    // The batch is far from full, but its first event has waited long enough.
+    let batch = tokio::time::timeout(Duration::from_secs(1), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(batch, [IdleEvent::Exists(1), IdleEvent::Expunge(Seq(1))]);
+
+    events_tx
+        .send(Err(anyhow!("connection lost")))
+        .await
+        .unwrap();
+    let err = forwarder.await.unwrap().unwrap_err();
+    assert_eq!(err.to_string(), "connection lost");
+}
+
+#[tokio::test]
+async fn retries_failed_deliveries() {
+    let mut forwarder =
+        EventForwarder::new(Flaky { failures: 2 }).retry(3, Duration::from_millis(1));
+    let stats = forwarder.run(tokio_stream::iter(events(3))).await.unwrap();
+    assert_eq!(stats.batches, 1);
+    assert_eq!(stats.events, 3);
+    assert_eq!(stats.retries, 2);
+}
+
+#[tokio::test]
+async fn gives_up_after_the_last_attempt() {
+    let mut forwarder =
+        EventForwarder::new(Flaky { failures: 3 }).retry(3, Duration::from_millis(1));
+    let err = forwarder
+        .run(tokio_stream::iter(events(2)))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        format!("{:#}", err),
+        "Dropping 2 events after 3 delivery attempts: webhook returned 503"
+    );
+}
+
+#[tokio::test]
+async fn delivers_what_is_left_before_a_stream_error() {
+    let (tx, mut rx) = mpsc::channel(8);
+    let mut stream = events(2);
+    stream.push(Err(anyhow!("connection lost")));
+    let err = EventForwarder::new(tx)
+        .run(tokio_stream::iter(stream))
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "connection lost");
+    assert_eq!(
+        rx.try_recv().unwrap(),
+        [IdleEvent::Exists(1), IdleEvent::Exists(2)]
+    );
+    assert!(rx.try_recv().is_err());
+}
@@ -0,0 +1,73 @@
+//! Client::events: untagged responses as typed events.
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use tokio_stream::StreamExt;
+
+use imap::types::common::{MessageFlags, Seq, SystemFlags, Uid};
+use imap::types::response::UnsolicitedEvent;
+
+#[tokio::test]
+async fn untagged_responses_become_events() {
+    let server = MockServer::new(|tag, cmd| {
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" => "",
+            "SELECT" => concat!(
+                "* FLAGS (\\Seen \\Deleted)\r\n",
+                "* 4 EXISTS\r\n",
+                "* 1 RECENT\r\n",
+                "* OK [UIDVALIDITY 7] UIDs valid\r\n",
+                "* 2 EXPUNGE\r\n",
+                "* 3 FETCH (UID 12 FLAGS (\\Seen))\r\n",
+                "* 1 FETCH (RFC822.SIZE 300)\r\n",
+                "* OK [ALERT] Maintenance at noon\r\n",
+            ),
+            "CHECK" => return b"* BYE Shutting down\r\n".to_vec(),
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let events = session.events();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    assert!(session.check().await.is_err());
+
+    let events: Vec<_> = events.take(6).collect().await;
+    assert_eq!(
+        events,
+        vec![
+            UnsolicitedEvent::Exists(4),
+            UnsolicitedEvent::Recent(1),
+            UnsolicitedEvent::Expunge(Seq(2)),
+            UnsolicitedEvent::FlagsChanged {
+                seq: Seq(3),
+                uid: Some(Uid(12)),
+                flags: MessageFlags {
+                    system: SystemFlags::SEEN,
+                    keywords: Vec::new(),
+                },
+            },
+            UnsolicitedEvent::Alert("Maintenance at noon".to_string()),
+            UnsolicitedEvent::Bye("Shutting down".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn events_end_when_the_connection_closes() {
+    let server = MockServer::new(|tag, _| format!("{} OK done\r\n", tag).into_bytes());
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut events = client.events();
+    client.logout().await.unwrap();
+    assert_eq!(events.next().await, None);
+}
@@ -0,0 +1,79 @@
+//! EXPUNGE, CHECK and CLOSE on the selected mailbox.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use tokio_stream::StreamExt;
+
+use imap::types::common::Seq;
+use imap::types::response::UnsolicitedEvent;
+
+#[tokio::test]
+async fn expunge_reports_removed_messages() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = match cmd {
+            "SELECT \"INBOX\"" => "* 8 EXISTS\r\n",
+            // The example from RFC 3501 section 6.4.3: messages 3, 4, 7 and 11 removed.
+            "EXPUNGE" => "* 3 EXPUNGE\r\n* 3 EXPUNGE\r\n* 5 EXPUNGE\r\n* 8 EXPUNGE\r\n",
+            _ => "",
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let events = session.events();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let removed = session.expunge().await.unwrap();
+    assert_eq!(removed, [Seq(3), Seq(3), Seq(5), Seq(8)]);
+    assert_eq!(session.mailbox_state().unwrap().exists, 4);
+    session.check().await.unwrap();
+
+    // Other listeners see the same removals as events.
+    let expunged: Vec<_> = events
+        .filter(|event| matches!(event, UnsolicitedEvent::Expunge(_)))
+        .take(4)
+        .collect()
+        .await;
+    assert_eq!(
+        expunged,
+        [3, 3, 5, 8].map(|n| UnsolicitedEvent::Expunge(Seq(n)))
+    );
+
+    let session = session.close().await.unwrap();
+    assert!(session.mailbox_state().is_none());
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[received.len() - 3..],
+        ["EXPUNGE", "CHECK", "CLOSE"]
+    );
+}
+
+#[tokio::test]
+async fn a_refused_close_is_an_error() {
+    let server = MockServer::new(|tag, cmd| match cmd {
+        "CLOSE" => format!("{} NO mailbox is locked\r\n", tag).into_bytes(),
+        _ => format!("{} OK done\r\n", tag).into_bytes(),
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (session, _) = session.select("INBOX").await.unwrap();
+    let err = session.close().await.err().unwrap();
+    assert!(
+        format!("{:#}", err).contains("mailbox is locked"),
+        "{:#}",
+        err
+    );
+}
@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use bindings::Builder;
+use bindings::test_util::{Faults, FaultyStream, MockServer};
+use imap::types::common::Uid;
+
+const SUBJECT: &str = "split\r\nsubject";
+
+fn fetch_response() -> String {
+    format!(
+        "* 1 FETCH (ENVELOPE (\"Mon, 1 Jan 2024 00:00:00 +0000\" {{{}}}\r\n{} NIL NIL NIL NIL NIL NIL NIL NIL))\r\n",
+        SUBJECT.len(),
+        SUBJECT
+    )
+}
+
+fn server() -> MockServer {
+    MockServer::new(|tag, cmd| {
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" => String::new(),
+            "SELECT" => "* 1 EXISTS\r\n".to_string(),
+            "FETCH" => fetch_response(),
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    })
+}
+
+/// Byte offsets of every `\n` in the server output up to (and including) the FETCH response.
+fn crlf_split_offsets() -> Vec<usize> {
+    let greeting = "* OK IMAP4rev1 mock server ready\r\n";
+    let login = "A0000 OK LOGIN completed\r\n";
+    let select = "* 1 EXISTS\r\nA0000 OK SELECT completed\r\n";
+    let transcript = format!("{}{}{}{}", greeting, login, select, fetch_response());
+    transcript
+        .bytes()
+        .enumerate()
+        .filter(|(_, b)| *b == b'\n')
+        .map(|(i, _)| i)
+        .collect()
+}
+
+async fn fetch_subject(faults: Faults) -> anyhow::Result<Vec<Option<String>>> {
+    let stream = FaultyStream::new(server().spawn(), faults);
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(stream)
+        .await?;
+    let mut session = client.login("user", "pass").await?;
+    let envelopes = session.fetch("INBOX", 1).await?;
+    Ok(envelopes.into_iter().map(|e| e.subject).collect())
+}
+
+#[tokio::test]
+async fn reads_split_inside_crlf() {
+    let subjects = fetch_subject(Faults::new().split_reads_at(crlf_split_offsets()))
+        .await
+        .unwrap();
+    assert_eq!(subjects, vec![Some(SUBJECT.to_string())]);
+}
+
+#[tokio::test]
+async fn single_byte_reads_inside_literal() {
+    let subjects = fetch_subject(Faults::new().max_read_chunk(1))
+        .await
+        .unwrap();
+    assert_eq!(subjects, vec![Some(SUBJECT.to_string())]);
+}
+
+#[tokio::test]
+async fn partial_writes_and_delays() {
+    let faults = Faults::new()
+        .max_write_chunk(3)
+        .max_read_chunk(7)
+        .read_delay(Duration::from_millis(1));
+    let subjects = fetch_subject(faults).await.unwrap();
+    assert_eq!(subjects, vec![Some(SUBJECT.to_string())]);
+}
+
+#[tokio::test]
+async fn disconnect_mid_literal_fails_the_command() {
+    let offsets = crlf_split_offsets();
+    // Drop the connection a few bytes into the FETCH literal.
+    let cut = offsets[offsets.len() - 2] + 4;
+    let result = fetch_subject(Faults::new().disconnect_after(cut)).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn skips_banner_lines_before_greeting() {
+    let stream = server()
+        .greeting("Welcome to the gateway\r\n* OK ready\r\n")
+        .spawn();
+    let client = Builder::new("mock:143")
+        .greeting_skip_lines(1)
+        .build()
+        .connect_stream(stream)
+        .await
+        .unwrap();
+    client.login("user", "pass").await.unwrap();
+
+    let stream = server()
+        .greeting("Welcome to the gateway\r\n* OK ready\r\n")
+        .spawn();
+    assert!(
+        Builder::new("mock:143")
+            .build()
+            .connect_stream(stream)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn literal_larger_than_line_cap() {
+    // CRLFs inside the literal must not end the response, and its size is not capped.
+    let body: Vec<u8> = (0..64 * 1024u32)
+        .map(|i| match i % 80 {
+            78 => b'\r',
+            79 => b'\n',
+            n => b'a' + (n % 26) as u8,
+        })
+        .collect();
+    let served = body.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        let mut out = Vec::new();
+        if cmd.starts_with("UID FETCH") {
+            out.extend(format!("* 1 FETCH (UID 7 BODY[] {{{}}}\r\n", served.len()).into_bytes());
+            out.extend(&served);
+            out.extend(b")\r\n");
+        }
+        out.extend(format!("{} OK completed\r\n", tag).into_bytes());
+        out
+    });
+    let stream = FaultyStream::new(server.spawn(), Faults::new().max_read_chunk(1500));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(stream)
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    let fetched = session.fetch_body("INBOX", Uid(7)).await.unwrap().unwrap();
+    assert_eq!(&fetched[..], &body[..]);
+}
@@ -0,0 +1,58 @@
+//! fetch_body: raw messages byte for byte, from literals and literal8.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::common::Uid;
+
+/// Invalid UTF-8, a NUL, a bare CR and a line that looks like a tagged completion.
+const RAW: &[u8] = b"Subject: \xff\xc3\r\n\r\nnul \0 cr \r here\r\nA1 OK done\r\n";
+
+fn literal(prefix: &str, uid: u32) -> Vec<u8> {
+    let mut out = format!(
+        "* 1 FETCH (UID {} BODY[] {}{{{}}}\r\n",
+        uid,
+        prefix,
+        RAW.len()
+    )
+    .into_bytes();
+    out.extend_from_slice(RAW);
+    out.extend_from_slice(b")\r\n");
+    out
+}
+
+#[tokio::test]
+async fn returns_the_message_unchanged() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let mut out = match cmd.strip_prefix("UID FETCH ") {
+            Some(rest) if rest.starts_with("7 ") => literal("", 7),
+            Some(rest) if rest.starts_with("8 ") => literal("~", 8),
+            // Data for another message is not the answer.
+            Some(rest) if rest.starts_with("9 ") => literal("", 4),
+            _ => Vec::new(),
+        };
+        out.extend_from_slice(format!("{} OK done\r\n", tag).as_bytes());
+        out
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let body = session.fetch_body("INBOX", Uid(7)).await.unwrap().unwrap();
+    assert_eq!(&body[..], RAW);
+    let body = session.fetch_body("INBOX", Uid(8)).await.unwrap().unwrap();
+    assert_eq!(&body[..], RAW);
+    assert_eq!(session.fetch_body("INBOX", Uid(9)).await.unwrap(), None);
+
+    let received = received.lock().unwrap();
+    assert_eq!(received[1], "SELECT \"INBOX\"");
+    assert_eq!(received[2], "UID FETCH 7 (UID BODY.PEEK[])");
+}
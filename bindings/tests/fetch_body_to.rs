@@ -0,0 +1,108 @@
+//! Streaming a body section into an `AsyncWrite`.
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use imap::commands::Section;
+use imap::types::common::Uid;
+use tokio::io::AsyncWrite;
+
+/// A megabyte of attachment data, with CRLFs that must not end the response.
+fn attachment() -> Vec<u8> {
+    (0..1024 * 1024)
+        .map(|i| {
+            if i % 76 == 75 {
+                b'\n'
+            } else {
+                b'\r' + (i % 64) as u8
+            }
+        })
+        .collect()
+}
+
+fn server() -> MockServer {
+    MockServer::new(|tag, cmd| {
+        let mut out = Vec::new();
+        match cmd {
+            "UID FETCH 3 (UID BODY.PEEK[2])" => {
+                let data = attachment();
+                out.extend_from_slice(
+                    format!("* 1 FETCH (UID 3 BODY[2] {{{}}}\r\n", data.len()).as_bytes(),
+                );
+                out.extend_from_slice(&data);
+                out.extend_from_slice(b" FLAGS (\\Seen))\r\n");
+            }
+            "UID FETCH 3 (UID BODY.PEEK[1])" => {
+                out.extend_from_slice(b"* 1 FETCH (UID 3 BODY[1] \"hello\")\r\n");
+            }
+            _ => {}
+        }
+        out.extend_from_slice(format!("{} OK done\r\n", tag).as_bytes());
+        out
+    })
+}
+
+/// Collects what is written, remembering the size of each write.
+#[derive(Default)]
+struct Recorder {
+    data: Vec<u8>,
+    writes: Vec<usize>,
+}
+
+impl AsyncWrite for Recorder {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.data.extend_from_slice(buf);
+        self.writes.push(buf.len());
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn streams_a_literal_in_pieces() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let mut out = Recorder::default();
+    let written = session
+        .fetch_body_to(Uid(3), Section::part(&[2]), &mut out)
+        .await
+        .unwrap();
+    assert_eq!(written, Some(attachment().len() as u64));
+    assert!(out.data == attachment());
+    assert!(out.writes.len() > 1, "the body arrived in one piece");
+
+    // The connection is still framed correctly afterwards.
+    let mut out = Vec::new();
+    let written = session
+        .fetch_body_to(Uid(3), Section::part(&[1]), &mut out)
+        .await
+        .unwrap();
+    assert_eq!(written, Some(5));
+    assert_eq!(out, b"hello");
+
+    let written = session
+        .fetch_body_to(Uid(4), Section::part(&[1]), &mut Vec::new())
+        .await
+        .unwrap();
+    assert_eq!(written, None);
+}
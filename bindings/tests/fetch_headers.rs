@@ -0,0 +1,107 @@
+//! fetch_headers: header fields for a UID list, in batches that fit the command line limit.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+use bindings::{AuthenticatedState, Builder};
+use tokio_stream::StreamExt;
+
+use imap::types::common::Uid;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+/// The UIDs of a set such as `1,3,5:7`.
+fn expand(set: &str) -> Vec<u32> {
+    set.split(',')
+        .flat_map(|part| match part.split_once(':') {
+            Some((lo, hi)) => (lo.parse().unwrap()..=hi.parse().unwrap()).collect(),
+            None => vec![part.parse().unwrap()],
+        })
+        .collect()
+}
+
+/// Answers each UID FETCH with a Subject for every UID in the set, except UID 13, and
+/// fails any batch containing UID 10000.
+async fn connect() -> (Client<AuthenticatedState>, Log) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let Some(rest) = cmd.strip_prefix("UID FETCH ") else {
+            return format!("{} OK done\r\n", tag).into_bytes();
+        };
+        let uids = expand(rest.split(' ').next().unwrap());
+        if uids.contains(&10_000) {
+            return format!("{} NO message is gone\r\n", tag).into_bytes();
+        }
+        let mut out = String::new();
+        for (i, uid) in uids.iter().filter(|&&uid| uid != 13).enumerate() {
+            let header = format!("Subject: message {}\r\n\r\n", uid);
+            out.push_str(&format!(
+                "* {} FETCH (UID {} BODY[HEADER.FIELDS (SUBJECT)] {{{}}}\r\n{})\r\n",
+                i + 1,
+                uid,
+                header.len(),
+                header
+            ));
+        }
+        format!("{}{} OK done\r\n", out, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    (client.login("user", "pass").await.unwrap(), received)
+}
+
+#[tokio::test]
+async fn batches_long_uid_lists() {
+    let (mut session, received) = connect().await;
+    // Every other UID, so the set cannot be compressed into ranges.
+    let uids: Vec<Uid> = (1..6000).step_by(2).map(Uid).collect();
+    let headers: Vec<_> = session
+        .fetch_headers("INBOX", &uids, &["SUBJECT"])
+        .await
+        .unwrap()
+        .collect()
+        .await;
+
+    // UID 13 got no response and is left out.
+    assert_eq!(headers.len(), uids.len() - 1);
+    let (uid, first) = headers[0].as_ref().unwrap();
+    assert_eq!(*uid, Uid(1));
+    assert_eq!(first.get("subject"), Some("message 1"));
+
+    let received = received.lock().unwrap();
+    let fetches: Vec<_> = received
+        .iter()
+        .filter(|cmd| cmd.starts_with("UID FETCH"))
+        .collect();
+    assert!(fetches.len() > 1, "{} batches", fetches.len());
+    let mut requested = Vec::new();
+    for cmd in &fetches {
+        assert!(cmd.len() < 8 * 1024, "{} bytes", cmd.len());
+        assert!(cmd.ends_with(" (UID BODY.PEEK[HEADER.FIELDS (SUBJECT)])"));
+        requested.extend(expand(cmd.split(' ').nth(2).unwrap()));
+    }
+    let expected: Vec<u32> = uids.iter().map(|uid| uid.0).collect();
+    assert_eq!(requested, expected);
+}
+
+#[tokio::test]
+async fn a_failed_batch_ends_the_stream_with_its_error() {
+    let (mut session, _) = connect().await;
+    let mut headers = session
+        .fetch_headers("INBOX", &[Uid(2), Uid(10_000)], &["FROM"])
+        .await
+        .unwrap();
+    let err = headers.next().await.unwrap().unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("message is gone"),
+        "{:#}",
+        err
+    );
+    assert!(headers.next().await.is_none());
+}
@@ -0,0 +1,67 @@
+//! The session's fetch profile, and per-call overrides of it.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::commands::FetchItem;
+use imap::types::common::Uid;
+
+#[tokio::test]
+async fn profile_chooses_the_fetched_items() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = if cmd.starts_with("FETCH") {
+            "* 1 FETCH (UID 7 FLAGS (\\Seen) RFC822.SIZE 120)\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    assert!(matches!(
+        session.fetch_profile(),
+        [FetchItem::Uid, FetchItem::Envelope]
+    ));
+
+    session.set_fetch_profile(vec![
+        FetchItem::Uid,
+        FetchItem::Flags,
+        FetchItem::Rfc822Size,
+    ]);
+    // The profile belongs to the session and survives the switch to the selected state.
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    let messages = session.fetch_messages("INBOX", 1).await.unwrap();
+    assert_eq!(messages[0].uid(), Some(Uid(7)));
+    assert!(messages[0].flags().is_some());
+
+    session
+        .fetch_messages_with("INBOX", 1, vec![FetchItem::Uid, FetchItem::InternalDate])
+        .await
+        .unwrap();
+    // fetch always asks for ENVELOPE, on top of the profile.
+    session.fetch("INBOX", 1).await.unwrap();
+
+    let received = received.lock().unwrap();
+    let fetches: Vec<&str> = received
+        .iter()
+        .filter(|c| c.starts_with("FETCH"))
+        .map(String::as_str)
+        .collect();
+    assert_eq!(
+        fetches,
+        [
+            "FETCH 1 (UID FLAGS RFC822.SIZE)",
+            "FETCH 1 (UID INTERNALDATE)",
+            "FETCH 1 (UID FLAGS RFC822.SIZE ENVELOPE)",
+        ]
+    );
+}
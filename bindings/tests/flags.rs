@@ -0,0 +1,85 @@
+//! Message flags: system flags as a bitset, keywords interned per mailbox.
+
+use std::sync::Arc;
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::commands::FetchItem;
+use imap::types::common::{Flag, KeywordInterner, MessageFlags, SystemFlags};
+use imap::types::response::FetchData;
+
+#[test]
+fn system_flags_form_a_bitset() {
+    let flags: SystemFlags = [Flag::Draft, Flag::Seen, Flag::Keyword("$Work".into())]
+        .iter()
+        .collect();
+    assert_eq!(flags, SystemFlags::SEEN | SystemFlags::DRAFT);
+    assert!(flags.contains(SystemFlags::SEEN));
+    assert!(!flags.contains(SystemFlags::SEEN | SystemFlags::FLAGGED));
+
+    let mut flags = flags;
+    flags.insert(SystemFlags::FLAGGED);
+    flags.remove(SystemFlags::SEEN);
+    // Expanded in a fixed order, whatever the order they were set in.
+    assert!(matches!(flags.to_flags()[..], [Flag::Flagged, Flag::Draft]));
+    assert!(SystemFlags::empty().is_empty());
+}
+
+#[test]
+fn keywords_are_shared() {
+    let mut interner = KeywordInterner::new();
+    let first = interner.intern("$Work");
+    let second = interner.intern("$Work");
+    assert!(Arc::ptr_eq(&first, &second));
+    interner.intern("$Junk");
+    assert_eq!(interner.len(), 2);
+
+    let flags = MessageFlags {
+        system: SystemFlags::ANSWERED,
+        keywords: vec![first],
+    };
+    assert!(flags.contains(&Flag::Answered));
+    assert!(flags.contains(&Flag::Keyword("$Work".into())));
+    assert!(!flags.contains(&Flag::Keyword("$Junk".into())));
+    assert!(matches!(
+        &flags.to_vec()[..],
+        [Flag::Answered, Flag::Keyword(k)] if k == "$Work"
+    ));
+}
+
+#[tokio::test]
+async fn fetched_keywords_share_one_allocation() {
+    let server = MockServer::new(|tag, cmd| {
+        let body = if cmd.starts_with("FETCH") {
+            "* 1 FETCH (FLAGS (\\Seen $Work))\r\n* 2 FETCH (FLAGS ($Work \\Flagged))\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let fetched = session
+        .fetch_items(1..=2, vec![FetchItem::Flags])
+        .await
+        .unwrap();
+    let flags: Vec<&MessageFlags> = fetched
+        .iter()
+        .flat_map(|(_, items)| items)
+        .filter_map(|item| match item {
+            FetchData::Flags(flags) => Some(flags),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(flags.len(), 2);
+    assert_eq!(flags[0].system, SystemFlags::SEEN);
+    assert_eq!(flags[1].system, SystemFlags::FLAGGED);
+    assert!(Arc::ptr_eq(&flags[0].keywords[0], &flags[1].keywords[0]));
+}
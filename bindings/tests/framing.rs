@@ -0,0 +1,87 @@
+//! Splitting server data into responses around literals, and refusing oversized ones.
+
+use bytes::BytesMut;
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use imap::ImapError;
+use imap::commands::FetchItem;
+use imap::framing::Framer;
+use imap::types::command::SequenceSet;
+
+#[test]
+fn literals_stay_inside_their_response() {
+    let mut framer = Framer::default();
+    let mut buf = BytesMut::from(&b"* 1 FETCH (BODY[] {4}\r\na\r\nb)\r\n* 2 EX"[..]);
+    let response = framer.next(&mut buf).unwrap().unwrap();
+    assert_eq!(&response[..], b"* 1 FETCH (BODY[] {4}\r\na\r\nb)\r\n");
+    assert!(framer.next(&mut buf).unwrap().is_none());
+    buf.extend_from_slice(b"ISTS\r\n");
+    assert_eq!(
+        &framer.next(&mut buf).unwrap().unwrap()[..],
+        b"* 2 EXISTS\r\n"
+    );
+}
+
+#[test]
+fn oversized_literal_announcements_fail() {
+    for announced in ["18446744073709551615", "99999999999999999999999"] {
+        let mut framer = Framer::default();
+        let line = format!("* 1 FETCH (BODY[] {{{}}}\r\n", announced);
+        let mut buf = BytesMut::from(line.as_bytes());
+        let err = framer.next(&mut buf).unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ImapError::LiteralTooLarge {
+                    size: usize::MAX,
+                    ..
+                }
+            ),
+            "{}",
+            err
+        );
+    }
+
+    let mut framer = Framer::default();
+    framer.max_literal(4);
+    let mut buf = BytesMut::from(&b"* 1 FETCH (BODY[] {4}\r\nabcd)\r\n"[..]);
+    assert!(framer.next(&mut buf).unwrap().is_some());
+    let mut buf = BytesMut::from(&b"* 2 FETCH (BODY[] {5+}\r\n"[..]);
+    let err = framer.next(&mut buf).unwrap_err();
+    assert!(matches!(
+        err,
+        ImapError::LiteralTooLarge { size: 5, max: 4 }
+    ));
+}
+
+#[tokio::test]
+async fn a_literal_over_the_limit_fails_the_connection() {
+    let server = MockServer::new(move |tag, cmd| {
+        let body = if cmd.starts_with("SELECT ") {
+            "* 1 EXISTS\r\n"
+        } else if cmd.starts_with("FETCH ") {
+            "* 1 FETCH (BODY[] {1048577}\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .max_literal_size(1024 * 1024)
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let err = session
+        .fetch_items(
+            SequenceSet::new().add_single(1),
+            vec![FetchItem::BodySection(String::new())],
+        )
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("1048577 bytes"), "{:#}", err);
+}
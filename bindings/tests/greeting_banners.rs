@@ -0,0 +1,59 @@
+//! Tolerating banner lines that middleboxes inject ahead of the greeting.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+const BANNERS: &str = "Welcome to the gateway\r\nAll traffic is logged\r\n";
+
+#[tokio::test]
+async fn skips_banners_up_to_the_limit() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let stream = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        format!("{} OK done\r\n", tag).into_bytes()
+    })
+    .greeting(format!(
+        "{}* OK [CAPABILITY IMAP4rev1 IDLE] ready\r\n",
+        BANNERS
+    ))
+    .spawn();
+    let mut client = Builder::new("mock:143")
+        .greeting_skip_lines(3)
+        .build()
+        .connect_stream(stream)
+        .await
+        .unwrap();
+
+    // The greeting after the banners still counts: its capabilities need no CAPABILITY.
+    assert!(client.capabilities().await.unwrap().has("IDLE"));
+    client.login("user", "pass").await.unwrap();
+    assert!(
+        !received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|cmd| cmd == "CAPABILITY")
+    );
+}
+
+#[tokio::test]
+async fn fails_on_more_banners_than_allowed() {
+    let stream = MockServer::new(|tag, _| format!("{} OK done\r\n", tag).into_bytes())
+        .greeting(format!("{}* OK ready\r\n", BANNERS))
+        .spawn();
+    let err = Builder::new("mock:143")
+        .greeting_skip_lines(1)
+        .build()
+        .connect_stream(stream)
+        .await
+        .err()
+        .unwrap();
+    assert!(
+        format!("{:#}", err).contains("Failed to parse IMAP greeting"),
+        "{:#}",
+        err
+    );
+}
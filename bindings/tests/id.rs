@@ -0,0 +1,45 @@
+//! The ID command (RFC 2971).
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+#[tokio::test]
+async fn exchanges_identification() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = match cmd {
+            "ID NIL" => "* ID NIL\r\n",
+            _ if cmd.starts_with("ID ") => {
+                "* ID (\"Name\" \"Dovecot\" \"support-url\" NIL \"version\" \"2.3\")\r\n"
+            }
+            _ => "",
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let mut client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+
+    // Allowed before login, as some providers require.
+    let server_info = client
+        .id(&[("name", "mailux"), ("version", "0.1")])
+        .await
+        .unwrap();
+    assert_eq!(server_info.len(), 3);
+    assert_eq!(server_info["name"].as_deref(), Some("Dovecot"));
+    assert_eq!(server_info["version"].as_deref(), Some("2.3"));
+    assert_eq!(server_info["support-url"], None);
+
+    let mut session = client.login("user", "pass").await.unwrap();
+    assert!(session.id(&[]).await.unwrap().is_empty());
+
+    let received = received.lock().unwrap();
+    assert_eq!(received[0], "ID (\"name\" \"mailux\" \"version\" \"0.1\")");
+    assert_eq!(received[2], "ID NIL");
+}
@@ -0,0 +1,100 @@
+//! IDLE (RFC 2177): mailbox changes as an event stream.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use tokio_stream::StreamExt;
+
+use imap::types::common::{MessageFlags, Seq, SystemFlags};
+use imap::types::response::IdleEvent;
+
+/// Answers IDLE with `idle_reply`, and DONE with the IDLE's tagged completion.
+fn server(idle_reply: &'static str, received: Arc<Mutex<Vec<String>>>) -> MockServer {
+    let idle_tag = Arc::new(Mutex::new(None::<String>));
+    MockServer::new(move |tag, cmd| {
+        received
+            .lock()
+            .unwrap()
+            .push(if tag == "DONE" { tag } else { cmd }.to_string());
+        if cmd == "IDLE" {
+            *idle_tag.lock().unwrap() = Some(tag.to_string());
+            return idle_reply.replace("{tag}", tag).into_bytes();
+        }
+        if tag == "DONE" {
+            let tag = idle_tag.lock().unwrap().take().unwrap();
+            return format!("{} OK IDLE terminated\r\n", tag).into_bytes();
+        }
+        format!("{} OK done\r\n", tag).into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn streams_mailbox_changes_until_done() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let idle_reply = concat!(
+        "+ idling\r\n",
+        "* 5 EXISTS\r\n",
+        "* 2 EXPUNGE\r\n",
+        "* 3 FETCH (FLAGS (\\Seen $Work))\r\n",
+    );
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(idle_reply, received.clone()).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let mut idle = session.idle().await.unwrap();
+    let mut events = Vec::new();
+    while events.len() < 3 {
+        let event = tokio::time::timeout(Duration::from_secs(2), idle.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        events.push(event);
+    }
+    assert_eq!(events[0], IdleEvent::Exists(5));
+    assert_eq!(events[1], IdleEvent::Expunge(Seq(2)));
+    assert_eq!(
+        events[2],
+        IdleEvent::FlagsChanged {
+            seq: Seq(3),
+            uid: None,
+            flags: MessageFlags {
+                system: SystemFlags::SEEN,
+                keywords: vec!["$Work".into()],
+            },
+        }
+    );
+    idle.done().await.unwrap();
+
+    // The session is usable again once IDLE has ended.
+    session.check().await.unwrap();
+    let received = received.lock().unwrap();
+    assert_eq!(received[received.len() - 3..], ["IDLE", "DONE", "CHECK"]);
+}
+
+#[tokio::test]
+async fn a_refused_idle_ends_the_stream_with_an_error() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("{tag} NO IDLE not allowed\r\n", received).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let mut idle = session.idle().await.unwrap();
+    let err = idle.next().await.unwrap().unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("IDLE not allowed"),
+        "{:#}",
+        err
+    );
+    assert!(idle.done().await.is_err());
+}
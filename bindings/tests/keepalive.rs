@@ -0,0 +1,118 @@
+//! NOOPs and IDLE restarts sent by the run loop on a quiet connection.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use tokio_stream::StreamExt;
+
+use imap::types::response::IdleEvent;
+
+#[tokio::test]
+async fn quiet_sessions_send_noop() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        format!("{} OK done\r\n", tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .keepalive(Duration::from_millis(50))
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(180)).await;
+    let noops = received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|c| *c == "NOOP")
+        .count();
+    assert!((1..=3).contains(&noops), "{} NOOPs", noops);
+
+    // The session still works, and traffic postpones the next NOOP.
+    session.list("", "*").await.unwrap();
+    let before = received.lock().unwrap().len();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(received.lock().unwrap().len(), before);
+}
+
+#[tokio::test]
+async fn idle_is_restarted_instead() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let idle_tag = Arc::new(Mutex::new(None::<String>));
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock()
+            .unwrap()
+            .push(if tag == "DONE" { tag } else { cmd }.to_string());
+        if cmd == "IDLE" {
+            let mut idle_tag = idle_tag.lock().unwrap();
+            let first = idle_tag.is_none();
+            *idle_tag = Some(tag.to_string());
+            // The second IDLE reports a new message.
+            let event = if first { "" } else { "* 5 EXISTS\r\n" };
+            return format!("+ idling\r\n{}", event).into_bytes();
+        }
+        if tag == "DONE" {
+            let tag = idle_tag.lock().unwrap().clone().unwrap();
+            return format!("{} OK IDLE terminated\r\n", tag).into_bytes();
+        }
+        let body = if cmd.starts_with("SELECT") {
+            "* 4 EXISTS\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .keepalive(Duration::from_millis(50))
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let mut idle = session.idle().await.unwrap();
+    let event = tokio::time::timeout(Duration::from_secs(2), idle.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(event, IdleEvent::Exists(5));
+    idle.done().await.unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received[2..5], ["IDLE", "DONE", "IDLE"]);
+    assert!(!received.iter().any(|c| c == "NOOP"));
+}
+
+#[tokio::test]
+async fn dropping_the_client_logs_out() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        format!("{} OK done\r\n", tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .keepalive(Duration::from_millis(20))
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    drop(session);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let sent = received.lock().unwrap().clone();
+    assert_eq!(sent.last().map(String::as_str), Some("LOGOUT"));
+    // No NOOP follows once the session is over.
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(*received.lock().unwrap(), sent);
+}
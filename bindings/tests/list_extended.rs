@@ -0,0 +1,202 @@
+//! LIST-EXTENDED (RFC 5258) options and LIST-STATUS (RFC 5819), and their fallbacks on
+//! servers without them.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+use bindings::{AuthenticatedState, Builder};
+
+use imap::special_use::MailboxRole;
+use imap::types::command::StatusItem;
+use imap::types::response::ListEntry;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+fn reply(cmd: &str, capabilities: &str) -> String {
+    if cmd == "CAPABILITY" {
+        return format!("* CAPABILITY {}\r\n", capabilities);
+    }
+    if cmd.starts_with("LSUB") {
+        return "* LSUB () \"/\" \"Lists\"\r\n".to_string();
+    }
+    if cmd.starts_with("LIST (SUBSCRIBED)") {
+        return concat!(
+            "* LIST (\\Subscribed) \"/\" \"Lists\"\r\n",
+            "* LIST (\\Subscribed \\NonExistent) \"/\" \"Gone\"\r\n",
+        )
+        .to_string();
+    }
+    if cmd.starts_with("LIST") {
+        let subscribed = if cmd.contains("RETURN (SUBSCRIBED)") {
+            "\\Subscribed"
+        } else {
+            ""
+        };
+        return format!(
+            "* LIST () \"/\" \"INBOX\"\r\n* LIST ({}) \"/\" \"Lists\"\r\n",
+            subscribed
+        );
+    }
+    String::new()
+}
+
+type Reply = fn(&str, &str) -> String;
+
+async fn connect(capabilities: &'static str, reply: Reply) -> (Client<AuthenticatedState>, Log) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        format!("{}{} OK done\r\n", reply(cmd, capabilities), tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    received.lock().unwrap().clear();
+    (session, received)
+}
+
+fn subscribed(entries: &[ListEntry]) -> Vec<(&str, bool)> {
+    entries
+        .iter()
+        .map(|e| (e.name.as_str(), e.is_subscribed()))
+        .collect()
+}
+
+#[tokio::test]
+async fn subscriptions_with_list_extended() {
+    let (mut session, log) = connect("IMAP4rev1 LIST-EXTENDED", reply).await;
+    let entries = session.list_with_subscriptions("", "*").await.unwrap();
+    assert_eq!(subscribed(&entries), [("INBOX", false), ("Lists", true)]);
+
+    let entries = session.list_subscribed("", "*").await.unwrap();
+    assert_eq!(subscribed(&entries), [("Lists", true), ("Gone", true)]);
+    assert!(entries[1].has_attribute("\\NonExistent"));
+
+    assert_eq!(
+        log.lock().unwrap()[1..],
+        [
+            "LIST \"\" \"*\" RETURN (SUBSCRIBED)",
+            "LIST (SUBSCRIBED) \"\" \"*\"",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn subscriptions_from_lsub() {
+    let (mut session, log) = connect("IMAP4rev1", reply).await;
+    let entries = session.list_with_subscriptions("", "*").await.unwrap();
+    assert_eq!(subscribed(&entries), [("INBOX", false), ("Lists", true)]);
+
+    let entries = session.list_subscribed("", "*").await.unwrap();
+    assert_eq!(subscribed(&entries), [("Lists", true)]);
+
+    assert_eq!(
+        log.lock().unwrap()[1..],
+        ["LSUB \"\" \"*\"", "LIST \"\" \"*\"", "LSUB \"\" \"*\""]
+    );
+}
+
+/// Two special-use mailboxes and a `\Noselect` parent, with STATUS answers for the rest.
+fn roles_reply(cmd: &str, capabilities: &str) -> String {
+    if cmd == "CAPABILITY" {
+        return format!("* CAPABILITY {}\r\n", capabilities);
+    }
+    if cmd.starts_with("LIST (SPECIAL-USE)") {
+        return "* LIST (\\Sent) \"/\" \"Sent\"\r\n* LIST (\\Trash) \"/\" \"Bin\"\r\n".to_string();
+    }
+    if cmd.starts_with("LIST") {
+        let mut lines = concat!(
+            "* LIST () \"/\" \"INBOX\"\r\n",
+            "* LIST (\\Sent) \"/\" \"Sent\"\r\n",
+            "* LIST (\\Noselect \\HasChildren) \"/\" \"Lists\"\r\n",
+        )
+        .to_string();
+        if cmd.contains("RETURN (STATUS") {
+            lines.push_str("* STATUS \"Sent\" (MESSAGES 12 UNSEEN 0)\r\n");
+            lines.push_str("* STATUS \"INBOX\" (MESSAGES 3 UNSEEN 1)\r\n");
+        }
+        return lines;
+    }
+    if let Some(mailbox) = cmd.strip_prefix("STATUS ") {
+        let name = mailbox.split(' ').next().unwrap();
+        return format!("* STATUS {} (MESSAGES 5 UNSEEN 2)\r\n", name);
+    }
+    String::new()
+}
+
+#[tokio::test]
+async fn special_use_listing() {
+    let (mut session, log) = connect("IMAP4rev1 LIST-EXTENDED SPECIAL-USE", roles_reply).await;
+    let entries = session.list_special_use().await.unwrap();
+    let roles: Vec<_> = entries
+        .iter()
+        .map(|e| (e.name.as_str(), e.special_use()))
+        .collect();
+    assert_eq!(
+        roles,
+        [
+            ("Sent", Some(MailboxRole::Sent)),
+            ("Bin", Some(MailboxRole::Trash))
+        ]
+    );
+    assert_eq!(log.lock().unwrap()[1..], ["LIST (SPECIAL-USE) \"\" \"*\""]);
+
+    // Without the selection option, a full LIST is filtered.
+    let (mut session, log) = connect("IMAP4rev1 SPECIAL-USE", roles_reply).await;
+    let entries = session.list_special_use().await.unwrap();
+    let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["Sent"]);
+    assert_eq!(log.lock().unwrap()[1..], ["LIST \"\" \"*\""]);
+}
+
+#[tokio::test]
+async fn status_in_one_round_trip_with_list_status() {
+    let (mut session, log) = connect("IMAP4rev1 LIST-EXTENDED LIST-STATUS", roles_reply).await;
+    let listed = session
+        .list_with_status("", "*", &[StatusItem::Messages, StatusItem::Unseen])
+        .await
+        .unwrap();
+    let counts: Vec<_> = listed
+        .iter()
+        .map(|(e, s)| (e.name.as_str(), s.as_ref().and_then(|s| s.messages)))
+        .collect();
+    assert_eq!(
+        counts,
+        [("INBOX", Some(3)), ("Sent", Some(12)), ("Lists", None)]
+    );
+    assert_eq!(
+        log.lock().unwrap()[1..],
+        ["LIST \"\" \"*\" RETURN (STATUS (MESSAGES UNSEEN))"]
+    );
+}
+
+#[tokio::test]
+async fn status_per_mailbox_without_list_status() {
+    let (mut session, log) = connect("IMAP4rev1", roles_reply).await;
+    let listed = session
+        .list_with_status("", "*", &[StatusItem::Messages, StatusItem::Unseen])
+        .await
+        .unwrap();
+    let counts: Vec<_> = listed
+        .iter()
+        .map(|(e, s)| (e.name.as_str(), s.as_ref().and_then(|s| s.unseen)))
+        .collect();
+    assert_eq!(
+        counts,
+        [("INBOX", Some(2)), ("Sent", Some(2)), ("Lists", None)]
+    );
+    // No STATUS for the \Noselect mailbox.
+    assert_eq!(
+        log.lock().unwrap()[1..],
+        [
+            "LIST \"\" \"*\"",
+            "STATUS \"INBOX\" (MESSAGES UNSEEN)",
+            "STATUS \"Sent\" (MESSAGES UNSEEN)",
+        ]
+    );
+}
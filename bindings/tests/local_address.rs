@@ -0,0 +1,38 @@
+//! Choosing the source address of the TCP connection.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
+
+use bindings::Builder;
+use tokio::net::TcpListener;
+
+/// Connects with `local` as the source address and returns the peer address the server
+/// saw, if the connection got that far. The TLS handshake never completes.
+async fn peer_seen_from(local: IpAddr) -> Option<IpAddr> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let accept = tokio::spawn(async move { listener.accept().await.unwrap().1.ip() });
+
+    let result = Builder::new(&addr)
+        .local_address(local)
+        .connect_timeout(Duration::from_millis(200))
+        .connect()
+        .await;
+    assert!(result.is_err());
+    tokio::time::timeout(Duration::from_millis(100), accept)
+        .await
+        .ok()
+        .map(|joined| joined.unwrap())
+}
+
+#[tokio::test]
+async fn binds_the_given_source_address() {
+    // All of 127.0.0.0/8 is loopback on Linux.
+    let local = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+    assert_eq!(peer_seen_from(local).await, Some(local));
+}
+
+#[tokio::test]
+async fn skips_server_addresses_of_the_other_family() {
+    assert_eq!(peer_seen_from(IpAddr::V6(Ipv6Addr::LOCALHOST)).await, None);
+}
@@ -0,0 +1,70 @@
+//! Capabilities sent along with the login completion, in whatever form the server chose.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::common::Capability;
+
+/// Logs in against a server whose LOGIN reply is `login` (with `{tag}` for the tag), and
+/// returns whether IDLE was reported and the commands sent.
+async fn idle_after_login(login: &'static str) -> (bool, Vec<String>) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let reply = match cmd.split(' ').next().unwrap() {
+            "LOGIN" => login.replace("{tag}", tag),
+            "CAPABILITY" => format!("* CAPABILITY IMAP4rev1 IDLE\r\n{} OK done\r\n", tag),
+            _ => format!("{} OK done\r\n", tag),
+        };
+        reply.into_bytes()
+    })
+    // The greeting's capabilities no longer apply after login.
+    .greeting("* OK [CAPABILITY IMAP4rev1 LOGINDISABLED STARTTLS] ready\r\n");
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    let idle = session
+        .capabilities()
+        .await
+        .unwrap()
+        .contains(&Capability::Idle);
+    let sent = received.lock().unwrap().clone();
+    (idle, sent)
+}
+
+#[tokio::test]
+async fn takes_the_response_code() {
+    let (idle, sent) = idle_after_login(
+        "* OK [ALERT] Scheduled maintenance\r\n{tag} OK [CAPABILITY IMAP4rev1 IDLE] Logged in\r\n",
+    )
+    .await;
+    assert!(idle);
+    assert_eq!(sent.len(), 1);
+}
+
+#[tokio::test]
+async fn takes_an_untagged_line_after_others() {
+    let (idle, sent) = idle_after_login(concat!(
+        "* 12 EXISTS\r\n",
+        "* OK [ALERT] Scheduled maintenance\r\n",
+        "* CAPABILITY IMAP4rev1 IDLE\r\n",
+        "{tag} OK Logged in\r\n",
+    ))
+    .await;
+    assert!(idle);
+    assert_eq!(sent.len(), 1);
+}
+
+#[tokio::test]
+async fn asks_when_none_were_sent() {
+    let (idle, sent) =
+        idle_after_login("* OK [ALERT] Scheduled maintenance\r\n{tag} OK Logged in\r\n").await;
+    assert!(idle);
+    assert_eq!(sent[1], "CAPABILITY");
+}
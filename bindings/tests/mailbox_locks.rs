@@ -0,0 +1,25 @@
+//! Advisory per-mailbox locks.
+
+use std::time::Duration;
+
+use bindings::async_impl::MailboxLocks;
+
+#[tokio::test]
+async fn lock_serializes_per_mailbox() {
+    let locks = MailboxLocks::new();
+    let inbox = locks.lock("INBOX").await;
+    assert!(locks.is_locked("INBOX"));
+    assert!(locks.try_lock("INBOX").is_none());
+    // Other mailboxes are independent.
+    assert!(locks.try_lock("Archive").is_some());
+
+    let waiter = tokio::spawn({
+        let locks = locks.clone();
+        async move { locks.lock("INBOX").await.mailbox().to_string() }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!waiter.is_finished());
+    drop(inbox);
+    assert_eq!(waiter.await.unwrap(), "INBOX");
+    assert!(!locks.is_locked("INBOX"));
+}
@@ -0,0 +1,69 @@
+//! CREATE, DELETE and RENAME, and their RFC 5530 refusals.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::async_impl::MailboxError;
+use bindings::test_util::MockServer;
+
+#[tokio::test]
+async fn create_delete_and_rename() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let reply = if cmd == "CREATE \"Archive\"" {
+            "NO [ALREADYEXISTS] Mailbox already exists"
+        } else if cmd == "DELETE \"Gone\"" {
+            "NO [NONEXISTENT] No such mailbox"
+        } else if cmd.starts_with("RENAME \"Old\"") {
+            "NO [nonexistent] No such mailbox"
+        } else if cmd.starts_with("RENAME \"Drafts\"") {
+            "NO [ALREADYEXISTS] Target exists"
+        } else if cmd == "DELETE \"Locked\"" {
+            "NO [INUSE] Mailbox in use"
+        } else {
+            "OK done"
+        };
+        format!("{} {}\r\n", tag, reply).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    session.create("Projects").await.unwrap();
+    session.rename("Projects", "Projects/2026").await.unwrap();
+    session.delete("Projects/2026").await.unwrap();
+
+    let refusal = |e: anyhow::Error| e.downcast_ref::<MailboxError>().cloned();
+    assert_eq!(
+        refusal(session.create("Archive").await.unwrap_err()),
+        Some(MailboxError::AlreadyExists("Archive".into()))
+    );
+    assert_eq!(
+        refusal(session.delete("Gone").await.unwrap_err()),
+        Some(MailboxError::NonExistent("Gone".into()))
+    );
+    assert_eq!(
+        refusal(session.rename("Old", "New").await.unwrap_err()),
+        Some(MailboxError::NonExistent("Old".into()))
+    );
+    assert_eq!(
+        refusal(session.rename("Drafts", "Sent").await.unwrap_err()),
+        Some(MailboxError::AlreadyExists("Sent".into()))
+    );
+    // Other refusals stay plain errors.
+    assert_eq!(refusal(session.delete("Locked").await.unwrap_err()), None);
+
+    assert_eq!(
+        received.lock().unwrap()[1..4],
+        [
+            "CREATE \"Projects\"",
+            "RENAME \"Projects\" \"Projects/2026\"",
+            "DELETE \"Projects/2026\"",
+        ]
+    );
+}
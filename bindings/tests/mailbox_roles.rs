@@ -0,0 +1,58 @@
+//! Mailbox roles from SPECIAL-USE attributes, with localized names as the fallback.
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::special_use::MailboxRole;
+
+const LIST: &str = concat!(
+    "* LIST (\\HasNoChildren) \"/\" \"INBOX\"\r\n",
+    "* LIST (\\HasNoChildren) \"/\" \"Papierkorb\"\r\n",
+    "* LIST (\\HasNoChildren \\Trash) \"/\" \"Bin\"\r\n",
+    "* LIST (\\HasNoChildren) \"/\" \"&AMk-l&AOk-ments envoy&AOk-s\"\r\n",
+    "* LIST (\\HasNoChildren) \"/\" \"Archiv/Gesendet\"\r\n",
+    "* LIST (\\HasNoChildren) \"/\" \"Archiv/Entw&APw-rfe\"\r\n",
+    "* LIST (\\Noselect \\HasChildren) \"/\" \"Junk\"\r\n",
+);
+
+fn server() -> MockServer {
+    MockServer::new(|tag, cmd| {
+        let body = if cmd.starts_with("LIST") { LIST } else { "" };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn attributes_win_over_names() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let roles = session.mailbox_roles().await.unwrap();
+    assert_eq!(
+        roles,
+        [
+            (MailboxRole::Trash, "Bin".to_string()),
+            // Localized names are matched after decoding modified UTF-7, but returned
+            // as sent; the first match in LIST order wins.
+            (
+                MailboxRole::Sent,
+                "&AMk-l&AOk-ments envoy&AOk-s".to_string()
+            ),
+            (MailboxRole::Drafts, "Archiv/Entw&APw-rfe".to_string()),
+        ]
+    );
+
+    assert_eq!(
+        session.mailbox_for_role(MailboxRole::Drafts).await.unwrap(),
+        Some("Archiv/Entw&APw-rfe".to_string())
+    );
+    // A \Noselect mailbox cannot hold messages, whatever its name.
+    assert_eq!(
+        session.mailbox_for_role(MailboxRole::Junk).await.unwrap(),
+        None
+    );
+}
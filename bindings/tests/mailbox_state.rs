@@ -0,0 +1,61 @@
+//! Client::mailbox_state: the selected mailbox kept up to date from untagged responses.
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::command::SearchKey;
+use imap::types::common::Flag;
+
+#[tokio::test]
+async fn follows_untagged_responses_after_select() {
+    let server = MockServer::new(|tag, cmd| {
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" | "CLOSE" => "",
+            "SELECT" => concat!(
+                "* FLAGS (\\Seen \\Deleted)\r\n",
+                "* 5 EXISTS\r\n",
+                "* 2 RECENT\r\n",
+                "* OK [UNSEEN 4] First unseen\r\n",
+                "* OK [UIDNEXT 10] Predicted next UID\r\n",
+            ),
+            "SEARCH" => concat!(
+                "* SEARCH 1\r\n",
+                "* 2 EXPUNGE\r\n",
+                "* 7 EXISTS\r\n",
+                "* 3 RECENT\r\n",
+                "* FLAGS (\\Seen \\Deleted $Junk)\r\n",
+            ),
+            "EXPUNGE" => "* 3 EXPUNGE\r\n",
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    assert!(session.mailbox_state().is_none());
+
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    let state = session.mailbox_state().unwrap();
+    assert_eq!((state.exists, state.recent, state.unseen), (5, 2, Some(4)));
+    assert_eq!(state.uid_next, Some(10));
+
+    // An EXPUNGE before the first unseen message moves it down.
+    session.search(vec![SearchKey::All]).await.unwrap();
+    let state = session.mailbox_state().unwrap();
+    assert_eq!((state.exists, state.recent, state.unseen), (7, 3, Some(3)));
+    assert_eq!(state.flags.len(), 3);
+    assert!(matches!(state.flags.last(), Some(Flag::Keyword(k)) if k == "$Junk"));
+
+    // Expunging the first unseen message itself leaves it unknown.
+    session.expunge().await.unwrap();
+    let state = session.mailbox_state().unwrap();
+    assert_eq!((state.exists, state.unseen), (6, None));
+
+    let session = session.close().await.unwrap();
+    assert!(session.mailbox_state().is_none());
+}
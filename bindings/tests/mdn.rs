@@ -0,0 +1,84 @@
+//! Read receipt requests and the `$MDNSent` keyword.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::mdn::{self, MdnRequest};
+use imap::types::common::{Flag, Uid};
+
+const FETCH: &str = concat!(
+    "* 1 FETCH (UID 5 FLAGS (\\Seen) BODY[HEADER.FIELDS (DISPOSITION-NOTIFICATION-TO)] {50}\r\n",
+    "Disposition-Notification-To: <ann@example.org>\r\n\r\n)\r\n",
+    "* 2 FETCH (UID 6 FLAGS ($mdnsent) BODY[HEADER.FIELDS (DISPOSITION-NOTIFICATION-TO)] {48}\r\n",
+    "Disposition-Notification-To: bob@example.org\r\n\r\n)\r\n",
+    "* 3 FETCH (UID 7 FLAGS () BODY[HEADER.FIELDS (DISPOSITION-NOTIFICATION-TO)] {2}\r\n",
+    "\r\n)\r\n",
+);
+
+#[tokio::test]
+async fn finds_requests_and_marks_them_sent() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = if cmd.starts_with("SELECT") {
+            "* 3 EXISTS\r\n* OK [PERMANENTFLAGS (\\Seen \\*)] ok\r\n"
+        } else if cmd.starts_with("UID FETCH") {
+            FETCH
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let requests = session
+        .mdn_requests("INBOX", &[Uid(5), Uid(6), Uid(7)])
+        .await
+        .unwrap();
+    // UID 7 asked for nothing; UID 6 was already handled by another client.
+    assert_eq!(
+        requests,
+        [
+            MdnRequest {
+                uid: Uid(5),
+                notify_to: "<ann@example.org>".to_string(),
+                sent: false,
+            },
+            MdnRequest {
+                uid: Uid(6),
+                notify_to: "bob@example.org".to_string(),
+                sent: true,
+            },
+        ]
+    );
+
+    session.mark_mdn_sent("INBOX", Uid(5)).await.unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[received.len() - 3..],
+        [
+            "SELECT \"INBOX\"",
+            "UID FETCH 5:7 (UID FLAGS BODY.PEEK[HEADER.FIELDS (Disposition-Notification-To)])",
+            "UID STORE 5 +FLAGS.SILENT ($MDNSent)",
+        ]
+    );
+}
+
+#[test]
+fn receipts_need_a_storable_keyword() {
+    assert!(mdn::can_mark_sent(&[
+        Flag::Seen,
+        Flag::Keyword("\\*".into())
+    ]));
+    assert!(mdn::can_mark_sent(&[Flag::Keyword("$MDNSent".into())]));
+    assert!(!mdn::can_mark_sent(&[Flag::Seen, Flag::Flagged]));
+}
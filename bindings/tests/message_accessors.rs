@@ -0,0 +1,59 @@
+//! `Message` accessors over parsed FETCH data.
+
+use imap::commands::Section;
+use imap::messages;
+use imap::parser::fetch::parse_fetch_responses;
+use imap::types::common::{Seq, SystemFlags, Uid};
+
+const RESPONSES: &[u8] = b"* 2 FETCH (UID 12 FLAGS (\\Seen) RFC822.SIZE 2048 \
+INTERNALDATE \"17-Jul-1996 02:44:25 -0700\" \
+ENVELOPE (\"Wed, 17 Jul 1996 02:23:25 -0700\" \"Status\" \
+((\"Terry Gray\" NIL \"gray\" \"cac.washington.edu\")) NIL NIL \
+((NIL NIL \"imap\" \"cac.washington.edu\")(\"Ops\" NIL \"ops\" \"example.org\")) \
+NIL NIL NIL \"<B27397-0100000@cac.washington.edu>\") \
+BODY[1] {5}\r\nhello BODY[HEADER] {15}\r\nSubject: Hi\r\n\r\n)\r\n\
+* 1 FETCH (UID 11 FLAGS ())\r\n\
+* 2 FETCH (FLAGS (\\Seen \\Flagged))\r\n";
+
+#[test]
+fn reads_the_fetched_items() {
+    let messages = messages::assemble(parse_fetch_responses(RESPONSES));
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].seq(), Seq(1));
+    assert_eq!(messages[0].uid(), Some(Uid(11)));
+    assert_eq!(messages[0].size(), None);
+    assert!(messages[0].from().is_empty());
+
+    let message = &messages[1];
+    assert_eq!(message.uid(), Some(Uid(12)));
+    assert_eq!(message.size(), Some(2048));
+    let internal_date = message.internal_date().unwrap();
+    assert_eq!(internal_date.to_string(), "17-Jul-1996 02:44:25 -0700");
+    assert!(message.date().unwrap() < internal_date);
+    assert_eq!(message.subject(), Some("Status"));
+    assert_eq!(
+        message.message_id(),
+        Some("<B27397-0100000@cac.washington.edu>")
+    );
+    assert_eq!(
+        message.from()[0].to_string(),
+        "Terry Gray <gray@cac.washington.edu>"
+    );
+    let to: Vec<String> = message.to().iter().map(|a| a.to_string()).collect();
+    assert_eq!(to, ["imap@cac.washington.edu", "Ops <ops@example.org>"]);
+
+    // The later FETCH for the same message updated its flags.
+    let flags = message.flags().unwrap();
+    assert!(
+        flags
+            .system
+            .contains(SystemFlags::SEEN | SystemFlags::FLAGGED)
+    );
+
+    assert_eq!(message.body(&Section::part(&[1])), Some(&b"hello"[..]));
+    assert_eq!(
+        message.body(&Section::full().header()),
+        Some(&b"Subject: Hi\r\n\r\n"[..])
+    );
+    assert_eq!(message.body(&Section::part(&[2])), None);
+}
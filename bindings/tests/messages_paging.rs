@@ -0,0 +1,119 @@
+//! Client::messages: every message of a mailbox, fetched a page at a time on demand.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::commands::FetchItem;
+use imap::types::common::{Seq, Uid};
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+/// A mailbox of `exists` messages whose UIDs are 100 plus their sequence number. A FETCH
+/// of message 4 fails.
+fn server(exists: u32, received: Log) -> MockServer {
+    MockServer::new(move |tag, cmd| {
+        received.lock().unwrap().push(cmd.to_string());
+        if cmd.starts_with("SELECT") {
+            return format!("* {} EXISTS\r\n{} OK done\r\n", exists, tag).into_bytes();
+        }
+        let Some(rest) = cmd.strip_prefix("FETCH ") else {
+            return format!("{} OK done\r\n", tag).into_bytes();
+        };
+        let set = rest.split(' ').next().unwrap();
+        let (first, last) = match set.split_once(':') {
+            Some((a, b)) => (a.parse().unwrap(), b.parse().unwrap()),
+            None => (set.parse().unwrap(), set.parse().unwrap()),
+        };
+        if (first..=last).contains(&4) {
+            return format!("{} NO message 4 is damaged\r\n", tag).into_bytes();
+        }
+        let mut out = String::new();
+        for seq in first..=last {
+            out += &format!("* {} FETCH (UID {})\r\n", seq, 100 + seq);
+        }
+        format!("{}{} OK done\r\n", out, tag).into_bytes()
+    })
+}
+
+fn fetches(received: &Log) -> Vec<String> {
+    received
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|cmd| cmd.starts_with("FETCH"))
+        .cloned()
+        .collect()
+}
+
+#[tokio::test]
+async fn pages_are_fetched_as_they_are_needed() {
+    let received = Log::default();
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(3, received.clone()).spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    let mut messages = session
+        .messages("INBOX")
+        .await
+        .unwrap()
+        .page_size(2)
+        .items(vec![FetchItem::Uid]);
+    assert!(fetches(&received).is_empty());
+
+    let first = messages.try_next().await.unwrap().unwrap();
+    assert_eq!((first.seq(), first.uid()), (Seq(1), Some(Uid(101))));
+    assert_eq!(fetches(&received), ["FETCH 1:2 (UID)"]);
+
+    let second = messages.try_next().await.unwrap().unwrap();
+    assert_eq!(second.seq(), Seq(2));
+    let third = messages.try_next().await.unwrap().unwrap();
+    assert_eq!(third.seq(), Seq(3));
+    assert!(messages.try_next().await.unwrap().is_none());
+    assert_eq!(fetches(&received), ["FETCH 1:2 (UID)", "FETCH 3:3 (UID)"]);
+}
+
+#[tokio::test]
+async fn an_empty_mailbox_fetches_nothing() {
+    let received = Log::default();
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(0, received.clone()).spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    let mut messages = session.messages("INBOX").await.unwrap();
+    assert!(messages.try_next().await.unwrap().is_none());
+    assert!(fetches(&received).is_empty());
+}
+
+#[tokio::test]
+async fn a_failed_page_ends_the_stream() {
+    let received = Log::default();
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(6, received.clone()).spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    let mut messages = session
+        .messages("INBOX")
+        .await
+        .unwrap()
+        .page_size(3)
+        .items(vec![FetchItem::Uid]);
+    for _ in 0..3 {
+        messages.try_next().await.unwrap().unwrap();
+    }
+    let err = messages.try_next().await.unwrap_err();
+    assert!(
+        format!("{:#}", err).contains("message 4 is damaged"),
+        "{:#}",
+        err
+    );
+    assert!(messages.try_next().await.unwrap().is_none());
+    assert_eq!(fetches(&received), ["FETCH 1:3 (UID)", "FETCH 4:6 (UID)"]);
+}
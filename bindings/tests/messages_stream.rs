@@ -0,0 +1,77 @@
+//! `Messages` yields each message as its FETCH response arrives.
+
+use std::time::Duration;
+
+use bindings::Builder;
+use imap::commands::FetchItem;
+use imap::types::common::Seq;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::sync::oneshot;
+use tokio_stream::StreamExt;
+
+/// Serves three messages in pages of two, holding back the rest of the first page until
+/// `release` fires.
+async fn serve(stream: DuplexStream, release: oneshot::Receiver<()>) {
+    let (read, mut write) = tokio::io::split(stream);
+    let mut lines = BufReader::new(read).lines();
+    let mut release = Some(release);
+    write
+        .write_all(b"* OK [CAPABILITY IMAP4rev1] ready\r\n")
+        .await
+        .unwrap();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let (tag, cmd) = line.split_once(' ').unwrap();
+        let mut out = String::new();
+        if cmd.starts_with("SELECT") {
+            out.push_str("* 3 EXISTS\r\n");
+        } else if cmd.starts_with("FETCH 1:2 ") {
+            write
+                .write_all(b"* 1 FETCH (UID 5 FLAGS ())\r\n")
+                .await
+                .unwrap();
+            if let Some(release) = release.take() {
+                release.await.unwrap();
+            }
+            out.push_str("* 2 FETCH (UID 6 FLAGS (\\Seen))\r\n");
+        } else if cmd.starts_with("FETCH 3:3 ") {
+            out.push_str("* 3 FETCH (UID 7 FLAGS ())\r\n");
+        }
+        out.push_str(&format!("{} OK done\r\n", tag));
+        write.write_all(out.as_bytes()).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn yields_messages_before_the_fetch_completes() {
+    let (client_end, server_end) = tokio::io::duplex(64 * 1024);
+    let (release_tx, release_rx) = oneshot::channel();
+    tokio::spawn(serve(server_end, release_rx));
+
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(client_end)
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    let mut messages = session
+        .messages("INBOX")
+        .await
+        .unwrap()
+        .page_size(2)
+        .items(vec![FetchItem::Uid, FetchItem::Flags]);
+
+    // The server sends the tagged OK only after the first message was seen.
+    let first = tokio::time::timeout(Duration::from_secs(5), messages.next())
+        .await
+        .expect("first message arrived only with the completion")
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.seq(), Seq(1));
+    release_tx.send(()).unwrap();
+
+    let rest: Vec<Seq> = messages
+        .map(|message| message.unwrap().seq())
+        .collect()
+        .await;
+    assert_eq!(rest, [Seq(2), Seq(3)]);
+}
@@ -0,0 +1,129 @@
+//! Builder::metrics: byte, command, latency and pipeline counts.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::async_impl::{CommandEvent, ConnectionMetrics};
+use bindings::test_util::MockServer;
+
+use imap::types::common::Status;
+
+#[derive(Default)]
+struct Recorder {
+    received: AtomicUsize,
+    sent: AtomicUsize,
+    commands: Mutex<Vec<String>>,
+    completed: Mutex<Vec<(String, bool)>>,
+    depths: Mutex<Vec<(usize, usize)>>,
+}
+
+impl ConnectionMetrics for Recorder {
+    fn bytes_received(&self, n: usize) {
+        self.received.fetch_add(n, Ordering::Relaxed);
+    }
+
+    fn bytes_sent(&self, n: usize) {
+        self.sent.fetch_add(n, Ordering::Relaxed);
+    }
+
+    fn command_sent(&self, name: &str) {
+        self.commands.lock().unwrap().push(name.to_string());
+    }
+
+    fn command_completed(&self, event: &CommandEvent) {
+        let ok = matches!(event.status, Some(Status::Ok));
+        assert!(event.sent_at.is_some_and(|sent| sent <= event.completed_at));
+        self.completed
+            .lock()
+            .unwrap()
+            .push((event.name.clone(), ok));
+    }
+
+    fn pipeline_depth(&self, queued: usize, in_flight: usize) {
+        self.depths.lock().unwrap().push((queued, in_flight));
+    }
+}
+
+fn server(wire: Arc<Mutex<(usize, usize)>>) -> MockServer {
+    MockServer::new(move |tag, cmd| {
+        let reply = if cmd.starts_with("SUBSCRIBE") {
+            format!("{} NO [NONEXISTENT] No such mailbox\r\n", tag)
+        } else {
+            format!("{} OK done\r\n", tag)
+        };
+        let mut wire = wire.lock().unwrap();
+        wire.0 += cmd.len() + tag.len() + 3;
+        wire.1 += reply.len();
+        reply.into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn counts_bytes_commands_and_latency() {
+    let recorder = Arc::new(Recorder::default());
+    let wire = Arc::new(Mutex::new((0, 0)));
+    let server = server(wire.clone());
+    let client = Builder::new("mock:143")
+        .shared_metrics(recorder.clone())
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    session.unsubscribe("Work").await.unwrap();
+    assert!(session.subscribe("Gone").await.is_err());
+
+    let (sent, received) = *wire.lock().unwrap();
+    assert_eq!(recorder.sent.load(Ordering::Relaxed), sent);
+    // Plus the greeting.
+    assert!(recorder.received.load(Ordering::Relaxed) > received);
+
+    let commands = recorder.commands.lock().unwrap().clone();
+    assert_eq!(commands.last().map(String::as_str), Some("SUBSCRIBE"));
+    assert!(commands.iter().any(|name| name == "UNSUBSCRIBE"));
+    let completed = recorder.completed.lock().unwrap().clone();
+    assert_eq!(completed.len(), commands.len());
+    assert_eq!(
+        completed[completed.len() - 2..],
+        [
+            ("UNSUBSCRIBE".to_string(), true),
+            ("SUBSCRIBE".to_string(), false)
+        ]
+    );
+
+    let depths = recorder.depths.lock().unwrap().clone();
+    assert!(depths.contains(&(0, 1)));
+    assert_eq!(depths.last(), Some(&(0, 0)));
+    assert!(depths.windows(2).all(|pair| pair[0] != pair[1]));
+}
+
+#[tokio::test]
+async fn shared_observer_sums_connections() {
+    let recorder = Arc::new(Recorder::default());
+    let wire = Arc::new(Mutex::new((0, 0)));
+    for _ in 0..2 {
+        let server = server(wire.clone());
+        let client = Builder::new("mock:143")
+            .shared_metrics(recorder.clone())
+            .build()
+            .connect_stream(server.spawn())
+            .await
+            .unwrap();
+        let mut session = client.login("user", "pass").await.unwrap();
+        session.unsubscribe("Work").await.unwrap();
+    }
+
+    assert_eq!(
+        recorder.sent.load(Ordering::Relaxed),
+        wire.lock().unwrap().0
+    );
+    let commands = recorder.commands.lock().unwrap();
+    assert_eq!(
+        commands
+            .iter()
+            .filter(|name| *name == "UNSUBSCRIBE")
+            .count(),
+        2
+    );
+}
@@ -0,0 +1,116 @@
+//! Migration: resuming, and messages that vanish or cannot be read mid-copy.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::async_impl::{Client, Migration};
+use bindings::test_util::MockServer;
+use bindings::{AuthenticatedState, Builder};
+
+fn fetched(uid: u32) -> String {
+    format!(
+        "* {} FETCH (UID {} FLAGS (\\Seen) BODY[] {{6}}\r\nbody-{})\r\n",
+        uid, uid, uid
+    )
+}
+
+/// UIDs 1 to 4; UID 2 is expunged before the FETCH, and UID 3 comes back without its body
+/// unless `readable`.
+fn source(readable: bool) -> MockServer {
+    MockServer::new(move |tag, cmd| {
+        let body = if cmd.starts_with("LIST") {
+            "* LIST () \"/\" INBOX\r\n".to_string()
+        } else if cmd.starts_with("EXAMINE") {
+            "* 4 EXISTS\r\n* OK [UIDVALIDITY 4] UIDs valid\r\n".to_string()
+        } else if cmd.starts_with("UID SEARCH UID") {
+            "* SEARCH 3\r\n".to_string()
+        } else if cmd.starts_with("UID SEARCH") {
+            "* SEARCH 1 2 3 4\r\n".to_string()
+        } else if cmd.starts_with("UID FETCH") {
+            let three = if readable {
+                fetched(3)
+            } else {
+                "* 2 FETCH (UID 3 FLAGS ())\r\n".to_string()
+            };
+            let wanted = |uid: u32| cmd.contains(&format!("{}", uid));
+            let mut out = String::new();
+            if cmd.starts_with("UID FETCH 1") {
+                out += &fetched(1);
+            }
+            if wanted(3) {
+                out += &three;
+            }
+            if wanted(4) {
+                out += &fetched(4);
+            }
+            out
+        } else {
+            String::new()
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    })
+}
+
+/// Accepts APPENDs and records the body of each.
+fn destination(appended: Arc<Mutex<Vec<String>>>) -> MockServer {
+    let pending = Mutex::new(None::<String>);
+    MockServer::new(move |tag, cmd| {
+        // The line after an APPEND's continuation is its literal.
+        if let Some(append_tag) = pending.lock().unwrap().take() {
+            appended.lock().unwrap().push(tag.to_string());
+            return format!("{} OK APPEND completed\r\n", append_tag).into_bytes();
+        }
+        if cmd.starts_with("APPEND") {
+            *pending.lock().unwrap() = Some(tag.to_string());
+            return b"+ Ready\r\n".to_vec();
+        }
+        let body = if cmd == "LIST \"\" \"*\"" {
+            "* LIST () \"/\" INBOX\r\n"
+        } else if cmd.starts_with("LIST") {
+            "* LIST (\\Noselect) \"/\" \"\"\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    })
+}
+
+async fn session(server: MockServer) -> Client<AuthenticatedState> {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    client.login("user", "pass").await.unwrap()
+}
+
+#[tokio::test]
+async fn stops_at_unreadable_messages_and_resumes() {
+    let dir = std::env::temp_dir().join(format!("mailux-migrate-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let state = dir.join("state");
+    let _ = std::fs::remove_file(&state);
+    let appended = Arc::new(Mutex::new(Vec::new()));
+
+    let mut from = session(source(false)).await;
+    let mut to = session(destination(appended.clone())).await;
+    let report = Migration::new(&mut from, &mut to, &state)
+        .batch_size(2)
+        .run()
+        .await
+        .unwrap();
+    let inbox = &report.folders[0];
+    assert_eq!((inbox.copied, inbox.skipped), (1, 0));
+    assert_eq!((inbox.vanished, inbox.remaining), (1, 2));
+    assert_eq!(*appended.lock().unwrap(), ["body-1"]);
+
+    // UID 3 is readable now; the next run continues with it.
+    let mut from = session(source(true)).await;
+    let report = Migration::new(&mut from, &mut to, &state)
+        .run()
+        .await
+        .unwrap();
+    let inbox = &report.folders[0];
+    assert_eq!((inbox.copied, inbox.skipped, inbox.remaining), (2, 2, 0));
+    assert_eq!(*appended.lock().unwrap(), ["body-1", "body-3", "body-4"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,43 @@
+//! Text bodies and attachments from BODYSTRUCTURE and fetched sections.
+
+use imap::messages;
+use imap::parser::fetch::parse_fetch_responses;
+
+/// multipart/mixed: an alternative (plain, html), a named inline image and a PDF.
+const RESPONSES: &[u8] = b"* 1 FETCH (UID 3 BODYSTRUCTURE (\
+((\"TEXT\" \"PLAIN\" (\"CHARSET\" \"iso-8859-1\") NIL NIL \"QUOTED-PRINTABLE\" 12 1 NIL NIL NIL NIL)\
+(\"TEXT\" \"HTML\" (\"CHARSET\" \"utf-8\") NIL NIL \"BASE64\" 16 1 NIL NIL NIL NIL) \"ALTERNATIVE\" (\"BOUNDARY\" \"b2\") NIL NIL NIL)\
+(\"IMAGE\" \"PNG\" (\"NAME\" \"logo.png\") \"<logo>\" NIL \"BASE64\" 8 NIL (\"INLINE\" NIL) NIL NIL)\
+(\"APPLICATION\" \"PDF\" NIL NIL NIL \"BASE64\" 12 NIL (\"ATTACHMENT\" (\"FILENAME\" \"report.pdf\")) NIL NIL) \
+\"MIXED\" (\"BOUNDARY\" \"b1\") NIL NIL NIL))\r\n\
+* 1 FETCH (UID 3 BODY[1.1] {13}\r\nGr=FC=DFe =\r\n BODY[1.2] {16}\r\nPGI+SGk8L2I+Cg==)\r\n";
+
+#[test]
+fn decodes_text_bodies_and_lists_attachments() {
+    let message = messages::assemble(parse_fetch_responses(RESPONSES)).remove(0);
+    assert_eq!(message.text_body().as_deref(), Some("Grüße "));
+    assert_eq!(message.html_body().as_deref(), Some("<b>Hi</b>\n"));
+
+    let attachments = message.attachments();
+    let listed: Vec<_> = attachments
+        .iter()
+        .map(|a| (a.section.as_str(), a.filename(), a.content_type()))
+        .collect();
+    assert_eq!(
+        listed,
+        [
+            ("2", Some("logo.png"), "image/png".to_string()),
+            ("3", Some("report.pdf"), "application/pdf".to_string()),
+        ]
+    );
+    assert_eq!(attachments[1].decode(b"JVBERi0xLjQK"), b"%PDF-1.4\n");
+}
+
+#[test]
+fn text_bodies_need_their_sections() {
+    let mut fetched = parse_fetch_responses(RESPONSES);
+    fetched.truncate(1);
+    let message = messages::assemble(fetched).remove(0);
+    assert_eq!(message.text_body(), None);
+    assert_eq!(message.attachments().len(), 2);
+}
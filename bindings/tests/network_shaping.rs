@@ -0,0 +1,111 @@
+//! Timing behaviour over a slow link, using the mock server's latency and bandwidth
+//! shaping.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use bindings::SelectedState;
+use bindings::Builder;
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+use imap::types::common::Uid;
+use tokio_stream::StreamExt;
+
+const LATENCY: Duration = Duration::from_millis(100);
+
+async fn session(server: MockServer) -> Client<SelectedState> {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (session, _) = session.select("INBOX").await.unwrap();
+    session
+}
+
+fn reply(tag: &str, cmd: &str, fetches: &AtomicUsize) -> Vec<u8> {
+    let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+    let data = match verb.as_str() {
+        "LOGIN" => String::new(),
+        "SELECT" => "* 1 EXISTS\r\n".to_string(),
+        "UID" => {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            if cmd.contains("BODY.PEEK[]") {
+                let body = "x".repeat(20_000);
+                format!(
+                    "* 1 FETCH (UID 1 BODY[] {{{}}}\r\n{})\r\n",
+                    body.len(),
+                    body
+                )
+            } else {
+                String::new()
+            }
+        }
+        _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+    };
+    format!("{}{} OK {} completed\r\n", data, tag, verb).into_bytes()
+}
+
+#[tokio::test]
+async fn pipelined_header_fetches_pay_latency_once() {
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let counter = fetches.clone();
+    let server = MockServer::new(move |tag, cmd| reply(tag, cmd, &counter)).latency(LATENCY);
+    let mut session = session(server).await;
+
+    // Every other UID, so the set cannot be compressed into ranges and needs several
+    // command lines.
+    let uids: Vec<Uid> = (1..8000).step_by(2).map(Uid).collect();
+    let start = Instant::now();
+    let mut headers = session
+        .fetch_headers("INBOX", &uids, &["SUBJECT"])
+        .await
+        .unwrap();
+    while let Some(item) = headers.next().await {
+        item.unwrap();
+    }
+    let elapsed = start.elapsed();
+
+    let batches = fetches.load(Ordering::SeqCst);
+    assert!(batches >= 3, "expected several batches, got {}", batches);
+    assert!(
+        elapsed < LATENCY * 2,
+        "{} batches took {:?}; they should overlap",
+        batches,
+        elapsed
+    );
+}
+
+#[tokio::test]
+async fn bandwidth_limits_download_time() {
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let counter = fetches.clone();
+    let server = MockServer::new(move |tag, cmd| reply(tag, cmd, &counter)).bandwidth(100_000);
+    let mut session = session(server).await;
+
+    let start = Instant::now();
+    let message = session.fetch_body("INBOX", Uid(1)).await.unwrap().unwrap();
+    let elapsed = start.elapsed();
+
+    assert_eq!(message.len(), 20_000);
+    // 20 kB at 100 kB/s.
+    assert!(elapsed >= Duration::from_millis(190), "took {:?}", elapsed);
+}
+
+#[tokio::test]
+async fn jitter_keeps_responses_in_order() {
+    let fetches = Arc::new(AtomicUsize::new(0));
+    let counter = fetches.clone();
+    let server = MockServer::new(move |tag, cmd| reply(tag, cmd, &counter))
+        .latency(Duration::from_millis(5))
+        .jitter(Duration::from_millis(20))
+        .seed(7);
+    let mut session = session(server).await;
+
+    for _ in 0..5 {
+        let message = session.fetch_body("INBOX", Uid(1)).await.unwrap();
+        assert_eq!(message.map(|m| m.len()), Some(20_000));
+    }
+}
@@ -0,0 +1,125 @@
+//! FETCH and SELECT responses with `NIL` and `()` where lists are expected, taken from
+//! transcripts of real servers.
+
+use bindings::SelectedState;
+use bindings::Builder;
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+
+use imap::commands::FetchItem;
+use imap::types::command::{SequenceBound, SequenceSet};
+use imap::types::common::Seq;
+use imap::types::response::FetchData;
+
+async fn session(fetch: &'static str) -> Client<SelectedState> {
+    let server = MockServer::new(move |tag, cmd| {
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" => "",
+            "SELECT" => {
+                "* 3 EXISTS\r\n* FLAGS ()\r\n* OK [PERMANENTFLAGS ()] No permanent flags\r\n"
+            }
+            "FETCH" => fetch,
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (session, status) = session.select("INBOX").await.unwrap();
+    assert_eq!(status.exists, 3);
+    assert!(status.flags.is_empty() && status.permanent_flags.is_empty());
+    session
+}
+
+async fn fetch_all(session: &mut Client<SelectedState>) -> Vec<(Seq, Vec<FetchData>)> {
+    let all = SequenceSet::new().add_range(SequenceBound::Number(1), SequenceBound::Star);
+    session
+        .fetch_items(
+            all,
+            vec![FetchItem::Uid, FetchItem::Flags, FetchItem::Envelope],
+        )
+        .await
+        .unwrap()
+}
+
+fn uid(items: &[FetchData]) -> Option<u32> {
+    items.iter().find_map(|item| match item {
+        FetchData::Uid(uid) => Some(uid.0),
+        _ => None,
+    })
+}
+
+#[tokio::test]
+async fn all_nil_envelope_and_empty_flags() {
+    let mut session = session(
+        "* 1 FETCH (UID 10 FLAGS () ENVELOPE (NIL NIL NIL NIL NIL NIL NIL NIL NIL NIL))\r\n",
+    )
+    .await;
+    let messages = fetch_all(&mut session).await;
+    assert_eq!(messages.len(), 1);
+    let (_, items) = &messages[0];
+    assert_eq!(uid(items), Some(10));
+    for item in items {
+        match item {
+            FetchData::Flags(flags) => {
+                assert!(flags.system.is_empty() && flags.keywords.is_empty())
+            }
+            FetchData::Envelope(env) => assert!(env.subject.is_none() && env.from.is_empty()),
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+async fn nil_flags_and_nil_address_entries() {
+    let mut session = session(concat!(
+        "* 2 FETCH (UID 11 FLAGS NIL ENVELOPE (\"Mon, 1 Jan 2024 00:00:00 +0000\" \"Hi\" ",
+        "(NIL (\"Ann\" NIL \"ann\" \"example.com\")) NIL NIL () NIL NIL NIL \"<id@example.com>\") ",
+        "BODYSTRUCTURE (\"text\" \"plain\" NIL NIL NIL \"7bit\" 0 0 NIL NIL NIL NIL))\r\n",
+    ))
+    .await;
+    let messages = fetch_all(&mut session).await;
+    let (_, items) = &messages[0];
+    assert_eq!(uid(items), Some(11));
+    let env = items
+        .iter()
+        .find_map(|item| match item {
+            FetchData::Envelope(env) => Some(env),
+            _ => None,
+        })
+        .expect("envelope parsed");
+    assert_eq!(env.subject.as_deref(), Some("Hi"));
+    assert_eq!(env.from.len(), 1);
+    assert_eq!(env.from[0].to_string(), "Ann <ann@example.com>");
+}
+
+#[tokio::test]
+async fn nil_envelope_and_empty_bodystructure_parameters() {
+    let mut session = session(concat!(
+        "* 3 FETCH (UID 12 ENVELOPE NIL RFC822.SIZE 42 BODYSTRUCTURE ((\"text\" \"plain\" () NIL NIL ",
+        "\"7bit\" 10 1)(\"text\" \"html\" (\"charset\" \"utf-8\") NIL NIL \"base64\" 20 1) ",
+        "\"alternative\" () NIL NIL))\r\n",
+        "* 4 FETCH (UID 13 FLAGS (\\Seen))\r\n",
+    ))
+    .await;
+    let messages = fetch_all(&mut session).await;
+    assert_eq!(messages.len(), 2);
+    let (_, items) = &messages[0];
+    assert_eq!(uid(items), Some(12));
+    assert!(
+        items
+            .iter()
+            .any(|item| matches!(item, FetchData::Rfc822Size(42)))
+    );
+    assert!(
+        !items
+            .iter()
+            .any(|item| matches!(item, FetchData::Envelope(_)))
+    );
+    assert_eq!(uid(&messages[1].1), Some(13));
+}
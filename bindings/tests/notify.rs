@@ -0,0 +1,80 @@
+//! NOTIFY (RFC 5465): events for several mailboxes over one connection.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use tokio_stream::StreamExt;
+
+use imap::types::command::{NotifyEvent, NotifyMailboxes};
+use imap::types::response::IdleEvent;
+
+#[tokio::test]
+async fn notify_events_arrive_while_idle() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let idle_tag = Arc::new(Mutex::new(None::<String>));
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" | "NOTIFY" => "",
+            "CAPABILITY" => "* CAPABILITY IMAP4rev1 IDLE NOTIFY\r\n",
+            "SELECT" => "* 2 EXISTS\r\n",
+            "IDLE" => {
+                *idle_tag.lock().unwrap() = Some(tag.to_string());
+                return b"+ idling\r\n* STATUS \"Lists/rust\" (MESSAGES 4 UIDNEXT 9)\r\n* LIST () \"/\" Projects\r\n* 3 EXISTS\r\n".to_vec();
+            }
+            _ if tag == "DONE" => {
+                let tag = idle_tag.lock().unwrap().take().unwrap();
+                return format!("{} OK IDLE terminated\r\n", tag).into_bytes();
+            }
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    session
+        .notify(
+            vec![
+                (
+                    NotifyMailboxes::Selected,
+                    vec![NotifyEvent::MessageNew, NotifyEvent::MessageExpunge],
+                ),
+                (
+                    NotifyMailboxes::Subtree(vec!["Lists".to_string()]),
+                    vec![NotifyEvent::MessageNew],
+                ),
+                (NotifyMailboxes::Personal, vec![NotifyEvent::MailboxName]),
+            ],
+            false,
+        )
+        .await
+        .unwrap();
+    assert!(received.lock().unwrap().iter().any(|c| c
+        == "NOTIFY SET (SELECTED (MessageNew MessageExpunge)) (SUBTREE \"Lists\" (MessageNew)) (PERSONAL (MailboxName))"));
+
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    let mut idle = session.idle().await.unwrap();
+    let mut events = Vec::new();
+    while events.len() < 3 {
+        events.push(idle.next().await.unwrap().unwrap());
+    }
+    idle.done().await.unwrap();
+
+    let IdleEvent::MailboxStatus(status) = &events[0] else {
+        panic!("expected a STATUS event, got {:?}", events[0]);
+    };
+    assert_eq!(status.mailbox, "Lists/rust");
+    assert_eq!((status.messages, status.uid_next), (Some(4), Some(9)));
+    let IdleEvent::MailboxName(entry) = &events[1] else {
+        panic!("expected a LIST event, got {:?}", events[1]);
+    };
+    assert_eq!(entry.name, "Projects");
+    assert_eq!(events[2], IdleEvent::Exists(3));
+}
@@ -0,0 +1,101 @@
+//! Partial fetches: `BODY[section]<offset.count>`.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use imap::commands::{FetchItem, Section};
+use imap::types::common::Uid;
+
+const ATTACHMENT: &[u8] = b"0123456789abcdefghij";
+
+#[test]
+fn serializes_partial_items() {
+    let item = FetchItem::body_range(Section::part(&[2]), 100..150);
+    assert_eq!(item.to_string(), "BODY[2]<100.50>");
+    assert_eq!(item.peek().to_string(), "BODY.PEEK[2]<100.50>");
+    assert_eq!(
+        FetchItem::body_peek_range(Section::full().text(), 0..10).to_string(),
+        "BODY.PEEK[TEXT]<0.10>"
+    );
+}
+
+/// Serves part 2 of UID 9 as ATTACHMENT, honoring the requested byte range.
+fn server(received: Arc<Mutex<Vec<String>>>) -> MockServer {
+    MockServer::new(move |tag, cmd| {
+        received.lock().unwrap().push(cmd.to_string());
+        let mut out = Vec::new();
+        if cmd.starts_with("SELECT") {
+            out.extend_from_slice(b"* 1 EXISTS\r\n");
+        } else if cmd.starts_with("UID FETCH 9 ") {
+            let range = cmd.split_once("]<").unwrap().1;
+            let (offset, count) = range.trim_end_matches(">)").split_once('.').unwrap();
+            let offset: usize = offset.parse().unwrap();
+            let count: usize = count.parse().unwrap();
+            let start = offset.min(ATTACHMENT.len());
+            let end = (offset + count).min(ATTACHMENT.len());
+            let chunk = &ATTACHMENT[start..end];
+            out.extend_from_slice(
+                format!(
+                    "* 1 FETCH (UID 9 BODY[2]<{}> {{{}}}\r\n",
+                    offset,
+                    chunk.len()
+                )
+                .as_bytes(),
+            );
+            out.extend_from_slice(chunk);
+            out.extend_from_slice(b")\r\n");
+        }
+        out.extend_from_slice(format!("{} OK done\r\n", tag).as_bytes());
+        out
+    })
+}
+
+#[tokio::test]
+async fn downloads_a_section_in_chunks() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server(received.clone()).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let mut downloaded = Vec::new();
+    loop {
+        let offset = downloaded.len() as u32;
+        let chunk = session
+            .fetch_body_range(Uid(9), Section::part(&[2]), offset..offset + 7)
+            .await
+            .unwrap()
+            .unwrap();
+        downloaded.extend_from_slice(&chunk);
+        if chunk.len() < 7 {
+            break;
+        }
+    }
+    assert_eq!(downloaded, ATTACHMENT);
+    assert!(
+        received
+            .lock()
+            .unwrap()
+            .contains(&"UID FETCH 9 (UID BODY.PEEK[2]<14.7>)".to_string())
+    );
+
+    assert_eq!(
+        session
+            .fetch_body_range(Uid(9), Section::part(&[2]), 40..50)
+            .await
+            .unwrap()
+            .unwrap(),
+        &b""[..]
+    );
+    assert!(
+        session
+            .fetch_body_range(Uid(10), Section::part(&[2]), 0..5)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
@@ -0,0 +1,88 @@
+//! Spreading UID FETCH work over several connections.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use bindings::Builder;
+use bindings::async_impl::Pool;
+use bindings::test_util::MockServer;
+use imap::commands::FetchItem;
+use imap::types::command::SequenceSet;
+use imap::types::common::Uid;
+use imap::types::response::FetchData;
+
+/// A mailbox where message `n` has UID `n`; counts the UID FETCH commands it serves.
+fn server(fetches: Arc<AtomicUsize>) -> MockServer {
+    MockServer::new(move |tag, cmd| {
+        let upper = cmd.to_ascii_uppercase();
+        let mut out = String::new();
+        if upper.starts_with("SELECT") {
+            out.push_str("* 100 EXISTS\r\n* OK [UIDVALIDITY 1] UIDs valid\r\n");
+        } else if upper.starts_with("UID FETCH") {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            let set = SequenceSet::parse(cmd.split(' ').nth(2).unwrap()).unwrap();
+            for uid in set.numbers() {
+                out.push_str(&format!("* {} FETCH (UID {})\r\n", uid, uid));
+            }
+        }
+        out.push_str(&format!("{} OK done\r\n", tag));
+        out.into_bytes()
+    })
+    .latency(Duration::from_millis(5))
+}
+
+async fn pool(counters: &[Arc<AtomicUsize>]) -> Pool {
+    let mut clients = Vec::new();
+    for fetches in counters {
+        let client = Builder::new("mock:143")
+            .build()
+            .connect_stream(server(fetches.clone()).spawn())
+            .await
+            .unwrap();
+        clients.push(client.login("user", "pass").await.unwrap());
+    }
+    Pool::from_clients(clients)
+}
+
+#[tokio::test]
+async fn fetches_every_uid_across_connections() {
+    let counters: Vec<_> = (0..3).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+    let mut pool = pool(&counters).await.batch_size(5);
+
+    let uids: Vec<Uid> = (1..=60).rev().map(Uid).collect();
+    let fetched = pool
+        .uid_fetch("INBOX", &uids, vec![FetchItem::Uid])
+        .await
+        .unwrap();
+
+    let got: Vec<Uid> = fetched
+        .iter()
+        .map(|(_, items)| match items[..] {
+            [FetchData::Uid(uid)] => uid,
+            _ => panic!("unexpected items: {:?}", items),
+        })
+        .collect();
+    assert_eq!(got, (1..=60).map(Uid).collect::<Vec<_>>());
+
+    let counts: Vec<usize> = counters.iter().map(|c| c.load(Ordering::SeqCst)).collect();
+    assert_eq!(counts.iter().sum::<usize>(), 12);
+    assert!(counts.iter().all(|&n| n > 0), "uneven split: {:?}", counts);
+    assert_eq!(pool.len(), 3);
+
+    let results = pool.logout(Duration::from_secs(1)).await;
+    assert!(results.iter().all(|r| r.is_ok()));
+}
+
+#[tokio::test]
+async fn nothing_to_fetch_sends_nothing() {
+    let counters: Vec<_> = (0..2).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+    let mut pool = pool(&counters).await;
+
+    let fetched = pool
+        .uid_fetch("INBOX", &[], vec![FetchItem::Uid])
+        .await
+        .unwrap();
+    assert!(fetched.is_empty());
+    assert!(counters.iter().all(|c| c.load(Ordering::SeqCst) == 0));
+}
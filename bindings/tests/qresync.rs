@@ -0,0 +1,84 @@
+//! QRESYNC (RFC 7162): resuming a mailbox and learning which UIDs vanished.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::common::Uid;
+
+fn server(
+    capabilities: &'static str,
+    enabled: &'static str,
+    received: Arc<Mutex<Vec<String>>>,
+) -> MockServer {
+    MockServer::new(move |tag, cmd| {
+        received.lock().unwrap().push(cmd.to_string());
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" => String::new(),
+            "CAPABILITY" => format!("* CAPABILITY {}\r\n", capabilities),
+            "ENABLE" => format!("* ENABLED {}\r\n", enabled),
+            "SELECT" => concat!(
+                "* 40 EXISTS\r\n",
+                "* OK [UIDVALIDITY 7] UIDs valid\r\n",
+                "* OK [HIGHESTMODSEQ 120] Highest\r\n",
+                "* VANISHED (EARLIER) 3:5,9\r\n",
+            )
+            .to_string(),
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn reports_vanished_uids() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1 CONDSTORE QRESYNC", "QRESYNC", received.clone()).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (_session, status) = session.select_qresync("INBOX", 7, 100).await.unwrap();
+
+    assert_eq!(status.exists, 40);
+    assert_eq!(status.uid_validity, Some(7));
+    assert_eq!(status.highest_modseq, Some(120));
+    assert_eq!(status.vanished, [Uid(3), Uid(4), Uid(5), Uid(9)]);
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[received.len() - 2..],
+        ["ENABLE QRESYNC", "SELECT \"INBOX\" (QRESYNC (7 100))"]
+    );
+}
+
+#[tokio::test]
+async fn requires_the_capability_and_its_enabling() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1 CONDSTORE", "", received.clone()).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let err = session.select_qresync("INBOX", 7, 100).await.err().unwrap();
+    assert!(err.to_string().contains("does not support QRESYNC"));
+
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1 QRESYNC", "CONDSTORE", received.clone()).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let err = session.select_qresync("INBOX", 7, 100).await.err().unwrap();
+    assert!(err.to_string().contains("did not enable QRESYNC"));
+    assert!(
+        !received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c.starts_with("SELECT"))
+    );
+}
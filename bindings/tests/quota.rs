@@ -0,0 +1,104 @@
+//! The QUOTA extension (RFC 9208).
+
+use std::sync::{Arc, Mutex};
+
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+use bindings::{AuthenticatedState, Builder};
+
+use imap::types::response::QuotaResource;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+async fn connect(capabilities: &'static str) -> (Client<AuthenticatedState>, Log) {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = match cmd {
+            "CAPABILITY" => format!("* CAPABILITY {}\r\n", capabilities),
+            "GETQUOTAROOT \"INBOX\"" => concat!(
+                "* QUOTAROOT INBOX \"\" \"#shared\"\r\n",
+                "* QUOTA \"\" (STORAGE 10 512 MESSAGE 3 1000)\r\n",
+                "* QUOTA \"#shared\" (STORAGE 40 2048)\r\n",
+            )
+            .to_string(),
+            "GETQUOTA \"\"" => "* QUOTA \"\" (STORAGE 10 512)\r\n".to_string(),
+            "SETQUOTA \"\" (STORAGE 1024 MESSAGE 5000)" => {
+                "* QUOTA \"\" (STORAGE 10 1024 MESSAGE 3 5000)\r\n".to_string()
+            }
+            _ => String::new(),
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    (client.login("user", "pass").await.unwrap(), received)
+}
+
+fn storage(usage: u64, limit: u64) -> QuotaResource {
+    QuotaResource {
+        name: "STORAGE".to_string(),
+        usage,
+        limit,
+    }
+}
+
+#[tokio::test]
+async fn reads_roots_and_usage() {
+    let (mut session, _) = connect("IMAP4rev1 QUOTA").await;
+
+    let root = session.quota_root("INBOX").await.unwrap();
+    assert_eq!(root.mailbox, "INBOX");
+    assert_eq!(root.roots, ["", "#shared"]);
+    assert_eq!(root.quotas.len(), 2);
+    assert_eq!(root.quotas[0].resource("storage"), Some(&storage(10, 512)));
+    assert_eq!(root.quotas[0].resource("MESSAGE").unwrap().limit, 1000);
+    assert_eq!(root.quotas[1].root, "#shared");
+
+    let quota = session.quota("").await.unwrap();
+    assert_eq!(quota.resources, [storage(10, 512)]);
+
+    // A root the server says nothing about is an error.
+    let err = session.quota("#other").await.unwrap_err();
+    assert!(format!("{:#}", err).contains("No QUOTA response"));
+}
+
+#[tokio::test]
+async fn sets_limits() {
+    let (mut session, received) = connect("IMAP4rev1 QUOTA").await;
+
+    let quota = session
+        .set_quota("", &[("STORAGE", 1024), ("MESSAGE", 5000)])
+        .await
+        .unwrap();
+    assert_eq!(quota.resource("STORAGE"), Some(&storage(10, 1024)));
+
+    // Without an untagged QUOTA the result is the root with no limits.
+    let quota = session.set_quota("#shared", &[]).await.unwrap();
+    assert_eq!(quota.root, "#shared");
+    assert!(quota.resources.is_empty());
+    assert_eq!(
+        received.lock().unwrap().last().unwrap(),
+        "SETQUOTA \"#shared\" ()"
+    );
+}
+
+#[tokio::test]
+async fn requires_the_capability() {
+    let (mut session, received) = connect("IMAP4rev1").await;
+
+    let err = session.quota_root("INBOX").await.unwrap_err();
+    assert!(err.to_string().contains("QUOTA"));
+    assert!(session.set_quota("", &[("STORAGE", 1)]).await.is_err());
+    assert!(
+        !received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|cmd| cmd.contains("QUOTA"))
+    );
+}
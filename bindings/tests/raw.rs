@@ -0,0 +1,49 @@
+//! Taking over a connection: raw commands and the detached transport.
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn server() -> MockServer {
+    MockServer::new(|tag, cmd| match cmd {
+        "XPING" => format!("* XPONG hello\r\n{} OK pong\r\n", tag).into_bytes(),
+        _ => format!("{} OK {} done\r\n", tag, cmd.split(' ').next().unwrap()).into_bytes(),
+    })
+}
+
+#[tokio::test]
+async fn raw_commands_return_every_line() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let mut raw = session.into_raw();
+    let mut lines = raw.lines().resubscribe();
+
+    let response = raw.execute("XPING").await.unwrap();
+    assert_eq!(response.len(), 2);
+    assert_eq!(&response[0][..], b"* XPONG hello\r\n");
+    assert!(response[1].ends_with(b" OK pong\r\n"));
+    assert_eq!(&lines.recv().await.unwrap()[..], b"* XPONG hello\r\n");
+}
+
+#[tokio::test]
+async fn into_inner_hands_over_the_stream() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let raw = session.into_inner().await.unwrap();
+    let (mut stream, mut received) = (raw.stream, raw.buffered);
+
+    // The run loop is gone, so tags are ours to choose.
+    stream.write_all(b"X1 XPING\r\n").await.unwrap();
+    while !received.ends_with(b"X1 OK pong\r\n") {
+        assert!(stream.read_buf(&mut received).await.unwrap() > 0);
+    }
+    assert_eq!(&received[..], b"* XPONG hello\r\nX1 OK pong\r\n");
+}
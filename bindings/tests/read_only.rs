@@ -0,0 +1,66 @@
+//! Sessions built with `Builder::read_only` never send commands that change state.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::commands::FetchItem;
+use imap::types::command::SequenceSet;
+
+#[tokio::test]
+async fn read_only_session_examines_peeks_and_refuses_writes() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" => "",
+            "EXAMINE" => "* 1 EXISTS\r\n",
+            "FETCH" => "* 1 FETCH (BODY[] {2}\r\nhi)\r\n",
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .read_only()
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    assert!(session.is_read_only());
+
+    let (mut session, status) = session.select("INBOX").await.unwrap();
+    assert_eq!(status.exists, 1);
+    let fetched = session
+        .fetch_items(
+            SequenceSet::new().add_single(1),
+            vec![FetchItem::BodySection(String::new())],
+        )
+        .await
+        .unwrap();
+    assert_eq!(fetched.len(), 1);
+
+    assert!(
+        session
+            .append("INBOX", Vec::new(), None, b"x")
+            .await
+            .is_err()
+    );
+    assert!(session.expunge().await.is_err());
+    assert!(
+        session
+            .uid_delete(SequenceSet::new().add_single(1))
+            .await
+            .is_err()
+    );
+    assert!(session.create("Archive").await.is_err());
+
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[1..],
+        ["EXAMINE \"INBOX\"", "FETCH 1 (BODY.PEEK[])"]
+    );
+}
@@ -0,0 +1,180 @@
+//! Reconnecting after the connection drops.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bindings::Builder;
+use bindings::async_impl::{ConnectionLost, ReconnectPolicy};
+use bindings::test_util::MockServer;
+use imap::types::command::SearchKey;
+use imap::types::common::Seq;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, DuplexStream};
+
+fn answer(tag: &str, cmd: &str) -> Vec<u8> {
+    let upper = cmd.to_ascii_uppercase();
+    let reply = if upper.starts_with("LOGIN") && cmd.contains("wrong") {
+        format!("{} NO [AUTHENTICATIONFAILED] Invalid credentials\r\n", tag)
+    } else if upper.starts_with("SELECT") {
+        format!(
+            "* 3 EXISTS\r\n* OK [UIDVALIDITY 7] UIDs valid\r\n{} OK [READ-WRITE] SELECT completed\r\n",
+            tag
+        )
+    } else if upper.starts_with("SEARCH") {
+        format!("* SEARCH 2 3\r\n{} OK SEARCH completed\r\n", tag)
+    } else if upper.starts_with("CAPABILITY") {
+        format!("* CAPABILITY IMAP4rev1\r\n{} OK done\r\n", tag)
+    } else {
+        format!("{} OK done\r\n", tag)
+    };
+    reply.into_bytes()
+}
+
+/// A server that answers like [`answer`] but drops the connection on the first SEARCH,
+/// after saying BYE if `bye` is set.
+fn flaky_server(bye: bool) -> DuplexStream {
+    let (client_end, server_end) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        let (read, mut write) = tokio::io::split(server_end);
+        let mut reader = BufReader::new(read);
+        write.write_all(b"* OK ready\r\n").await.unwrap();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await.unwrap() == 0 {
+                return;
+            }
+            let (tag, cmd) = line.trim_end().split_once(' ').unwrap();
+            if cmd.to_ascii_uppercase().starts_with("SEARCH") {
+                if bye {
+                    let _ = write.write_all(b"* BYE Server restarting\r\n").await;
+                }
+                return;
+            }
+            write.write_all(&answer(tag, cmd)).await.unwrap();
+        }
+    });
+    client_end
+}
+
+/// Dials the flaky server first and recording mock servers afterwards.
+fn dialer(
+    seen: Arc<Mutex<Vec<String>>>,
+    dials: Arc<AtomicUsize>,
+) -> impl Fn() -> std::future::Ready<anyhow::Result<DuplexStream>> + Send + Sync + 'static {
+    dialer_with(seen, dials, false)
+}
+
+fn dialer_with(
+    seen: Arc<Mutex<Vec<String>>>,
+    dials: Arc<AtomicUsize>,
+    bye: bool,
+) -> impl Fn() -> std::future::Ready<anyhow::Result<DuplexStream>> + Send + Sync + 'static {
+    move || {
+        if dials.fetch_add(1, Ordering::SeqCst) == 0 {
+            return std::future::ready(Ok(flaky_server(bye)));
+        }
+        let seen = seen.clone();
+        let server = MockServer::new(move |tag, cmd| {
+            seen.lock().unwrap().push(cmd.to_string());
+            answer(tag, cmd)
+        });
+        std::future::ready(Ok(server.spawn()))
+    }
+}
+
+fn policy(pass: &str) -> ReconnectPolicy {
+    ReconnectPolicy::with_login("alice", pass)
+        .max_attempts(3)
+        .backoff(Duration::from_millis(10), Duration::from_millis(10))
+}
+
+#[tokio::test]
+async fn restores_the_session_and_fails_commands_in_flight() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let dials = Arc::new(AtomicUsize::new(0));
+    let client = Builder::new("mock:143")
+        .reconnect(policy("rotated"))
+        .build()
+        .connect_with(dialer(seen.clone(), dials.clone()))
+        .await
+        .unwrap();
+    let session = client.login("alice", "secret").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let err = session.search(vec![SearchKey::All]).await.unwrap_err();
+    assert!(
+        err.chain().any(|e| e.is::<ConnectionLost>()),
+        "unexpected error: {:#}",
+        err
+    );
+
+    assert_eq!(
+        session.search(vec![SearchKey::All]).await.unwrap(),
+        vec![Seq(2), Seq(3)]
+    );
+    assert_eq!(dials.load(Ordering::SeqCst), 2);
+    let seen = seen.lock().unwrap();
+    assert!(seen[0].starts_with("LOGIN") && seen[0].contains("rotated"));
+    assert!(seen[1].starts_with("SELECT") && seen[1].contains("INBOX"));
+    assert!(seen[2].starts_with("SEARCH"));
+}
+
+#[tokio::test]
+async fn reconnects_after_bye() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let dials = Arc::new(AtomicUsize::new(0));
+    let client = Builder::new("mock:143")
+        .reconnect(policy("secret"))
+        .build()
+        .connect_with(dialer_with(seen, dials.clone(), true))
+        .await
+        .unwrap();
+    let session = client.login("alice", "secret").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let err = session.search(vec![SearchKey::All]).await.unwrap_err();
+    assert!(err.chain().any(|e| e.is::<ConnectionLost>()));
+    assert_eq!(
+        session.search(vec![SearchKey::All]).await.unwrap(),
+        vec![Seq(2), Seq(3)]
+    );
+    assert_eq!(dials.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn gives_up_when_the_credentials_are_rejected() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let dials = Arc::new(AtomicUsize::new(0));
+    let client = Builder::new("mock:143")
+        .reconnect(policy("wrong"))
+        .build()
+        .connect_with(dialer(seen.clone(), dials.clone()))
+        .await
+        .unwrap();
+    let session = client.login("alice", "secret").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    assert!(session.search(vec![SearchKey::All]).await.is_err());
+    assert!(session.search(vec![SearchKey::All]).await.is_err());
+    // A refusal is not retried.
+    assert_eq!(dials.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn stays_down_without_a_policy() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let dials = Arc::new(AtomicUsize::new(0));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_with(dialer(seen, dials.clone()))
+        .await
+        .unwrap();
+    let session = client.login("alice", "secret").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let err = session.search(vec![SearchKey::All]).await.unwrap_err();
+    assert!(!err.chain().any(|e| e.is::<ConnectionLost>()));
+    assert!(session.search(vec![SearchKey::All]).await.is_err());
+    assert_eq!(dials.load(Ordering::SeqCst), 1);
+}
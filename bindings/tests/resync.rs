@@ -0,0 +1,52 @@
+//! Recovering after a tagged BAD: a bare CRLF and a NOOP probe before further commands.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+#[tokio::test]
+async fn commands_complete_after_a_bad() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        match cmd {
+            // The bare CRLF sent to end a misparsed command.
+            "" => Vec::new(),
+            "BOGUS" => format!("{} BAD Unknown command\r\n", tag).into_bytes(),
+            _ => format!("{} OK {} completed\r\n", tag, cmd).into_bytes(),
+        }
+    })
+    // Keeps the BAD in flight until the next command has been written.
+    .latency(Duration::from_millis(20));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let raw = client.into_raw();
+
+    let (bad, next) = tokio::join!(raw.execute("BOGUS"), raw.execute("CHECK"));
+    assert!(
+        bad.unwrap()
+            .last()
+            .unwrap()
+            .ends_with(b"BAD Unknown command\r\n")
+    );
+    assert!(
+        next.unwrap()
+            .last()
+            .unwrap()
+            .ends_with(b"OK CHECK completed\r\n")
+    );
+    // Held until the probe completes.
+    let after = raw.execute("NOOP").await.unwrap();
+    assert!(after.last().unwrap().ends_with(b"OK NOOP completed\r\n"));
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        ["BOGUS", "CHECK", "", "NOOP", "NOOP"]
+    );
+}
@@ -0,0 +1,87 @@
+//! Classifying failures as retryable or not.
+
+use std::time::Duration;
+
+use bindings::Builder;
+use bindings::async_impl::{error_kind, is_retryable};
+use bindings::test_util::MockServer;
+
+use imap::ErrorKind;
+
+fn server() -> MockServer {
+    MockServer::new(|tag, cmd| {
+        let reply = if cmd.contains("wrong") {
+            "NO [AUTHENTICATIONFAILED] Invalid credentials"
+        } else if cmd.starts_with("CREATE") {
+            "NO [UNAVAILABLE] Try again later"
+        } else if cmd.starts_with("DELETE") {
+            "NO [NOPERM] Not allowed"
+        } else if cmd.starts_with("SUBSCRIBE") {
+            "BAD Syntax error"
+        } else if cmd.starts_with("LIST") {
+            return b"* BYE Going down\r\n".to_vec();
+        } else if cmd == "CHECK" {
+            // Never answered.
+            return Vec::new();
+        } else {
+            "OK done"
+        };
+        format!("{} {}\r\n", tag, reply).into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn refusals_by_code() {
+    let connect = || async {
+        Builder::new("mock:143")
+            .build()
+            .connect_stream(server().spawn())
+            .await
+            .unwrap()
+    };
+    let err = connect().await.login("user", "wrong").await.err().unwrap();
+    assert_eq!(error_kind(&err), Some(ErrorKind::Authentication));
+    assert!(!is_retryable(&err));
+
+    let mut session = connect().await.login("user", "pass").await.unwrap();
+    let err = session.create("Archive").await.unwrap_err();
+    assert_eq!(error_kind(&err), Some(ErrorKind::Unavailable));
+    assert!(is_retryable(&err));
+
+    let err = session.delete("INBOX").await.unwrap_err();
+    assert_eq!(error_kind(&err), Some(ErrorKind::Refused));
+    assert!(!is_retryable(&err));
+
+    let err = session.subscribe("INBOX").await.unwrap_err();
+    assert_eq!(error_kind(&err), Some(ErrorKind::Protocol));
+
+    // Errors raised by the client itself are not classified.
+    let err = session.quota_root("INBOX").await.unwrap_err();
+    assert_eq!(error_kind(&err), None);
+    assert!(!is_retryable(&err));
+}
+
+#[tokio::test]
+async fn bye_and_timeouts_are_retryable() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    let err = session.list("", "*").await.unwrap_err();
+    assert_eq!(error_kind(&err), Some(ErrorKind::Connection));
+    assert!(is_retryable(&err));
+
+    let client = Builder::new("mock:143")
+        .read_timeout(Duration::from_millis(50))
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    let err = session.check().await.unwrap_err();
+    assert_eq!(error_kind(&err), Some(ErrorKind::Timeout));
+    assert!(is_retryable(&err));
+}
@@ -0,0 +1,125 @@
+//! rfc822: the RFC822, RFC822.HEADER and RFC822.TEXT fetch items.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::commands::FetchItem;
+use imap::types::command::SequenceSet;
+use imap::types::common::Seq;
+use imap::types::response::FetchData;
+
+const HEADER: &[u8] = b"Subject: hi\r\n\r\n";
+const TEXT: &[u8] = b"body \xff\r\nA1 OK not the end\r\n";
+
+fn fetch_response(message: &[u8]) -> Vec<u8> {
+    let mut out = format!(
+        "* 1 FETCH (RFC822.SIZE {} RFC822 {{{}}}\r\n",
+        message.len(),
+        message.len()
+    )
+    .into_bytes();
+    out.extend_from_slice(message);
+    out.extend_from_slice(format!(" RFC822.HEADER {{{}}}\r\n", HEADER.len()).as_bytes());
+    out.extend_from_slice(HEADER);
+    out.extend_from_slice(b" RFC822.TEXT NIL)\r\n");
+    out
+}
+
+fn items() -> Vec<FetchItem> {
+    vec![
+        FetchItem::Rfc822Size,
+        FetchItem::Rfc822,
+        FetchItem::Rfc822Header,
+        FetchItem::Rfc822Text,
+    ]
+}
+
+#[tokio::test]
+async fn parses_each_item_byte_for_byte() {
+    let message = [HEADER, TEXT].concat();
+    let response = fetch_response(&message);
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let mut out = if cmd.starts_with("SELECT ") {
+            b"* 1 EXISTS\r\n".to_vec()
+        } else if cmd.starts_with("FETCH ") {
+            response.clone()
+        } else {
+            Vec::new()
+        };
+        out.extend_from_slice(format!("{} OK done\r\n", tag).as_bytes());
+        out
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let fetched = session
+        .fetch_items(SequenceSet::new().add_single(1), items())
+        .await
+        .unwrap();
+    assert_eq!(fetched.len(), 1);
+    let (seq, data) = &fetched[0];
+    assert_eq!(*seq, Seq(1));
+    assert_eq!(data.len(), 4);
+    assert!(matches!(data[0], FetchData::Rfc822Size(n) if n as usize == message.len()));
+    assert!(matches!(&data[1], FetchData::Rfc822(Some(b)) if b[..] == message[..]));
+    assert!(matches!(&data[2], FetchData::Rfc822Header(Some(b)) if &b[..] == HEADER));
+    assert!(matches!(data[3], FetchData::Rfc822Text(None)));
+
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[2],
+        "FETCH 1 (RFC822.SIZE RFC822 RFC822.HEADER RFC822.TEXT)"
+    );
+}
+
+#[tokio::test]
+async fn read_only_session_peeks_instead() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = if cmd.starts_with("EXAMINE ") {
+            "* 1 EXISTS\r\n"
+        } else if cmd.starts_with("FETCH ") {
+            "* 1 FETCH (RFC822.SIZE 2 BODY[] {2}\r\nhi RFC822.HEADER NIL BODY[TEXT] NIL)\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .read_only()
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let fetched = session
+        .fetch_items(SequenceSet::new().add_single(1), items())
+        .await
+        .unwrap();
+    let data = &fetched[0].1;
+    assert!(matches!(
+        &data[1],
+        FetchData::BodySection { section, .. } if section.is_empty()
+    ));
+    assert!(matches!(data[2], FetchData::Rfc822Header(None)));
+
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[2],
+        "FETCH 1 (RFC822.SIZE BODY.PEEK[] RFC822.HEADER BODY.PEEK[TEXT])"
+    );
+}
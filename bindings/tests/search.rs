@@ -0,0 +1,228 @@
+//! SEARCH and UID SEARCH on the selected mailbox.
+
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, DuplexStream};
+use tokio::task::JoinHandle;
+
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+use bindings::{Builder, SelectedState};
+
+use imap::types::command::SearchKey;
+use imap::types::common::{Seq, Uid};
+
+#[tokio::test]
+async fn searches_by_sequence_number_and_uid() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = if cmd.starts_with("SEARCH UNSEEN") {
+            "* SEARCH 2 3 17\r\n"
+        } else if cmd.starts_with("UID SEARCH") {
+            "* SEARCH 4827 4830\r\n"
+        } else if cmd.starts_with("SEARCH") {
+            "* SEARCH\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let found = session
+        .search(vec![
+            SearchKey::Unseen,
+            SearchKey::From("Ann \"A\" Smith".to_string()),
+            SearchKey::Or(
+                Box::new(SearchKey::Flagged),
+                Box::new(SearchKey::Not(Box::new(SearchKey::Deleted))),
+            ),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(found, [Seq(2), Seq(3), Seq(17)]);
+
+    let found = session
+        .uid_search(vec![
+            SearchKey::Larger(1000),
+            SearchKey::Header {
+                name: "List-Id".to_string(),
+                value: "dev".to_string(),
+            },
+        ])
+        .await
+        .unwrap();
+    assert_eq!(found, [Uid(4827), Uid(4830)]);
+
+    // No keys matches everything; an empty SEARCH response is no matches.
+    assert!(session.search(Vec::new()).await.unwrap().is_empty());
+
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[received.len() - 3..],
+        [
+            "SEARCH UNSEEN FROM \"Ann \\\"A\\\" Smith\" OR (FLAGGED) (NOT (DELETED))",
+            "UID SEARCH LARGER 1000 HEADER \"List-Id\" \"dev\"",
+            "SEARCH ALL",
+        ]
+    );
+}
+
+/// Answers every command with OK, SEARCH with one match, and literal announcements with a
+/// continuation. Returns each SEARCH as received, literals included, without the final
+/// CRLF, and the names of the other commands.
+fn serve(stream: DuplexStream, capabilities: &'static str) -> JoinHandle<Vec<String>> {
+    tokio::spawn(async move {
+        let (read, mut write) = tokio::io::split(stream);
+        let mut reader = BufReader::new(read);
+        let greeting = format!("* OK [CAPABILITY IMAP4rev1 {}] ready\r\n", capabilities);
+        write.write_all(greeting.as_bytes()).await.unwrap();
+        let mut commands = Vec::new();
+        loop {
+            let mut command = Vec::new();
+            if reader.read_until(b'\n', &mut command).await.unwrap() == 0 {
+                break;
+            }
+            // Each line ending in `{n}` or `{n+}` is followed by n bytes and more command.
+            while let Some(open) = command.iter().rposition(|&b| b == b'{')
+                && command.ends_with(b"}\r\n")
+            {
+                let announced = std::str::from_utf8(&command[open + 1..command.len() - 3])
+                    .unwrap()
+                    .to_string();
+                let (size, sync) = match announced.strip_suffix('+') {
+                    Some(n) => (n.parse::<usize>().unwrap(), false),
+                    None => (announced.parse::<usize>().unwrap(), true),
+                };
+                if sync {
+                    write.write_all(b"+ go ahead\r\n").await.unwrap();
+                }
+                let mut literal = vec![0u8; size];
+                reader.read_exact(&mut literal).await.unwrap();
+                command.extend_from_slice(&literal);
+                reader.read_until(b'\n', &mut command).await.unwrap();
+            }
+            command.truncate(command.len() - 2);
+            let text = String::from_utf8_lossy(&command).into_owned();
+            let (tag, rest) = text.split_once(' ').unwrap();
+            let body = if rest.contains("SEARCH") {
+                "* SEARCH 1\r\n"
+            } else if rest.starts_with("SELECT") {
+                "* 1 EXISTS\r\n"
+            } else if rest == "CAPABILITY" {
+                &format!("* CAPABILITY IMAP4rev1 {}\r\n", capabilities)
+            } else {
+                ""
+            };
+            let reply = format!("{}{} OK done\r\n", body, tag);
+            write.write_all(reply.as_bytes()).await.unwrap();
+            commands.push(if rest.contains("SEARCH") {
+                rest.to_string()
+            } else {
+                rest.split(' ').next().unwrap().to_string()
+            });
+            if rest == "LOGOUT" {
+                break;
+            }
+        }
+        commands
+    })
+}
+
+async fn selected(capabilities: &'static str) -> (Client<SelectedState>, JoinHandle<Vec<String>>) {
+    let (client_end, server_end) = tokio::io::duplex(4096);
+    let server = serve(server_end, capabilities);
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(client_end)
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (session, _) = session.select("INBOX").await.unwrap();
+    (session, server)
+}
+
+#[tokio::test]
+async fn line_breaks_are_sent_as_a_literal() {
+    let (mut session, server) = selected("").await;
+    let found = session
+        .search(vec![
+            SearchKey::Subject("x\r\nA1 DELETE INBOX".to_string()),
+            SearchKey::Unseen,
+        ])
+        .await
+        .unwrap();
+    assert_eq!(found, [Seq(1)]);
+    session.logout().await.unwrap();
+
+    // One command, with no DELETE of its own.
+    let commands = server.await.unwrap();
+    assert_eq!(
+        commands[commands.len() - 2..],
+        [
+            "SEARCH SUBJECT {18}\r\nx\r\nA1 DELETE INBOX UNSEEN",
+            "LOGOUT"
+        ]
+    );
+    assert!(!commands.iter().any(|c| c.starts_with("DELETE")));
+}
+
+#[tokio::test]
+async fn non_ascii_text_adds_the_charset() {
+    let (mut session, server) = selected("").await;
+    let found = session
+        .uid_search(vec![SearchKey::Subject("Grüße".to_string())])
+        .await
+        .unwrap();
+    assert_eq!(found, [Uid(1)]);
+    session.logout().await.unwrap();
+
+    let commands = server.await.unwrap();
+    assert_eq!(
+        commands[commands.len() - 2],
+        "UID SEARCH CHARSET UTF-8 SUBJECT {7}\r\nGrüße"
+    );
+}
+
+#[tokio::test]
+async fn several_literals_need_literal_plus() {
+    let keys = || {
+        vec![
+            SearchKey::From("Jörg".to_string()),
+            SearchKey::Subject("Grüße".to_string()),
+        ]
+    };
+    let (mut session, server) = selected("").await;
+    let err = session.search(keys()).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("LITERAL+"), "{:#}", err);
+    let err = session
+        .search(vec![SearchKey::Text("nul \0".to_string())])
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("cannot be sent"), "{:#}", err);
+    let err = session
+        .search(vec![SearchKey::Keyword("a b".to_string())])
+        .await
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("cannot be sent"), "{:#}", err);
+    session.logout().await.unwrap();
+    // Nothing reached the server.
+    assert!(!server.await.unwrap().iter().any(|c| c.contains("SEARCH")));
+
+    let (mut session, server) = selected("LITERAL+").await;
+    assert_eq!(session.search(keys()).await.unwrap(), [Seq(1)]);
+    session.logout().await.unwrap();
+    let commands = server.await.unwrap();
+    assert_eq!(
+        commands[commands.len() - 2],
+        "SEARCH CHARSET UTF-8 FROM {5+}\r\nJörg SUBJECT {7+}\r\nGrüße"
+    );
+}
@@ -0,0 +1,85 @@
+//! Typed BODY section specifications.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use imap::commands::{FetchItem, Section};
+use imap::types::command::SequenceSet;
+use imap::types::response::FetchData;
+
+#[test]
+fn serializes_section_specs() {
+    let cases = [
+        (Section::full(), ""),
+        (Section::full().header(), "HEADER"),
+        (Section::full().text(), "TEXT"),
+        (Section::part(&[3]), "3"),
+        (Section::part(&[1, 2]).text(), "1.2.TEXT"),
+        (Section::part(&[2]).mime(), "2.MIME"),
+        (Section::part(&[4, 1]).header(), "4.1.HEADER"),
+        (
+            Section::full().header_fields(&["From", "Subject"]),
+            "HEADER.FIELDS (From Subject)",
+        ),
+        (
+            Section::part(&[2]).header_fields_not(&["Received"]),
+            "2.HEADER.FIELDS.NOT (Received)",
+        ),
+        (
+            Section::full().header_fields(&["X-Odd]Name", ""]),
+            "HEADER.FIELDS (\"X-Odd]Name\" \"\")",
+        ),
+    ];
+    for (section, expected) in cases {
+        assert_eq!(section.to_string(), expected);
+    }
+}
+
+#[tokio::test]
+async fn fetches_typed_sections() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = if cmd.starts_with("UID FETCH") {
+            "* 1 FETCH (UID 7 BODY[1.2.TEXT] {5}\r\nhello BODY[2.MIME] {0}\r\n)\r\n"
+        } else if cmd.starts_with("SELECT") {
+            "* 1 EXISTS\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let items = vec![
+        FetchItem::Uid,
+        FetchItem::body(Section::part(&[1, 2]).text()),
+        FetchItem::body_peek(Section::part(&[2]).mime()),
+    ];
+    let fetched = session
+        .uid_fetch(SequenceSet::new().add_single(7), items)
+        .await
+        .unwrap();
+
+    let fetch = received
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|c| c.starts_with("UID FETCH"))
+        .cloned()
+        .unwrap();
+    assert_eq!(fetch, "UID FETCH 7 (UID BODY[1.2.TEXT] BODY.PEEK[2.MIME])");
+    assert!(fetched[0].1.iter().any(|item| matches!(
+        item,
+        FetchData::BodySection { section, data: Some(data), .. }
+            if section == "1.2.TEXT" && &data[..] == b"hello"
+    )));
+}
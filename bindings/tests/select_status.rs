@@ -0,0 +1,78 @@
+//! The typed mailbox status returned by select and examine.
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::common::Flag;
+
+/// The SELECT example from RFC 3501 section 6.3.1, plus HIGHESTMODSEQ.
+const SELECTED: &str = concat!(
+    "* 172 EXISTS\r\n",
+    "* 1 RECENT\r\n",
+    "* OK [UNSEEN 12] Message 12 is first unseen\r\n",
+    "* OK [UIDVALIDITY 3857529045] UIDs valid\r\n",
+    "* OK [UIDNEXT 4392] Predicted next UID\r\n",
+    "* FLAGS (\\Answered \\Flagged \\Deleted \\Seen \\Draft)\r\n",
+    "* OK [PERMANENTFLAGS (\\Deleted \\Seen \\*)] Limited\r\n",
+    "* OK [HIGHESTMODSEQ 715194045007] Highest\r\n",
+);
+
+fn server() -> MockServer {
+    MockServer::new(|tag, cmd| {
+        let verb = cmd.split(' ').next().unwrap_or("");
+        match verb {
+            "SELECT" if cmd.contains("Missing") => {
+                format!("{} NO [NONEXISTENT] No such mailbox\r\n", tag)
+            }
+            "SELECT" => format!("{}{} OK [READ-WRITE] SELECT completed\r\n", SELECTED, tag),
+            "EXAMINE" => format!("{}{} OK [READ-ONLY] EXAMINE completed\r\n", SELECTED, tag),
+            _ => format!("{} OK done\r\n", tag),
+        }
+        .into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn select_reports_the_mailbox_state() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+
+    let (session, status) = session.select("INBOX").await.unwrap();
+    assert_eq!((status.exists, status.recent), (172, 1));
+    assert_eq!(status.unseen, Some(12));
+    assert_eq!(status.uid_validity, Some(3857529045));
+    assert_eq!(status.uid_next, Some(4392));
+    assert_eq!(status.highest_modseq, Some(715194045007));
+    assert_eq!(status.flags.len(), 5);
+    assert!(matches!(status.flags[4], Flag::Draft));
+    assert!(matches!(
+        &status.permanent_flags[..],
+        [Flag::Deleted, Flag::Seen, Flag::Keyword(any)] if any == "\\*"
+    ));
+    assert!(!status.read_only);
+    assert!(status.vanished.is_empty());
+
+    let (_, status) = session.examine("INBOX").await.unwrap();
+    assert!(status.read_only);
+    assert_eq!(status.exists, 172);
+}
+
+#[tokio::test]
+async fn a_refused_select_is_an_error() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server().spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let err = session.select("Missing").await.err().unwrap();
+    assert!(
+        format!("{:#}", err).contains("No such mailbox"),
+        "{:#}",
+        err
+    );
+}
@@ -0,0 +1,111 @@
+//! Moving between the authenticated and selected typestates.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::async_impl::Client;
+use bindings::test_util::MockServer;
+use bindings::{Builder, ExaminedState};
+
+use imap::types::command::SearchKey;
+use imap::types::common::{Seq, Uid};
+
+#[tokio::test]
+async fn select_switch_and_unselect() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" | "UNSELECT" => "",
+            "CAPABILITY" => "* CAPABILITY IMAP4rev1 UNSELECT\r\n",
+            "SELECT" => "* 2 EXISTS\r\n",
+            "EXAMINE" => "* 9 EXISTS\r\n",
+            "SEARCH" => "* SEARCH 2\r\n",
+            "LIST" => "* LIST () \"/\" INBOX\r\n",
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+
+    let (mut inbox, status) = session.select("INBOX").await.unwrap();
+    assert_eq!(status.exists, 2);
+    assert_eq!(
+        inbox.search(vec![SearchKey::Unseen]).await.unwrap(),
+        vec![Seq(2)]
+    );
+
+    // Examining from the selected state switches mailboxes and drops write access.
+    let (mut archive, status): (Client<ExaminedState>, _) = inbox.examine("Archive").await.unwrap();
+    assert_eq!(status.exists, 9);
+    assert_eq!(
+        archive.search(vec![SearchKey::Unseen]).await.unwrap(),
+        vec![Seq(2)]
+    );
+
+    let mut session = archive.unselect().await.unwrap();
+    assert_eq!(session.list("", "*").await.unwrap().len(), 1);
+
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received[1..],
+        [
+            "SELECT \"INBOX\"",
+            "SEARCH UNSEEN",
+            "EXAMINE \"Archive\"",
+            "SEARCH UNSEEN",
+            "CAPABILITY",
+            "UNSELECT",
+            "LIST \"\" \"*\"",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn examined_session_never_selects_or_stores() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" => "",
+            "EXAMINE" => "* 1 EXISTS\r\n",
+            "FETCH" | "UID" => "* 1 FETCH (UID 7 ENVELOPE NIL BODY[] {2}\r\nhi)\r\n",
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut archive, _) = session.examine("Archive").await.unwrap();
+
+    // Commands that name another mailbox EXAMINE it rather than SELECT it.
+    archive.fetch("Other", 1).await.unwrap();
+    archive.fetch_body("Other", Uid(7)).await.unwrap();
+    // The open mailbox is not opened again.
+    let mut messages = archive.messages("Other").await.unwrap();
+    while messages.try_next().await.unwrap().is_some() {}
+
+    let received = received.lock().unwrap();
+    let verbs: Vec<_> = received[1..]
+        .iter()
+        .map(|cmd| cmd.split(' ').next().unwrap())
+        .collect();
+    assert_eq!(verbs, ["EXAMINE", "EXAMINE", "FETCH", "UID", "FETCH"]);
+    assert!(
+        !received
+            .iter()
+            .any(|cmd| cmd.contains("SELECT") || cmd.contains("STORE"))
+    );
+}
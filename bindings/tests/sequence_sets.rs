@@ -0,0 +1,81 @@
+//! Building sequence sets from numbers, ranges and lists.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::command::SequenceSet;
+use imap::types::common::{Seq, Uid};
+
+#[test]
+fn converts_numbers_ranges_and_lists() {
+    assert_eq!(SequenceSet::from(7).to_string(), "7");
+    assert_eq!(SequenceSet::from(1..4).to_string(), "1:3");
+    assert_eq!(SequenceSet::from(5..=5).to_string(), "5");
+    assert_eq!(SequenceSet::from(2..=10).to_string(), "2:10");
+    assert_eq!(SequenceSet::from(&[1, 2, 4, 9]).to_string(), "1:2,4,9");
+    assert_eq!(SequenceSet::from(&[3, 1, 2][..]).to_string(), "3,1:2");
+    assert_eq!(SequenceSet::from(vec![8, 9, 10, 12]).to_string(), "8:10,12");
+    let set: SequenceSet = (1..=3).chain([u32::MAX - 1, u32::MAX]).collect();
+    assert_eq!(set.to_string(), "1:3,4294967294:4294967295");
+}
+
+#[test]
+fn converts_uids_and_sequence_numbers() {
+    assert_eq!(SequenceSet::from(Uid(40)).to_string(), "40");
+    assert_eq!(
+        SequenceSet::from(&[Uid(4), Uid(5), Uid(9)]).to_string(),
+        "4:5,9"
+    );
+    assert_eq!(SequenceSet::from(vec![Seq(2), Seq(3)]).to_string(), "2:3");
+    let set: SequenceSet = [Seq(7), Seq(1)].into_iter().collect();
+    assert_eq!(set.to_string(), "7,1");
+    assert_eq!(
+        SequenceSet::batched(&[Uid(3), Uid(1), Uid(2)], 100)[0].to_string(),
+        "1:3"
+    );
+}
+
+#[test]
+fn empty_ranges_give_empty_sets() {
+    assert!(SequenceSet::from(4..4).is_empty());
+    assert!(SequenceSet::from(0..0).is_empty());
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = SequenceSet::from(5..=2);
+    assert!(reversed.is_empty());
+    assert!(SequenceSet::from(&[] as &[u32]).is_empty());
+}
+
+#[tokio::test]
+async fn fetch_takes_a_list_of_sequence_numbers() {
+    let sent = Arc::new(Mutex::new(Vec::new()));
+    let seen = sent.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" => "",
+            "SELECT" => "* 9 EXISTS\r\n",
+            "FETCH" => {
+                seen.lock().unwrap().push(cmd.to_string());
+                "* 4 FETCH (ENVELOPE (NIL \"Hi\" NIL NIL NIL NIL NIL NIL NIL NIL))\r\n"
+            }
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let envelopes = session.fetch("INBOX", &[1, 2, 4, 9]).await.unwrap();
+    assert_eq!(envelopes[0].subject.as_deref(), Some("Hi"));
+    session.fetch("INBOX", 3..=5).await.unwrap();
+
+    let sent = sent.lock().unwrap();
+    assert!(sent[0].starts_with("FETCH 1:2,4,9 "), "{}", sent[0]);
+    assert!(sent[1].starts_with("FETCH 3:5 "), "{}", sent[1]);
+}
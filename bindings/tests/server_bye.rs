@@ -0,0 +1,51 @@
+//! An untagged BYE in the middle of a session.
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::ImapError;
+
+#[tokio::test]
+async fn bye_fails_pending_commands_with_its_text() {
+    let server = MockServer::new(|tag, cmd| {
+        if cmd == "CAPABILITY" {
+            return b"* BYE Server shutting down for maintenance\r\n".to_vec();
+        }
+        format!("{} OK done\r\n", tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let err = session.capabilities().await.unwrap_err();
+    match err.downcast_ref::<ImapError>() {
+        Some(ImapError::ServerBye(text)) => {
+            assert_eq!(text, "Server shutting down for maintenance")
+        }
+        _ => panic!("expected ServerBye, got {:#}", err),
+    }
+    // The loop has stopped, so later commands fail straight away.
+    assert!(session.list("", "*").await.is_err());
+}
+
+#[tokio::test]
+async fn bye_during_logout_is_expected() {
+    let server = MockServer::new(|tag, cmd| {
+        let body = if cmd == "LOGOUT" {
+            "* BYE logging out\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    session.logout().await.unwrap();
+}
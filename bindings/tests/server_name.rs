@@ -0,0 +1,54 @@
+//! Verifying a TLS name other than the connect address.
+
+mod tls;
+
+use bindings::Builder;
+use rustls::RootCertStore;
+use rustls::client::Resumption;
+use rustls::pki_types::CertificateDer;
+use rustls::pki_types::pem::PemObject;
+
+const CERT: &[u8] = include_bytes!("tls/localhost.der");
+const KEY: &[u8] = include_bytes!("tls/localhost.key.der");
+const CA: &[u8] = include_bytes!("tls/ca.pem");
+
+/// Trusts only the test CA, which issued the certificate for `localhost`.
+fn builder(addr: &str) -> Builder {
+    let mut roots = RootCertStore::empty();
+    roots
+        .add(CertificateDer::from_pem_slice(CA).unwrap())
+        .unwrap();
+    Builder::new(addr).tls_config(imap::tls::create_tls_config_with_roots(
+        roots,
+        Resumption::disabled(),
+    ))
+}
+
+#[tokio::test]
+async fn an_ip_address_does_not_match_the_certificate() {
+    let port = tls::serve(CERT, KEY).await;
+    let result = builder(&format!("127.0.0.1:{}", port)).connect().await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn verifies_the_overridden_name() {
+    let port = tls::serve(CERT, KEY).await;
+    let client = builder(&format!("127.0.0.1:{}", port))
+        .server_name("localhost")
+        .connect()
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    session.logout().await.unwrap();
+}
+
+#[tokio::test]
+async fn the_overridden_name_is_still_checked() {
+    let port = tls::serve(CERT, KEY).await;
+    let result = builder(&format!("localhost:{}", port))
+        .server_name("imap.example.com")
+        .connect()
+        .await;
+    assert!(result.is_err());
+}
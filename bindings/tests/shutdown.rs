@@ -0,0 +1,72 @@
+//! Orderly shutdown of several connections.
+
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use bindings::AuthenticatedState;
+use bindings::Builder;
+use bindings::async_impl::Client;
+use bindings::async_impl::shutdown::logout_all;
+use bindings::test_util::MockServer;
+
+async fn session(answer_logout: bool) -> Client<AuthenticatedState> {
+    let server = MockServer::new(move |tag, cmd| {
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        match verb.as_str() {
+            "LOGIN" => format!("{} OK LOGIN completed\r\n", tag).into_bytes(),
+            "LOGOUT" if answer_logout => {
+                format!("* BYE logging out\r\n{} OK LOGOUT completed\r\n", tag).into_bytes()
+            }
+            _ => Vec::new(),
+        }
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    client.login("user", "pass").await.unwrap()
+}
+
+#[tokio::test]
+async fn logout_all_reports_each_connection() {
+    let sessions = vec![
+        session(true).await,
+        session(false).await,
+        session(true).await,
+    ];
+    let results = logout_all(sessions, Duration::from_millis(500)).await;
+    let ok: Vec<bool> = results.iter().map(Result::is_ok).collect();
+    assert_eq!(ok, [true, false, true]);
+}
+
+#[tokio::test]
+async fn connection_is_closed_after_the_deadline() {
+    let (client_end, server_end) = tokio::io::duplex(4096);
+    let (read, mut write) = tokio::io::split(server_end);
+    let server = tokio::spawn(async move {
+        let mut lines = BufReader::new(read).lines();
+        write.write_all(b"* OK ready\r\n").await.unwrap();
+        // Answers LOGIN, ignores LOGOUT, then waits for the client to hang up.
+        while let Some(line) = lines.next_line().await.unwrap() {
+            let (tag, cmd) = line.split_once(' ').unwrap();
+            if cmd.starts_with("LOGIN") {
+                let reply = format!("{} OK LOGIN completed\r\n", tag);
+                write.write_all(reply.as_bytes()).await.unwrap();
+            }
+        }
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(client_end)
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+
+    let results = logout_all(vec![session], Duration::from_millis(50)).await;
+    assert!(results[0].is_err());
+    tokio::time::timeout(Duration::from_secs(1), server)
+        .await
+        .expect("the connection stayed open")
+        .unwrap();
+}
@@ -0,0 +1,60 @@
+//! examine_then_fetch: reading a mailbox without changing it, then leaving it.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+const FETCH: &str = "* 1 FETCH (UID 7 ENVELOPE (NIL \"hi\" NIL NIL NIL NIL NIL NIL NIL NIL))\r\n";
+
+#[tokio::test]
+async fn examines_reads_and_unselects() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = match cmd.split(' ').next().unwrap() {
+            "CAPABILITY" => "* CAPABILITY IMAP4rev1 UNSELECT\r\n",
+            "EXAMINE" => "* 1 EXISTS\r\n",
+            "FETCH" => FETCH,
+            _ => "",
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let subject = session
+        .examine_then_fetch("INBOX", async |s| {
+            let envelopes = s.fetch("INBOX", 1).await?;
+            Ok(envelopes[0].subject.clone())
+        })
+        .await
+        .unwrap();
+    assert_eq!(subject.as_deref(), Some("hi"));
+
+    // Switching mailboxes inside the snapshot is refused, and the mailbox is still left.
+    let err = session
+        .examine_then_fetch("INBOX", async |s| s.fetch("Archive", 1).await)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("read-only snapshot"));
+
+    let received = received.lock().unwrap();
+    let sent: Vec<&str> = received[1..].iter().map(String::as_str).collect();
+    assert_eq!(
+        sent,
+        [
+            "EXAMINE \"INBOX\"",
+            "FETCH 1 (UID ENVELOPE)",
+            "CAPABILITY",
+            "UNSELECT",
+            "EXAMINE \"INBOX\"",
+            "UNSELECT",
+        ]
+    );
+}
@@ -0,0 +1,112 @@
+//! SORT, and the collation (RFC 5255) that decides how SEARCH and SORT compare strings.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::command::{SearchKey, SortKey};
+use imap::types::common::Seq;
+use imap::types::response::I18nLevel;
+
+fn server(capabilities: &'static str, received: Arc<Mutex<Vec<String>>>) -> MockServer {
+    MockServer::new(move |tag, cmd| {
+        received.lock().unwrap().push(cmd.to_string());
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" => String::new(),
+            "CAPABILITY" => format!("* CAPABILITY {}\r\n", capabilities),
+            "SELECT" => "* 7 EXISTS\r\n".to_string(),
+            "SORT" => "* SORT 5 2 7\r\n".to_string(),
+            "COMPARATOR" => "* COMPARATOR i;unicode-casemap\r\n".to_string(),
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn sorts_by_the_given_criteria() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1 SORT", received.clone()).spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let order = session
+        .sort(
+            "INBOX",
+            vec![SortKey::Reverse(Box::new(SortKey::Date)), SortKey::Subject],
+            vec![SearchKey::Unseen],
+        )
+        .await
+        .unwrap();
+    assert_eq!(order, [Seq(5), Seq(2), Seq(7)]);
+    let received = received.lock().unwrap();
+    assert_eq!(
+        received.last().unwrap(),
+        "SORT (REVERSE DATE SUBJECT) UTF-8 UNSEEN"
+    );
+}
+
+#[tokio::test]
+async fn reports_octet_only_collation() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1 SORT", received.clone()).spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let collation = session.collation().await.unwrap();
+    assert_eq!(collation.level, I18nLevel::None);
+    assert!(collation.is_octet_only());
+    assert!(!collation.comparator);
+    let err = session
+        .set_comparator(&["i;unicode-casemap"])
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("COMPARATOR"));
+    assert!(
+        !received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c.starts_with("COMPARATOR"))
+    );
+}
+
+#[tokio::test]
+async fn selects_a_comparator() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(
+            server(
+                "IMAP4rev1 SORT I18NLEVEL=1 I18NLEVEL=2 COMPARATOR",
+                received.clone(),
+            )
+            .spawn(),
+        )
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let collation = session.collation().await.unwrap();
+    assert_eq!(collation.level, I18nLevel::Level2);
+    assert!(!collation.is_octet_only());
+    assert!(collation.comparator);
+
+    let active = session
+        .set_comparator(&["i;unicode-casemap", "default"])
+        .await
+        .unwrap();
+    assert_eq!(active.as_deref(), Some("i;unicode-casemap"));
+    assert_eq!(
+        received.lock().unwrap().last().unwrap(),
+        "COMPARATOR \"i;unicode-casemap\" \"default\""
+    );
+}
@@ -0,0 +1,47 @@
+//! Upgrading a plaintext connection with STARTTLS.
+
+mod tls;
+
+use bindings::Builder;
+use imap::types::common::{Capability, SaslMechanism};
+
+const CERT: &[u8] = include_bytes!("tls/localhost.der");
+const KEY: &[u8] = include_bytes!("tls/localhost.key.der");
+
+#[tokio::test]
+async fn upgrades_and_refreshes_capabilities() {
+    let (port, log) = tls::serve_starttls(CERT, KEY, "").await;
+    let mut client = Builder::new(&format!("localhost:{}", port))
+        .starttls()
+        .tls_config(tls::trusting_ca())
+        .build()
+        .connect()
+        .await
+        .unwrap();
+    assert!(client.tls_info().is_some());
+
+    // The plaintext greeting's capabilities are not trusted; they are asked for again.
+    let caps = client.capabilities().await.unwrap();
+    assert!(caps.contains(&Capability::Auth(SaslMechanism::Plain)));
+    assert!(!caps.contains(&Capability::LoginDisabled));
+    assert!(!caps.has("STARTTLS"));
+
+    let session = client.login("user", "pass").await.unwrap();
+    session.logout().await.unwrap();
+    let log = log.lock().unwrap();
+    assert_eq!(log[0], "CAPABILITY");
+    assert!(log[1].starts_with("LOGIN "));
+}
+
+#[tokio::test]
+async fn rejects_plaintext_sent_after_the_ok() {
+    let (port, _) =
+        tls::serve_starttls(CERT, KEY, "* OK [CAPABILITY IMAP4rev1] injected\r\n").await;
+    let result = Builder::new(&format!("localhost:{}", port))
+        .starttls()
+        .tls_config(tls::trusting_ca())
+        .build()
+        .connect()
+        .await;
+    assert!(result.is_err());
+}
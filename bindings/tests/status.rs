@@ -0,0 +1,73 @@
+//! STATUS for a mailbox that is not selected.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::command::StatusItem;
+
+#[tokio::test]
+async fn status_reports_the_requested_counters() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        if cmd.starts_with("STATUS") {
+            format!(
+                "* STATUS \"Other\" (MESSAGES 1)\r\n\
+                 * STATUS \"Sent Items\" (MESSAGES 231 UIDNEXT 44292 UNSEEN 3)\r\n\
+                 {} OK STATUS completed\r\n",
+                tag
+            )
+            .into_bytes()
+        } else {
+            format!("{} OK done\r\n", tag).into_bytes()
+        }
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let status = session
+        .status(
+            "Sent Items",
+            &[
+                StatusItem::Messages,
+                StatusItem::UidNext,
+                StatusItem::Unseen,
+            ],
+        )
+        .await
+        .unwrap();
+    assert_eq!(status.mailbox, "Sent Items");
+    assert_eq!(status.messages, Some(231));
+    assert_eq!(status.uid_next, Some(44292));
+    assert_eq!(status.unseen, Some(3));
+    assert_eq!(status.uid_validity, None);
+    assert_eq!(
+        received.lock().unwrap().last().unwrap(),
+        "STATUS \"Sent Items\" (MESSAGES UIDNEXT UNSEEN)"
+    );
+}
+
+#[tokio::test]
+async fn status_fails_without_a_response() {
+    let server = MockServer::new(|tag, _| format!("{} OK done\r\n", tag).into_bytes());
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    assert!(
+        session
+            .status("INBOX", &[StatusItem::Messages])
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,59 @@
+//! SUBSCRIBE, UNSUBSCRIBE and LSUB.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+#[tokio::test]
+async fn manage_the_subscription_list() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = if cmd.starts_with("LSUB") {
+            "* LSUB () \"/\" INBOX\r\n* LSUB (\\Noselect) \"/\" \"Lists/Rust\"\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    session.subscribe("Lists/Rust").await.unwrap();
+    session.unsubscribe("Old Stuff").await.unwrap();
+    let entries = session.lsub("", "*").await.unwrap();
+    let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["INBOX", "Lists/Rust"]);
+    assert!(entries.iter().all(|e| e.is_subscribed()));
+    assert!(entries[1].has_attribute("\\Noselect"));
+
+    assert_eq!(
+        received.lock().unwrap()[1..],
+        [
+            "SUBSCRIBE \"Lists/Rust\"",
+            "UNSUBSCRIBE \"Old Stuff\"",
+            "LSUB \"\" \"*\"",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn read_only_sessions_do_not_subscribe() {
+    let server = MockServer::new(|tag, _| format!("{} OK done\r\n", tag).into_bytes());
+    let client = Builder::new("mock:143")
+        .read_only()
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    assert!(session.subscribe("INBOX").await.is_err());
+    assert!(session.unsubscribe("INBOX").await.is_err());
+}
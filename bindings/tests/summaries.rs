@@ -0,0 +1,63 @@
+//! Envelope summaries: the few fields a message list needs, without the full envelope.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::common::{SystemFlags, Uid};
+
+const FETCH: &str = concat!(
+    "* 1 FETCH (UID 42 INTERNALDATE \"17-Jul-1996 02:44:25 -0700\" RFC822.SIZE 4286 ",
+    "FLAGS (\\Seen \\Flagged $Work) ENVELOPE (\"Wed, 17 Jul 1996 02:23:25 -0700 (PDT)\" ",
+    "\"IMAP4rev1 WG mtg summary and minutes\" ",
+    "((\"Terry Gray\" NIL \"gray\" \"cac.washington.edu\")) ",
+    "((\"Terry Gray\" NIL \"gray\" \"cac.washington.edu\")) ",
+    "((\"Terry Gray\" NIL \"gray\" \"cac.washington.edu\")) ",
+    "((NIL NIL \"imap\" \"cac.washington.edu\")) NIL NIL NIL ",
+    "\"<B27397-0100000@cac.washington.edu>\"))\r\n",
+    "* 2 FETCH (UID 43 INTERNALDATE \"garbage\" RFC822.SIZE 10 FLAGS () ",
+    "ENVELOPE (NIL NIL NIL NIL NIL NIL NIL NIL NIL NIL))\r\n",
+);
+
+#[tokio::test]
+async fn summaries_carry_the_list_fields() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let log = received.clone();
+    let server = MockServer::new(move |tag, cmd| {
+        log.lock().unwrap().push(cmd.to_string());
+        let body = if cmd.starts_with("FETCH") { FETCH } else { "" };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let summaries = session.fetch_summaries("INBOX", 1..=2).await.unwrap();
+    assert_eq!(summaries.len(), 2);
+    let first = &summaries[0];
+    assert_eq!(first.uid, Uid(42));
+    assert_eq!(first.date, 837_596_665);
+    assert_eq!(&*first.subject, "IMAP4rev1 WG mtg summary and minutes");
+    assert_eq!(&*first.from, "Terry Gray <gray@cac.washington.edu>");
+    assert_eq!(first.size, 4286);
+    assert!(first.flags.contains(SystemFlags::SEEN));
+    assert!(first.flags.contains(SystemFlags::FLAGGED));
+    assert!(!first.flags.contains(SystemFlags::ANSWERED));
+
+    // Missing or unparseable fields fall back to empty values.
+    let second = &summaries[1];
+    assert_eq!(second.uid, Uid(43));
+    assert_eq!(second.date, 0);
+    assert_eq!(&*second.subject, "");
+    assert_eq!(&*second.from, "");
+    assert_eq!(second.flags, SystemFlags::empty());
+
+    assert_eq!(
+        received.lock().unwrap().last().unwrap(),
+        "FETCH 1:2 (UID INTERNALDATE RFC822.SIZE FLAGS ENVELOPE)"
+    );
+}
@@ -0,0 +1,23 @@
+//! Trusting roots from the PEM trust bundle named by SSL_CERT_FILE.
+
+mod tls;
+
+use bindings::Builder;
+
+const CERT: &[u8] = include_bytes!("tls/localhost.der");
+const KEY: &[u8] = include_bytes!("tls/localhost.key.der");
+
+#[tokio::test]
+async fn trusts_a_root_from_ssl_cert_file() {
+    // The only test in this binary, so nothing else reads the environment meanwhile.
+    let ca = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/tls/ca.pem");
+    unsafe { std::env::set_var("SSL_CERT_FILE", ca) };
+
+    let port = tls::serve(CERT, KEY).await;
+    let addr = format!("localhost:{}", port);
+    assert!(Builder::new(&addr).connect().await.is_err());
+
+    let client = Builder::new(&addr).system_roots().connect().await.unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    session.logout().await.unwrap();
+}
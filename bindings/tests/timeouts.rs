@@ -0,0 +1,124 @@
+//! Connect, greeting, read and write timeouts.
+
+use std::time::{Duration, Instant};
+
+use bindings::Builder;
+use bindings::async_impl::TimedOut;
+use bindings::test_util::MockServer;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+fn operation(err: &anyhow::Error) -> Option<&'static str> {
+    err.chain()
+        .find_map(|e| e.downcast_ref::<TimedOut>())
+        .map(|t| t.operation)
+}
+
+#[tokio::test]
+async fn connect_times_out_when_the_handshake_stalls() {
+    // The kernel accepts the connection but nobody answers the TLS ClientHello.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    let started = Instant::now();
+    let err = Builder::new(&addr)
+        .tls()
+        .connect_timeout(Duration::from_millis(100))
+        .connect()
+        .await
+        .err()
+        .expect("connect should time out");
+    assert_eq!(operation(&err), Some("connect"));
+    assert!(started.elapsed() < Duration::from_secs(5));
+    drop(listener);
+}
+
+#[tokio::test]
+async fn greeting_times_out() {
+    let server = MockServer::new(|tag, _| format!("{} OK done\r\n", tag).into_bytes())
+        .latency(Duration::from_secs(30));
+    let err = Builder::new("mock:143")
+        .greeting_timeout(Duration::from_millis(50))
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .err()
+        .expect("greeting should time out");
+    assert_eq!(operation(&err), Some("greeting"));
+}
+
+#[tokio::test]
+async fn read_times_out_while_a_command_waits() {
+    let server = MockServer::new(|tag, cmd| {
+        if cmd.starts_with("LOGIN") {
+            format!("{} OK LOGIN completed\r\n", tag).into_bytes()
+        } else {
+            // Never answer anything else.
+            Vec::new()
+        }
+    });
+    let client = Builder::new("mock:143")
+        .read_timeout(Duration::from_millis(100))
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+
+    // Silence with nothing in flight is fine.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let started = Instant::now();
+    assert!(session.capabilities().await.is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn write_times_out_when_the_server_stops_reading() {
+    let (client_end, server_end) = tokio::io::duplex(256);
+    tokio::spawn(async move {
+        let (read, mut write) = tokio::io::split(server_end);
+        let mut reader = BufReader::new(read);
+        write.write_all(b"* OK ready\r\n").await.unwrap();
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).await.unwrap() == 0 {
+                return;
+            }
+            let tag = line.split(' ').next().unwrap().to_string();
+            if line.contains("LOGIN") {
+                write
+                    .write_all(format!("{} OK LOGIN completed\r\n", tag).as_bytes())
+                    .await
+                    .unwrap();
+            } else if line.contains("CAPABILITY") {
+                let reply = format!("* CAPABILITY IMAP4rev1\r\n{} OK done\r\n", tag);
+                write.write_all(reply.as_bytes()).await.unwrap();
+            } else if line.contains("APPEND") {
+                // Ask for the literal, then never read it.
+                write.write_all(b"+ go ahead\r\n").await.unwrap();
+                break;
+            }
+        }
+        tokio::time::sleep(Duration::from_secs(30)).await;
+        drop(reader);
+    });
+
+    let client = Builder::new("mock:143")
+        .write_timeout(Duration::from_millis(100))
+        .build()
+        .connect_stream(client_end)
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+
+    let body = vec![b'x'; 64 * 1024];
+    let started = Instant::now();
+    assert!(
+        session
+            .append("INBOX", Vec::new(), None, &body)
+            .await
+            .is_err()
+    );
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
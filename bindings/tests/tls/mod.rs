@@ -0,0 +1,146 @@
+//! A local TLS server for certificate tests.
+//!
+//! Fixtures, all for `localhost`:
+//! - `self_signed.der`: a self-signed certificate, with its PKCS#8 key in
+//!   `self_signed.key.der`;
+//! - `localhost.der`: a certificate issued by the `ca.pem` root, with its key in
+//!   `localhost.key.der`.
+
+// Each test binary uses only some of the servers.
+#![allow(dead_code)]
+
+use std::sync::{Arc, Mutex};
+
+use rustls::HandshakeKind;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+/// A client configuration trusting only the `ca.pem` root.
+pub fn trusting_ca() -> Arc<rustls::ClientConfig> {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in CertificateDer::pem_slice_iter(include_bytes!("ca.pem")) {
+        roots.add(cert.unwrap()).unwrap();
+    }
+    let config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Arc::new(config)
+}
+
+fn acceptor(cert: &[u8], key: &[u8]) -> TlsAcceptor {
+    let config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![CertificateDer::from(cert.to_vec())],
+            PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.to_vec())),
+        )
+        .unwrap();
+    TlsAcceptor::from(Arc::new(config))
+}
+
+/// Serves TLS with `cert` and `key`, greeting every client and answering each command
+/// with OK. Returns the port.
+pub async fn serve(cert: &[u8], key: &[u8]) -> u16 {
+    serve_recording_resumption(cert, key).await.0
+}
+
+/// Like [`serve`], also recording for each accepted connection, in order, whether its
+/// handshake resumed an earlier TLS session.
+pub async fn serve_recording_resumption(cert: &[u8], key: &[u8]) -> (u16, Arc<Mutex<Vec<bool>>>) {
+    let acceptor = acceptor(cert, key);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let resumed = log.clone();
+    tokio::spawn(async move {
+        loop {
+            let Ok((sock, _)) = listener.accept().await else {
+                return;
+            };
+            let acceptor = acceptor.clone();
+            let resumed = resumed.clone();
+            tokio::spawn(async move {
+                // A client that refuses the certificate aborts the handshake.
+                let stream = acceptor.accept(sock).await?;
+                let kind = stream.get_ref().1.handshake_kind();
+                resumed
+                    .lock()
+                    .unwrap()
+                    .push(kind == Some(HandshakeKind::Resumed));
+                let (read, mut write) = tokio::io::split(stream);
+                let mut reader = BufReader::new(read);
+                write.write_all(b"* OK test server ready\r\n").await?;
+                let mut line = String::new();
+                while reader.read_line(&mut line).await? > 0 {
+                    let tag = line.split(' ').next().unwrap_or("*").to_string();
+                    write
+                        .write_all(format!("{} OK done\r\n", tag).as_bytes())
+                        .await?;
+                    line.clear();
+                }
+                Ok::<_, std::io::Error>(())
+            });
+        }
+    });
+    (port, log)
+}
+
+/// Serves one STARTTLS connection: a plaintext greeting advertising STARTTLS and
+/// LOGINDISABLED, then, after the tagged OK to STARTTLS and anything in `after_ok`, a TLS
+/// session with `cert` and `key` whose CAPABILITY offers AUTH=PLAIN and no LOGINDISABLED.
+/// Returns the port and the commands received over TLS.
+pub async fn serve_starttls(
+    cert: &[u8],
+    key: &[u8],
+    after_ok: &'static str,
+) -> (u16, Arc<Mutex<Vec<String>>>) {
+    let acceptor = acceptor(cert, key);
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let seen = log.clone();
+    tokio::spawn(async move {
+        let (mut sock, _) = listener.accept().await?;
+        sock.write_all(b"* OK [CAPABILITY IMAP4rev1 STARTTLS LOGINDISABLED] ready\r\n")
+            .await?;
+        // Read the STARTTLS line byte by byte so nothing sent after it is buffered here.
+        let mut line = Vec::new();
+        while !line.ends_with(b"\n") {
+            let mut byte = [0u8];
+            if tokio::io::AsyncReadExt::read(&mut sock, &mut byte).await? == 0 {
+                return Ok(());
+            }
+            line.push(byte[0]);
+        }
+        let line = String::from_utf8_lossy(&line);
+        let tag = line.split(' ').next().unwrap_or("*").to_string();
+        assert_eq!(line.trim_end(), format!("{} STARTTLS", tag));
+        sock.write_all(format!("{} OK begin TLS\r\n{}", tag, after_ok).as_bytes())
+            .await?;
+
+        let stream = acceptor.accept(sock).await?;
+        let (read, mut write) = tokio::io::split(stream);
+        let mut reader = BufReader::new(read);
+        let mut line = String::new();
+        while reader.read_line(&mut line).await? > 0 {
+            let mut words = line.trim_end().splitn(2, ' ');
+            let tag = words.next().unwrap_or("*").to_string();
+            let command = words.next().unwrap_or("").to_string();
+            seen.lock().unwrap().push(command.clone());
+            if command.eq_ignore_ascii_case("CAPABILITY") {
+                write
+                    .write_all(b"* CAPABILITY IMAP4rev1 AUTH=PLAIN\r\n")
+                    .await?;
+            }
+            write
+                .write_all(format!("{} OK done\r\n", tag).as_bytes())
+                .await?;
+            line.clear();
+        }
+        Ok::<_, std::io::Error>(())
+    });
+    (port, log)
+}
@@ -0,0 +1,45 @@
+//! The negotiated TLS parameters and the server's certificates, as seen by the client.
+
+mod tls;
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+const CERT: &[u8] = include_bytes!("tls/localhost.der");
+const KEY: &[u8] = include_bytes!("tls/localhost.key.der");
+
+#[tokio::test]
+async fn describes_the_tls_session() {
+    let port = tls::serve(CERT, KEY).await;
+    let client = Builder::new(&format!("localhost:{}", port))
+        .tls_config(tls::trusting_ca())
+        .build()
+        .connect()
+        .await
+        .unwrap();
+    let info = client.tls_info().unwrap().clone();
+    assert_eq!(info.protocol.as_deref(), Some("TLSv1_3"));
+    assert!(info.cipher_suite.as_deref().unwrap().starts_with("TLS13_"));
+
+    let leaf = info.leaf().unwrap();
+    assert_eq!(leaf.subject, "CN=localhost");
+    assert_eq!(leaf.issuer, "CN=mailux test CA");
+    assert_eq!(leaf.subject_alt_names, ["localhost"]);
+    assert_eq!(leaf.not_before, 1791973036);
+    assert_eq!(leaf.not_after, 4945573036);
+
+    // Kept across login.
+    let session = client.login("user", "pass").await.unwrap();
+    assert_eq!(session.tls_info().unwrap().leaf(), Some(leaf));
+}
+
+#[tokio::test]
+async fn is_absent_without_tls() {
+    let server = MockServer::new(|tag, _| format!("{} OK done\r\n", tag).into_bytes());
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    assert!(client.tls_info().is_none());
+}
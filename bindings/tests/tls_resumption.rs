@@ -0,0 +1,53 @@
+//! TLS session resumption across connections from one connector or TLS configuration.
+
+mod tls;
+
+use bindings::Builder;
+use rustls::client::Resumption;
+
+const CERT: &[u8] = include_bytes!("tls/localhost.der");
+const KEY: &[u8] = include_bytes!("tls/localhost.key.der");
+
+#[tokio::test]
+async fn reconnects_resume_the_session() {
+    let (port, resumed) = tls::serve_recording_resumption(CERT, KEY).await;
+    let connector = Builder::new(&format!("localhost:{}", port))
+        .danger_accept_invalid_certs(true)
+        .build();
+    for _ in 0..2 {
+        let client = connector.clone().connect().await.unwrap();
+        client.logout().await.unwrap();
+    }
+    assert_eq!(*resumed.lock().unwrap(), [false, true]);
+}
+
+#[tokio::test]
+async fn disabled_resumption_forces_full_handshakes() {
+    let (port, resumed) = tls::serve_recording_resumption(CERT, KEY).await;
+    let connector = Builder::new(&format!("localhost:{}", port))
+        .danger_accept_invalid_certs(true)
+        .tls_resumption(Resumption::disabled())
+        .build();
+    for _ in 0..2 {
+        let client = connector.clone().connect().await.unwrap();
+        client.logout().await.unwrap();
+    }
+    assert_eq!(*resumed.lock().unwrap(), [false, false]);
+}
+
+#[tokio::test]
+async fn builders_sharing_a_config_share_sessions() {
+    let (port, resumed) = tls::serve_recording_resumption(CERT, KEY).await;
+    let addr = format!("localhost:{}", port);
+    let shared = tls::trusting_ca();
+    for config in [shared.clone(), shared, tls::trusting_ca()] {
+        let client = Builder::new(&addr)
+            .tls_config(config)
+            .connect()
+            .await
+            .unwrap();
+        client.logout().await.unwrap();
+    }
+    // The third builder has a configuration, and a session cache, of its own.
+    assert_eq!(*resumed.lock().unwrap(), [false, true, false]);
+}
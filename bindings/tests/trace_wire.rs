@@ -0,0 +1,84 @@
+//! The `trace-wire` feature's protocol log.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use tracing::Level;
+
+use imap::commands::redact;
+
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn lines_are_logged_without_credentials() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(Level::TRACE)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let server = MockServer::new(|tag, cmd| {
+        let body = if cmd.starts_with("LIST") {
+            "* LIST () \"/\" INBOX\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("alice", "hunter2").await.unwrap();
+    session.list("", "*").await.unwrap();
+    session.logout().await.unwrap();
+
+    let log = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    let wire: Vec<_> = log
+        .lines()
+        .filter(|l| l.contains("mailux_imap::wire"))
+        .collect();
+    assert!(
+        wire.iter()
+            .any(|l| l.ends_with("S: * OK IMAP4rev1 mock server ready"))
+    );
+    assert!(wire.iter().any(|l| l.ends_with("LOGIN <redacted>")));
+    assert!(wire.iter().any(|l| l.ends_with("LIST \"\" \"*\"")));
+    assert!(wire.iter().any(|l| l.ends_with("S: * LIST () \"/\" INBOX")));
+    assert!(!log.contains("hunter2"), "{}", log);
+}
+
+#[test]
+fn redact_hides_login_and_sasl_arguments() {
+    assert_eq!(
+        redact("a1 LOGIN alice \"hunter2\"\r\n"),
+        "a1 LOGIN <redacted>\r\n"
+    );
+    assert_eq!(
+        redact("a2 AUTHENTICATE XOAUTH2 dXNlcj1hbGljZQ==\r\n"),
+        "a2 AUTHENTICATE XOAUTH2 <redacted>\r\n"
+    );
+    assert_eq!(
+        redact("a3 AUTHENTICATE PLAIN\r\n"),
+        "a3 AUTHENTICATE PLAIN\r\n"
+    );
+    assert_eq!(redact("a4 SELECT INBOX\r\n"), "a4 SELECT INBOX\r\n");
+}
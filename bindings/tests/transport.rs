@@ -0,0 +1,80 @@
+//! Running the client over transports the connector did not open.
+#![cfg(unix)]
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bindings::Builder;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+/// Serves IMAP on a Unix socket, answering every command with OK; counts connections.
+fn unix_server(path: &std::path::Path) -> Arc<AtomicUsize> {
+    let _ = std::fs::remove_file(path);
+    let listener = UnixListener::bind(path).unwrap();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = accepted.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            counter.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                let (read, mut write) = tokio::io::split(stream);
+                let mut reader = BufReader::new(read);
+                write.write_all(b"* OK unix socket ready\r\n").await?;
+                let mut line = String::new();
+                while reader.read_line(&mut line).await? > 0 {
+                    let tag = line.split(' ').next().unwrap_or("*").to_string();
+                    let reply = if line.contains("LOGOUT") {
+                        format!("* BYE\r\n{} OK done\r\n", tag)
+                    } else {
+                        format!("{} OK done\r\n", tag)
+                    };
+                    write.write_all(reply.as_bytes()).await?;
+                    line.clear();
+                }
+                Ok::<_, std::io::Error>(())
+            });
+        }
+    });
+    accepted
+}
+
+fn socket_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("mailux-{}-{}.sock", name, std::process::id()))
+}
+
+#[tokio::test]
+async fn connects_over_a_unix_socket() {
+    let path = socket_path("stream");
+    unix_server(&path);
+
+    let stream = UnixStream::connect(&path).await.unwrap();
+    let client = Builder::new("localhost:143")
+        .build()
+        .connect_stream(stream)
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    session.logout().await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn dials_through_a_callback() {
+    let path = socket_path("dial");
+    let accepted = unix_server(&path);
+
+    let dial_path = path.clone();
+    let client = Builder::new("localhost:143")
+        .build()
+        .connect_with(move || {
+            let path = dial_path.clone();
+            async move { Ok(UnixStream::connect(path).await?) }
+        })
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    session.logout().await.unwrap();
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,101 @@
+//! UIDPLUS (RFC 4315): the UIDs assigned by APPEND, COPY and MOVE, and UID EXPUNGE.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::common::{Seq, Uid};
+
+fn server(capabilities: &'static str) -> MockServer {
+    let pending = Arc::new(Mutex::new(None::<String>));
+    MockServer::new(move |tag, cmd| {
+        let mut pending = pending.lock().unwrap();
+        if let Some(append_tag) = pending.take() {
+            // The message literal, on a line of its own.
+            return format!(
+                "{} OK [APPENDUID 38505 3955] APPEND completed\r\n",
+                append_tag
+            )
+            .into_bytes();
+        }
+        let reply = match cmd.split(' ').next().unwrap() {
+            "CAPABILITY" => format!("* CAPABILITY {}\r\n{} OK done\r\n", capabilities, tag),
+            "APPEND" => {
+                *pending = Some(tag.to_string());
+                "+ Ready\r\n".to_string()
+            }
+            "COPY" => format!("{} OK [COPYUID 38505 304,319:320 3956:3958] Done\r\n", tag),
+            // MOVE reports the mapping in an untagged OK before the expunges (RFC 6851).
+            "MOVE" => format!(
+                "* OK [COPYUID 38505 7 3959] Moved\r\n* 2 EXPUNGE\r\n{} OK Done\r\n",
+                tag
+            ),
+            "UID" if cmd == "UID EXPUNGE 3:4" => {
+                format!("* 3 EXPUNGE\r\n* 3 EXPUNGE\r\n{} OK Done\r\n", tag)
+            }
+            _ => format!("{} OK done\r\n", tag),
+        };
+        reply.into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn append_returns_the_new_uid() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1 UIDPLUS").spawn())
+        .await
+        .unwrap();
+    let mut session = client.login("user", "pass").await.unwrap();
+    let uid = session
+        .append("INBOX", Vec::new(), None, b"hello")
+        .await
+        .unwrap();
+    assert_eq!(uid, Some(Uid(3955)));
+}
+
+#[tokio::test]
+async fn copy_and_move_return_the_mapping() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1 UIDPLUS MOVE").spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let copied = session.copy(1..=3, "Archive").await.unwrap().unwrap();
+    assert_eq!(copied.uid_validity, 38505);
+    assert_eq!(copied.source, [Uid(304), Uid(319), Uid(320)]);
+    assert_eq!(copied.destination, [Uid(3956), Uid(3957), Uid(3958)]);
+    assert_eq!(copied.destination_of(Uid(319)), Some(Uid(3957)));
+    assert_eq!(copied.destination_of(Uid(5)), None);
+
+    let moved = session.mv(2, "Archive").await.unwrap().unwrap();
+    assert_eq!(moved.source, [Uid(7)]);
+    assert_eq!(moved.destination, [Uid(3959)]);
+}
+
+#[tokio::test]
+async fn uid_expunge_removes_only_the_given_uids() {
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1 UIDPLUS").spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    let expunged = session.uid_expunge(3..=4).await.unwrap();
+    assert_eq!(expunged, [Seq(3), Seq(3)]);
+
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1").spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+    let err = session.uid_expunge(3..=4).await.unwrap_err();
+    assert!(err.to_string().contains("UIDPLUS"));
+}
@@ -0,0 +1,92 @@
+//! Server data this crate does not interpret is handed back as received, not dropped.
+
+use std::time::Duration;
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+use bytes::Bytes;
+use tokio_stream::StreamExt;
+
+use imap::commands::FetchItem;
+use imap::types::command::SequenceSet;
+use imap::types::common::Uid;
+use imap::types::response::{FetchData, IdleEvent};
+
+#[tokio::test]
+async fn unknown_fetch_items_are_kept_raw() {
+    let server = MockServer::new(move |tag, cmd| {
+        let body = if cmd.starts_with("SELECT ") {
+            "* 1 EXISTS\r\n"
+        } else if cmd.starts_with("FETCH ") {
+            "* 1 FETCH (X-GM-MSGID 1278455344230334865 X-GM-LABELS (\\Inbox \"Work\") UID 4)\r\n"
+        } else {
+            ""
+        };
+        format!("{}{} OK done\r\n", body, tag).into_bytes()
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let fetched = session
+        .fetch_items(SequenceSet::new().add_single(1), vec![FetchItem::Uid])
+        .await
+        .unwrap();
+    let data = &fetched[0].1;
+    assert_eq!(data.len(), 3);
+    assert!(matches!(
+        &data[0],
+        FetchData::Other { name, value } if name == "X-GM-MSGID" && &value[..] == b"1278455344230334865"
+    ));
+    assert!(matches!(
+        &data[1],
+        FetchData::Other { name, value } if name == "X-GM-LABELS" && &value[..] == b"(\\Inbox \"Work\")"
+    ));
+    assert!(matches!(data[2], FetchData::Uid(Uid(4))));
+}
+
+#[tokio::test]
+async fn unknown_untagged_data_while_idling_is_reported() {
+    let server = MockServer::new(move |tag, cmd| match cmd {
+        "IDLE" => concat!(
+            "+ idling\r\n",
+            "* OK Still here\r\n",
+            "* 4 FETCH (MODSEQ (12))\r\n",
+            "* XSTATE busy\r\n",
+            "* 2 EXISTS\r\n",
+        )
+        .as_bytes()
+        .to_vec(),
+        _ => format!("{} OK done\r\n", tag).into_bytes(),
+    });
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server.spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let mut idle = session.idle().await.unwrap();
+    let mut events = Vec::new();
+    while events.len() < 3 {
+        let event = tokio::time::timeout(Duration::from_secs(2), idle.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        events.push(event);
+    }
+    assert_eq!(
+        events,
+        [
+            IdleEvent::Other(Bytes::from_static(b"4 FETCH (MODSEQ (12))")),
+            IdleEvent::Other(Bytes::from_static(b"XSTATE busy")),
+            IdleEvent::Exists(2),
+        ]
+    );
+}
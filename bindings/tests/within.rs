@@ -0,0 +1,82 @@
+//! WITHIN (RFC 5032): OLDER and YOUNGER search keys.
+
+use std::sync::{Arc, Mutex};
+
+use bindings::Builder;
+use bindings::test_util::MockServer;
+
+use imap::types::command::SearchKey;
+use imap::types::common::Uid;
+
+fn server(capabilities: &'static str, received: Arc<Mutex<Vec<String>>>) -> MockServer {
+    MockServer::new(move |tag, cmd| {
+        received.lock().unwrap().push(cmd.to_string());
+        let verb = cmd.split(' ').next().unwrap_or("").to_ascii_uppercase();
+        let body = match verb.as_str() {
+            "LOGIN" => String::new(),
+            "CAPABILITY" => format!("* CAPABILITY {}\r\n", capabilities),
+            "SELECT" => "* 3 EXISTS\r\n".to_string(),
+            "UID" => "* SEARCH 4 9\r\n".to_string(),
+            _ => return format!("{} BAD unknown\r\n", tag).into_bytes(),
+        };
+        format!("{}{} OK {} completed\r\n", body, tag, verb).into_bytes()
+    })
+}
+
+#[tokio::test]
+async fn younger_and_older_are_sent() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1 WITHIN", received.clone()).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let uids = session
+        .uid_search(vec![
+            SearchKey::Younger(3600),
+            SearchKey::Not(Box::new(SearchKey::Older(60))),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(uids, vec![Uid(4), Uid(9)]);
+    assert!(
+        received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c == "UID SEARCH YOUNGER 3600 NOT (OLDER 60)")
+    );
+}
+
+#[tokio::test]
+async fn within_requires_the_capability() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let client = Builder::new("mock:143")
+        .build()
+        .connect_stream(server("IMAP4rev1", received.clone()).spawn())
+        .await
+        .unwrap();
+    let session = client.login("user", "pass").await.unwrap();
+    let (mut session, _) = session.select("INBOX").await.unwrap();
+
+    let key = SearchKey::Or(
+        Box::new(SearchKey::Seen),
+        Box::new(SearchKey::Younger(3600)),
+    );
+    let err = session.uid_search(vec![key]).await.unwrap_err();
+    assert!(err.to_string().contains("WITHIN"));
+    assert!(
+        !received
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|c| c.starts_with("UID SEARCH"))
+    );
+
+    // Plain keys still work without WITHIN.
+    let uids = session.uid_search(vec![SearchKey::Seen]).await.unwrap();
+    assert_eq!(uids, vec![Uid(4), Uid(9)]);
+}
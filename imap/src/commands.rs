@@ -1,7 +1,12 @@
 use crate::format::quote_astring;
-use crate::types::command::{SearchKey, SequenceSet, StatusItem};
-use crate::types::common::Flag;
+use crate::types::command::{
+    AclChange, CatenatePart, NotifyEvent, NotifyMailboxes, SearchKey, SearchReturn, SequenceSet,
+    SortKey, StatusItem, render_search_keys,
+};
+use crate::types::common::{DateTime, Flag};
+use std::borrow::Cow;
 use std::fmt::{self, Display, Write};
+use std::ops::Range;
 
 fn join_paren_space<T: Display>(items: &[T]) -> String {
     let mut s = String::from("(");
@@ -18,6 +23,26 @@ fn join_paren_space<T: Display>(items: &[T]) -> String {
     s
 }
 
+/// `command` as it may be logged: the arguments of LOGIN and the initial response of
+/// AUTHENTICATE are replaced by `<redacted>`. Other commands are returned unchanged.
+pub fn redact(command: &str) -> Cow<'_, str> {
+    let line = command.trim_end_matches(['\r', '\n']);
+    let ending = &command[line.len()..];
+    let mut words = line.splitn(3, ' ');
+    let (Some(tag), Some(name), Some(rest)) = (words.next(), words.next(), words.next()) else {
+        return Cow::Borrowed(command);
+    };
+    if name.eq_ignore_ascii_case("LOGIN") {
+        return Cow::Owned(format!("{} {} <redacted>{}", tag, name, ending));
+    }
+    if name.eq_ignore_ascii_case("AUTHENTICATE")
+        && let Some((mechanism, _)) = rest.split_once(' ')
+    {
+        return Cow::Owned(format!("{} {} {} <redacted>{}", tag, name, mechanism, ending));
+    }
+    Cow::Borrowed(command)
+}
+
 #[derive(Debug, Clone)]
 pub enum FetchItem {
     All,
@@ -25,8 +50,23 @@ pub enum FetchItem {
     Full,
     Body,
     BodyPeek,
+    BodyStructure,
     BodySection(String),
     BodyPeekSection(String),
+    /// `BODY[section]<offset.count>`: `count` bytes of the section from `offset` on.
+    BodyPartial {
+        section: String,
+        offset: u32,
+        count: u32,
+    },
+    /// `BODY.PEEK[section]<offset.count>`.
+    BodyPeekPartial {
+        section: String,
+        offset: u32,
+        count: u32,
+    },
+    Binary(String),
+    BinaryPeek(String),
     Envelope,
     Flags,
     InternalDate,
@@ -37,6 +77,55 @@ pub enum FetchItem {
     Uid,
 }
 
+impl FetchItem {
+    /// `BODY[section]`, which sets `\Seen` on the message.
+    pub fn body(section: Section) -> Self {
+        FetchItem::BodySection(section.to_string())
+    }
+
+    /// `BODY.PEEK[section]`, which leaves `\Seen` alone.
+    pub fn body_peek(section: Section) -> Self {
+        FetchItem::BodyPeekSection(section.to_string())
+    }
+
+    /// `BODY[section]<offset.count>` for the bytes in `range`, e.g. to download a large
+    /// attachment in resumable chunks. Sets `\Seen` on the message.
+    pub fn body_range(section: Section, range: Range<u32>) -> Self {
+        FetchItem::BodyPartial {
+            section: section.to_string(),
+            offset: range.start,
+            count: range.end.saturating_sub(range.start),
+        }
+    }
+
+    /// Like [`FetchItem::body_range`], with `BODY.PEEK`, which leaves `\Seen` alone.
+    pub fn body_peek_range(section: Section, range: Range<u32>) -> Self {
+        FetchItem::body_range(section, range).peek()
+    }
+
+    /// The form of this item that leaves `\Seen` alone: body sections become `BODY.PEEK`
+    /// and `BINARY.PEEK`, and `RFC822` / `RFC822.TEXT` become `BODY.PEEK[]` /
+    /// `BODY.PEEK[TEXT]` (so their data is returned as a body section).
+    pub fn peek(self) -> Self {
+        match self {
+            FetchItem::BodySection(sec) => FetchItem::BodyPeekSection(sec),
+            FetchItem::BodyPartial {
+                section,
+                offset,
+                count,
+            } => FetchItem::BodyPeekPartial {
+                section,
+                offset,
+                count,
+            },
+            FetchItem::Binary(sec) => FetchItem::BinaryPeek(sec),
+            FetchItem::Rfc822 => FetchItem::BodyPeekSection(String::new()),
+            FetchItem::Rfc822Text => FetchItem::BodyPeekSection("TEXT".to_string()),
+            item => item,
+        }
+    }
+}
+
 impl Display for FetchItem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -45,8 +134,21 @@ impl Display for FetchItem {
             FetchItem::Full => f.write_str("FULL"),
             FetchItem::Body => f.write_str("BODY"),
             FetchItem::BodyPeek => f.write_str("BODY.PEEK"),
+            FetchItem::BodyStructure => f.write_str("BODYSTRUCTURE"),
             FetchItem::BodySection(sec) => write!(f, "BODY[{}]", sec),
             FetchItem::BodyPeekSection(sec) => write!(f, "BODY.PEEK[{}]", sec),
+            FetchItem::BodyPartial {
+                section,
+                offset,
+                count,
+            } => write!(f, "BODY[{}]<{}.{}>", section, offset, count),
+            FetchItem::BodyPeekPartial {
+                section,
+                offset,
+                count,
+            } => write!(f, "BODY.PEEK[{}]<{}.{}>", section, offset, count),
+            FetchItem::Binary(sec) => write!(f, "BINARY[{}]", sec),
+            FetchItem::BinaryPeek(sec) => write!(f, "BINARY.PEEK[{}]", sec),
             FetchItem::Envelope => f.write_str("ENVELOPE"),
             FetchItem::Flags => f.write_str("FLAGS"),
             FetchItem::InternalDate => f.write_str("INTERNALDATE"),
@@ -59,18 +161,135 @@ impl Display for FetchItem {
     }
 }
 
-fn join_search_keys(keys: &[SearchKey]) -> String {
-    let mut s = String::new();
-    let mut first = true;
-    for k in keys {
-        if !first {
-            s.push(' ');
-        } else {
-            first = false;
+/// A body section specification for `BODY[...]` (RFC 3501 section 6.4.5), e.g. `HEADER`,
+/// `1.2.TEXT` or `HEADER.FIELDS (From Subject)`.
+///
+/// Start from [`Section::full`] for the whole message or [`Section::part`] for a MIME part,
+/// then optionally narrow it down to headers or text: `Section::part(&[1, 2]).text()` is
+/// `1.2.TEXT`, `Section::full().header_fields(&["From"])` is `HEADER.FIELDS (From)`. Use
+/// [`FetchItem::body`] or [`FetchItem::body_peek`] to fetch it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Section {
+    /// MIME part numbers, outermost first; empty for the whole message.
+    part: Vec<u32>,
+    text: Option<SectionText>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SectionText {
+    Header,
+    HeaderFields(Vec<String>),
+    HeaderFieldsNot(Vec<String>),
+    Text,
+    Mime,
+}
+
+impl Section {
+    /// The whole message: `BODY[]`.
+    pub fn full() -> Self {
+        Self::default()
+    }
+
+    /// The MIME part at `path`, e.g. `&[1, 2]` for `1.2`. An empty path is the whole
+    /// message.
+    pub fn part(path: &[u32]) -> Self {
+        Self {
+            part: path.to_vec(),
+            text: None,
+        }
+    }
+
+    /// The MIME part at a dotted path such as `1.2`, as [`mime`](crate::mime) returns
+    /// them; `None` unless every component is a number. An empty path is the whole message.
+    pub fn part_at(path: &str) -> Option<Self> {
+        if path.is_empty() {
+            return Some(Self::full());
+        }
+        let part = path
+            .split('.')
+            .map(|n| n.parse().ok())
+            .collect::<Option<Vec<u32>>>()?;
+        Some(Self::part(&part))
+    }
+
+    /// Only the header (`HEADER`). For a part, that is the header of the message it
+    /// encapsulates, if it is a `message/rfc822` part.
+    pub fn header(self) -> Self {
+        self.with(SectionText::Header)
+    }
+
+    /// Only the named header fields (`HEADER.FIELDS (...)`).
+    pub fn header_fields(self, fields: &[&str]) -> Self {
+        self.with(SectionText::HeaderFields(owned(fields)))
+    }
+
+    /// The header without the named fields (`HEADER.FIELDS.NOT (...)`).
+    pub fn header_fields_not(self, fields: &[&str]) -> Self {
+        self.with(SectionText::HeaderFieldsNot(owned(fields)))
+    }
+
+    /// Only the body, without the header (`TEXT`).
+    pub fn text(self) -> Self {
+        self.with(SectionText::Text)
+    }
+
+    /// The MIME header of a part (`MIME`). Only valid with a part number.
+    pub fn mime(self) -> Self {
+        self.with(SectionText::Mime)
+    }
+
+    fn with(mut self, text: SectionText) -> Self {
+        self.text = Some(text);
+        self
+    }
+}
+
+fn owned(fields: &[&str]) -> Vec<String> {
+    fields.iter().map(|f| f.to_string()).collect()
+}
+
+/// A header field name as an astring: bare if it is an atom, quoted otherwise.
+fn header_field_name(name: &str) -> String {
+    let atom = !name.is_empty()
+        && name.bytes().all(|b| {
+            b.is_ascii_graphic()
+                && !matches!(b, b'(' | b')' | b'{' | b'%' | b'*' | b'"' | b'\\' | b']')
+        });
+    if atom {
+        name.to_string()
+    } else {
+        quote_astring(name)
+    }
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, n) in self.part.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", n)?;
+        }
+        let Some(text) = &self.text else {
+            return Ok(());
+        };
+        if !self.part.is_empty() {
+            f.write_str(".")?;
+        }
+        let fields = |names: &[String]| {
+            let names: Vec<String> = names.iter().map(|n| header_field_name(n)).collect();
+            join_paren_space(&names)
+        };
+        match text {
+            SectionText::Header => f.write_str("HEADER"),
+            SectionText::HeaderFields(names) => write!(f, "HEADER.FIELDS {}", fields(names)),
+            SectionText::HeaderFieldsNot(names) => {
+                write!(f, "HEADER.FIELDS.NOT {}", fields(names))
+            }
+            SectionText::Text => f.write_str("TEXT"),
+            SectionText::Mime => f.write_str("MIME"),
         }
-        let _ = write!(&mut s, "{}", k);
     }
-    s
 }
 
 pub struct CommandBuilder {
@@ -97,6 +316,21 @@ impl CommandBuilder {
     pub fn starttls(self) -> SimpleCommand {
         SimpleCommand::new(self.tag, "STARTTLS")
     }
+    pub fn idle(self) -> SimpleCommand {
+        SimpleCommand::new(self.tag, "IDLE")
+    }
+    pub fn notify_set(self) -> NotifyCommandBuilder {
+        NotifyCommandBuilder::new(self.tag)
+    }
+    pub fn notify_none(self) -> SimpleCommand {
+        SimpleCommand::new(self.tag, "NOTIFY NONE")
+    }
+    pub fn id(self, fields: &[(&str, &str)]) -> IdCommand {
+        IdCommand::new(self.tag, fields)
+    }
+    pub fn enable(self, extensions: &[&str]) -> SimpleWithArg {
+        SimpleWithArg::new(self.tag, "ENABLE", &extensions.join(" "))
+    }
 
     // Auth
     pub fn authenticate(self, mechanism: &str) -> SimpleWithArg {
@@ -140,16 +374,67 @@ impl CommandBuilder {
         StatusCommand::new(self.tag, mailbox, items)
     }
 
+    // Access control (RFC 4314)
+    pub fn setacl(self, mailbox: &str, identifier: &str, change: AclChange) -> SimpleWithArg {
+        let arg = format!(
+            "{} {} {}",
+            quote_astring(mailbox),
+            quote_astring(identifier),
+            quote_astring(&change.to_string())
+        );
+        SimpleWithArg::new(self.tag, "SETACL", &arg)
+    }
+    pub fn deleteacl(self, mailbox: &str, identifier: &str) -> SimpleWithArg {
+        let arg = format!("{} {}", quote_astring(mailbox), quote_astring(identifier));
+        SimpleWithArg::new(self.tag, "DELETEACL", &arg)
+    }
+    pub fn getacl(self, mailbox: &str) -> MailboxCommand {
+        MailboxCommand::new(self.tag, "GETACL", mailbox)
+    }
+    pub fn listrights(self, mailbox: &str, identifier: &str) -> SimpleWithArg {
+        let arg = format!("{} {}", quote_astring(mailbox), quote_astring(identifier));
+        SimpleWithArg::new(self.tag, "LISTRIGHTS", &arg)
+    }
+    pub fn myrights(self, mailbox: &str) -> MailboxCommand {
+        MailboxCommand::new(self.tag, "MYRIGHTS", mailbox)
+    }
+
+    // Quota (RFC 9208)
+    pub fn getquota(self, root: &str) -> SimpleWithArg {
+        SimpleWithArg::new(self.tag, "GETQUOTA", &quote_astring(root))
+    }
+    pub fn getquotaroot(self, mailbox: &str) -> MailboxCommand {
+        MailboxCommand::new(self.tag, "GETQUOTAROOT", mailbox)
+    }
+    /// `SETQUOTA root (STORAGE 512 ...)`; an empty list removes all limits.
+    pub fn setquota(self, root: &str, limits: &[(&str, u64)]) -> SimpleWithArg {
+        let limits: Vec<String> = limits
+            .iter()
+            .map(|(resource, limit)| format!("{} {}", resource, limit))
+            .collect();
+        let arg = format!("{} ({})", quote_astring(root), limits.join(" "));
+        SimpleWithArg::new(self.tag, "SETQUOTA", &arg)
+    }
+
     // Message ops
     pub fn append(self, mailbox: &str) -> AppendCommandBuilder {
         AppendCommandBuilder::new(self.tag, mailbox)
     }
+    pub fn multiappend(self, mailbox: &str) -> MultiAppendCommandBuilder {
+        MultiAppendCommandBuilder::new(self.tag, mailbox)
+    }
+    pub fn catenate(self, mailbox: &str) -> CatenateCommandBuilder {
+        CatenateCommandBuilder::new(self.tag, mailbox)
+    }
     pub fn check(self) -> SimpleCommand {
         SimpleCommand::new(self.tag, "CHECK")
     }
     pub fn close(self) -> SimpleCommand {
         SimpleCommand::new(self.tag, "CLOSE")
     }
+    pub fn unselect(self) -> SimpleCommand {
+        SimpleCommand::new(self.tag, "UNSELECT")
+    }
     pub fn expunge(self) -> SimpleCommand {
         SimpleCommand::new(self.tag, "EXPUNGE")
     }
@@ -157,6 +442,12 @@ impl CommandBuilder {
     pub fn search(self) -> SearchCommandBuilder {
         SearchCommandBuilder::new(self.tag, None)
     }
+    pub fn sort(self, criteria: Vec<SortKey>) -> SortCommandBuilder {
+        SortCommandBuilder::new(self.tag, false, criteria)
+    }
+    pub fn comparator(self, comparators: &[&str]) -> ComparatorCommand {
+        ComparatorCommand::new(self.tag, comparators)
+    }
     pub fn fetch(self, set: SequenceSet) -> FetchCommandBuilder {
         FetchCommandBuilder::new(self.tag, false, set)
     }
@@ -166,6 +457,9 @@ impl CommandBuilder {
     pub fn copy(self, set: SequenceSet, mailbox: &str) -> CopyCommand {
         CopyCommand::new(self.tag, false, set, mailbox)
     }
+    pub fn mv(self, set: SequenceSet, mailbox: &str) -> MoveCommand {
+        MoveCommand::new(self.tag, false, set, mailbox)
+    }
 
     // UID scope
     pub fn uid(self) -> UidScope {
@@ -204,10 +498,39 @@ impl SimpleWithArg {
     }
 }
 
+pub struct IdCommand {
+    tag: String,
+    fields: Vec<(String, String)>,
+}
+impl IdCommand {
+    fn new(tag: String, fields: &[(&str, &str)]) -> Self {
+        Self {
+            tag,
+            fields: fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+    /// `ID ("name" "value" ...)`, or `ID NIL` without fields.
+    pub fn as_string(&self) -> String {
+        if self.fields.is_empty() {
+            return format!("{} ID NIL\r\n", self.tag);
+        }
+        let pairs: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(k, v)| format!("{} {}", quote_astring(k), quote_astring(v)))
+            .collect();
+        format!("{} ID ({})\r\n", self.tag, pairs.join(" "))
+    }
+}
+
 pub struct MailboxCommand {
     tag: String,
     name: &'static str,
     mailbox: String,
+    qresync: Option<String>,
 }
 impl MailboxCommand {
     fn new(tag: String, name: &'static str, mailbox: &str) -> Self {
@@ -215,15 +538,40 @@ impl MailboxCommand {
             tag,
             name,
             mailbox: mailbox.to_string(),
+            qresync: None,
         }
     }
+    /// Adds the QRESYNC parameter (RFC 7162) to a SELECT or EXAMINE, with the state from
+    /// the last sync and optionally the UIDs known to the client.
+    pub fn qresync(
+        mut self,
+        uid_validity: u32,
+        modseq: u64,
+        known_uids: Option<SequenceSet>,
+    ) -> Self {
+        let mut param = format!("{} {}", uid_validity, modseq);
+        if let Some(uids) = known_uids {
+            let _ = write!(&mut param, " {}", uids);
+        }
+        self.qresync = Some(param);
+        self
+    }
     pub fn as_string(&self) -> String {
-        format!(
-            "{} {} {}\r\n",
-            self.tag,
-            self.name,
-            quote_astring(&self.mailbox)
-        )
+        match &self.qresync {
+            Some(param) => format!(
+                "{} {} {} (QRESYNC ({}))\r\n",
+                self.tag,
+                self.name,
+                quote_astring(&self.mailbox),
+                param
+            ),
+            None => format!(
+                "{} {} {}\r\n",
+                self.tag,
+                self.name,
+                quote_astring(&self.mailbox)
+            ),
+        }
     }
 }
 
@@ -255,6 +603,8 @@ pub struct ListCommand {
     name: &'static str,
     reference: String,
     pattern: String,
+    selection: Vec<String>,
+    returns: Vec<String>,
 }
 impl ListCommand {
     fn new(tag: String, name: &'static str, reference: &str, pattern: &str) -> Self {
@@ -263,16 +613,42 @@ impl ListCommand {
             name,
             reference: reference.to_string(),
             pattern: pattern.to_string(),
+            selection: Vec::new(),
+            returns: Vec::new(),
         }
     }
+    /// Adds a LIST-EXTENDED selection option (RFC 5258), e.g. `SUBSCRIBED`.
+    pub fn select_option(mut self, option: &str) -> Self {
+        self.selection.push(option.to_string());
+        self
+    }
+    /// Adds a LIST-EXTENDED return option (RFC 5258), e.g. `SUBSCRIBED` or `CHILDREN`.
+    pub fn return_option(mut self, option: &str) -> Self {
+        self.returns.push(option.to_string());
+        self
+    }
+    /// Asks for a `* STATUS` response per listed mailbox (LIST-STATUS, RFC 5819).
+    pub fn return_status(mut self, items: &[StatusItem]) -> Self {
+        self.returns
+            .push(format!("STATUS {}", join_paren_space(items)));
+        self
+    }
     pub fn as_string(&self) -> String {
-        format!(
-            "{} {} {} {}\r\n",
-            self.tag,
-            self.name,
+        let mut s = format!("{} {} ", self.tag, self.name);
+        if !self.selection.is_empty() {
+            let _ = write!(&mut s, "({}) ", self.selection.join(" "));
+        }
+        let _ = write!(
+            &mut s,
+            "{} {}",
             quote_astring(&self.reference),
             quote_astring(&self.pattern)
-        )
+        );
+        if !self.returns.is_empty() {
+            let _ = write!(&mut s, " RETURN ({})", self.returns.join(" "));
+        }
+        s.push_str("\r\n");
+        s
     }
 }
 
@@ -303,9 +679,11 @@ pub struct AppendCommandBuilder {
     tag: String,
     mailbox: String,
     flags: Vec<Flag>,
-    internal_date: Option<String>,
+    internal_date: Option<DateTime>,
     literal_len: Option<usize>,
     literal: Option<Vec<u8>>,
+    literal_plus: bool,
+    binary: bool,
 }
 impl AppendCommandBuilder {
     fn new(tag: String, mailbox: &str) -> Self {
@@ -316,14 +694,29 @@ impl AppendCommandBuilder {
             internal_date: None,
             literal_len: None,
             literal: None,
+            literal_plus: false,
+            binary: false,
         }
     }
+    /// Announce the literal as non-synchronizing (`{n+}`, RFC 7888), so it can be sent
+    /// without waiting for a continuation request.
+    pub fn literal_plus(mut self) -> Self {
+        self.literal_plus = true;
+        self
+    }
+    /// Announce the literal as `literal8` (`~{n}`, RFC 3516), which may contain NUL and
+    /// bare CR/LF, so 8-bit and binary parts need no transfer encoding.
+    pub fn binary(mut self) -> Self {
+        self.binary = true;
+        self
+    }
     pub fn flags(mut self, flags: Vec<Flag>) -> Self {
         self.flags = flags;
         self
     }
-    pub fn internal_date(mut self, rfc822_date_time: &str) -> Self {
-        self.internal_date = Some(rfc822_date_time.to_string());
+    /// The INTERNALDATE to give the message, sent as `"17-Jul-1996 02:44:25 -0700"`.
+    pub fn internal_date(mut self, date_time: DateTime) -> Self {
+        self.internal_date = Some(date_time);
         self
     }
     pub fn literal(mut self, bytes: Vec<u8>) -> Self {
@@ -344,10 +737,12 @@ impl AppendCommandBuilder {
             s.push_str(&join_paren_space(&self.flags));
         }
         if let Some(date) = &self.internal_date {
-            let _ = write!(&mut s, " {}", quote_astring(date));
+            let _ = write!(&mut s, " \"{}\"", date);
         }
         if let Some(n) = self.literal_len {
-            let _ = write!(&mut s, " {{{}}}\r\n", n);
+            let plus = if self.literal_plus { "+" } else { "" };
+            let tilde = if self.binary { "~" } else { "" };
+            let _ = write!(&mut s, " {}{{{}{}}}\r\n", tilde, n, plus);
         } else {
             s.push_str("\r\n");
         }
@@ -358,11 +753,232 @@ impl AppendCommandBuilder {
     }
 }
 
+/// MULTIAPPEND (RFC 3502). Every message after the first is announced inside the
+/// first literal's continuation, so literals are always non-synchronizing (LITERAL+).
+pub struct MultiAppendCommandBuilder {
+    tag: String,
+    mailbox: String,
+    messages: Vec<(Vec<Flag>, Option<DateTime>, Vec<u8>)>,
+}
+impl MultiAppendCommandBuilder {
+    fn new(tag: String, mailbox: &str) -> Self {
+        Self {
+            tag,
+            mailbox: mailbox.to_string(),
+            messages: Vec::new(),
+        }
+    }
+    pub fn message(mut self, flags: Vec<Flag>, internal_date: Option<DateTime>, body: Vec<u8>) -> Self {
+        self.messages.push((flags, internal_date, body));
+        self
+    }
+    /// The command line, up to and including the first literal announcement.
+    pub fn as_string(&self) -> String {
+        let mut s = format!("{} APPEND {}", self.tag, quote_astring(&self.mailbox));
+        match self.messages.first() {
+            Some(first) => push_append_message(&mut s, first),
+            None => s.push_str("\r\n"),
+        }
+        s
+    }
+    /// Everything after [`as_string`](Self::as_string) except the final CRLF.
+    pub fn literal_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (i, message) in self.messages.iter().enumerate() {
+            if i > 0 {
+                let mut s = String::new();
+                push_append_message(&mut s, message);
+                out.extend_from_slice(s.as_bytes());
+            }
+            out.extend_from_slice(&message.2);
+        }
+        out
+    }
+}
+
+/// NOTIFY SET (RFC 5465).
+pub struct NotifyCommandBuilder {
+    tag: String,
+    status: bool,
+    groups: Vec<(NotifyMailboxes, Vec<NotifyEvent>)>,
+}
+impl NotifyCommandBuilder {
+    fn new(tag: String) -> Self {
+        Self {
+            tag,
+            status: false,
+            groups: Vec::new(),
+        }
+    }
+    /// Ask for the STATUS of every mailbox in a group right away (the `STATUS` option).
+    pub fn status(mut self) -> Self {
+        self.status = true;
+        self
+    }
+    /// Report `events` for `mailboxes`; no events means `NONE`, turning the group off.
+    pub fn group(mut self, mailboxes: NotifyMailboxes, events: Vec<NotifyEvent>) -> Self {
+        self.groups.push((mailboxes, events));
+        self
+    }
+    pub fn as_string(&self) -> String {
+        let mut s = format!("{} NOTIFY SET", self.tag);
+        if self.status {
+            s.push_str(" STATUS");
+        }
+        for (mailboxes, events) in &self.groups {
+            if events.is_empty() {
+                let _ = write!(s, " ({} NONE)", mailboxes);
+            } else {
+                let _ = write!(s, " ({} {})", mailboxes, join_paren_space(events));
+            }
+        }
+        s.push_str("\r\n");
+        s
+    }
+}
+
+/// APPEND with CATENATE (RFC 4469): the message is assembled by the server from URLs of
+/// existing messages and literal text.
+///
+/// Like MULTIAPPEND, everything from the first `TEXT` literal on is sent as one block
+/// after the command line; without [`literal_plus`](Self::literal_plus) that block may
+/// contain no further `TEXT` parts.
+pub struct CatenateCommandBuilder {
+    tag: String,
+    mailbox: String,
+    flags: Vec<Flag>,
+    internal_date: Option<DateTime>,
+    parts: Vec<CatenatePart>,
+    literal_plus: bool,
+}
+impl CatenateCommandBuilder {
+    fn new(tag: String, mailbox: &str) -> Self {
+        Self {
+            tag,
+            mailbox: mailbox.to_string(),
+            flags: Vec::new(),
+            internal_date: None,
+            parts: Vec::new(),
+            literal_plus: false,
+        }
+    }
+    pub fn literal_plus(mut self) -> Self {
+        self.literal_plus = true;
+        self
+    }
+    pub fn flags(mut self, flags: Vec<Flag>) -> Self {
+        self.flags = flags;
+        self
+    }
+    pub fn internal_date(mut self, date_time: DateTime) -> Self {
+        self.internal_date = Some(date_time);
+        self
+    }
+    pub fn part(mut self, part: CatenatePart) -> Self {
+        self.parts.push(part);
+        self
+    }
+    fn first_text(&self) -> usize {
+        self.parts
+            .iter()
+            .position(|p| matches!(p, CatenatePart::Text(_)))
+            .unwrap_or(self.parts.len())
+    }
+    fn push_part(&self, s: &mut String, part: &CatenatePart) {
+        match part {
+            CatenatePart::Url(url) => {
+                let _ = write!(s, "URL {}", quote_astring(url));
+            }
+            CatenatePart::Text(text) => {
+                let plus = if self.literal_plus { "+" } else { "" };
+                let _ = write!(s, "TEXT {{{}{}}}\r\n", text.len(), plus);
+            }
+        }
+    }
+    /// The command line, up to and including the first `TEXT` literal announcement.
+    pub fn as_string(&self) -> String {
+        let mut s = format!("{} APPEND {}", self.tag, quote_astring(&self.mailbox));
+        if !self.flags.is_empty() {
+            s.push(' ');
+            s.push_str(&join_paren_space(&self.flags));
+        }
+        if let Some(date) = &self.internal_date {
+            let _ = write!(s, " \"{}\"", date);
+        }
+        s.push_str(" CATENATE (");
+        let first_text = self.first_text();
+        let end = (first_text + 1).min(self.parts.len());
+        for (i, part) in self.parts[..end].iter().enumerate() {
+            if i > 0 {
+                s.push(' ');
+            }
+            self.push_part(&mut s, part);
+        }
+        if first_text == self.parts.len() {
+            s.push_str(")\r\n");
+        }
+        s
+    }
+    /// Everything after [`as_string`](Self::as_string) except the final CRLF, or `None`
+    /// without `TEXT` parts.
+    pub fn literal_bytes(&self) -> Option<Vec<u8>> {
+        let first_text = self.first_text();
+        let CatenatePart::Text(text) = self.parts.get(first_text)? else {
+            return None;
+        };
+        let mut out = text.clone();
+        for part in &self.parts[first_text + 1..] {
+            let mut s = String::from(" ");
+            self.push_part(&mut s, part);
+            out.extend_from_slice(s.as_bytes());
+            if let CatenatePart::Text(text) = part {
+                out.extend_from_slice(text);
+            }
+        }
+        out.push(b')');
+        Some(out)
+    }
+}
+
+/// A rendered command cut after its first literal announcement.
+fn command_line((mut s, first_literal): (String, Option<usize>)) -> String {
+    if let Some(i) = first_literal {
+        s.truncate(i);
+    }
+    s
+}
+
+/// What follows [`command_line`]: the literal data and the rest of the command, without the
+/// final CRLF.
+fn literal_rest((s, first_literal): (String, Option<usize>)) -> Option<Vec<u8>> {
+    let i = first_literal?;
+    Some(s.as_bytes()[i..s.len() - 2].to_vec())
+}
+
+fn push_append_message(s: &mut String, (flags, date, body): &(Vec<Flag>, Option<DateTime>, Vec<u8>)) {
+    if !flags.is_empty() {
+        s.push(' ');
+        s.push_str(&join_paren_space(flags));
+    }
+    if let Some(date) = date {
+        let _ = write!(s, " \"{}\"", date);
+    }
+    let _ = write!(s, " {{{}+}}\r\n", body.len());
+}
+
+/// SEARCH. String values with line breaks or non-ASCII text are sent as literals, and
+/// non-ASCII text adds `CHARSET UTF-8` unless a charset is set.
+///
+/// Like CATENATE, everything from the first literal on is sent as one block after the
+/// command line; without [`literal_plus`](Self::literal_plus) that block may contain no
+/// further literals.
 pub struct SearchCommandBuilder {
     tag: String,
     charset: Option<String>,
     keys: Vec<SearchKey>,
     uid: bool,
+    returns: Option<Vec<SearchReturn>>,
+    literal_plus: bool,
 }
 impl SearchCommandBuilder {
     fn new(tag: String, charset: Option<String>) -> Self {
@@ -371,8 +987,20 @@ impl SearchCommandBuilder {
             charset,
             keys: Vec::new(),
             uid: false,
+            returns: None,
+            literal_plus: false,
         }
     }
+    pub fn literal_plus(mut self) -> Self {
+        self.literal_plus = true;
+        self
+    }
+    /// Asks for an `* ESEARCH` reply (RFC 4731) with these results; an empty list means
+    /// `ALL`.
+    pub fn returning(mut self, returns: Vec<SearchReturn>) -> Self {
+        self.returns = Some(returns);
+        self
+    }
     pub fn charset(mut self, charset: &str) -> Self {
         self.charset = Some(charset.to_string());
         self
@@ -385,15 +1013,128 @@ impl SearchCommandBuilder {
         self.keys.extend(keys);
         self
     }
-    pub fn as_string(&self) -> String {
+    fn render(&self) -> (String, Option<usize>) {
         let mut s = String::new();
         let cmd = if self.uid { "UID SEARCH" } else { "SEARCH" };
         let _ = write!(&mut s, "{} {}", self.tag, cmd);
-        if let Some(cs) = &self.charset {
-            let _ = write!(&mut s, " CHARSET {}", cs);
+        if let Some(returns) = &self.returns {
+            let _ = write!(&mut s, " RETURN {}", join_paren_space(returns));
+        }
+        match &self.charset {
+            Some(cs) => {
+                let _ = write!(&mut s, " CHARSET {}", cs);
+            }
+            None if !self.keys.iter().all(SearchKey::is_ascii) => s.push_str(" CHARSET UTF-8"),
+            None => {}
         }
+        let mut first_literal = None;
         if !self.keys.is_empty() {
-            let _ = write!(&mut s, " {}", join_search_keys(&self.keys));
+            s.push(' ');
+            let (keys, first) = render_search_keys(&self.keys, self.literal_plus);
+            first_literal = first.map(|i| s.len() + i);
+            s.push_str(&keys);
+        }
+        s.push_str("\r\n");
+        (s, first_literal)
+    }
+    /// The command line, up to and including the first literal announcement.
+    pub fn as_string(&self) -> String {
+        command_line(self.render())
+    }
+    /// Everything after [`as_string`](Self::as_string) except the final CRLF, or `None`
+    /// without literals.
+    pub fn literal_bytes(&self) -> Option<Vec<u8>> {
+        literal_rest(self.render())
+    }
+}
+
+/// SORT (RFC 5256). String values are sent as for [`SearchCommandBuilder`].
+pub struct SortCommandBuilder {
+    tag: String,
+    uid: bool,
+    criteria: Vec<SortKey>,
+    charset: String,
+    keys: Vec<SearchKey>,
+    literal_plus: bool,
+}
+impl SortCommandBuilder {
+    fn new(tag: String, uid: bool, criteria: Vec<SortKey>) -> Self {
+        Self {
+            tag,
+            uid,
+            criteria,
+            charset: "UTF-8".to_string(),
+            keys: Vec::new(),
+            literal_plus: false,
+        }
+    }
+    pub fn literal_plus(mut self) -> Self {
+        self.literal_plus = true;
+        self
+    }
+    pub fn charset(mut self, charset: &str) -> Self {
+        self.charset = charset.to_string();
+        self
+    }
+    pub fn key(mut self, key: SearchKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+    pub fn keys(mut self, keys: Vec<SearchKey>) -> Self {
+        self.keys.extend(keys);
+        self
+    }
+    fn render(&self) -> (String, Option<usize>) {
+        let mut s = String::new();
+        let cmd = if self.uid { "UID SORT" } else { "SORT" };
+        let _ = write!(
+            &mut s,
+            "{} {} {} {}",
+            self.tag,
+            cmd,
+            join_paren_space(&self.criteria),
+            self.charset
+        );
+        // SORT requires at least one search key.
+        let mut first_literal = None;
+        if self.keys.is_empty() {
+            s.push_str(" ALL");
+        } else {
+            s.push(' ');
+            let (keys, first) = render_search_keys(&self.keys, self.literal_plus);
+            first_literal = first.map(|i| s.len() + i);
+            s.push_str(&keys);
+        }
+        s.push_str("\r\n");
+        (s, first_literal)
+    }
+    /// The command line, up to and including the first literal announcement.
+    pub fn as_string(&self) -> String {
+        command_line(self.render())
+    }
+    /// Everything after [`as_string`](Self::as_string) except the final CRLF, or `None`
+    /// without literals.
+    pub fn literal_bytes(&self) -> Option<Vec<u8>> {
+        literal_rest(self.render())
+    }
+}
+
+pub struct ComparatorCommand {
+    tag: String,
+    comparators: Vec<String>,
+}
+impl ComparatorCommand {
+    fn new(tag: String, comparators: &[&str]) -> Self {
+        Self {
+            tag,
+            comparators: comparators.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+    pub fn as_string(&self) -> String {
+        let mut s = format!("{} COMPARATOR", self.tag);
+        for c in &self.comparators {
+            s.push(' ');
+            s.push_str(&quote_astring(c));
         }
         s.push_str("\r\n");
         s
@@ -526,6 +1267,34 @@ impl CopyCommand {
     }
 }
 
+/// MOVE (RFC 6851).
+pub struct MoveCommand {
+    tag: String,
+    uid: bool,
+    set: SequenceSet,
+    mailbox: String,
+}
+impl MoveCommand {
+    fn new(tag: String, uid: bool, set: SequenceSet, mailbox: &str) -> Self {
+        Self {
+            tag,
+            uid,
+            set,
+            mailbox: mailbox.to_string(),
+        }
+    }
+    pub fn as_string(&self) -> String {
+        let cmd = if self.uid { "UID MOVE" } else { "MOVE" };
+        format!(
+            "{} {} {} {}\r\n",
+            self.tag,
+            cmd,
+            self.set,
+            quote_astring(&self.mailbox)
+        )
+    }
+}
+
 pub struct UidScope {
     tag: String,
 }
@@ -535,6 +1304,9 @@ impl UidScope {
         b.uid = true;
         b
     }
+    pub fn sort(self, criteria: Vec<SortKey>) -> SortCommandBuilder {
+        SortCommandBuilder::new(self.tag, true, criteria)
+    }
     pub fn fetch(self, set: SequenceSet) -> FetchCommandBuilder {
         FetchCommandBuilder::new(self.tag, true, set)
     }
@@ -544,6 +1316,23 @@ impl UidScope {
     pub fn copy(self, set: SequenceSet, mailbox: &str) -> CopyCommand {
         CopyCommand::new(self.tag, true, set, mailbox)
     }
+    pub fn mv(self, set: SequenceSet, mailbox: &str) -> MoveCommand {
+        MoveCommand::new(self.tag, true, set, mailbox)
+    }
+    pub fn expunge(self, set: SequenceSet) -> UidExpungeCommand {
+        UidExpungeCommand { tag: self.tag, set }
+    }
+}
+
+/// UID EXPUNGE (RFC 4315): expunges only the `\Deleted` messages in the set.
+pub struct UidExpungeCommand {
+    tag: String,
+    set: SequenceSet,
+}
+impl UidExpungeCommand {
+    pub fn as_string(&self) -> String {
+        format!("{} UID EXPUNGE {}\r\n", self.tag, self.set)
+    }
 }
 
 pub struct NoUsername;
@@ -1,9 +1,33 @@
 use thiserror::Error;
 
+use crate::types::response::ResponseCode;
+
 #[derive(Error, Debug)]
 pub enum ImapError {
     #[error("Connection failed: {0}")]
     ConnectionFailed(String),
+    #[error("Command failed: {0}")]
+    CommandFailed(String),
+    /// The server refused the command with a tagged NO.
+    #[error("NO {}", with_code(.code, .text))]
+    No {
+        code: Option<ResponseCode>,
+        text: String,
+    },
+    /// The server rejected the command as invalid with a tagged BAD.
+    #[error("BAD {}", with_code(.code, .text))]
+    Bad {
+        code: Option<ResponseCode>,
+        text: String,
+    },
+    #[error("Server closed the connection: {0}")]
+    ServerBye(String),
+    /// The server announced a literal larger than the configured maximum.
+    #[error("Server announced a literal of {size} bytes, more than the limit of {max}")]
+    LiteralTooLarge { size: usize, max: usize },
+    /// [`add_system_roots`](crate::tls::add_system_roots) found no PEM trust bundle.
+    #[error("No PEM trust bundle found; set SSL_CERT_FILE or SSL_CERT_DIR")]
+    NoTrustBundle,
     #[error("Invalid address format: {0}")]
     InvalidAddressFormat(String),
     #[error("DNS name error: {0}")]
@@ -11,3 +35,85 @@ pub enum ImapError {
     #[error(transparent)]
     IoError(#[from] std::io::Error),
 }
+
+/// What kind of condition an error reports, and so whether trying again can help.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A limit on connecting, reading or writing ran out.
+    Timeout,
+    /// The connection failed or the server ended the session (BYE).
+    Connection,
+    /// The server is busy or the resource temporarily unavailable: `UNAVAILABLE`,
+    /// `INUSE` or `LIMIT`.
+    Unavailable,
+    /// The credentials were rejected or have expired, or the server demands TLS.
+    Authentication,
+    /// The server refused the command for a reason that will not go away by itself, e.g.
+    /// `OVERQUOTA`, `NOPERM` or `TRYCREATE`.
+    Refused,
+    /// The server could not parse the command (BAD) or the data it was sent.
+    Protocol,
+    /// The address or server name is invalid, or no trust bundle was found.
+    Config,
+}
+
+impl ErrorKind {
+    /// Whether the same operation may succeed later without changes: timeouts, lost
+    /// connections and temporary unavailability.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            ErrorKind::Timeout | ErrorKind::Connection | ErrorKind::Unavailable
+        )
+    }
+}
+
+impl ImapError {
+    /// What kind of failure this is; see [`ErrorKind`].
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ImapError::ConnectionFailed(_) | ImapError::ServerBye(_) => ErrorKind::Connection,
+            ImapError::IoError(e) if e.kind() == std::io::ErrorKind::TimedOut => ErrorKind::Timeout,
+            ImapError::IoError(_) => ErrorKind::Connection,
+            ImapError::CommandFailed(_) => ErrorKind::Refused,
+            ImapError::No { code, .. } => match code {
+                Some(ResponseCode::Unavailable | ResponseCode::InUse | ResponseCode::Limit) => {
+                    ErrorKind::Unavailable
+                }
+                Some(
+                    ResponseCode::AuthenticationFailed
+                    | ResponseCode::AuthorizationFailed
+                    | ResponseCode::Expired
+                    | ResponseCode::PrivacyRequired,
+                ) => ErrorKind::Authentication,
+                Some(ResponseCode::Parse | ResponseCode::ClientBug) => ErrorKind::Protocol,
+                _ => ErrorKind::Refused,
+            },
+            ImapError::Bad { .. } | ImapError::LiteralTooLarge { .. } => ErrorKind::Protocol,
+            ImapError::InvalidAddressFormat(_)
+            | ImapError::InvalidDnsName(_)
+            | ImapError::NoTrustBundle => ErrorKind::Config,
+        }
+    }
+
+    /// Shorthand for `self.kind().is_retryable()`.
+    pub fn is_retryable(&self) -> bool {
+        self.kind().is_retryable()
+    }
+
+    /// The response code of a NO or BAD, e.g. [`ResponseCode::TryCreate`].
+    pub fn code(&self) -> Option<&ResponseCode> {
+        match self {
+            ImapError::No { code, .. } | ImapError::Bad { code, .. } => code.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+fn with_code(code: &Option<ResponseCode>, text: &str) -> String {
+    match code {
+        Some(code) => format!("[{}] {}", code, text),
+        None => text.to_string(),
+    }
+}
@@ -1,3 +1,9 @@
+/// Whether `input` has to be sent as a literal: a quoted string cannot hold CR, LF or
+/// 8-bit characters.
+pub(crate) fn needs_literal(input: &str) -> bool {
+    input.bytes().any(|b| b == b'\r' || b == b'\n' || b >= 0x80)
+}
+
 pub(crate) fn quote_astring(input: &str) -> String {
     let mut out = String::with_capacity(input.len() + 2);
     out.push('"');
@@ -0,0 +1,149 @@
+//! Splitting a stream of server data into responses, without doing any IO.
+//!
+//! Connectors read into a buffer however their runtime does it and call
+//! [`Framer::next`] after every read, so the framing rules live in one place whatever
+//! drives the socket.
+//!
+//! With [`Framer::stream_literals`] on, literal data does not stay in the buffer: the
+//! caller takes it out with [`Framer::take_literal`] as it arrives, e.g. to write a large
+//! message body straight to a file.
+//!
+//! A literal announced larger than [`Framer::max_literal`] allows fails framing with
+//! [`ImapError::LiteralTooLarge`] before anything is buffered for it.
+
+use bytes::{Bytes, BytesMut};
+use memchr::memmem;
+
+use crate::ImapError;
+use crate::parser;
+
+/// The largest literal a [`Framer`] accepts unless told otherwise.
+pub const DEFAULT_MAX_LITERAL: usize = 64 * 1024 * 1024;
+
+/// Splits complete responses off the front of a read buffer.
+///
+/// A response is a line plus, for every literal it announces, the literal bytes and the rest
+/// of the line that follows them, so CRLFs inside literals never end a response.
+#[derive(Debug)]
+pub struct Framer {
+    /// Offset in the buffer up to which the current response is known (lines and literals).
+    scanned: usize,
+    /// Buffer length needed to complete the literal being received, if any.
+    want: usize,
+    /// Where the literal being received starts in the buffer.
+    literal: Option<usize>,
+    /// Whether literal data is left for [`Framer::take_literal`].
+    stream_literals: bool,
+    /// Longest line seen, not counting literal data.
+    longest_line: usize,
+    /// Largest literal accepted.
+    max_literal: usize,
+}
+
+impl Default for Framer {
+    fn default() -> Self {
+        Self {
+            scanned: 0,
+            want: 0,
+            literal: None,
+            stream_literals: false,
+            longest_line: 0,
+            max_literal: DEFAULT_MAX_LITERAL,
+        }
+    }
+}
+
+impl Framer {
+    /// Takes the next complete response off the front of `buf`, or `None` until more data
+    /// has been read. `buf` must start at a response boundary when framing begins.
+    ///
+    /// While literals are streamed, also `None` until the literal being received has been
+    /// taken in full.
+    ///
+    /// Fails if a literal is announced larger than [`Framer::max_literal`] allows; the
+    /// connection cannot be framed any further then.
+    pub fn next(&mut self, buf: &mut BytesMut) -> Result<Option<Bytes>, ImapError> {
+        if let Some(start) = self.literal {
+            if (self.stream_literals && self.want > start) || buf.len() < self.want {
+                return Ok(None);
+            }
+            self.literal = None;
+        }
+        loop {
+            let Some(pos) = memmem::find(&buf[self.scanned..], b"\r\n") else {
+                return Ok(None);
+            };
+            let line_end = self.scanned + pos + 2;
+            self.longest_line = self.longest_line.max(pos + 2);
+            match parser::literal_announcement(&buf[self.scanned..line_end]) {
+                Some(n) => {
+                    let end = line_end
+                        .checked_add(n)
+                        .filter(|_| n <= self.max_literal)
+                        .ok_or(ImapError::LiteralTooLarge {
+                            size: n,
+                            max: self.max_literal,
+                        })?;
+                    self.scanned = end;
+                    self.want = end;
+                    if (self.stream_literals && n > 0) || buf.len() < self.want {
+                        self.literal = Some(line_end);
+                        return Ok(None);
+                    }
+                }
+                None => {
+                    self.scanned = 0;
+                    self.want = 0;
+                    return Ok(Some(buf.split_to(line_end).freeze()));
+                }
+            }
+        }
+    }
+
+    /// Sets the largest literal accepted, in bytes; [`DEFAULT_MAX_LITERAL`] by default.
+    pub fn max_literal(&mut self, max: usize) {
+        self.max_literal = max;
+    }
+
+    /// Leaves literal data in `buf` for [`Framer::take_literal`] instead of returning it as
+    /// part of the response from [`Framer::next`].
+    ///
+    /// The response then has nothing between each literal announcement and the rest of the
+    /// line. Switching applies from the literal being received, if any.
+    pub fn stream_literals(&mut self, on: bool) {
+        self.stream_literals = on;
+    }
+
+    /// Removes the data of the literal being received that has arrived so far from `buf`;
+    /// empty if there is none.
+    pub fn take_literal(&mut self, buf: &mut BytesMut) -> Bytes {
+        let Some(start) = self.literal else {
+            return Bytes::new();
+        };
+        let end = buf.len().min(self.want);
+        if end <= start {
+            return Bytes::new();
+        }
+        let mut rest = buf.split_off(start);
+        let data = rest.split_to(end - start);
+        buf.unsplit(rest);
+        self.want -= data.len();
+        self.scanned -= data.len();
+        data.freeze()
+    }
+
+    /// How many more bytes the literal being received needs, or 0 if none is.
+    pub fn literal_missing(&self, buf: &BytesMut) -> usize {
+        self.want.saturating_sub(buf.len())
+    }
+
+    /// Length of the line being received, which has no CRLF yet.
+    pub fn partial_line_len(&self, buf: &BytesMut) -> usize {
+        buf.len().saturating_sub(self.scanned)
+    }
+
+    /// The longest line framed so far, in bytes, not counting literal data.
+    pub fn longest_line(&self) -> usize {
+        self.longest_line
+    }
+}
@@ -1,8 +1,17 @@
 mod error;
+pub use error::{ErrorKind, ImapError};
 
 pub(crate) mod format;
 
 pub mod commands;
+pub mod framing;
+pub mod mdn;
+pub mod messages;
+pub mod mime;
 pub mod parser;
+pub mod sasl;
+pub mod special_use;
 pub mod tls;
 pub mod types;
+pub mod utf7;
+pub mod x509;
@@ -0,0 +1,49 @@
+//! Read receipts: detecting requests for message disposition notifications (RFC 8098)
+//! and the `$MDNSent` keyword that records a receipt was sent (RFC 3503).
+
+use crate::types::common::{Flag, MessageFlags, Uid};
+use crate::types::response::HeaderMap;
+
+/// The keyword set on a message once a receipt has been sent for it, or the user declined
+/// to send one.
+pub const MDN_SENT: &str = "$MDNSent";
+
+/// The header through which a sender asks for a receipt.
+pub const NOTIFICATION_HEADER: &str = "Disposition-Notification-To";
+
+/// A message whose sender asked for a read receipt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdnRequest {
+    pub uid: Uid,
+    /// Where to send the receipt, as written in the header.
+    pub notify_to: String,
+    /// `$MDNSent` is already set: another client has handled the request, so no receipt
+    /// may be sent.
+    pub sent: bool,
+}
+
+/// The address a receipt was requested for, if any.
+pub fn requested_by(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(NOTIFICATION_HEADER)
+        .map(str::trim)
+        .filter(|to| !to.is_empty())
+}
+
+pub fn is_sent(flags: &MessageFlags) -> bool {
+    flags
+        .keywords
+        .iter()
+        .any(|k| k.eq_ignore_ascii_case(MDN_SENT))
+}
+
+/// Whether `$MDNSent` can be stored in a mailbox with these PERMANENTFLAGS.
+///
+/// RFC 3503 asks clients not to send receipts from mailboxes where they cannot record
+/// having done so, since every other client would then send one too.
+pub fn can_mark_sent(permanent_flags: &[Flag]) -> bool {
+    permanent_flags.iter().any(|flag| match flag {
+        Flag::Keyword(k) => k == "\\*" || k.eq_ignore_ascii_case(MDN_SENT),
+        _ => false,
+    })
+}
@@ -0,0 +1,185 @@
+//! Messages assembled from FETCH responses.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+
+use crate::commands::Section;
+use crate::mime::{self, Attachment};
+use crate::parser::datetime;
+use crate::types::common::{DateTime, MessageFlags, Seq, Uid};
+use crate::types::response::{Address, BodyStructure, Envelope, FetchData};
+
+/// One message and the data items fetched for it.
+///
+/// The accessors return `None` (or nothing) for items that were not fetched. When an item
+/// arrived more than once, e.g. FLAGS in a later unsolicited FETCH, the latest one counts.
+#[derive(Debug, Clone)]
+pub struct Message {
+    seq: Seq,
+    items: Vec<FetchData>,
+}
+
+impl Message {
+    pub fn new(seq: Seq, items: Vec<FetchData>) -> Self {
+        Self { seq, items }
+    }
+
+    /// Adds the items of another FETCH response for the same message.
+    pub fn merge(&mut self, items: Vec<FetchData>) {
+        self.items.extend(items);
+    }
+
+    /// Sequence number at the time of the fetch.
+    pub fn seq(&self) -> Seq {
+        self.seq
+    }
+
+    pub fn items(&self) -> &[FetchData] {
+        &self.items
+    }
+
+    fn latest<'a, T>(&'a self, f: impl FnMut(&'a FetchData) -> Option<T>) -> Option<T> {
+        self.items.iter().rev().find_map(f)
+    }
+
+    pub fn uid(&self) -> Option<Uid> {
+        self.latest(|item| match item {
+            FetchData::Uid(uid) => Some(*uid),
+            _ => None,
+        })
+    }
+
+    pub fn flags(&self) -> Option<&MessageFlags> {
+        self.latest(|item| match item {
+            FetchData::Flags(flags) => Some(flags),
+            _ => None,
+        })
+    }
+
+    /// INTERNALDATE, when the server was asked for it and it parses.
+    pub fn internal_date(&self) -> Option<DateTime> {
+        self.latest(|item| match item {
+            FetchData::InternalDate(date) => datetime::parse_date_time(date),
+            _ => None,
+        })
+    }
+
+    /// RFC822.SIZE in bytes.
+    pub fn size(&self) -> Option<u32> {
+        self.latest(|item| match item {
+            FetchData::Rfc822Size(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    pub fn envelope(&self) -> Option<&Envelope> {
+        self.latest(|item| match item {
+            FetchData::Envelope(env) => Some(env),
+            _ => None,
+        })
+    }
+
+    pub fn subject(&self) -> Option<&str> {
+        self.envelope()?.subject.as_deref()
+    }
+
+    /// The From addresses from the envelope.
+    pub fn from(&self) -> &[Address] {
+        self.envelope().map_or(&[], |env| &env.from)
+    }
+
+    /// The To addresses from the envelope.
+    pub fn to(&self) -> &[Address] {
+        self.envelope().map_or(&[], |env| &env.to)
+    }
+
+    /// The Cc addresses from the envelope.
+    pub fn cc(&self) -> &[Address] {
+        self.envelope().map_or(&[], |env| &env.cc)
+    }
+
+    /// The Bcc addresses from the envelope, which servers usually only know for drafts and
+    /// sent mail.
+    pub fn bcc(&self) -> &[Address] {
+        self.envelope().map_or(&[], |env| &env.bcc)
+    }
+
+    /// The Date header from the envelope, if it parses. [`Envelope::date`] has it as sent.
+    pub fn date(&self) -> Option<DateTime> {
+        datetime::parse_rfc2822_date(self.envelope()?.date.as_deref()?)
+    }
+
+    pub fn message_id(&self) -> Option<&str> {
+        self.envelope()?.message_id.as_deref()
+    }
+
+    /// The data of a fetched `BODY[section]`, `BODY.PEEK[section]` or its `RFC822` form.
+    ///
+    /// `None` if the section was not fetched or the server sent NIL. Partial fetches are
+    /// included under their section; see [`FetchData::BodySection`] for the origin.
+    pub fn body(&self, section: &Section) -> Option<&[u8]> {
+        let spec = section.to_string();
+        self.latest(|item| match item {
+            FetchData::BodySection { section, data, .. } if section.eq_ignore_ascii_case(&spec) => {
+                Some(data.as_deref())
+            }
+            FetchData::Rfc822(data) if spec.is_empty() => Some(data.as_deref()),
+            FetchData::Rfc822Header(data) if spec == "HEADER" => Some(data.as_deref()),
+            FetchData::Rfc822Text(data) if spec == "TEXT" => Some(data.as_deref()),
+            _ => None,
+        })
+        .flatten()
+    }
+
+    pub fn body_structure(&self) -> Option<&BodyStructure> {
+        self.latest(|item| match item {
+            FetchData::BodyStructure(structure) => Some(structure),
+            _ => None,
+        })
+    }
+
+    /// The decoded `text/plain` body, picked by [`mime::find_text_part`].
+    ///
+    /// Needs BODYSTRUCTURE and that part to have been fetched, e.g. with
+    /// [`mime::text_sections`].
+    pub fn text_body(&self) -> Option<String> {
+        self.text_part(false)
+    }
+
+    /// The decoded `text/html` body; see [`Message::text_body`].
+    pub fn html_body(&self) -> Option<String> {
+        self.text_part(true)
+    }
+
+    fn text_part(&self, html: bool) -> Option<String> {
+        let (path, part) = mime::find_text_part(self.body_structure()?, html)?;
+        if part.is("text", "html") != html {
+            return None;
+        }
+        let data = self.body(&Section::part_at(&path)?)?;
+        Some(mime::decode_text(data, part))
+    }
+
+    /// The attachments listed in BODYSTRUCTURE; see [`mime::attachments`]. Their data is
+    /// fetched separately.
+    pub fn attachments(&self) -> Vec<Attachment<'_>> {
+        self.body_structure()
+            .map(mime::attachments)
+            .unwrap_or_default()
+    }
+}
+
+/// Turns parsed FETCH responses into one [`Message`] per sequence number, in ascending
+/// order, merging the items of responses that name the same message.
+pub fn assemble(fetched: Vec<(Seq, Vec<FetchData>)>) -> Vec<Message> {
+    let mut messages: BTreeMap<Seq, Message> = BTreeMap::new();
+    for (seq, items) in fetched {
+        match messages.entry(seq) {
+            Entry::Occupied(mut message) => message.get_mut().merge(items),
+            Entry::Vacant(slot) => {
+                slot.insert(Message::new(seq, items));
+            }
+        }
+    }
+    messages.into_values().collect()
+}
@@ -0,0 +1,246 @@
+//! Choosing and decoding the readable text of a message from its [`BodyStructure`], and
+//! finding its attachments.
+
+use crate::types::response::{BodyPart, BodyStructure};
+
+/// A message's main text, decoded to a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextBody {
+    /// `text/html` rather than `text/plain`.
+    pub html: bool,
+    pub text: String,
+}
+
+/// Finds the part to show as the message body and its section path (`1`, `2.1`, ...).
+///
+/// In `multipart/alternative` the last alternative of the preferred type wins, falling
+/// back to the other type; in `multipart/related` only the root (first) part is
+/// considered; elsewhere the first inline text part is taken. Attached messages are not
+/// searched.
+pub fn find_text_part(structure: &BodyStructure, prefer_html: bool) -> Option<(String, &BodyPart)> {
+    let root = match structure {
+        BodyStructure::Single(_) => "1",
+        BodyStructure::Multipart { .. } => "",
+    };
+    pick(structure, root.to_string(), prefer_html)
+}
+
+/// The section paths of the `text/plain` and `text/html` bodies, which is all a reader
+/// needs to fetch besides BODYSTRUCTURE.
+pub fn text_sections(structure: &BodyStructure) -> Vec<String> {
+    let mut paths = Vec::new();
+    for prefer_html in [false, true] {
+        if let Some((path, _)) = find_text_part(structure, prefer_html)
+            && !paths.contains(&path)
+        {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+fn pick(structure: &BodyStructure, path: String, prefer_html: bool) -> Option<(String, &BodyPart)> {
+    let (parts, subtype) = match structure {
+        BodyStructure::Single(part) => {
+            let text = part.is("text", "plain") || part.is("text", "html");
+            return (text && !part.is_attachment()).then_some((path, part));
+        }
+        BodyStructure::Multipart { parts, subtype, .. } => (parts, subtype),
+    };
+    let mut children = parts.iter().enumerate().map(|(i, child)| {
+        let child_path = if path.is_empty() {
+            (i + 1).to_string()
+        } else {
+            format!("{}.{}", path, i + 1)
+        };
+        (child, child_path)
+    });
+
+    if subtype.eq_ignore_ascii_case("alternative") {
+        let candidates: Vec<_> = children
+            .filter_map(|(child, child_path)| pick(child, child_path, prefer_html))
+            .collect();
+        let preferred = candidates
+            .iter()
+            .rposition(|(_, part)| part.is("text", "html") == prefer_html);
+        let index = preferred.unwrap_or(0);
+        return candidates.into_iter().nth(index);
+    }
+    if subtype.eq_ignore_ascii_case("related") {
+        let (root, root_path) = children.next()?;
+        return pick(root, root_path, prefer_html);
+    }
+    children.find_map(|(child, child_path)| pick(child, child_path, prefer_html))
+}
+
+/// A part of a message that is meant to be saved rather than shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attachment<'a> {
+    /// The section path (`2`, `1.3`, ...) to fetch it with.
+    pub section: String,
+    pub part: &'a BodyPart,
+}
+
+impl Attachment<'_> {
+    /// The sender's file name. It may contain a path; never use it as one unchecked.
+    pub fn filename(&self) -> Option<&str> {
+        self.part.filename()
+    }
+
+    /// The media type in lower case, e.g. `application/pdf`.
+    pub fn content_type(&self) -> String {
+        format!("{}/{}", self.part.media_type, self.part.subtype).to_ascii_lowercase()
+    }
+
+    /// Size in octets, still transfer-encoded.
+    pub fn size(&self) -> u32 {
+        self.part.size
+    }
+
+    /// Removes the transfer encoding from the fetched `BODY[section]` data.
+    pub fn decode(&self, data: &[u8]) -> Vec<u8> {
+        decode_transfer(data, &self.part.encoding)
+    }
+}
+
+/// Lists the attachments in `structure`: parts with `Content-Disposition: attachment` and
+/// other parts that carry a file name, such as named inline images. Attached messages count
+/// as one attachment and are not searched.
+pub fn attachments(structure: &BodyStructure) -> Vec<Attachment<'_>> {
+    let mut out = Vec::new();
+    collect_attachments(structure, String::new(), &mut out);
+    out
+}
+
+fn collect_attachments<'a>(
+    structure: &'a BodyStructure,
+    path: String,
+    out: &mut Vec<Attachment<'a>>,
+) {
+    match structure {
+        BodyStructure::Single(part) => {
+            if part.is_attachment() || part.filename().is_some() {
+                let section = if path.is_empty() {
+                    "1".to_string()
+                } else {
+                    path
+                };
+                out.push(Attachment { section, part });
+            }
+        }
+        BodyStructure::Multipart { parts, .. } => {
+            for (i, child) in parts.iter().enumerate() {
+                let child_path = if path.is_empty() {
+                    (i + 1).to_string()
+                } else {
+                    format!("{}.{}", path, i + 1)
+                };
+                collect_attachments(child, child_path, out);
+            }
+        }
+    }
+}
+
+/// Decodes a fetched part: removes its transfer encoding and converts its charset.
+pub fn decode_text(data: &[u8], part: &BodyPart) -> String {
+    decode_charset(&decode_transfer(data, &part.encoding), part.charset())
+}
+
+/// Removes a Content-Transfer-Encoding. `7bit`, `8bit`, `binary` and unknown encodings
+/// are passed through.
+pub fn decode_transfer(data: &[u8], encoding: &str) -> Vec<u8> {
+    if encoding.eq_ignore_ascii_case("base64") {
+        decode_base64(data)
+    } else if encoding.eq_ignore_ascii_case("quoted-printable") {
+        decode_quoted_printable(data)
+    } else {
+        data.to_vec()
+    }
+}
+
+/// Lenient base64: line breaks and other characters outside the alphabet are ignored.
+pub fn decode_base64(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for &b in data {
+        let value = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => continue,
+        };
+        acc = (acc << 6) | u32::from(value);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    out
+}
+
+/// Quoted-printable (RFC 2045 section 6.7). Malformed escapes are kept as they are.
+pub fn decode_quoted_printable(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        if data[i] != b'=' {
+            out.push(data[i]);
+            i += 1;
+            continue;
+        }
+        match data.get(i + 1..) {
+            // Soft line break.
+            Some([b'\r', b'\n', ..]) => i += 3,
+            Some([b'\n', ..]) => i += 2,
+            Some([hi, lo, ..]) if hi.is_ascii_hexdigit() && lo.is_ascii_hexdigit() => {
+                out.push((hex(*hi) << 4) | hex(*lo));
+                i += 3;
+            }
+            _ => {
+                out.push(b'=');
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn hex(b: u8) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'a'..=b'f' => b - b'a' + 10,
+        _ => b - b'A' + 10,
+    }
+}
+
+/// Windows-1252 code points for 0x80..=0x9F; undefined bytes map to U+FFFD.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{fffd}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{fffd}', 'Ž',
+    '\u{fffd}', '\u{fffd}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{fffd}',
+    'ž', 'Ÿ',
+];
+
+/// Converts text in `charset` to a string. UTF-8 and US-ASCII (the default), ISO-8859-1
+/// and Windows-1252 are understood; anything else is read as UTF-8, replacing invalid
+/// sequences.
+pub fn decode_charset(data: &[u8], charset: Option<&str>) -> String {
+    let charset = charset.unwrap_or("us-ascii").to_ascii_lowercase();
+    match charset.as_str() {
+        "iso-8859-1" | "iso_8859-1" | "latin1" | "l1" => {
+            data.iter().map(|&b| char::from(b)).collect()
+        }
+        "windows-1252" | "cp1252" => data
+            .iter()
+            .map(|&b| match b {
+                0x80..=0x9f => WINDOWS_1252[usize::from(b - 0x80)],
+                _ => char::from(b),
+            })
+            .collect(),
+        _ => String::from_utf8_lossy(data).into_owned(),
+    }
+}
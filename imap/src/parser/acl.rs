@@ -0,0 +1,46 @@
+//! ACL, LISTRIGHTS and MYRIGHTS responses (RFC 4314).
+
+use super::fetch::parse_astring;
+use super::untagged_lines;
+use crate::types::common::Rights;
+use crate::types::response::{AclEntry, ListRights};
+
+/// Parses the `* ACL mailbox identifier rights ...` lines in `buf`.
+pub fn parse_acl(buf: &[u8]) -> Vec<AclEntry> {
+    untagged_lines(buf, "ACL")
+        .flat_map(|rest| {
+            let mut words = astrings(rest).into_iter().skip(1);
+            std::iter::from_fn(move || {
+                let identifier = words.next()?;
+                let rights = Rights::parse(&words.next()?);
+                Some(AclEntry { identifier, rights })
+            })
+        })
+        .collect()
+}
+
+/// Parses `* LISTRIGHTS mailbox identifier required optional...`.
+pub fn parse_listrights(buf: &[u8]) -> Option<ListRights> {
+    let rest = untagged_lines(buf, "LISTRIGHTS").next()?;
+    let mut words = astrings(rest).into_iter().skip(1);
+    Some(ListRights {
+        identifier: words.next()?,
+        required: Rights::parse(&words.next()?),
+        optional: words.map(|w| Rights::parse(&w)).collect(),
+    })
+}
+
+/// Parses `* MYRIGHTS mailbox rights`.
+pub fn parse_myrights(buf: &[u8]) -> Option<Rights> {
+    let rest = untagged_lines(buf, "MYRIGHTS").next()?;
+    astrings(rest).get(1).map(|rights| Rights::parse(rights))
+}
+
+fn astrings(mut buf: &[u8]) -> Vec<String> {
+    let mut words = Vec::new();
+    while let Some((word, next)) = parse_astring(buf, 0) {
+        words.push(String::from_utf8_lossy(&word).into_owned());
+        buf = &buf[next..];
+    }
+    words
+}
@@ -0,0 +1,52 @@
+use crate::types::common::Capabilities;
+
+/// Collects the capability atoms from every `* CAPABILITY ...` line in `buf`.
+pub fn parse_capabilities(buf: &[u8]) -> Capabilities {
+    untagged_atoms(buf, "CAPABILITY").into_iter().collect()
+}
+
+/// Capabilities announced in passing, from a `* CAPABILITY` line or a `[CAPABILITY ...]`
+/// response code, as servers send in greetings and authentication completions.
+///
+/// Returns `None` if the server sent neither; any other untagged lines are ignored.
+pub fn harvest_capabilities(buf: &[u8]) -> Option<Capabilities> {
+    let caps = parse_capabilities(buf);
+    if !caps.is_empty() {
+        return Some(caps);
+    }
+    let text = String::from_utf8_lossy(buf);
+    text.split("\r\n").find_map(|line| {
+        let start = line.to_ascii_uppercase().find("[CAPABILITY ")? + 12;
+        let end = start + line[start..].find(']')?;
+        Some(
+            line[start..end]
+                .split_ascii_whitespace()
+                .map(str::to_string)
+                .collect(),
+        )
+    })
+}
+
+/// The extensions listed in `* ENABLED` responses (RFC 5161).
+pub fn parse_enabled(buf: &[u8]) -> Vec<String> {
+    untagged_atoms(buf, "ENABLED")
+}
+
+/// Collects the atoms following `* <keyword>` on every matching line in `buf`.
+pub(crate) fn untagged_atoms(buf: &[u8], keyword: &str) -> Vec<String> {
+    let text = String::from_utf8_lossy(buf);
+    let mut atoms = Vec::new();
+    for line in text.split("\r\n") {
+        let Some(rest) = line.strip_prefix("* ") else {
+            continue;
+        };
+        let mut words = rest.split_ascii_whitespace();
+        if words
+            .next()
+            .is_some_and(|w| w.eq_ignore_ascii_case(keyword))
+        {
+            atoms.extend(words.map(str::to_string));
+        }
+    }
+    atoms
+}
@@ -0,0 +1,159 @@
+//! Dates as IMAP sends them: the `date-time` of INTERNALDATE and the RFC 2822 dates in
+//! ENVELOPE.
+
+use crate::types::common::DateTime;
+
+/// Month names as IMAP writes them.
+pub(crate) const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parses an IMAP `date-time` (`"17-Jul-1996 02:44:25 -0700"`, as used by INTERNALDATE)
+/// into seconds since the Unix epoch.
+pub fn parse_internal_date(s: &str) -> Option<i64> {
+    parse_date_time(s).map(|d| d.timestamp())
+}
+
+/// Parses an IMAP `date-time` (`"17-Jul-1996 02:44:25 -0700"`, as used by INTERNALDATE).
+pub fn parse_date_time(s: &str) -> Option<DateTime> {
+    let s = s.trim();
+    let (date, rest) = s.split_once(' ')?;
+    let (time, zone) = rest.trim_start().split_once(' ')?;
+
+    let mut date_parts = date.split('-');
+    let day: i64 = date_parts.next()?.trim().parse().ok()?;
+    let month = month_number(date_parts.next()?)?;
+    let year: i64 = date_parts.next()?.parse().ok()?;
+
+    let seconds = parse_time(time)?;
+    let offset = parse_numeric_zone(zone)?;
+    build(year, month, day, seconds, offset)
+}
+
+/// Parses an RFC 2822 date as in a Date header or ENVELOPE, e.g.
+/// `"Wed, 17 Jul 1996 02:23:25 -0700 (PDT)"`.
+///
+/// The day of week is optional and not checked; seconds may be left out; two-digit years
+/// and the obsolete zone names (`GMT`, `EST`, ...) of RFC 2822 section 4.3 are accepted.
+/// A missing zone is read as UTC.
+pub fn parse_rfc2822_date(s: &str) -> Option<DateTime> {
+    let s = strip_comments(s);
+    let s = s.split_once(',').map_or(s.as_str(), |(_, rest)| rest);
+    let mut words = s.split_whitespace();
+
+    let day: i64 = words.next()?.parse().ok()?;
+    let month = month_number(words.next()?)?;
+    let year_text = words.next()?;
+    let year: i64 = year_text.parse().ok()?;
+    let year = match year_text.len() {
+        1 | 2 if year < 50 => year + 2000,
+        1..=3 => year + 1900,
+        _ => year,
+    };
+    let seconds = parse_time(words.next()?)?;
+    let offset = match words.next() {
+        None => 0,
+        Some(zone) => parse_numeric_zone(zone).or_else(|| named_zone(zone))?,
+    };
+    build(year, month, day, seconds, offset)
+}
+
+fn build(year: i64, month: i64, day: i64, seconds: i64, offset: i32) -> Option<DateTime> {
+    if !(1..=31).contains(&day) {
+        return None;
+    }
+    let local = days_from_civil(year, month, day) * 86_400 + seconds;
+    Some(DateTime::new(local - i64::from(offset), offset))
+}
+
+/// `HH:MM:SS` or `HH:MM` as seconds after midnight.
+fn parse_time(time: &str) -> Option<i64> {
+    let mut parts = time.split(':');
+    let hour: i64 = parts.next()?.parse().ok()?;
+    let minute: i64 = parts.next()?.parse().ok()?;
+    let second: i64 = match parts.next() {
+        Some(s) => s.parse().ok()?,
+        None => 0,
+    };
+    if parts.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    Some(hour * 3600 + minute * 60 + second)
+}
+
+/// `+hhmm` or `-hhmm` as seconds east of UTC.
+fn parse_numeric_zone(zone: &str) -> Option<i32> {
+    let (sign, digits) = match zone.as_bytes().first()? {
+        b'+' => (1, &zone[1..]),
+        b'-' => (-1, &zone[1..]),
+        _ => return None,
+    };
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+/// The obsolete zone names of RFC 2822; military letters mean UTC, as the RFC advises.
+fn named_zone(zone: &str) -> Option<i32> {
+    let hours = match zone.to_ascii_uppercase().as_str() {
+        "UT" | "GMT" | "Z" => 0,
+        "EDT" => -4,
+        "EST" | "CDT" => -5,
+        "CST" | "MDT" => -6,
+        "MST" | "PDT" => -7,
+        "PST" => -8,
+        z if z.len() == 1 && z.bytes().all(|b| b.is_ascii_alphabetic()) => 0,
+        _ => return None,
+    };
+    Some(hours * 3600)
+}
+
+/// Removes parenthesized comments, which may nest.
+fn strip_comments(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut depth = 0usize;
+    for c in s.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+fn month_number(name: &str) -> Option<i64> {
+    MONTHS
+        .iter()
+        .position(|m| m.eq_ignore_ascii_case(name))
+        .map(|i| i as i64 + 1)
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01, as (year, month, day).
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -1,7 +1,14 @@
-use crate::types::response::{Envelope, FetchData};
+use bytes::Bytes;
 
-pub fn fetch_envelopes(buf: &[u8]) -> Vec<(u32, FetchData)> {
-    let mut res: Vec<(u32, FetchData)> = Vec::new();
+use super::datetime::parse_internal_date;
+use crate::types::common::Flag;
+use crate::types::common::{KeywordInterner, MessageFlags, Seq, SystemFlags, Uid};
+use crate::types::response::{
+    Address, BodyPart, BodyStructure, Disposition, Envelope, EnvelopeSummary, FetchData,
+};
+
+pub fn fetch_envelopes(buf: &[u8]) -> Vec<(Seq, FetchData)> {
+    let mut res: Vec<(Seq, FetchData)> = Vec::new();
     let mut i = 0;
     while let Some(pos) = find_subsequence(&buf[i..], b"* ") {
         let start = i + pos + 2; // after "* "
@@ -30,12 +37,13 @@ pub fn fetch_envelopes(buf: &[u8]) -> Vec<(u32, FetchData)> {
         }
 
         // parse date, subject
-        if let Some((_date, next)) = parse_string(buf, j) {
+        let date = if let Some((date, next)) = parse_string(buf, j) {
             j = next;
+            date
         } else {
             i = j;
             continue;
-        }
+        };
         let subject = match parse_string(buf, j) {
             Some((s, next)) => {
                 j = next;
@@ -43,19 +51,40 @@ pub fn fetch_envelopes(buf: &[u8]) -> Vec<(u32, FetchData)> {
             }
             None => None,
         };
-        res.push((num, FetchData::Envelope(Envelope { subject })));
+        let from = match parse_address_list(buf, j) {
+            Some((from, next)) => {
+                j = next;
+                from
+            }
+            None => Vec::new(),
+        };
+        let mut env = Envelope {
+            date,
+            subject,
+            from,
+            ..Envelope::default()
+        };
+        if let Some(next) = parse_envelope_tail(buf, j, &mut env) {
+            j = next;
+        }
+        res.push((Seq(num), FetchData::Envelope(env)));
         i = j;
     }
     res
 }
 
-fn parse_string(buf: &[u8], mut i: usize) -> Option<(Option<String>, usize)> {
+fn parse_string(buf: &[u8], i: usize) -> Option<(Option<String>, usize)> {
+    let (bytes, next) = parse_nstring(buf, i)?;
+    let s = bytes.map(|b| String::from_utf8_lossy(&b).into_owned());
+    Some((s, next))
+}
+
+pub(crate) fn parse_nstring(buf: &[u8], mut i: usize) -> Option<(Option<Vec<u8>>, usize)> {
     skip_ws(buf, &mut i);
     if i >= buf.len() {
         return None;
     }
-    // NIL
-    if buf.get(i..i + 3)? == b"NIL" {
+    if is_nil(buf, i) {
         return Some((None, i + 3));
     }
     // Quoted
@@ -63,21 +92,21 @@ fn parse_string(buf: &[u8], mut i: usize) -> Option<(Option<String>, usize)> {
         let (s, n) = parse_quoted(buf, i + 1)?;
         return Some((Some(s), n));
     }
-    // Literal
-    if buf[i] == b'{' {
+    // Literal, or literal8 (RFC 3516)
+    if buf[i] == b'{' || buf[i] == b'~' {
         let (s, n) = parse_literal(buf, i)?;
-        return Some((Some(s), n));
+        return Some((Some(s.to_vec()), n));
     }
     None
 }
 
-fn parse_quoted(buf: &[u8], mut i: usize) -> Option<(String, usize)> {
-    let mut out = String::new();
+fn parse_quoted(buf: &[u8], mut i: usize) -> Option<(Vec<u8>, usize)> {
+    let mut out = Vec::new();
     let mut escaped = false;
     while i < buf.len() {
         let b = buf[i];
         if escaped {
-            out.push(b as char);
+            out.push(b);
             escaped = false;
             i += 1;
             continue;
@@ -91,7 +120,7 @@ fn parse_quoted(buf: &[u8], mut i: usize) -> Option<(String, usize)> {
                 return Some((out, i + 1));
             }
             _ => {
-                out.push(b as char);
+                out.push(b);
                 i += 1;
             }
         }
@@ -99,9 +128,12 @@ fn parse_quoted(buf: &[u8], mut i: usize) -> Option<(String, usize)> {
     None
 }
 
-fn parse_literal(buf: &[u8], mut i: usize) -> Option<(String, usize)> {
-    // Expect {digits}\r\n content
-    if buf[i] != b'{' {
+fn parse_literal(buf: &[u8], mut i: usize) -> Option<(&[u8], usize)> {
+    // Expect {digits}\r\n content, optionally prefixed by ~ for literal8
+    if buf[i] == b'~' {
+        i += 1;
+    }
+    if buf.get(i) != Some(&b'{') {
         return None;
     }
     i += 1;
@@ -124,11 +156,35 @@ fn parse_literal(buf: &[u8], mut i: usize) -> Option<(String, usize)> {
     if content_end > buf.len() {
         return None;
     }
-    let s = String::from_utf8_lossy(&buf[content_start..content_end]).into_owned();
-    Some((s, content_end))
+    Some((&buf[content_start..content_end], content_end))
+}
+
+/// Parses an astring: a quoted string, a literal, or an atom (which may contain `]`).
+pub(crate) fn parse_astring(buf: &[u8], mut i: usize) -> Option<(Vec<u8>, usize)> {
+    skip_ws(buf, &mut i);
+    match buf.get(i)? {
+        b'"' => parse_quoted(buf, i + 1),
+        b'{' => parse_literal(buf, i).map(|(s, n)| (s.to_vec(), n)),
+        _ => {
+            let mut j = i;
+            while j < buf.len() && !matches!(buf[j], b' ' | b'(' | b')' | b'"' | b'\r' | b'\n') {
+                j += 1;
+            }
+            (j > i).then(|| (buf[i..j].to_vec(), j))
+        }
+    }
 }
 
-fn skip_ws(buf: &[u8], i: &mut usize) {
+/// Whether the atom at `i` is `NIL` (and not merely starts with it).
+fn is_nil(buf: &[u8], i: usize) -> bool {
+    buf.get(i..i + 3)
+        .is_some_and(|w| w.eq_ignore_ascii_case(b"NIL"))
+        && !buf
+            .get(i + 3)
+            .is_some_and(|b| !matches!(b, b' ' | b'(' | b')' | b'\r' | b'\n'))
+}
+
+pub(crate) fn skip_ws(buf: &[u8], i: &mut usize) {
     while *i < buf.len() && buf[*i].is_ascii_whitespace() {
         *i += 1;
     }
@@ -153,3 +209,532 @@ fn parse_number(buf: &[u8], mut i: usize) -> Option<(u32, usize)> {
     let n: u32 = std::str::from_utf8(&buf[start..i]).ok()?.parse().ok()?;
     Some((n, i))
 }
+
+/// Parses every `* n FETCH (...)` response in `buf` into its data items.
+///
+/// Unknown attributes are skipped; lines that are not FETCH responses are ignored.
+pub fn parse_fetch_responses(buf: &[u8]) -> Vec<(Seq, Vec<FetchData>)> {
+    parse_fetch_responses_with(buf, &mut KeywordInterner::new())
+}
+
+/// Like [`parse_fetch_responses`], interning keyword flags into `keywords`.
+///
+/// Reusing one interner for a mailbox shares keyword allocations across messages.
+pub fn parse_fetch_responses_with(
+    buf: &[u8],
+    keywords: &mut KeywordInterner,
+) -> Vec<(Seq, Vec<FetchData>)> {
+    let mut res = Vec::new();
+    let mut i = 0;
+    while i < buf.len() {
+        match parse_fetch_response(buf, i, keywords) {
+            Some((seq, items, next)) => {
+                res.push((seq, items));
+                i = next;
+            }
+            None => match find_subsequence(&buf[i..], b"\r\n") {
+                Some(pos) => i += pos + 2,
+                None => break,
+            },
+        }
+    }
+    res
+}
+
+fn parse_fetch_response(
+    buf: &[u8],
+    i: usize,
+    keywords: &mut KeywordInterner,
+) -> Option<(Seq, Vec<FetchData>, usize)> {
+    if buf.get(i..i + 2)? != b"* " {
+        return None;
+    }
+    let (seq, mut j) = parse_number(buf, i + 2)?;
+    if !buf.get(j..j + 8)?.eq_ignore_ascii_case(b" FETCH (") {
+        return None;
+    }
+    j += 8;
+
+    let mut items = Vec::new();
+    loop {
+        skip_ws(buf, &mut j);
+        match buf.get(j)? {
+            b')' => {
+                j += 1;
+                break;
+            }
+            _ => {
+                let (item, next) = parse_fetch_item(buf, j, keywords)?;
+                items.extend(item);
+                j = next;
+            }
+        }
+    }
+    if buf.get(j..j + 2) == Some(b"\r\n") {
+        j += 2;
+    }
+    Some((Seq(seq), items, j))
+}
+
+fn parse_fetch_item(
+    buf: &[u8],
+    i: usize,
+    keywords: &mut KeywordInterner,
+) -> Option<(Option<FetchData>, usize)> {
+    let (name, j) = parse_atom(buf, i)?;
+    match name.to_ascii_uppercase().as_slice() {
+        b"UID" => {
+            let (n, j) = parse_number(buf, j)?;
+            Some((Some(FetchData::Uid(Uid(n))), j))
+        }
+        b"RFC822.SIZE" => {
+            let (n, j) = parse_number(buf, j)?;
+            Some((Some(FetchData::Rfc822Size(n)), j))
+        }
+        b"RFC822" => parse_bytes_item(buf, j, FetchData::Rfc822),
+        b"RFC822.HEADER" => parse_bytes_item(buf, j, FetchData::Rfc822Header),
+        b"RFC822.TEXT" => parse_bytes_item(buf, j, FetchData::Rfc822Text),
+        b"FLAGS" => {
+            let (flags, j) = parse_message_flags(buf, j, keywords)?;
+            Some((Some(FetchData::Flags(flags)), j))
+        }
+        b"INTERNALDATE" => {
+            let (date, j) = parse_string(buf, j)?;
+            Some((date.map(FetchData::InternalDate), j))
+        }
+        b"ENVELOPE" => {
+            let mut k = j;
+            skip_ws(buf, &mut k);
+            if is_nil(buf, k) {
+                // Sent by some servers for messages they cannot parse.
+                return Some((None, k + 3));
+            }
+            if buf.get(k) != Some(&b'(') {
+                return None;
+            }
+            let (date, k) = parse_string(buf, k + 1)?;
+            let (subject, k) = parse_string(buf, k)?;
+            let (from, k) = parse_address_list(buf, k)?;
+            let mut env = Envelope {
+                date,
+                subject,
+                from,
+                ..Envelope::default()
+            };
+            // Servers that send a short envelope still get date, subject and from.
+            let _ = parse_envelope_tail(buf, k, &mut env);
+            let end = skip_value(buf, j)?;
+            Some((Some(FetchData::Envelope(env)), end))
+        }
+        b"BODY" if buf.get(j) == Some(&b'[') => {
+            let (section, origin, data, k) = parse_section_data(buf, j)?;
+            Some((
+                Some(FetchData::BodySection {
+                    section,
+                    origin,
+                    data,
+                }),
+                k,
+            ))
+        }
+        b"BINARY" if buf.get(j) == Some(&b'[') => {
+            let (section, origin, data, k) = parse_section_data(buf, j)?;
+            Some((
+                Some(FetchData::BinarySection {
+                    section,
+                    origin,
+                    data,
+                }),
+                k,
+            ))
+        }
+        b"BODYSTRUCTURE" | b"BODY" => match parse_body_structure(buf, j) {
+            Some((structure, k)) => Some((Some(FetchData::BodyStructure(structure)), k)),
+            None => other_item(buf, name, j),
+        },
+        _ => other_item(buf, name, j),
+    }
+}
+
+/// Keeps an item the parser does not understand as [`FetchData::Other`].
+fn other_item(buf: &[u8], name: &[u8], mut i: usize) -> Option<(Option<FetchData>, usize)> {
+    let end = skip_value(buf, i)?;
+    skip_ws(buf, &mut i);
+    Some((
+        Some(FetchData::Other {
+            name: String::from_utf8_lossy(name).into_owned(),
+            value: Bytes::copy_from_slice(&buf[i..end]),
+        }),
+        end,
+    ))
+}
+
+/// Parses a `body` (RFC 3501 section 9): one part, or a multipart with its children.
+///
+/// Extension data beyond the disposition (language, location) is skipped.
+fn parse_body_structure(buf: &[u8], mut i: usize) -> Option<(BodyStructure, usize)> {
+    skip_ws(buf, &mut i);
+    if buf.get(i) != Some(&b'(') {
+        return None;
+    }
+    i += 1;
+    skip_ws(buf, &mut i);
+
+    if buf.get(i) == Some(&b'(') {
+        let mut parts = Vec::new();
+        while buf.get(i) == Some(&b'(') {
+            let (part, next) = parse_body_structure(buf, i)?;
+            parts.push(part);
+            i = next;
+            skip_ws(buf, &mut i);
+        }
+        let (subtype, mut i) = parse_string(buf, i)?;
+        let mut params = Vec::new();
+        let mut disposition = None;
+        if has_more(buf, &mut i) {
+            (params, i) = parse_body_params(buf, i)?;
+            if has_more(buf, &mut i) {
+                (disposition, i) = parse_disposition(buf, i)?;
+            }
+        }
+        let structure = BodyStructure::Multipart {
+            parts,
+            subtype: subtype.unwrap_or_default(),
+            params,
+            disposition,
+        };
+        return Some((structure, close_list(buf, i)?));
+    }
+
+    let (media_type, i) = parse_string(buf, i)?;
+    let (subtype, i) = parse_string(buf, i)?;
+    let (params, i) = parse_body_params(buf, i)?;
+    let (id, i) = parse_string(buf, i)?;
+    let (description, i) = parse_string(buf, i)?;
+    let (encoding, i) = parse_string(buf, i)?;
+    let (size, mut i) = parse_number(buf, i)?;
+    let mut part = BodyPart {
+        media_type: media_type.unwrap_or_default(),
+        subtype: subtype.unwrap_or_default(),
+        params,
+        id,
+        description,
+        encoding: encoding.unwrap_or_default(),
+        size,
+        lines: None,
+        message: None,
+        disposition: None,
+    };
+
+    if part.is("message", "rfc822") || part.is("message", "global") {
+        let after_envelope = skip_value(buf, i)?;
+        let (message, next) = parse_body_structure(buf, after_envelope)?;
+        let (lines, next) = parse_number(buf, next)?;
+        part.message = Some(Box::new(message));
+        part.lines = Some(lines);
+        i = next;
+    } else if part.media_type.eq_ignore_ascii_case("text") {
+        let (lines, next) = parse_number(buf, i)?;
+        part.lines = Some(lines);
+        i = next;
+    }
+
+    if has_more(buf, &mut i) {
+        // body-fld-md5
+        i = skip_value(buf, i)?;
+        if has_more(buf, &mut i) {
+            (part.disposition, i) = parse_disposition(buf, i)?;
+        }
+    }
+    Some((BodyStructure::Single(part), close_list(buf, i)?))
+}
+
+/// Whether another field follows before the closing paren.
+fn has_more(buf: &[u8], i: &mut usize) -> bool {
+    skip_ws(buf, i);
+    buf.get(*i).is_some_and(|b| *b != b')')
+}
+
+/// Skips any remaining fields and the closing paren.
+fn close_list(buf: &[u8], mut i: usize) -> Option<usize> {
+    while has_more(buf, &mut i) {
+        i = skip_value(buf, i)?;
+    }
+    buf.get(i).map(|_| i + 1)
+}
+
+/// Parses `body-fld-param`: `NIL` or `("name" "value" ...)`.
+fn parse_body_params(buf: &[u8], mut i: usize) -> Option<(Vec<(String, String)>, usize)> {
+    skip_ws(buf, &mut i);
+    if is_nil(buf, i) {
+        return Some((Vec::new(), i + 3));
+    }
+    if buf.get(i) != Some(&b'(') {
+        return None;
+    }
+    i += 1;
+    let mut params = Vec::new();
+    while has_more(buf, &mut i) {
+        let (name, next) = parse_string(buf, i)?;
+        let (value, next) = parse_string(buf, next)?;
+        if let Some(name) = name {
+            params.push((name, value.unwrap_or_default()));
+        }
+        i = next;
+    }
+    Some((params, i + 1))
+}
+
+/// Parses `body-fld-dsp`: `NIL` or `("attachment" params)`.
+fn parse_disposition(buf: &[u8], mut i: usize) -> Option<(Option<Disposition>, usize)> {
+    skip_ws(buf, &mut i);
+    if is_nil(buf, i) {
+        return Some((None, i + 3));
+    }
+    if buf.get(i) != Some(&b'(') {
+        return None;
+    }
+    let (kind, next) = parse_string(buf, i + 1)?;
+    let (params, next) = parse_body_params(buf, next)?;
+    let disposition = kind.map(|kind| Disposition { kind, params });
+    Some((disposition, close_list(buf, next)?))
+}
+
+/// Parses an nstring item value, keeping a literal payload byte for byte.
+fn parse_bytes_item(
+    buf: &[u8],
+    i: usize,
+    item: fn(Option<Bytes>) -> FetchData,
+) -> Option<(Option<FetchData>, usize)> {
+    let (data, j) = parse_nstring(buf, i)?;
+    Some((Some(item(data.map(Bytes::from))), j))
+}
+
+/// Parses an envelope address list: `NIL`, `()` or `((name adl mailbox host) ...)`.
+///
+/// `NIL` entries inside the list, which some servers send for unparseable addresses, are
+/// skipped.
+fn parse_address_list(buf: &[u8], mut i: usize) -> Option<(Vec<Address>, usize)> {
+    skip_ws(buf, &mut i);
+    if is_nil(buf, i) {
+        return Some((Vec::new(), i + 3));
+    }
+    if buf.get(i) != Some(&b'(') {
+        return None;
+    }
+    i += 1;
+    let mut addrs = Vec::new();
+    loop {
+        skip_ws(buf, &mut i);
+        match buf.get(i)? {
+            b')' => return Some((addrs, i + 1)),
+            _ if is_nil(buf, i) => i += 3,
+            b'(' => {
+                let (name, j) = parse_string(buf, i + 1)?;
+                let (_adl, j) = parse_string(buf, j)?;
+                let (mailbox, j) = parse_string(buf, j)?;
+                let (host, mut j) = parse_string(buf, j)?;
+                skip_ws(buf, &mut j);
+                if buf.get(j) != Some(&b')') {
+                    return None;
+                }
+                // A NIL host marks the start (mailbox = group name) or end (NIL mailbox)
+                // of an RFC 2822 group, which is not an address itself.
+                if host.is_some() {
+                    addrs.push(Address {
+                        name,
+                        mailbox,
+                        host,
+                    });
+                }
+                i = j + 1;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Parses the envelope fields after From (sender, reply-to, to, cc, bcc, in-reply-to and
+/// message-id) into `env`, returning the offset after the last one.
+fn parse_envelope_tail(buf: &[u8], i: usize, env: &mut Envelope) -> Option<usize> {
+    let (_sender, i) = parse_address_list(buf, i)?;
+    let (_reply_to, i) = parse_address_list(buf, i)?;
+    let (to, i) = parse_address_list(buf, i)?;
+    env.to = to;
+    let (cc, i) = parse_address_list(buf, i)?;
+    env.cc = cc;
+    let (bcc, i) = parse_address_list(buf, i)?;
+    env.bcc = bcc;
+    let (_in_reply_to, i) = parse_string(buf, i)?;
+    let (message_id, i) = parse_string(buf, i)?;
+    env.message_id = message_id;
+    Some(i)
+}
+
+/// Builds an [`EnvelopeSummary`] for every FETCH response in `buf` that carries a UID.
+pub fn parse_envelope_summaries(buf: &[u8]) -> Vec<EnvelopeSummary> {
+    parse_fetch_responses(buf)
+        .into_iter()
+        .filter_map(|(_seq, items)| {
+            let mut uid = None;
+            let mut summary = EnvelopeSummary {
+                uid: Uid(0),
+                date: 0,
+                subject: Box::default(),
+                from: Box::default(),
+                size: 0,
+                flags: SystemFlags::empty(),
+            };
+            for item in items {
+                match item {
+                    FetchData::Uid(u) => uid = Some(u),
+                    FetchData::InternalDate(d) => {
+                        summary.date = parse_internal_date(&d).unwrap_or_default()
+                    }
+                    FetchData::Rfc822Size(n) => summary.size = n,
+                    FetchData::Flags(flags) => summary.flags = flags.system,
+                    FetchData::Envelope(env) => {
+                        summary.subject = env.subject.unwrap_or_default().into_boxed_str();
+                        if let Some(from) = env.from.first() {
+                            summary.from = from.to_string().into_boxed_str();
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            summary.uid = uid?;
+            Some(summary)
+        })
+        .collect()
+}
+
+/// Parses `[section]<origin> nstring` starting at the `[`. The data is kept as raw bytes.
+fn parse_section_data(buf: &[u8], j: usize) -> Option<(String, Option<u32>, Option<Bytes>, usize)> {
+    let close = j + find_subsequence(&buf[j..], b"]")?;
+    let section = String::from_utf8_lossy(&buf[j + 1..close]).into_owned();
+    let mut k = close + 1;
+    let mut origin = None;
+    if buf.get(k) == Some(&b'<') {
+        let (n, next) = parse_number(buf, k + 1)?;
+        if buf.get(next) != Some(&b'>') {
+            return None;
+        }
+        origin = Some(n);
+        k = next + 1;
+    }
+    let (data, k) = parse_nstring(buf, k)?;
+    Some((section, origin, data.map(Bytes::from), k))
+}
+
+pub(crate) fn parse_flag(atom: &str) -> Flag {
+    match atom.to_ascii_lowercase().as_str() {
+        "\\seen" => Flag::Seen,
+        "\\answered" => Flag::Answered,
+        "\\flagged" => Flag::Flagged,
+        "\\deleted" => Flag::Deleted,
+        "\\draft" => Flag::Draft,
+        "\\recent" => Flag::Recent,
+        _ => Flag::Keyword(atom.to_string()),
+    }
+}
+
+/// Parses a parenthesized flag list; `NIL` is read as an empty list.
+pub(crate) fn parse_flag_list(buf: &[u8], mut i: usize) -> Option<(Vec<Flag>, usize)> {
+    skip_ws(buf, &mut i);
+    if is_nil(buf, i) {
+        return Some((Vec::new(), i + 3));
+    }
+    if buf.get(i) != Some(&b'(') {
+        return None;
+    }
+    i += 1;
+    let mut flags = Vec::new();
+    loop {
+        skip_ws(buf, &mut i);
+        if buf.get(i)? == &b')' {
+            return Some((flags, i + 1));
+        }
+        let (atom, next) = parse_atom(buf, i)?;
+        flags.push(parse_flag(&String::from_utf8_lossy(atom)));
+        i = next;
+    }
+}
+
+/// Parses a message's flag list without allocating for system flags. `NIL` is read as no
+/// flags.
+fn parse_message_flags(
+    buf: &[u8],
+    mut i: usize,
+    keywords: &mut KeywordInterner,
+) -> Option<(MessageFlags, usize)> {
+    skip_ws(buf, &mut i);
+    if is_nil(buf, i) {
+        return Some((MessageFlags::default(), i + 3));
+    }
+    if buf.get(i) != Some(&b'(') {
+        return None;
+    }
+    i += 1;
+    let mut flags = MessageFlags::default();
+    loop {
+        skip_ws(buf, &mut i);
+        if buf.get(i)? == &b')' {
+            return Some((flags, i + 1));
+        }
+        let (atom, next) = parse_atom(buf, i)?;
+        let bit = match atom.to_ascii_lowercase().as_slice() {
+            b"\\seen" => Some(SystemFlags::SEEN),
+            b"\\answered" => Some(SystemFlags::ANSWERED),
+            b"\\flagged" => Some(SystemFlags::FLAGGED),
+            b"\\deleted" => Some(SystemFlags::DELETED),
+            b"\\draft" => Some(SystemFlags::DRAFT),
+            b"\\recent" => Some(SystemFlags::RECENT),
+            _ => None,
+        };
+        match bit {
+            Some(bit) => flags.system.insert(bit),
+            None => flags
+                .keywords
+                .push(keywords.intern(&String::from_utf8_lossy(atom))),
+        }
+        i = next;
+    }
+}
+
+pub(crate) fn parse_atom(buf: &[u8], i: usize) -> Option<(&[u8], usize)> {
+    let mut j = i;
+    while j < buf.len() && !matches!(buf[j], b' ' | b'(' | b')' | b'[' | b'"' | b'\r' | b'\n') {
+        j += 1;
+    }
+    if j == i {
+        return None;
+    }
+    Some((&buf[i..j], j))
+}
+
+/// Skips one value (atom, string, literal or parenthesized list) and returns the next offset.
+fn skip_value(buf: &[u8], mut i: usize) -> Option<usize> {
+    skip_ws(buf, &mut i);
+    match buf.get(i)? {
+        b'(' => {
+            i += 1;
+            loop {
+                skip_ws(buf, &mut i);
+                if buf.get(i)? == &b')' {
+                    return Some(i + 1);
+                }
+                i = skip_value(buf, i)?;
+            }
+        }
+        b'"' => parse_quoted(buf, i + 1).map(|(_, n)| n),
+        b'{' | b'~' => parse_literal(buf, i).map(|(_, n)| n),
+        _ => {
+            let (_, mut j) = parse_atom(buf, i)?;
+            // Section specs like BODY[HEADER.FIELDS (A B)] belong to the atom.
+            if buf.get(j) == Some(&b'[') {
+                j += find_subsequence(&buf[j..], b"]")? + 1;
+            }
+            Some(j)
+        }
+    }
+}
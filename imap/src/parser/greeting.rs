@@ -12,7 +12,7 @@ pub struct Greeting<'a> {
     pub text: &'a [u8],
 }
 
-pub fn try_parse(buf: &[u8]) -> Result<Option<(Greeting, usize)>, ParserError> {
+pub fn try_parse(buf: &[u8]) -> Result<Option<(Greeting<'_>, usize)>, ParserError> {
     match parse_greeting(buf) {
         Ok((remaining, greeting)) => Ok(Some((greeting, buf.offset(remaining)))),
         Err(nom::Err::Incomplete(_)) => Err(ParserError::Incomplete),
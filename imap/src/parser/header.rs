@@ -0,0 +1,31 @@
+use crate::types::response::HeaderMap;
+
+/// Parses an RFC 5322 header block, unfolding continuation lines.
+pub fn parse_header_block(raw: &[u8]) -> HeaderMap {
+    let text = String::from_utf8_lossy(raw);
+    let mut headers = HeaderMap::new();
+    let mut current: Option<(String, String)> = None;
+
+    for line in text.split("\r\n").flat_map(|l| l.split('\n')) {
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = &mut current {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((name, value)) = current.take() {
+            headers.insert(&name, &value);
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            current = Some((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    if let Some((name, value)) = current {
+        headers.insert(&name, &value);
+    }
+    headers
+}
@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use super::fetch::{parse_nstring, skip_ws};
+
+/// Parses the `* ID (...)` response (RFC 2971) in `buf` into field/value pairs.
+///
+/// Field names are lowercased, as they are case-insensitive. `* ID NIL`, or no ID response
+/// at all, gives an empty map.
+pub fn parse_id(buf: &[u8]) -> HashMap<String, Option<String>> {
+    let mut fields = HashMap::new();
+    let Some(start) = buf
+        .windows(5)
+        .position(|w| w.eq_ignore_ascii_case(b"* ID "))
+        .filter(|&p| p == 0 || buf[p - 1] == b'\n')
+    else {
+        return fields;
+    };
+    let mut i = start + 5;
+    skip_ws(buf, &mut i);
+    if buf.get(i) != Some(&b'(') {
+        return fields;
+    }
+    i += 1;
+    loop {
+        skip_ws(buf, &mut i);
+        if buf.get(i).is_none_or(|&b| b == b')') {
+            break;
+        }
+        let Some((Some(name), next)) = parse_nstring(buf, i) else {
+            break;
+        };
+        let Some((value, next)) = parse_nstring(buf, next) else {
+            break;
+        };
+        fields.insert(
+            String::from_utf8_lossy(&name).to_lowercase(),
+            value.map(|v| String::from_utf8_lossy(&v).into_owned()),
+        );
+        i = next;
+    }
+    fields
+}
@@ -0,0 +1,505 @@
+use bytes::Bytes;
+
+use super::fetch::{
+    parse_astring, parse_atom, parse_fetch_responses_with, parse_flag_list, parse_nstring, skip_ws,
+};
+use crate::types::common::{KeywordInterner, Seq, Uid};
+use super::search::parse_search;
+use crate::ImapError;
+use crate::types::response::{
+    CopyUid, FetchData, IdleEvent, ListEntry, MailboxStatus, MailboxStatusSummary, ResponseCode,
+    UnsolicitedEvent, UntaggedResponse, Vanished,
+};
+
+/// Builds a [`MailboxStatus`] from the responses to a SELECT or EXAMINE command.
+pub fn parse_select_response(buf: &[u8], tag: &str) -> MailboxStatus {
+    let mut status = MailboxStatus::default();
+    for line in buf.split(|&b| b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if let Some(rest) = line.strip_prefix(b"* ") {
+            if let Some(vanished) = parse_vanished(line) {
+                status.vanished.extend(vanished.uids);
+            } else {
+                apply_untagged(&mut status, rest);
+            }
+        } else if let Some(rest) = line.strip_prefix(tag.as_bytes()) {
+            let upper = rest.to_ascii_uppercase();
+            if upper.starts_with(b" OK [READ-ONLY]") {
+                status.read_only = true;
+            }
+        }
+    }
+    status
+}
+
+/// Parses every `* LIST` (or `* LSUB`) response in `buf`, in order.
+///
+/// Mailbox names sent as literals are supported; malformed lines are skipped.
+pub fn parse_list(buf: &[u8]) -> Vec<ListEntry> {
+    let mut entries = Vec::new();
+    let mut i = 0;
+    while i < buf.len() {
+        let rest = &buf[i..];
+        let parsed = [&b"* LIST "[..], b"* LSUB "]
+            .iter()
+            .find(|prefix| {
+                rest.len() >= prefix.len() && rest[..prefix.len()].eq_ignore_ascii_case(prefix)
+            })
+            .and_then(|prefix| parse_list_entry(buf, i + prefix.len()));
+        match parsed {
+            Some((entry, next)) => {
+                entries.push(entry);
+                i = next;
+            }
+            None => {
+                i += rest
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(rest.len(), |n| n + 1);
+            }
+        }
+    }
+    entries
+}
+
+fn parse_list_entry(buf: &[u8], mut i: usize) -> Option<(ListEntry, usize)> {
+    skip_ws(buf, &mut i);
+    if buf.get(i) != Some(&b'(') {
+        return None;
+    }
+    i += 1;
+    let mut attributes = Vec::new();
+    loop {
+        skip_ws(buf, &mut i);
+        if buf.get(i)? == &b')' {
+            i += 1;
+            break;
+        }
+        let (atom, next) = parse_atom(buf, i)?;
+        attributes.push(String::from_utf8_lossy(atom).into_owned());
+        i = next;
+    }
+    let (delimiter, i) = parse_nstring(buf, i)?;
+    let delimiter = delimiter.and_then(|d| d.first().map(|&b| b as char));
+    let (name, i) = parse_astring(buf, i)?;
+    let entry = ListEntry {
+        attributes,
+        delimiter,
+        name: String::from_utf8_lossy(&name).into_owned(),
+    };
+    Some((entry, i))
+}
+
+/// Parses every `* STATUS mailbox (item value ...)` response in `buf`, in order.
+pub fn parse_status_responses(buf: &[u8]) -> Vec<MailboxStatusSummary> {
+    let mut summaries = Vec::new();
+    let mut i = 0;
+    while i < buf.len() {
+        let rest = &buf[i..];
+        let parsed = rest
+            .get(..9)
+            .filter(|prefix| prefix.eq_ignore_ascii_case(b"* STATUS "))
+            .and_then(|_| parse_status_entry(buf, i + 9));
+        match parsed {
+            Some((summary, next)) => {
+                summaries.push(summary);
+                i = next;
+            }
+            None => {
+                i += rest
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(rest.len(), |n| n + 1);
+            }
+        }
+    }
+    summaries
+}
+
+fn parse_status_entry(buf: &[u8], i: usize) -> Option<(MailboxStatusSummary, usize)> {
+    let (name, mut i) = parse_astring(buf, i)?;
+    let mut summary = MailboxStatusSummary {
+        mailbox: String::from_utf8_lossy(&name).into_owned(),
+        ..MailboxStatusSummary::default()
+    };
+    skip_ws(buf, &mut i);
+    if buf.get(i) != Some(&b'(') {
+        return None;
+    }
+    i += 1;
+    loop {
+        skip_ws(buf, &mut i);
+        if buf.get(i)? == &b')' {
+            return Some((summary, i + 1));
+        }
+        let (item, mut next) = parse_atom(buf, i)?;
+        skip_ws(buf, &mut next);
+        let (value, next) = parse_atom(buf, next)?;
+        let value = std::str::from_utf8(value).ok()?;
+        match item.to_ascii_uppercase().as_slice() {
+            b"MESSAGES" => summary.messages = value.parse().ok(),
+            b"RECENT" => summary.recent = value.parse().ok(),
+            b"UIDNEXT" => summary.uid_next = value.parse().ok(),
+            b"UIDVALIDITY" => summary.uid_validity = value.parse().ok(),
+            b"UNSEEN" => summary.unseen = value.parse().ok(),
+            b"HIGHESTMODSEQ" => summary.highest_modseq = value.parse().ok(),
+            _ => {}
+        }
+        i = next;
+    }
+}
+
+/// Collects the sequence numbers from every `* n EXPUNGE` line in `buf`, in order.
+///
+/// Each number refers to the mailbox as it was after the previous expunge.
+pub fn parse_expunge(buf: &[u8]) -> Vec<Seq> {
+    buf.split(|&b| b == b'\n')
+        .filter_map(|line| {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            let (n, keyword) = number_keyword(line.strip_prefix(b"* ")?)?;
+            keyword.eq_ignore_ascii_case(b"EXPUNGE").then_some(Seq(n))
+        })
+        .collect()
+}
+
+/// Returns the UIDVALIDITY and assigned UIDs from the `[APPENDUID ...]` code (RFC 4315) of
+/// the tagged completion for `tag`. Ranges in the UID set are expanded, in order.
+pub fn parse_append_uid(buf: &[u8], tag: &str) -> Option<(u32, Vec<Uid>)> {
+    let line = buf
+        .split(|&b| b == b'\n')
+        .find(|line| line.starts_with(tag.as_bytes()) && line.get(tag.len()) == Some(&b' '))?;
+    let code = response_code(line, b"APPENDUID")?;
+    let (validity, set) = code.split_once(' ')?;
+    Some((validity.parse().ok()?, parse_uid_set(set)?))
+}
+
+/// The name of the bracketed response code in the tagged completion for `tag`, e.g.
+/// `ALREADYEXISTS` for `a1 NO [ALREADYEXISTS] Mailbox exists`.
+pub fn parse_completion_code<'a>(buf: &'a [u8], tag: &str) -> Option<&'a str> {
+    let (_, code, _) = split_completion(buf, tag)?;
+    std::str::from_utf8(code?).ok()
+}
+
+/// The refusal in the tagged completion for `tag`, as [`ImapError::No`] or
+/// [`ImapError::Bad`] with its response code and text. `None` for OK or without a
+/// completion.
+pub fn parse_completion_error(buf: &[u8], tag: &str) -> Option<ImapError> {
+    let (status, code, text) = split_completion(buf, tag)?;
+    let code = code.map(|name| ResponseCode::parse(&String::from_utf8_lossy(name)));
+    let text = String::from_utf8_lossy(text).trim().to_string();
+    if status.eq_ignore_ascii_case(b"NO") {
+        Some(ImapError::No { code, text })
+    } else if status.eq_ignore_ascii_case(b"BAD") {
+        Some(ImapError::Bad { code, text })
+    } else {
+        None
+    }
+}
+
+/// The status word, response code name and text of a tagged completion.
+type Completion<'a> = (&'a [u8], Option<&'a [u8]>, &'a [u8]);
+
+/// Splits the tagged completion for `tag` into its parts.
+fn split_completion<'a>(buf: &'a [u8], tag: &str) -> Option<Completion<'a>> {
+    let line = buf
+        .split(|&b| b == b'\n')
+        .find(|line| line.starts_with(tag.as_bytes()) && line.get(tag.len()) == Some(&b' '))?;
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let rest = &line[tag.len() + 1..];
+    let (status, rest) = match rest.iter().position(|&b| b == b' ') {
+        Some(n) => (&rest[..n], &rest[n + 1..]),
+        None => (rest, &[][..]),
+    };
+    let Some(bracketed) = rest.strip_prefix(b"[") else {
+        return Some((status, None, rest));
+    };
+    let close = bracketed.iter().position(|&b| b == b']')?;
+    let inner = &bracketed[..close];
+    let name = &inner[..inner.iter().position(|&b| b == b' ').unwrap_or(inner.len())];
+    Some((status, Some(name), &bracketed[close + 1..]))
+}
+
+/// Returns the `[COPYUID ...]` code (RFC 4315) from the responses to a COPY or MOVE.
+///
+/// COPY carries it in the tagged completion, MOVE in an untagged OK (RFC 6851), so every
+/// line is searched.
+pub fn parse_copy_uid(buf: &[u8]) -> Option<CopyUid> {
+    let code = buf
+        .split(|&b| b == b'\n')
+        .find_map(|line| response_code(line, b"COPYUID"))?;
+    let mut parts = code.splitn(3, ' ');
+    let (Some(validity), Some(source), Some(destination)) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return None;
+    };
+    Some(CopyUid {
+        uid_validity: validity.parse().ok()?,
+        source: parse_uid_set(source)?,
+        destination: parse_uid_set(destination)?,
+    })
+}
+
+/// The arguments of the bracketed response code `name` in `line`, e.g. `1 2:3` for
+/// `[APPENDUID 1 2:3]`.
+fn response_code<'a>(line: &'a [u8], name: &[u8]) -> Option<&'a str> {
+    let upper = line.to_ascii_uppercase();
+    let start = upper
+        .windows(name.len() + 2)
+        .position(|w| w[0] == b'[' && &w[1..=name.len()] == name && w[name.len() + 1] == b' ')?
+        + name.len()
+        + 2;
+    let end = start + line[start..].iter().position(|&b| b == b']')?;
+    std::str::from_utf8(&line[start..end]).ok()
+}
+
+/// Parses one `* VANISHED [(EARLIER)] <uid set>` line.
+pub fn parse_vanished(line: &[u8]) -> Option<Vanished> {
+    let line = line.strip_suffix(b"\r\n").unwrap_or(line);
+    let rest = line.strip_prefix(b"* ")?;
+    if rest.len() < 9 || !rest[..9].eq_ignore_ascii_case(b"VANISHED ") {
+        return None;
+    }
+    let rest = std::str::from_utf8(&rest[9..]).ok()?.trim();
+    let (earlier, set) = match rest.get(..10) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("(EARLIER) ") => (true, &rest[10..]),
+        _ => (false, rest),
+    };
+    Some(Vanished {
+        earlier,
+        uids: parse_uid_set(set.trim())?,
+    })
+}
+
+/// Expands a set of UIDs such as `3,5:7`, in order. `*` is not allowed.
+fn parse_uid_set(set: &str) -> Option<Vec<Uid>> {
+    let mut uids = Vec::new();
+    for part in set.split(',') {
+        match part.split_once(':') {
+            Some((a, b)) => {
+                let (a, b): (u32, u32) = (a.parse().ok()?, b.parse().ok()?);
+                uids.extend((a.min(b)..=a.max(b)).map(Uid));
+            }
+            None => uids.push(Uid(part.parse().ok()?)),
+        }
+    }
+    Some(uids)
+}
+
+/// Parses one untagged line into an [`IdleEvent`]. Status responses, tagged lines and
+/// continuation requests give `None`; untagged data other than EXISTS, EXPUNGE, VANISHED, a
+/// FETCH carrying FLAGS, or the STATUS and LIST responses NOTIFY sends becomes
+/// [`IdleEvent::Other`].
+pub fn parse_idle_event(line: &[u8], keywords: &mut KeywordInterner) -> Option<IdleEvent> {
+    if let Some(vanished) = parse_vanished(line) {
+        return Some(IdleEvent::Vanished(vanished.uids));
+    }
+    let rest = line.strip_prefix(b"* ")?;
+    let trimmed = rest.strip_suffix(b"\r\n").unwrap_or(rest);
+    let other = || Some(IdleEvent::Other(Bytes::copy_from_slice(trimmed)));
+    let Some((n, keyword)) = number_keyword(trimmed) else {
+        let word = trimmed.split(|&b| b == b' ').next().unwrap_or_default();
+        return match word.to_ascii_uppercase().as_slice() {
+            b"OK" | b"NO" | b"BAD" | b"BYE" | b"PREAUTH" => None,
+            b"STATUS" => parse_status_responses(line)
+                .into_iter()
+                .next()
+                .map(IdleEvent::MailboxStatus)
+                .or_else(other),
+            b"LIST" => parse_list(line)
+                .into_iter()
+                .next()
+                .map(IdleEvent::MailboxName)
+                .or_else(other),
+            _ => other(),
+        };
+    };
+    match keyword.to_ascii_uppercase().as_slice() {
+        b"EXISTS" => Some(IdleEvent::Exists(n)),
+        b"EXPUNGE" => Some(IdleEvent::Expunge(Seq(n))),
+        b"FETCH" => {
+            let (seq, items) = parse_fetch_responses_with(line, keywords)
+                .into_iter()
+                .next()?;
+            let mut uid = None;
+            let mut flags = None;
+            for item in items {
+                match item {
+                    FetchData::Uid(u) => uid = Some(u),
+                    FetchData::Flags(f) => flags = Some(f),
+                    _ => {}
+                }
+            }
+            match flags {
+                Some(flags) => Some(IdleEvent::FlagsChanged { seq, uid, flags }),
+                None => other(),
+            }
+        }
+        _ => other(),
+    }
+}
+
+/// Parses one untagged line into [`UntaggedResponse`]s: one per data item of a FETCH, at
+/// most one otherwise. Tagged lines, continuation requests and status responses other than
+/// BYE or an `[ALERT]` give nothing.
+pub fn parse_untagged(line: &[u8], keywords: &mut KeywordInterner) -> Vec<UntaggedResponse> {
+    let Some(rest) = line.strip_prefix(b"* ") else {
+        return Vec::new();
+    };
+    let trimmed = rest.strip_suffix(b"\r\n").unwrap_or(rest);
+    let text = |skip: usize| String::from_utf8_lossy(&trimmed[skip..]).trim().to_string();
+    let other = || vec![UntaggedResponse::Other(Bytes::copy_from_slice(trimmed))];
+    let Some((n, keyword)) = number_keyword(trimmed) else {
+        let word = trimmed.split(|&b| b == b' ').next().unwrap_or_default();
+        return match word.to_ascii_uppercase().as_slice() {
+            b"BYE" => vec![UntaggedResponse::Bye(text(word.len()))],
+            b"OK" | b"NO" | b"BAD" => {
+                let code = trimmed[word.len()..].trim_ascii_start();
+                match code.get(..7) {
+                    Some(alert) if alert.eq_ignore_ascii_case(b"[ALERT]") => {
+                        let skip = trimmed.len() - code.len() + 7;
+                        vec![UntaggedResponse::Alert(text(skip))]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            b"PREAUTH" => Vec::new(),
+            b"FLAGS" => match parse_flag_list(trimmed, 5) {
+                Some((flags, _)) => vec![UntaggedResponse::Flags(flags)],
+                None => other(),
+            },
+            b"SEARCH" => vec![UntaggedResponse::Search(parse_search(line))],
+            _ => other(),
+        };
+    };
+    match keyword.to_ascii_uppercase().as_slice() {
+        b"EXISTS" => vec![UntaggedResponse::Exists(n)],
+        b"RECENT" => vec![UntaggedResponse::Recent(n)],
+        b"EXPUNGE" => vec![UntaggedResponse::Expunge(Seq(n))],
+        b"FETCH" => match parse_fetch_responses_with(line, keywords).into_iter().next() {
+            Some((seq, items)) => items
+                .into_iter()
+                .map(|data| UntaggedResponse::Fetch { seq, data })
+                .collect(),
+            None => other(),
+        },
+        _ => other(),
+    }
+}
+
+/// Parses one line into an [`UnsolicitedEvent`], by way of [`parse_untagged`]. A FETCH
+/// becomes [`UnsolicitedEvent::FlagsChanged`] if it carries FLAGS; other responses give
+/// `None`.
+pub fn parse_unsolicited_event(
+    line: &[u8],
+    keywords: &mut KeywordInterner,
+) -> Option<UnsolicitedEvent> {
+    let mut fetched = None;
+    let mut uid = None;
+    let mut flags = None;
+    for response in parse_untagged(line, keywords) {
+        match response {
+            UntaggedResponse::Exists(n) => return Some(UnsolicitedEvent::Exists(n)),
+            UntaggedResponse::Recent(n) => return Some(UnsolicitedEvent::Recent(n)),
+            UntaggedResponse::Expunge(seq) => return Some(UnsolicitedEvent::Expunge(seq)),
+            UntaggedResponse::Bye(text) => return Some(UnsolicitedEvent::Bye(text)),
+            UntaggedResponse::Alert(text) => return Some(UnsolicitedEvent::Alert(text)),
+            UntaggedResponse::Fetch { seq, data } => {
+                fetched = Some(seq);
+                match data {
+                    FetchData::Uid(u) => uid = Some(u),
+                    FetchData::Flags(f) => flags = Some(f),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(UnsolicitedEvent::FlagsChanged {
+        seq: fetched?,
+        uid,
+        flags: flags?,
+    })
+}
+
+/// Applies one untagged response received while the mailbox is selected to `status`.
+///
+/// EXPUNGE and VANISHED lower `exists`, and EXPUNGE shifts `unseen` down when it removes
+/// an earlier message. Removing the first unseen message itself, or any VANISHED, leaves
+/// `unseen` unknown. Other lines, including tagged ones, are ignored.
+pub fn update_mailbox_status(status: &mut MailboxStatus, line: &[u8]) {
+    let line = line.strip_suffix(b"\r\n").unwrap_or(line);
+    let Some(rest) = line.strip_prefix(b"* ") else {
+        return;
+    };
+    if let Some(vanished) = parse_vanished(line) {
+        if !vanished.earlier {
+            let removed = u32::try_from(vanished.uids.len()).unwrap_or(u32::MAX);
+            status.exists = status.exists.saturating_sub(removed);
+            status.unseen = None;
+        }
+        return;
+    }
+    match number_keyword(rest) {
+        Some((n, keyword)) if keyword.eq_ignore_ascii_case(b"EXPUNGE") => {
+            status.exists = status.exists.saturating_sub(1);
+            status.unseen = match status.unseen {
+                Some(unseen) if unseen > n => Some(unseen - 1),
+                Some(unseen) if unseen < n => Some(unseen),
+                _ => None,
+            };
+        }
+        _ => apply_untagged(status, rest),
+    }
+}
+
+fn apply_untagged(status: &mut MailboxStatus, rest: &[u8]) {
+    if let Some((n, keyword)) = number_keyword(rest) {
+        match keyword.to_ascii_uppercase().as_slice() {
+            b"EXISTS" => status.exists = n,
+            b"RECENT" => status.recent = n,
+            _ => {}
+        }
+        return;
+    }
+
+    let upper = rest.to_ascii_uppercase();
+    if upper.starts_with(b"FLAGS ") {
+        if let Some((flags, _)) = parse_flag_list(rest, 6) {
+            status.flags = flags;
+        }
+    } else if let Some(code) = upper.strip_prefix(b"OK [") {
+        let code_start = rest.len() - code.len();
+        if code.starts_with(b"PERMANENTFLAGS ") {
+            if let Some((flags, _)) = parse_flag_list(rest, code_start + 15) {
+                status.permanent_flags = flags;
+            }
+        } else if let Some(v) = code_number(code, b"UIDVALIDITY ") {
+            status.uid_validity = Some(v);
+        } else if let Some(v) = code_number(code, b"UIDNEXT ") {
+            status.uid_next = Some(v);
+        } else if let Some(v) = code_number(code, b"UNSEEN ") {
+            status.unseen = Some(v);
+        } else if let Some(v) = code_number(code, b"HIGHESTMODSEQ ") {
+            status.highest_modseq = Some(v);
+        }
+    }
+}
+
+/// Splits `<number> <keyword>...` as used by EXISTS/RECENT/EXPUNGE.
+pub(crate) fn number_keyword(rest: &[u8]) -> Option<(u32, &[u8])> {
+    let space = rest.iter().position(|&b| b == b' ')?;
+    let n = std::str::from_utf8(&rest[..space]).ok()?.parse().ok()?;
+    let keyword = &rest[space + 1..];
+    let end = keyword
+        .iter()
+        .position(|&b| b == b' ')
+        .unwrap_or(keyword.len());
+    Some((n, &keyword[..end]))
+}
+
+fn code_number<T: std::str::FromStr>(code: &[u8], name: &[u8]) -> Option<T> {
+    let value = code.strip_prefix(name)?;
+    let end = value.iter().position(|b| !b.is_ascii_digit())?;
+    std::str::from_utf8(&value[..end]).ok()?.parse().ok()
+}
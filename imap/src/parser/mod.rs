@@ -2,9 +2,17 @@ use nom::{IResult, Parser, branch::alt, bytes::streaming::tag_no_case, combinato
 use thiserror::Error;
 use crate::types::common::Status;
 
+pub mod acl;
 pub mod auth;
+pub mod capability;
+pub mod datetime;
 pub mod fetch;
 pub mod greeting;
+pub mod header;
+pub mod id;
+pub mod mailbox;
+pub mod quota;
+pub mod search;
 
 #[derive(Error, Debug)]
 pub enum ParserError {
@@ -23,6 +31,39 @@ pub fn parse_status(i: &[u8]) -> IResult<&[u8], Status> {
     .parse(i)
 }
 
+/// Returns the length announced by a trailing `{n}` (or `{n+}` / `~{n}`) literal marker
+/// on a CRLF-terminated line. A length too large for `usize` is returned as `usize::MAX`.
+pub fn literal_announcement(line: &[u8]) -> Option<usize> {
+    let line = line.strip_suffix(b"\r\n")?;
+    let line = line.strip_suffix(b"}")?;
+    let line = line.strip_suffix(b"+").unwrap_or(line);
+    let open = line.iter().rposition(|&b| b == b'{')?;
+    let digits = &line[open + 1..];
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    Some(
+        digits
+            .iter()
+            .try_fold(0usize, |n, d| n.checked_mul(10)?.checked_add(usize::from(d - b'0')))
+            .unwrap_or(usize::MAX),
+    )
+}
+
+/// The remainder of each line in `buf` starting with `* <keyword> `, without its CRLF.
+pub(crate) fn untagged_lines<'a>(
+    buf: &'a [u8],
+    keyword: &'a str,
+) -> impl Iterator<Item = &'a [u8]> {
+    buf.split(|&b| b == b'\n').filter_map(move |line| {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let rest = line.strip_prefix(b"* ")?;
+        let word = rest.get(..keyword.len())?;
+        (word.eq_ignore_ascii_case(keyword.as_bytes()) && rest.get(keyword.len()) == Some(&b' '))
+            .then(|| &rest[keyword.len() + 1..])
+    })
+}
+
 #[derive(Debug, Clone)]
 pub enum Response<'a> {
     Tagged {
@@ -0,0 +1,58 @@
+//! QUOTA and QUOTAROOT responses (RFC 9208, formerly RFC 2087).
+
+use super::fetch::{parse_astring, skip_ws};
+use super::untagged_lines;
+use crate::types::response::{Quota, QuotaResource, QuotaRoot};
+
+/// Parses every `* QUOTA root (resource usage limit ...)` line in `buf`.
+pub fn parse_quotas(buf: &[u8]) -> Vec<Quota> {
+    untagged_lines(buf, "QUOTA")
+        .filter_map(parse_quota)
+        .collect()
+}
+
+/// Parses a GETQUOTAROOT reply: the `* QUOTAROOT` line and the `* QUOTA` lines after it.
+pub fn parse_quota_root(buf: &[u8]) -> QuotaRoot {
+    let mut root = QuotaRoot {
+        quotas: parse_quotas(buf),
+        ..QuotaRoot::default()
+    };
+    if let Some(rest) = untagged_lines(buf, "QUOTAROOT").next() {
+        let mut names = Vec::new();
+        let mut i = 0;
+        while let Some((name, next)) = parse_astring(rest, i) {
+            names.push(String::from_utf8_lossy(&name).into_owned());
+            i = next;
+        }
+        if !names.is_empty() {
+            root.mailbox = names.remove(0);
+            root.roots = names;
+        }
+    }
+    root
+}
+
+fn parse_quota(buf: &[u8]) -> Option<Quota> {
+    let (root, mut i) = parse_astring(buf, 0)?;
+    skip_ws(buf, &mut i);
+    let list = buf.get(i..)?.strip_prefix(b"(")?;
+    let list = &list[..list.iter().position(|&b| b == b')')?];
+    let words: Vec<&str> = std::str::from_utf8(list)
+        .ok()?
+        .split_ascii_whitespace()
+        .collect();
+    let resources = words
+        .chunks_exact(3)
+        .map(|chunk| {
+            Some(QuotaResource {
+                name: chunk[0].to_ascii_uppercase(),
+                usage: chunk[1].parse().ok()?,
+                limit: chunk[2].parse().ok()?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(Quota {
+        root: String::from_utf8_lossy(&root).into_owned(),
+        resources,
+    })
+}
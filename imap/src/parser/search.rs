@@ -0,0 +1,75 @@
+use super::capability::untagged_atoms;
+use super::untagged_lines;
+use crate::types::command::SequenceSet;
+use crate::types::response::EsearchResult;
+
+/// Collects the numbers from every `* SEARCH ...` line in `buf`.
+pub fn parse_search(buf: &[u8]) -> Vec<u32> {
+    untagged_atoms(buf, "SEARCH")
+        .iter()
+        .filter_map(|n| n.parse().ok())
+        .collect()
+}
+
+/// Parses the `* ESEARCH` reply to the command tagged `tag`.
+///
+/// Replies without a `(TAG ...)` correlator are accepted too. No reply at all gives an
+/// empty result, as servers may omit it when nothing matched.
+pub fn parse_esearch(buf: &[u8], tag: &str) -> EsearchResult {
+    for rest in untagged_lines(buf, "ESEARCH") {
+        let text = String::from_utf8_lossy(rest);
+        let mut text = text.trim();
+        let mut result = EsearchResult::default();
+        if let Some(correlator) = text.strip_prefix('(') {
+            let Some((inner, after)) = correlator.split_once(')') else {
+                continue;
+            };
+            result.tag = inner
+                .trim()
+                .split_once(' ')
+                .filter(|(key, _)| key.eq_ignore_ascii_case("TAG"))
+                .map(|(_, t)| t.trim().trim_matches('"').to_string());
+            text = after;
+        }
+        if result.tag.as_deref().is_some_and(|t| t != tag) {
+            continue;
+        }
+
+        let mut words = text.split_ascii_whitespace();
+        while let Some(name) = words.next() {
+            if name.eq_ignore_ascii_case("UID") {
+                result.uid = true;
+                continue;
+            }
+            let Some(value) = words.next() else {
+                break;
+            };
+            match name.to_ascii_uppercase().as_str() {
+                "MIN" => result.min = value.parse().ok(),
+                "MAX" => result.max = value.parse().ok(),
+                "COUNT" => result.count = value.parse().ok(),
+                "ALL" => result.all = SequenceSet::parse(value),
+                "MODSEQ" => result.modseq = value.parse().ok(),
+                _ => {}
+            }
+        }
+        return result;
+    }
+    EsearchResult::default()
+}
+
+/// Collects the numbers from every `* SORT ...` line in `buf`.
+pub fn parse_sort(buf: &[u8]) -> Vec<u32> {
+    untagged_atoms(buf, "SORT")
+        .iter()
+        .filter_map(|n| n.parse().ok())
+        .collect()
+}
+
+/// Returns the comparator reported by `* COMPARATOR <active> [<matching>...]`, if any.
+pub fn parse_active_comparator(buf: &[u8]) -> Option<String> {
+    untagged_atoms(buf, "COMPARATOR")
+        .into_iter()
+        .next()
+        .map(|c| c.trim_matches('"').to_string())
+}
@@ -0,0 +1,36 @@
+//! SASL mechanism helpers for AUTHENTICATE.
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The base64-encoded PLAIN (RFC 4616) response: `authzid NUL authcid NUL password`.
+/// The authorization identity is left empty, i.e. the same as `user`.
+pub fn plain_response(user: &str, pass: &str) -> String {
+    let mut message = Vec::with_capacity(user.len() + pass.len() + 2);
+    message.push(0);
+    message.extend_from_slice(user.as_bytes());
+    message.push(0);
+    message.extend_from_slice(pass.as_bytes());
+    encode_base64(&message)
+}
+
+/// Standard base64 with padding, as AUTHENTICATE uses (RFC 4648).
+pub fn encode_base64(input: &[u8]) -> String {
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
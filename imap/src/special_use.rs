@@ -0,0 +1,247 @@
+//! Mailbox roles (RFC 6154), with a fallback for servers without SPECIAL-USE.
+
+use crate::types::response::ListEntry;
+use crate::utf7;
+
+/// What a mailbox is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MailboxRole {
+    All,
+    Archive,
+    Drafts,
+    Flagged,
+    Junk,
+    Sent,
+    Trash,
+}
+
+impl MailboxRole {
+    pub const ALL: [MailboxRole; 7] = [
+        MailboxRole::All,
+        MailboxRole::Archive,
+        MailboxRole::Drafts,
+        MailboxRole::Flagged,
+        MailboxRole::Junk,
+        MailboxRole::Sent,
+        MailboxRole::Trash,
+    ];
+
+    /// The SPECIAL-USE attribute for this role, e.g. `\Sent`.
+    pub fn attribute(self) -> &'static str {
+        match self {
+            MailboxRole::All => "\\All",
+            MailboxRole::Archive => "\\Archive",
+            MailboxRole::Drafts => "\\Drafts",
+            MailboxRole::Flagged => "\\Flagged",
+            MailboxRole::Junk => "\\Junk",
+            MailboxRole::Sent => "\\Sent",
+            MailboxRole::Trash => "\\Trash",
+        }
+    }
+
+    pub fn from_attribute(attribute: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|role| role.attribute().eq_ignore_ascii_case(attribute))
+    }
+}
+
+/// Well-known folder names per role, lowercase, as created by common clients and servers
+/// in various languages.
+const NAMES: &[(MailboxRole, &[&str])] = &[
+    (
+        MailboxRole::Sent,
+        &[
+            "sent",
+            "sent items",
+            "sent messages",
+            "sent mail",
+            "envoyés",
+            "éléments envoyés",
+            "messages envoyés",
+            "gesendet",
+            "gesendete elemente",
+            "gesendete objekte",
+            "enviados",
+            "elementos enviados",
+            "itens enviados",
+            "inviati",
+            "posta inviata",
+            "elementi inviati",
+            "verzonden",
+            "verzonden items",
+            "skickat",
+            "skickade objekt",
+            "sendt",
+            "sendte elementer",
+            "wysłane",
+            "elementy wysłane",
+            "odeslané",
+            "отправленные",
+            "lähetetyt",
+            "送信済み",
+            "已发送",
+        ],
+    ),
+    (
+        MailboxRole::Drafts,
+        &[
+            "drafts",
+            "draft",
+            "brouillons",
+            "entwürfe",
+            "borradores",
+            "rascunhos",
+            "bozze",
+            "concepten",
+            "utkast",
+            "kladder",
+            "kopie robocze",
+            "koncepty",
+            "черновики",
+            "luonnokset",
+            "下書き",
+            "草稿",
+        ],
+    ),
+    (
+        MailboxRole::Trash,
+        &[
+            "trash",
+            "deleted",
+            "deleted items",
+            "deleted messages",
+            "bin",
+            "corbeille",
+            "éléments supprimés",
+            "papierkorb",
+            "gelöschte elemente",
+            "gelöschte objekte",
+            "papelera",
+            "elementos eliminados",
+            "lixeira",
+            "lixo",
+            "itens excluídos",
+            "cestino",
+            "posta eliminata",
+            "elementi eliminati",
+            "prullenbak",
+            "verwijderde items",
+            "papperskorgen",
+            "borttagna objekt",
+            "papirkurv",
+            "slettede elementer",
+            "kosz",
+            "elementy usunięte",
+            "koš",
+            "корзина",
+            "удаленные",
+            "roskakori",
+            "ゴミ箱",
+            "已删除",
+        ],
+    ),
+    (
+        MailboxRole::Junk,
+        &[
+            "junk",
+            "spam",
+            "junk e-mail",
+            "junk email",
+            "bulk mail",
+            "courrier indésirable",
+            "indésirables",
+            "junk-e-mail",
+            "spamverdacht",
+            "correo no deseado",
+            "lixo eletrônico",
+            "posta indesiderata",
+            "ongewenste e-mail",
+            "skräppost",
+            "uønsket e-post",
+            "wiadomości-śmieci",
+            "nevyžádaná pošta",
+            "нежелательная почта",
+            "спам",
+            "roskaposti",
+            "迷惑メール",
+            "垃圾邮件",
+        ],
+    ),
+    (
+        MailboxRole::Archive,
+        &[
+            "archive",
+            "archives",
+            "archiv",
+            "archivo",
+            "arquivo",
+            "archivio",
+            "archief",
+            "arkiv",
+            "archiwum",
+            "архив",
+            "arkisto",
+            "アーカイブ",
+            "存档",
+        ],
+    ),
+    (
+        MailboxRole::All,
+        &["all mail", "tous les messages", "alle nachrichten", "todos"],
+    ),
+    (
+        MailboxRole::Flagged,
+        &["starred", "flagged", "suivis", "markiert"],
+    ),
+];
+
+/// Guesses a role from a mailbox's name alone, using its last hierarchy component.
+pub fn role_from_name(name: &str, delimiter: Option<char>) -> Option<MailboxRole> {
+    let decoded = utf7::decode(name).unwrap_or_else(|| name.to_string());
+    let leaf = match delimiter {
+        Some(d) => decoded.rsplit(d).next().unwrap_or(&decoded),
+        None => &decoded,
+    };
+    let leaf = leaf.trim().to_lowercase();
+    NAMES
+        .iter()
+        .find(|(_, names)| names.contains(&leaf.as_str()))
+        .map(|(role, _)| *role)
+}
+
+/// Assigns mailboxes from a LIST response to roles.
+///
+/// SPECIAL-USE attributes win; roles still unassigned are filled from the name table,
+/// taking the first matching mailbox in LIST order. INBOX and `\Noselect` mailboxes never
+/// get a role. Names are returned as sent, ready for SELECT.
+pub fn resolve_roles(entries: &[ListEntry]) -> Vec<(MailboxRole, String)> {
+    let candidates: Vec<&ListEntry> = entries
+        .iter()
+        .filter(|e| !e.name.eq_ignore_ascii_case("INBOX"))
+        .filter(|e| !e.has_attribute("\\Noselect") && !e.has_attribute("\\NonExistent"))
+        .collect();
+
+    let mut roles: Vec<(MailboxRole, String)> = Vec::new();
+    for entry in &candidates {
+        for role in entry
+            .attributes
+            .iter()
+            .filter_map(|a| MailboxRole::from_attribute(a))
+        {
+            if !roles.iter().any(|(r, _)| *r == role) {
+                roles.push((role, entry.name.clone()));
+            }
+        }
+    }
+    for entry in &candidates {
+        if let Some(role) = role_from_name(&entry.name, entry.delimiter)
+            && !roles
+                .iter()
+                .any(|(r, name)| *r == role || *name == entry.name)
+        {
+            roles.push((role, entry.name.clone()));
+        }
+    }
+    roles
+}
@@ -1,16 +1,73 @@
 use crate::error::ImapError;
+use crate::x509::{self, CertificateInfo};
+use rustls::client::Resumption;
 use rustls::pki_types::ServerName;
-use rustls::{ClientConfig, RootCertStore};
+use rustls::{ClientConfig, ClientConnection, RootCertStore};
 use std::sync::Arc;
 
+/// What was negotiated for a TLS connection, for display or custom policy checks.
+#[derive(Debug, Clone)]
+pub struct TlsInfo {
+    /// Protocol version, e.g. `TLSv1_3`.
+    pub protocol: Option<String>,
+    /// Cipher suite, e.g. `TLS13_AES_256_GCM_SHA384`.
+    pub cipher_suite: Option<String>,
+    /// The server's certificate chain, leaf first. Certificates that fail to parse are
+    /// left out.
+    pub peer_certificates: Vec<CertificateInfo>,
+}
+
+impl TlsInfo {
+    pub fn from_connection(conn: &ClientConnection) -> Self {
+        Self {
+            protocol: conn.protocol_version().map(|v| format!("{:?}", v)),
+            cipher_suite: conn
+                .negotiated_cipher_suite()
+                .map(|s| format!("{:?}", s.suite())),
+            peer_certificates: conn
+                .peer_certificates()
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|cert| x509::parse_certificate(cert))
+                .collect(),
+        }
+    }
+
+    /// The server's own certificate.
+    pub fn leaf(&self) -> Option<&CertificateInfo> {
+        self.peer_certificates.first()
+    }
+}
+
 pub fn create_tls_config() -> Arc<ClientConfig> {
-    let root_store = RootCertStore {
+    create_tls_config_with_resumption(Resumption::default())
+}
+
+/// Like [`create_tls_config`], with an explicit session resumption policy.
+///
+/// Connections sharing the returned config can resume earlier TLS sessions (TLS 1.3
+/// tickets, TLS 1.2 session ids) instead of performing a full handshake.
+pub fn create_tls_config_with_resumption(resumption: Resumption) -> Arc<ClientConfig> {
+    create_tls_config_with_roots(webpki_root_store(), resumption)
+}
+
+/// The Mozilla root certificates bundled with the crate.
+pub fn webpki_root_store() -> RootCertStore {
+    RootCertStore {
         roots: webpki_roots::TLS_SERVER_ROOTS.into(),
-    };
+    }
+}
 
+/// Like [`create_tls_config_with_resumption`], trusting `root_store` instead of the
+/// bundled roots.
+pub fn create_tls_config_with_roots(
+    root_store: RootCertStore,
+    resumption: Resumption,
+) -> Arc<ClientConfig> {
     let mut config = rustls::ClientConfig::builder()
         .with_root_certificates(root_store)
         .with_no_client_auth();
+    config.resumption = resumption;
 
     if cfg!(debug_assertions) {
         config.key_log = Arc::new(rustls::KeyLogFile::new());
@@ -19,6 +76,170 @@ pub fn create_tls_config() -> Arc<ClientConfig> {
     Arc::new(config)
 }
 
+/// Like [`create_tls_config_with_resumption`], but skipping certificate checks, for test
+/// servers with self-signed certificates. Never use this against a real server.
+///
+/// `accept_invalid_certs` accepts any certificate, including expired and self-signed ones
+/// and ones for other host names. `accept_invalid_hostnames` only accepts a trusted
+/// certificate issued for a different host name. The handshake signatures are still
+/// verified, so the server must hold the key of the certificate it presents.
+#[cfg(feature = "dangerous-tls")]
+pub fn create_tls_config_dangerous(
+    root_store: RootCertStore,
+    resumption: Resumption,
+    accept_invalid_certs: bool,
+    accept_invalid_hostnames: bool,
+) -> Arc<ClientConfig> {
+    let roots = Arc::new(root_store);
+    let config = create_tls_config_with_roots(RootCertStore::clone(&roots), resumption);
+    if !accept_invalid_certs && !accept_invalid_hostnames {
+        return config;
+    }
+
+    let inner = rustls::client::WebPkiServerVerifier::builder(roots)
+        .build()
+        .expect("a root store of parsed trust anchors is valid");
+    let mut config = Arc::unwrap_or_clone(config);
+    config
+        .dangerous()
+        .set_certificate_verifier(Arc::new(DangerousVerifier {
+            inner,
+            accept_invalid_certs,
+            accept_invalid_hostnames,
+        }));
+    Arc::new(config)
+}
+
+#[cfg(feature = "dangerous-tls")]
+#[derive(Debug)]
+struct DangerousVerifier {
+    inner: Arc<rustls::client::WebPkiServerVerifier>,
+    accept_invalid_certs: bool,
+    accept_invalid_hostnames: bool,
+}
+
+#[cfg(feature = "dangerous-tls")]
+impl rustls::client::danger::ServerCertVerifier for DangerousVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::pki_types::CertificateDer<'_>,
+        intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        use rustls::{CertificateError, Error};
+
+        if self.accept_invalid_certs {
+            tracing::warn!(server_name = %server_name.to_str(), "Accepting the server certificate unchecked");
+            return Ok(rustls::client::danger::ServerCertVerified::assertion());
+        }
+        match self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        ) {
+            Err(Error::InvalidCertificate(
+                CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. },
+            )) if self.accept_invalid_hostnames => {
+                tracing::warn!(server_name = %server_name.to_str(), "Accepting a certificate for another host name");
+                Ok(rustls::client::danger::ServerCertVerified::assertion())
+            }
+            result => result,
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+/// Bundles of trusted certificates maintained by the operating system, system-wide
+/// (Debian and Ubuntu, Fedora and RHEL, openSUSE, Alpine, macOS and the BSDs).
+#[cfg(feature = "system-roots")]
+const SYSTEM_BUNDLES: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/ca-trust/extracted/pem/tls-ca-bundle.pem",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/ssl/ca-bundle.pem",
+    "/etc/ssl/cert.pem",
+];
+
+/// Adds the certificates of the Unix PEM trust bundle to `root_store`, so that roots
+/// an administrator added to it (e.g. a corporate proxy CA) are honored.
+///
+/// Reads `SSL_CERT_FILE` and the files in the colon-separated `SSL_CERT_DIR` when set,
+/// like OpenSSL, and the system PEM bundle otherwise. Certificates that fail to parse are
+/// skipped. Returns how many were added; fails with [`ImapError::NoTrustBundle`] if no
+/// source could be read.
+///
+/// Stores that are not PEM files, such as the macOS keychain and the Windows certificate
+/// store, are not read; on those systems point `SSL_CERT_FILE` at an exported bundle.
+#[cfg(feature = "system-roots")]
+pub fn add_system_roots(root_store: &mut RootCertStore) -> Result<usize, ImapError> {
+    use rustls::pki_types::CertificateDer;
+    use rustls::pki_types::pem::PemObject;
+    use std::path::PathBuf;
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    if let Some(file) = std::env::var_os("SSL_CERT_FILE") {
+        files.push(file.into());
+    }
+    if let Some(dirs) = std::env::var_os("SSL_CERT_DIR") {
+        for dir in std::env::split_paths(&dirs) {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                tracing::warn!(dir = %dir.display(), "Cannot read certificate directory");
+                continue;
+            };
+            files.extend(entries.flatten().map(|e| e.path()).filter(|p| p.is_file()));
+        }
+    }
+    if files.is_empty()
+        && let Some(bundle) = SYSTEM_BUNDLES
+            .iter()
+            .find(|p| std::path::Path::new(p).is_file())
+    {
+        files.push(bundle.into());
+    }
+    if files.is_empty() {
+        return Err(ImapError::NoTrustBundle);
+    }
+
+    let mut added = 0;
+    for file in &files {
+        let Ok(certs) = CertificateDer::pem_file_iter(file) else {
+            tracing::warn!(file = %file.display(), "Cannot read certificate file");
+            continue;
+        };
+        let (ok, ignored) = root_store.add_parsable_certificates(certs.flatten());
+        if ignored > 0 {
+            tracing::debug!(file = %file.display(), ignored, "Skipped unusable certificates");
+        }
+        added += ok;
+    }
+    Ok(added)
+}
+
 pub fn parse_server_name(addr: &str) -> Result<ServerName<'static>, ImapError> {
     let (host, _) = addr
         .rsplit_once(':')
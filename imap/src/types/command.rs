@@ -1,5 +1,7 @@
-use crate::format::quote_astring;
+use crate::format::{needs_literal, quote_astring};
+use crate::types::common::{Capability, Date, Rights, Seq, Uid};
 use std::fmt::{self, Display};
+use std::ops::{Range, RangeInclusive};
 
 #[derive(Debug, Clone)]
 pub enum SequenceBound {
@@ -43,6 +45,186 @@ impl SequenceSet {
     pub fn is_empty(&self) -> bool {
         self.parts.is_empty()
     }
+
+    /// Parses a sequence set as sent by the server, e.g. `2,10:15,20`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let bound = |b: &str| match b {
+            "*" => Some(SequenceBound::Star),
+            _ => b.parse().ok().map(SequenceBound::Number),
+        };
+        let mut set = Self::new();
+        for part in s.split(',') {
+            let range = match part.split_once(':') {
+                Some((start, end)) => SequenceRange::Range(bound(start)?, bound(end)?),
+                None => SequenceRange::Single(bound(part)?),
+            };
+            set.parts.push(range);
+        }
+        Some(set)
+    }
+
+    /// The numbers in the set, range by range. `*` has no fixed value and is skipped.
+    pub fn numbers(&self) -> impl Iterator<Item = u32> + '_ {
+        self.parts
+            .iter()
+            .filter_map(|part| match part {
+                SequenceRange::Single(SequenceBound::Number(n)) => Some(*n..=*n),
+                SequenceRange::Range(SequenceBound::Number(a), SequenceBound::Number(b)) => {
+                    Some(*a.min(b)..=*a.max(b))
+                }
+                _ => None,
+            })
+            .flatten()
+    }
+
+    /// How many numbers the set holds, without expanding it; `*` is not counted.
+    pub fn count(&self) -> u64 {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                SequenceRange::Single(SequenceBound::Number(_)) => 1,
+                SequenceRange::Range(SequenceBound::Number(a), SequenceBound::Number(b)) => {
+                    u64::from(a.abs_diff(*b)) + 1
+                }
+                _ => 0,
+            })
+            .sum()
+    }
+
+    /// Compresses `numbers` into ranges and splits them into sets whose serialized form
+    /// stays within `max_len` bytes, so each set fits in a single command line.
+    pub fn batched<T: Copy + Into<u32>>(numbers: &[T], max_len: usize) -> Vec<SequenceSet> {
+        let mut sorted: Vec<u32> = numbers.iter().map(|&n| n.into()).collect();
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        for n in sorted {
+            match runs.last_mut() {
+                Some((_, end)) if n == *end + 1 => *end = n,
+                _ => runs.push((n, n)),
+            }
+        }
+
+        let mut batches = Vec::new();
+        let mut current = SequenceSet::new();
+        let mut len = 0;
+        for (start, end) in runs {
+            let part = SequenceRange::numbers(start, end);
+            let part_len = part.to_string().len();
+            if !current.is_empty() && len + 1 + part_len > max_len {
+                batches.push(std::mem::take(&mut current));
+                len = 0;
+            }
+            len += part_len + usize::from(!current.is_empty());
+            current.parts.push(part);
+        }
+        if !current.is_empty() {
+            batches.push(current);
+        }
+        batches
+    }
+}
+
+impl SequenceRange {
+    fn numbers(start: u32, end: u32) -> Self {
+        if start == end {
+            SequenceRange::Single(SequenceBound::Number(start))
+        } else {
+            SequenceRange::Range(SequenceBound::Number(start), SequenceBound::Number(end))
+        }
+    }
+}
+
+impl From<u32> for SequenceSet {
+    fn from(n: u32) -> Self {
+        Self::new().add_single(n)
+    }
+}
+
+impl From<Uid> for SequenceSet {
+    fn from(uid: Uid) -> Self {
+        uid.0.into()
+    }
+}
+
+impl From<Seq> for SequenceSet {
+    fn from(seq: Seq) -> Self {
+        seq.0.into()
+    }
+}
+
+/// `start..end` as `start:end-1`; an empty range gives an empty set.
+impl From<Range<u32>> for SequenceSet {
+    fn from(range: Range<u32>) -> Self {
+        match range.end.checked_sub(1) {
+            Some(last) if range.start <= last => (range.start..=last).into(),
+            _ => Self::new(),
+        }
+    }
+}
+
+impl From<RangeInclusive<u32>> for SequenceSet {
+    fn from(range: RangeInclusive<u32>) -> Self {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return Self::new();
+        }
+        Self {
+            parts: vec![SequenceRange::numbers(start, end)],
+        }
+    }
+}
+
+/// Slices, arrays, vectors and iterators of plain numbers, [`Uid`]s or [`Seq`]s. The numbers
+/// keep the order given, with runs of consecutive ones joined into ranges: `[1, 2, 3, 7, 5]`
+/// becomes `1:3,7,5`.
+macro_rules! sequence_set_from_numbers {
+    ($($number:ty),*) => {$(
+        impl From<&[$number]> for SequenceSet {
+            fn from(numbers: &[$number]) -> Self {
+                numbers.iter().copied().collect()
+            }
+        }
+
+        impl<const N: usize> From<&[$number; N]> for SequenceSet {
+            fn from(numbers: &[$number; N]) -> Self {
+                numbers.iter().copied().collect()
+            }
+        }
+
+        impl From<Vec<$number>> for SequenceSet {
+            fn from(numbers: Vec<$number>) -> Self {
+                numbers.into_iter().collect()
+            }
+        }
+
+        impl FromIterator<$number> for SequenceSet {
+            fn from_iter<I: IntoIterator<Item = $number>>(iter: I) -> Self {
+                Self::from_runs(iter.into_iter().map(u32::from))
+            }
+        }
+    )*};
+}
+
+sequence_set_from_numbers!(u32, Uid, Seq);
+
+impl SequenceSet {
+    fn from_runs(numbers: impl Iterator<Item = u32>) -> Self {
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        for n in numbers {
+            match runs.last_mut() {
+                Some((_, end)) if end.checked_add(1) == Some(n) => *end = n,
+                _ => runs.push((n, n)),
+            }
+        }
+        Self {
+            parts: runs
+                .into_iter()
+                .map(|(start, end)| SequenceRange::numbers(start, end))
+                .collect(),
+        }
+    }
 }
 
 impl Display for SequenceBound {
@@ -99,13 +281,155 @@ impl Display for StatusItem {
     }
 }
 
+/// A result option for `SEARCH RETURN (...)` (ESEARCH, RFC 4731).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchReturn {
+    Min,
+    Max,
+    Count,
+    /// All matches, as a compact sequence set.
+    All,
+}
+
+impl Display for SearchReturn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchReturn::Min => f.write_str("MIN"),
+            SearchReturn::Max => f.write_str("MAX"),
+            SearchReturn::Count => f.write_str("COUNT"),
+            SearchReturn::All => f.write_str("ALL"),
+        }
+    }
+}
+
+/// One part of an APPEND with CATENATE (RFC 4469).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatenatePart {
+    /// An IMAP URL (RFC 5092) of a message or section on this server, e.g.
+    /// `/INBOX;UIDVALIDITY=385759045/;UID=20/;SECTION=HEADER`.
+    Url(String),
+    /// Data inserted as is.
+    Text(Vec<u8>),
+}
+
+impl CatenatePart {
+    /// A URL part for message `uid` in `mailbox`, or only its `section` (e.g. `"TEXT"` or
+    /// `"1.2"`).
+    pub fn message(mailbox: &str, uid_validity: u32, uid: Uid, section: Option<&str>) -> Self {
+        let mut url = format!(
+            "/{};UIDVALIDITY={}/;UID={}",
+            url_encode(mailbox),
+            uid_validity,
+            uid
+        );
+        if let Some(section) = section {
+            url.push_str("/;SECTION=");
+            url.push_str(&url_encode(section));
+        }
+        CatenatePart::Url(url)
+    }
+}
+
+/// Percent-encodes everything outside RFC 5092's `bchar`.
+fn url_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for &b in s.as_bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~&=:@/".contains(&b) {
+            out.push(char::from(b));
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// An event type for NOTIFY SET (RFC 5465).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotifyEvent {
+    MessageNew,
+    MessageExpunge,
+    FlagChange,
+    AnnotationChange,
+    MailboxName,
+    SubscriptionChange,
+    MailboxMetadataChange,
+    ServerMetadataChange,
+}
+
+impl Display for NotifyEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NotifyEvent::MessageNew => "MessageNew",
+            NotifyEvent::MessageExpunge => "MessageExpunge",
+            NotifyEvent::FlagChange => "FlagChange",
+            NotifyEvent::AnnotationChange => "AnnotationChange",
+            NotifyEvent::MailboxName => "MailboxName",
+            NotifyEvent::SubscriptionChange => "SubscriptionChange",
+            NotifyEvent::MailboxMetadataChange => "MailboxMetadataChange",
+            NotifyEvent::ServerMetadataChange => "ServerMetadataChange",
+        })
+    }
+}
+
+/// The mailboxes a NOTIFY SET event group applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotifyMailboxes {
+    /// The selected mailbox, with events sent as they happen.
+    Selected,
+    /// The selected mailbox, with expunges held back until a command allows them.
+    SelectedDelayed,
+    Inboxes,
+    Personal,
+    Subscribed,
+    /// These mailboxes and everything below them.
+    Subtree(Vec<String>),
+    Mailboxes(Vec<String>),
+}
+
+impl Display for NotifyMailboxes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, mailboxes) = match self {
+            NotifyMailboxes::Selected => return f.write_str("SELECTED"),
+            NotifyMailboxes::SelectedDelayed => return f.write_str("SELECTED-DELAYED"),
+            NotifyMailboxes::Inboxes => return f.write_str("INBOXES"),
+            NotifyMailboxes::Personal => return f.write_str("PERSONAL"),
+            NotifyMailboxes::Subscribed => return f.write_str("SUBSCRIBED"),
+            NotifyMailboxes::Subtree(mailboxes) => ("SUBTREE", mailboxes),
+            NotifyMailboxes::Mailboxes(mailboxes) => ("MAILBOXES", mailboxes),
+        };
+        let quoted: Vec<String> = mailboxes.iter().map(|m| quote_astring(m)).collect();
+        match quoted.as_slice() {
+            [one] => write!(f, "{} {}", name, one),
+            many => write!(f, "{} ({})", name, many.join(" ")),
+        }
+    }
+}
+
+/// The rights argument of SETACL (RFC 4314).
+#[derive(Debug, Clone, Copy)]
+pub enum AclChange {
+    Replace(Rights),
+    Add(Rights),
+    Remove(Rights),
+}
+
+impl Display for AclChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AclChange::Replace(rights) => write!(f, "{}", rights),
+            AclChange::Add(rights) => write!(f, "+{}", rights),
+            AclChange::Remove(rights) => write!(f, "-{}", rights),
+        }
+    }
+}
+
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum SearchKey {
     All,
     Answered,
     Bcc(String),
-    Before(String),
+    Before(Date),
     Body(String),
     Cc(String),
     Deleted,
@@ -118,14 +442,16 @@ pub enum SearchKey {
     New,
     Not(Box<SearchKey>),
     Old,
-    On(String),
+    /// Internal date at least this many seconds ago (RFC 5032, needs WITHIN).
+    Older(u32),
+    On(Date),
     Or(Box<SearchKey>, Box<SearchKey>),
     Recent,
     Seen,
-    SentBefore(String),
-    SentOn(String),
-    SentSince(String),
-    Since(String),
+    SentBefore(Date),
+    SentOn(Date),
+    SentSince(Date),
+    Since(Date),
     Smaller(u32),
     Subject(String),
     Text(String),
@@ -137,49 +463,242 @@ pub enum SearchKey {
     Unkeyword(String),
     Unseen,
     Uid(SequenceSet),
+    /// Internal date at most this many seconds ago (RFC 5032, needs WITHIN).
+    Younger(u32),
 }
 
-impl Display for SearchKey {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl SearchKey {
+    /// The capability the server must advertise to accept this key, looking inside `NOT`
+    /// and `OR`: `WITHIN` for [`SearchKey::Older`] and [`SearchKey::Younger`].
+    pub fn required_capability(&self) -> Option<Capability> {
+        match self {
+            SearchKey::Older(_) | SearchKey::Younger(_) => Some(Capability::Within),
+            SearchKey::Not(k) => k.required_capability(),
+            SearchKey::Or(a, b) => a.required_capability().or_else(|| b.required_capability()),
+            _ => None,
+        }
+    }
+
+    /// Whether every string value is ASCII; otherwise the search needs `CHARSET UTF-8`.
+    pub fn is_ascii(&self) -> bool {
+        let mut ascii = true;
+        self.for_each_value(&mut |s| ascii &= s.is_ascii());
+        ascii
+    }
+
+    /// How many string values are sent as literals, because they contain a line break or
+    /// non-ASCII text.
+    pub fn literals(&self) -> usize {
+        let mut n = 0;
+        self.for_each_value(&mut |s| n += usize::from(needs_literal(s)));
+        n
+    }
+
+    /// A value that cannot be sent in any form: a string with a NUL, or a keyword that is
+    /// not an atom.
+    pub fn unsendable_value(&self) -> Option<&str> {
+        match self {
+            SearchKey::Keyword(s) | SearchKey::Unkeyword(s) if !is_atom(s) => Some(s),
+            SearchKey::Not(k) => k.unsendable_value(),
+            SearchKey::Or(a, b) => a.unsendable_value().or_else(|| b.unsendable_value()),
+            SearchKey::Bcc(s)
+            | SearchKey::Body(s)
+            | SearchKey::Cc(s)
+            | SearchKey::From(s)
+            | SearchKey::Subject(s)
+            | SearchKey::Text(s)
+            | SearchKey::To(s)
+                if s.contains('\0') =>
+            {
+                Some(s)
+            }
+            SearchKey::Header { name, value } => [name, value]
+                .into_iter()
+                .find(|s| s.contains('\0'))
+                .map(String::as_str),
+            _ => None,
+        }
+    }
+
+    fn for_each_value<'a>(&'a self, f: &mut impl FnMut(&'a str)) {
+        match self {
+            SearchKey::Bcc(s)
+            | SearchKey::Body(s)
+            | SearchKey::Cc(s)
+            | SearchKey::From(s)
+            | SearchKey::Subject(s)
+            | SearchKey::Text(s)
+            | SearchKey::To(s) => f(s),
+            SearchKey::Header { name, value } => {
+                f(name);
+                f(value);
+            }
+            SearchKey::Not(k) => k.for_each_value(f),
+            SearchKey::Or(a, b) => {
+                a.for_each_value(f);
+                b.for_each_value(f);
+            }
+            _ => {}
+        }
+    }
+
+    fn write(&self, w: &mut KeyWriter) -> fmt::Result {
+        use fmt::Write;
         use SearchKey as K;
         match self {
-            K::All => f.write_str("ALL"),
-            K::Answered => f.write_str("ANSWERED"),
-            K::Bcc(s) => write!(f, "BCC {}", quote_astring(s)),
-            K::Before(s) => write!(f, "BEFORE {}", s),
-            K::Body(s) => write!(f, "BODY {}", quote_astring(s)),
-            K::Cc(s) => write!(f, "CC {}", quote_astring(s)),
-            K::Deleted => f.write_str("DELETED"),
-            K::Draft => f.write_str("DRAFT"),
-            K::Flagged => f.write_str("FLAGGED"),
-            K::From(s) => write!(f, "FROM {}", quote_astring(s)),
+            K::All => w.write_str("ALL"),
+            K::Answered => w.write_str("ANSWERED"),
+            K::Bcc(s) => w.item("BCC", s),
+            K::Before(d) => write!(w, "BEFORE {}", d),
+            K::Body(s) => w.item("BODY", s),
+            K::Cc(s) => w.item("CC", s),
+            K::Deleted => w.write_str("DELETED"),
+            K::Draft => w.write_str("DRAFT"),
+            K::Flagged => w.write_str("FLAGGED"),
+            K::From(s) => w.item("FROM", s),
             K::Header { name, value } => {
-                write!(f, "HEADER {} {}", quote_astring(name), quote_astring(value))
+                w.write_str("HEADER ")?;
+                w.string(name)?;
+                w.write_char(' ')?;
+                w.string(value)
+            }
+            K::Keyword(s) => write!(w, "KEYWORD {}", s),
+            K::Larger(n) => write!(w, "LARGER {}", n),
+            K::New => w.write_str("NEW"),
+            K::Not(k) => {
+                w.write_str("NOT (")?;
+                k.write(w)?;
+                w.write_char(')')
+            }
+            K::Old => w.write_str("OLD"),
+            K::Older(n) => write!(w, "OLDER {}", n),
+            K::On(d) => write!(w, "ON {}", d),
+            K::Or(a, b) => {
+                w.write_str("OR (")?;
+                a.write(w)?;
+                w.write_str(") (")?;
+                b.write(w)?;
+                w.write_char(')')
             }
-            K::Keyword(s) => write!(f, "KEYWORD {}", s),
-            K::Larger(n) => write!(f, "LARGER {}", n),
-            K::New => f.write_str("NEW"),
-            K::Not(k) => write!(f, "NOT ({})", k),
-            K::Old => f.write_str("OLD"),
-            K::On(s) => write!(f, "ON {}", s),
-            K::Or(a, b) => write!(f, "OR ({}) ({})", a, b),
-            K::Recent => f.write_str("RECENT"),
-            K::Seen => f.write_str("SEEN"),
-            K::SentBefore(s) => write!(f, "SENTBEFORE {}", s),
-            K::SentOn(s) => write!(f, "SENTON {}", s),
-            K::SentSince(s) => write!(f, "SENTSINCE {}", s),
-            K::Since(s) => write!(f, "SINCE {}", s),
-            K::Smaller(n) => write!(f, "SMALLER {}", n),
-            K::Subject(s) => write!(f, "SUBJECT {}", quote_astring(s)),
-            K::Text(s) => write!(f, "TEXT {}", quote_astring(s)),
-            K::To(s) => write!(f, "TO {}", quote_astring(s)),
-            K::Unanswered => f.write_str("UNANSWERED"),
-            K::Undeleted => f.write_str("UNDELETED"),
-            K::Undraft => f.write_str("UNDRAFT"),
-            K::Unflagged => f.write_str("UNFLAGGED"),
-            K::Unkeyword(s) => write!(f, "UNKEYWORD {}", s),
-            K::Unseen => f.write_str("UNSEEN"),
-            K::Uid(set) => write!(f, "UID {}", set),
+            K::Recent => w.write_str("RECENT"),
+            K::Seen => w.write_str("SEEN"),
+            K::SentBefore(d) => write!(w, "SENTBEFORE {}", d),
+            K::SentOn(d) => write!(w, "SENTON {}", d),
+            K::SentSince(d) => write!(w, "SENTSINCE {}", d),
+            K::Since(d) => write!(w, "SINCE {}", d),
+            K::Smaller(n) => write!(w, "SMALLER {}", n),
+            K::Subject(s) => w.item("SUBJECT", s),
+            K::Text(s) => w.item("TEXT", s),
+            K::To(s) => w.item("TO", s),
+            K::Unanswered => w.write_str("UNANSWERED"),
+            K::Undeleted => w.write_str("UNDELETED"),
+            K::Undraft => w.write_str("UNDRAFT"),
+            K::Unflagged => w.write_str("UNFLAGGED"),
+            K::Unkeyword(s) => write!(w, "UNKEYWORD {}", s),
+            K::Unseen => w.write_str("UNSEEN"),
+            K::Uid(set) => write!(w, "UID {}", set),
+            K::Younger(n) => write!(w, "YOUNGER {}", n),
+        }
+    }
+}
+
+impl Display for SearchKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut w = KeyWriter::new(false);
+        self.write(&mut w)?;
+        f.write_str(&w.out)
+    }
+}
+
+/// Search keys as sent: the text, with string values that cannot be quoted written as
+/// literals, and the offset in it where the first literal's data starts.
+pub(crate) fn render_search_keys(
+    keys: &[SearchKey],
+    literal_plus: bool,
+) -> (String, Option<usize>) {
+    let mut w = KeyWriter::new(literal_plus);
+    for (i, key) in keys.iter().enumerate() {
+        if i > 0 {
+            w.out.push(' ');
+        }
+        let _ = key.write(&mut w);
+    }
+    (w.out, w.first_literal)
+}
+
+struct KeyWriter {
+    out: String,
+    literal_plus: bool,
+    first_literal: Option<usize>,
+}
+
+impl KeyWriter {
+    fn new(literal_plus: bool) -> Self {
+        Self {
+            out: String::new(),
+            literal_plus,
+            first_literal: None,
+        }
+    }
+
+    /// A key taking one string value, e.g. `SUBJECT`.
+    fn item(&mut self, name: &str, value: &str) -> fmt::Result {
+        self.out.push_str(name);
+        self.out.push(' ');
+        self.string(value)
+    }
+
+    fn string(&mut self, s: &str) -> fmt::Result {
+        if !needs_literal(s) {
+            self.out.push_str(&quote_astring(s));
+            return Ok(());
+        }
+        let plus = if self.literal_plus { "+" } else { "" };
+        self.out.push_str(&format!("{{{}{}}}\r\n", s.len(), plus));
+        self.first_literal.get_or_insert(self.out.len());
+        self.out.push_str(s);
+        Ok(())
+    }
+}
+
+impl fmt::Write for KeyWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.push_str(s);
+        Ok(())
+    }
+}
+
+/// Whether `s` is a non-empty IMAP atom, as flag keywords must be.
+fn is_atom(s: &str) -> bool {
+    !s.is_empty()
+        && s
+            .bytes()
+            .all(|b| b.is_ascii_graphic() && !b"(){%*\"\\]".contains(&b))
+}
+
+#[derive(Debug, Clone)]
+pub enum SortKey {
+    Arrival,
+    Cc,
+    Date,
+    From,
+    Size,
+    Subject,
+    To,
+    Reverse(Box<SortKey>),
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SortKey::Arrival => f.write_str("ARRIVAL"),
+            SortKey::Cc => f.write_str("CC"),
+            SortKey::Date => f.write_str("DATE"),
+            SortKey::From => f.write_str("FROM"),
+            SortKey::Size => f.write_str("SIZE"),
+            SortKey::Subject => f.write_str("SUBJECT"),
+            SortKey::To => f.write_str("TO"),
+            SortKey::Reverse(k) => write!(f, "REVERSE {}", k),
         }
     }
 }
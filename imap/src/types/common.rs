@@ -1,4 +1,8 @@
+use std::collections::HashSet;
 use std::fmt::Display;
+use std::sync::Arc;
+
+use crate::parser::datetime::{MONTHS, civil_from_days};
 
 #[derive(Debug, Clone)]
 pub enum Flag {
@@ -25,9 +29,624 @@ impl Display for Flag {
     }
 }
 
+/// The system flags as a bitset; keywords are not represented.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SystemFlags(u8);
+
+impl SystemFlags {
+    pub const SEEN: Self = Self(1);
+    pub const ANSWERED: Self = Self(1 << 1);
+    pub const FLAGGED: Self = Self(1 << 2);
+    pub const DELETED: Self = Self(1 << 3);
+    pub const DRAFT: Self = Self(1 << 4);
+    pub const RECENT: Self = Self(1 << 5);
+
+    pub fn empty() -> Self {
+        Self(0)
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The bit for `flag`, or `None` for keywords.
+    pub fn from_flag(flag: &Flag) -> Option<Self> {
+        match flag {
+            Flag::Seen => Some(Self::SEEN),
+            Flag::Answered => Some(Self::ANSWERED),
+            Flag::Flagged => Some(Self::FLAGGED),
+            Flag::Deleted => Some(Self::DELETED),
+            Flag::Draft => Some(Self::DRAFT),
+            Flag::Recent => Some(Self::RECENT),
+            Flag::Keyword(_) => None,
+        }
+    }
+
+    /// Expands the bitset back into flags, in a fixed order.
+    pub fn to_flags(self) -> Vec<Flag> {
+        [
+            (Self::SEEN, Flag::Seen),
+            (Self::ANSWERED, Flag::Answered),
+            (Self::FLAGGED, Flag::Flagged),
+            (Self::DELETED, Flag::Deleted),
+            (Self::DRAFT, Flag::Draft),
+            (Self::RECENT, Flag::Recent),
+        ]
+        .into_iter()
+        .filter(|(bit, _)| self.contains(*bit))
+        .map(|(_, flag)| flag)
+        .collect()
+    }
+}
+
+impl std::ops::BitOr for SystemFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl<'a> FromIterator<&'a Flag> for SystemFlags {
+    fn from_iter<I: IntoIterator<Item = &'a Flag>>(iter: I) -> Self {
+        let mut flags = Self::empty();
+        for flag in iter {
+            if let Some(bit) = Self::from_flag(flag) {
+                flags.insert(bit);
+            }
+        }
+        flags
+    }
+}
+
+/// Access rights on a mailbox (ACL, RFC 4314) as a bitset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rights(u16);
+
+impl Rights {
+    pub const LOOKUP: Self = Self(1);
+    pub const READ: Self = Self(1 << 1);
+    pub const SEEN: Self = Self(1 << 2);
+    pub const WRITE: Self = Self(1 << 3);
+    pub const INSERT: Self = Self(1 << 4);
+    pub const POST: Self = Self(1 << 5);
+    pub const CREATE_MAILBOX: Self = Self(1 << 6);
+    pub const DELETE_MAILBOX: Self = Self(1 << 7);
+    pub const DELETE_MESSAGES: Self = Self(1 << 8);
+    pub const EXPUNGE: Self = Self(1 << 9);
+    pub const ADMINISTER: Self = Self(1 << 10);
+
+    /// The rights letters in RFC 4314 order.
+    const LETTERS: [(char, Self); 11] = [
+        ('l', Self::LOOKUP),
+        ('r', Self::READ),
+        ('s', Self::SEEN),
+        ('w', Self::WRITE),
+        ('i', Self::INSERT),
+        ('p', Self::POST),
+        ('k', Self::CREATE_MAILBOX),
+        ('x', Self::DELETE_MAILBOX),
+        ('t', Self::DELETE_MESSAGES),
+        ('e', Self::EXPUNGE),
+        ('a', Self::ADMINISTER),
+    ];
+
+    pub fn empty() -> Self {
+        Self(0)
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Parses a rights string such as `lrswipkxtea`.
+    ///
+    /// The obsolete RFC 2086 rights are mapped to their replacements: `c` to `k`, `d` to
+    /// `xte`. Letters this type does not know, such as server-specific digits, are dropped.
+    pub fn parse(s: &str) -> Self {
+        let mut rights = Self::empty();
+        for ch in s.chars() {
+            match ch {
+                'c' => rights.insert(Self::CREATE_MAILBOX),
+                'd' => rights.insert(Self::DELETE_MAILBOX | Self::DELETE_MESSAGES | Self::EXPUNGE),
+                _ => {
+                    if let Some((_, right)) = Self::LETTERS.iter().find(|(c, _)| *c == ch) {
+                        rights.insert(*right);
+                    }
+                }
+            }
+        }
+        rights
+    }
+}
+
+impl std::ops::BitOr for Rights {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl Display for Rights {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (ch, right) in Self::LETTERS {
+            if self.contains(right) {
+                write!(f, "{}", ch)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Interns keyword flags so messages in one mailbox share a single allocation per keyword.
+#[derive(Debug, Clone, Default)]
+pub struct KeywordInterner {
+    keywords: HashSet<Arc<str>>,
+}
+
+impl KeywordInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, keyword: &str) -> Arc<str> {
+        if let Some(existing) = self.keywords.get(keyword) {
+            return existing.clone();
+        }
+        let keyword: Arc<str> = Arc::from(keyword);
+        self.keywords.insert(keyword.clone());
+        keyword
+    }
+
+    pub fn len(&self) -> usize {
+        self.keywords.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keywords.is_empty()
+    }
+}
+
+/// The flags of one message: system flags as a bitset, keywords interned.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MessageFlags {
+    pub system: SystemFlags,
+    pub keywords: Vec<Arc<str>>,
+}
+
+impl MessageFlags {
+    pub fn contains(&self, flag: &Flag) -> bool {
+        match (SystemFlags::from_flag(flag), flag) {
+            (Some(bit), _) => self.system.contains(bit),
+            (None, Flag::Keyword(k)) => self.keywords.iter().any(|kw| **kw == **k),
+            (None, _) => false,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<Flag> {
+        let mut flags = self.system.to_flags();
+        flags.extend(self.keywords.iter().map(|k| Flag::Keyword(k.to_string())));
+        flags
+    }
+}
+
+impl From<MessageFlags> for Vec<Flag> {
+    fn from(flags: MessageFlags) -> Self {
+        flags.to_vec()
+    }
+}
+
+/// A SASL mechanism from an `AUTH=` capability.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SaslMechanism {
+    Plain,
+    Login,
+    CramMd5,
+    ScramSha1,
+    ScramSha256,
+    XOAuth2,
+    OAuthBearer,
+    External,
+    /// Any other mechanism, uppercased.
+    Other(String),
+}
+
+impl SaslMechanism {
+    const NAMES: [(&'static str, SaslMechanism); 8] = [
+        ("PLAIN", SaslMechanism::Plain),
+        ("LOGIN", SaslMechanism::Login),
+        ("CRAM-MD5", SaslMechanism::CramMd5),
+        ("SCRAM-SHA-1", SaslMechanism::ScramSha1),
+        ("SCRAM-SHA-256", SaslMechanism::ScramSha256),
+        ("XOAUTH2", SaslMechanism::XOAuth2),
+        ("OAUTHBEARER", SaslMechanism::OAuthBearer),
+        ("EXTERNAL", SaslMechanism::External),
+    ];
+
+    pub fn parse(name: &str) -> Self {
+        Self::NAMES
+            .into_iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, m)| m)
+            .unwrap_or_else(|| SaslMechanism::Other(name.to_ascii_uppercase()))
+    }
+
+    /// The mechanism name as used in `AUTHENTICATE`, e.g. `SCRAM-SHA-256`.
+    pub fn name(&self) -> &str {
+        match self {
+            SaslMechanism::Other(name) => name,
+            known => Self::NAMES
+                .iter()
+                .find(|(_, m)| m == known)
+                .map_or("", |(n, _)| n),
+        }
+    }
+}
+
+impl Display for SaslMechanism {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// One capability atom, with parameterized forms like `AUTH=PLAIN` and
+/// `APPENDLIMIT=26214400` parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Capability {
+    Imap4rev1,
+    Imap4rev2,
+    Auth(SaslMechanism),
+    /// LOGIN is refused, usually until STARTTLS.
+    LoginDisabled,
+    Idle,
+    Move,
+    Quota,
+    CompressDeflate,
+    /// The largest message APPEND accepts, in octets (RFC 7889). `None` when the limit
+    /// differs per mailbox and has to be read with STATUS.
+    AppendLimit(Option<u64>),
+    /// Non-synchronizing literals (RFC 7888).
+    LiteralPlus,
+    /// Several messages in one APPEND (RFC 3502).
+    MultiAppend,
+    Catenate,
+    Binary,
+    /// COPYUID, APPENDUID and UID EXPUNGE (RFC 4315).
+    UidPlus,
+    Unselect,
+    Qresync,
+    Esearch,
+    /// The OLDER and YOUNGER search keys (RFC 5032).
+    Within,
+    ListExtended,
+    ListStatus,
+    SpecialUse,
+    Acl,
+    Notify,
+    /// Any other atom, as sent.
+    Unknown(String),
+}
+
+impl Capability {
+    /// The atoms without a parameter, as servers usually spell them.
+    const NAMES: [(&'static str, Capability); 22] = [
+        ("IMAP4rev1", Capability::Imap4rev1),
+        ("IMAP4rev2", Capability::Imap4rev2),
+        ("LOGINDISABLED", Capability::LoginDisabled),
+        ("IDLE", Capability::Idle),
+        ("MOVE", Capability::Move),
+        ("QUOTA", Capability::Quota),
+        ("COMPRESS=DEFLATE", Capability::CompressDeflate),
+        ("APPENDLIMIT", Capability::AppendLimit(None)),
+        ("LITERAL+", Capability::LiteralPlus),
+        ("MULTIAPPEND", Capability::MultiAppend),
+        ("CATENATE", Capability::Catenate),
+        ("BINARY", Capability::Binary),
+        ("UIDPLUS", Capability::UidPlus),
+        ("UNSELECT", Capability::Unselect),
+        ("QRESYNC", Capability::Qresync),
+        ("ESEARCH", Capability::Esearch),
+        ("WITHIN", Capability::Within),
+        ("LIST-EXTENDED", Capability::ListExtended),
+        ("LIST-STATUS", Capability::ListStatus),
+        ("SPECIAL-USE", Capability::SpecialUse),
+        ("ACL", Capability::Acl),
+        ("NOTIFY", Capability::Notify),
+    ];
+
+    pub fn parse(atom: &str) -> Self {
+        if let Some((_, capability)) = Self::NAMES
+            .into_iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(atom))
+        {
+            return capability;
+        }
+        let upper = atom.to_ascii_uppercase();
+        if let Some(mechanism) = upper.strip_prefix("AUTH=") {
+            return Capability::Auth(SaslMechanism::parse(mechanism));
+        }
+        if let Some(limit) = upper.strip_prefix("APPENDLIMIT=")
+            && let Ok(limit) = limit.parse()
+        {
+            return Capability::AppendLimit(Some(limit));
+        }
+        Capability::Unknown(atom.to_string())
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Capability::Auth(mechanism) => write!(f, "AUTH={}", mechanism),
+            Capability::AppendLimit(Some(limit)) => write!(f, "APPENDLIMIT={}", limit),
+            Capability::Unknown(atom) => f.write_str(atom),
+            known => f.write_str(
+                Self::NAMES
+                    .iter()
+                    .find(|(_, capability)| capability == known)
+                    .map_or("", |(name, _)| name),
+            ),
+        }
+    }
+}
+
+/// The capability atoms a server advertises, e.g. `IMAP4rev1`, `IDLE`, `AUTH=PLAIN`.
+///
+/// Lookups are case-insensitive; atoms keep the server's spelling. [`Capabilities::parsed`]
+/// gives them as [`Capability`] values.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    atoms: Vec<String>,
+}
+
+impl Capabilities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn has(&self, capability: &str) -> bool {
+        self.atoms
+            .iter()
+            .any(|a| a.eq_ignore_ascii_case(capability))
+    }
+
+    /// SASL mechanisms from the `AUTH=` atoms, as sent.
+    pub fn auth_mechanisms(&self) -> impl Iterator<Item = &str> {
+        self.atoms.iter().filter_map(|a| {
+            let (prefix, mechanism) = a.split_at_checked(5)?;
+            prefix.eq_ignore_ascii_case("AUTH=").then_some(mechanism)
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.atoms.iter().map(String::as_str)
+    }
+
+    pub fn parsed(&self) -> impl Iterator<Item = Capability> + '_ {
+        self.atoms.iter().map(|a| Capability::parse(a))
+    }
+
+    pub fn contains(&self, capability: &Capability) -> bool {
+        self.parsed().any(|c| c == *capability)
+    }
+
+    /// The SASL mechanisms from the `AUTH=` atoms.
+    pub fn sasl_mechanisms(&self) -> impl Iterator<Item = SaslMechanism> + '_ {
+        self.parsed().filter_map(|c| match c {
+            Capability::Auth(mechanism) => Some(mechanism),
+            _ => None,
+        })
+    }
+
+    /// The server-wide APPENDLIMIT, if it advertises one.
+    pub fn append_limit(&self) -> Option<u64> {
+        self.parsed().find_map(|c| match c {
+            Capability::AppendLimit(limit) => limit,
+            _ => None,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.atoms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.atoms.is_empty()
+    }
+}
+
+impl FromIterator<String> for Capabilities {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        let mut caps = Capabilities::new();
+        for atom in iter {
+            if !caps.has(&atom) {
+                caps.atoms.push(atom);
+            }
+        }
+        caps
+    }
+}
+
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Status {
     Ok,
     No,
     Bad,
 }
+
+/// A point in time and the UTC offset it was written with, e.g. from INTERNALDATE or a
+/// Date header. Parse one with [`parser::datetime`](crate::parser::datetime).
+///
+/// Orders by instant first. Displays in the IMAP `date-time` form used by INTERNALDATE and
+/// APPEND, `17-Jul-1996 02:44:25 -0700`, in its own offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DateTime {
+    timestamp: i64,
+    offset: i32,
+}
+
+impl DateTime {
+    /// `timestamp` seconds since the Unix epoch, shown at `offset` seconds east of UTC.
+    pub fn new(timestamp: i64, offset: i32) -> Self {
+        Self { timestamp, offset }
+    }
+
+    /// Seconds since the Unix epoch.
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// Seconds east of UTC.
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+
+    /// The calendar date in its own offset, or `None` outside the years IMAP can write.
+    pub fn date(&self) -> Option<Date> {
+        let local = self.timestamp + i64::from(self.offset);
+        let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+        Date::new(u16::try_from(year).ok()?, month as u8, day as u8)
+    }
+}
+
+/// A calendar date, as searches take it. Displays in the IMAP `date` form, `1-Feb-2024`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+impl Date {
+    /// `None` unless the day exists and the year is between 1 and 9999.
+    pub fn new(year: u16, month: u8, day: u8) -> Option<Self> {
+        let leap =
+            year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+        let days = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap => 29,
+            2 => 28,
+            _ => return None,
+        };
+        ((1..=9999).contains(&year) && (1..=days).contains(&day)).then_some(Self {
+            year,
+            month,
+            day,
+        })
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+}
+
+impl Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}-{:04}",
+            self.day,
+            MONTHS[usize::from(self.month) - 1],
+            self.year
+        )
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let local = self.timestamp + i64::from(self.offset);
+        let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+        let secs = local.rem_euclid(86_400);
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        let offset = self.offset.unsigned_abs() / 60;
+        write!(
+            f,
+            "{:02}-{}-{:04} {:02}:{:02}:{:02} {}{:02}{:02}",
+            day,
+            MONTHS[month as usize - 1],
+            year,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60,
+            sign,
+            offset / 60,
+            offset % 60
+        )
+    }
+}
+
+/// A message's UID, which stays the same for as long as the mailbox's UIDVALIDITY does.
+///
+/// Kept apart from [`Seq`] so the two cannot be mixed up: sequence numbers shift whenever a
+/// message is expunged, UIDs do not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uid(pub u32);
+
+/// A message's sequence number: its position in the mailbox, counting from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Seq(pub u32);
+
+impl From<Uid> for u32 {
+    fn from(uid: Uid) -> Self {
+        uid.0
+    }
+}
+
+impl From<Seq> for u32 {
+    fn from(seq: Seq) -> Self {
+        seq.0
+    }
+}
+
+impl Display for Uid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Display for Seq {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -1,6 +1,11 @@
-use super::common::{Flag, Status};
+use bytes::Bytes;
+
+use super::command::SequenceSet;
+use super::common::{Flag, MessageFlags, Rights, Seq, Status, SystemFlags, Uid};
+use crate::special_use::MailboxRole;
 
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum Response {
     Tagged {
         tag: String,
@@ -13,25 +18,564 @@ pub enum Response {
 }
 
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum UntaggedResponse {
     Exists(u32),
     Recent(u32),
-    Expunge(u32),
+    Expunge(Seq),
     Flags(Vec<Flag>),
     Search(Vec<u32>),
-    Fetch { seq: u32, data: FetchData },
+    Fetch {
+        seq: Seq,
+        data: FetchData,
+    },
+    /// `* BYE`: the server is closing the connection, with its reason.
+    Bye(String),
+    /// The text of an untagged status response with the `[ALERT]` code, which RFC 3501
+    /// requires to be shown to the user.
+    Alert(String),
+    /// A response this crate does not interpret, as received without the leading `* ` and
+    /// the trailing CRLF.
+    Other(Bytes),
 }
 
-#[derive(Debug, Clone)]
+/// A mailbox change pushed by the server, e.g. while idling (RFC 2177).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum IdleEvent {
+    /// The mailbox now holds this many messages.
+    Exists(u32),
+    /// The message with this sequence number was removed.
+    Expunge(Seq),
+    /// Messages with these UIDs were removed; sent instead of EXPUNGE once QRESYNC is
+    /// enabled.
+    Vanished(Vec<Uid>),
+    FlagsChanged {
+        seq: Seq,
+        uid: Option<Uid>,
+        flags: MessageFlags,
+    },
+    /// The status of a mailbox other than the selected one, sent for NOTIFY MessageNew and
+    /// MessageExpunge events (RFC 5465).
+    MailboxStatus(MailboxStatusSummary),
+    /// A mailbox was created, deleted (`\NonExistent`) or renamed, or its subscription
+    /// changed; sent for NOTIFY MailboxName and SubscriptionChange events.
+    MailboxName(ListEntry),
+    /// Any other untagged data, such as a FETCH without FLAGS or a response from an
+    /// extension, as received without the leading `* ` and the trailing CRLF. Plain status
+    /// responses (`* OK Still here`) are not reported.
+    Other(Bytes),
+}
+
+/// An untagged response the server sent on its own account rather than as data for a
+/// command, such as a new message arriving or a warning before shutdown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnsolicitedEvent {
+    /// The selected mailbox now holds this many messages.
+    Exists(u32),
+    /// This many messages in the selected mailbox are `\Recent`.
+    Recent(u32),
+    /// The message with this sequence number was removed.
+    Expunge(Seq),
+    FlagsChanged {
+        seq: Seq,
+        uid: Option<Uid>,
+        flags: MessageFlags,
+    },
+    /// The server is closing the connection, with its reason.
+    Bye(String),
+    /// Text the server wants shown to the user.
+    Alert(String),
+}
+
+/// Mailbox state reported by SELECT or EXAMINE.
+#[derive(Debug, Clone, Default)]
+pub struct MailboxStatus {
+    pub exists: u32,
+    pub recent: u32,
+    pub flags: Vec<Flag>,
+    pub permanent_flags: Vec<Flag>,
+    pub uid_validity: Option<u32>,
+    pub uid_next: Option<u32>,
+    /// Sequence number of the first unseen message, if the server reported one.
+    pub unseen: Option<u32>,
+    pub read_only: bool,
+    /// HIGHESTMODSEQ (RFC 7162), if the server supports CONDSTORE for this mailbox.
+    pub highest_modseq: Option<u64>,
+    /// UIDs reported by `* VANISHED (EARLIER)` when selecting with QRESYNC: the messages
+    /// removed since the state passed to SELECT.
+    pub vanished: Vec<Uid>,
+}
+
+/// The counters from a `* STATUS` response, for a mailbox that need not be selected.
+///
+/// Only the items that were asked for are set.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MailboxStatusSummary {
+    /// The mailbox name, still in modified UTF-7.
+    pub mailbox: String,
+    pub messages: Option<u32>,
+    pub recent: Option<u32>,
+    pub uid_next: Option<u32>,
+    pub uid_validity: Option<u32>,
+    pub unseen: Option<u32>,
+    pub highest_modseq: Option<u64>,
+}
+
+/// A bracketed response code without arguments from a status response (RFC 3501,
+/// RFC 5530), e.g. `[TRYCREATE]`. Codes with data such as COPYUID have parsers of their
+/// own.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ResponseCode {
+    Alert,
+    Parse,
+    ReadOnly,
+    ReadWrite,
+    TryCreate,
+    Unavailable,
+    AuthenticationFailed,
+    AuthorizationFailed,
+    Expired,
+    PrivacyRequired,
+    ContactAdmin,
+    NoPerm,
+    InUse,
+    ExpungeIssued,
+    Corruption,
+    ServerBug,
+    ClientBug,
+    Cannot,
+    Limit,
+    OverQuota,
+    AlreadyExists,
+    NonExistent,
+    /// Any other code, upper-cased, without its arguments.
+    Other(String),
+}
+
+impl ResponseCode {
+    const NAMES: [(&'static str, ResponseCode); 22] = [
+        ("ALERT", ResponseCode::Alert),
+        ("PARSE", ResponseCode::Parse),
+        ("READ-ONLY", ResponseCode::ReadOnly),
+        ("READ-WRITE", ResponseCode::ReadWrite),
+        ("TRYCREATE", ResponseCode::TryCreate),
+        ("UNAVAILABLE", ResponseCode::Unavailable),
+        ("AUTHENTICATIONFAILED", ResponseCode::AuthenticationFailed),
+        ("AUTHORIZATIONFAILED", ResponseCode::AuthorizationFailed),
+        ("EXPIRED", ResponseCode::Expired),
+        ("PRIVACYREQUIRED", ResponseCode::PrivacyRequired),
+        ("CONTACTADMIN", ResponseCode::ContactAdmin),
+        ("NOPERM", ResponseCode::NoPerm),
+        ("INUSE", ResponseCode::InUse),
+        ("EXPUNGEISSUED", ResponseCode::ExpungeIssued),
+        ("CORRUPTION", ResponseCode::Corruption),
+        ("SERVERBUG", ResponseCode::ServerBug),
+        ("CLIENTBUG", ResponseCode::ClientBug),
+        ("CANNOT", ResponseCode::Cannot),
+        ("LIMIT", ResponseCode::Limit),
+        ("OVERQUOTA", ResponseCode::OverQuota),
+        ("ALREADYEXISTS", ResponseCode::AlreadyExists),
+        ("NONEXISTENT", ResponseCode::NonExistent),
+    ];
+
+    /// The code for `name`, e.g. `OVERQUOTA`, in any case.
+    pub fn parse(name: &str) -> Self {
+        Self::NAMES
+            .into_iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, code)| code)
+            .unwrap_or_else(|| ResponseCode::Other(name.to_ascii_uppercase()))
+    }
+
+    /// The code as the server sends it, e.g. `TRYCREATE`.
+    pub fn name(&self) -> &str {
+        match self {
+            ResponseCode::Other(name) => name,
+            known => Self::NAMES
+                .iter()
+                .find(|(_, code)| code == known)
+                .map_or("", |(n, _)| n),
+        }
+    }
+}
+
+impl std::fmt::Display for ResponseCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The `[COPYUID ...]` response code (RFC 4315) of a COPY or MOVE.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyUid {
+    /// UIDVALIDITY of the destination mailbox.
+    pub uid_validity: u32,
+    /// UIDs of the copied messages in the source mailbox, in the server's order.
+    pub source: Vec<Uid>,
+    /// The UIDs assigned in the destination, pairwise with `source`.
+    pub destination: Vec<Uid>,
+}
+
+impl CopyUid {
+    /// The destination UID of the message with source UID `uid`.
+    pub fn destination_of(&self, uid: Uid) -> Option<Uid> {
+        let i = self.source.iter().position(|&u| u == uid)?;
+        self.destination.get(i).copied()
+    }
+}
+
+/// A `* VANISHED` response (RFC 7162).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vanished {
+    /// Set for `VANISHED (EARLIER)`, which reports removals from before the current
+    /// SELECT rather than live expunges.
+    pub earlier: bool,
+    pub uids: Vec<Uid>,
+}
+
+/// One mailbox returned by LIST or LSUB.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListEntry {
+    /// Name attributes such as `\Noselect` or `\HasChildren`, as sent.
+    pub attributes: Vec<String>,
+    /// Hierarchy delimiter, or `None` for a flat namespace.
+    pub delimiter: Option<char>,
+    /// Full mailbox name, still in modified UTF-7.
+    pub name: String,
+}
+
+impl ListEntry {
+    pub fn has_attribute(&self, attribute: &str) -> bool {
+        self.attributes
+            .iter()
+            .any(|a| a.eq_ignore_ascii_case(attribute))
+    }
+
+    /// Whether the mailbox is subscribed. Only known for entries from a LIST with the
+    /// SUBSCRIBED option (RFC 5258) or from LSUB; plain LIST never reports it.
+    pub fn is_subscribed(&self) -> bool {
+        self.has_attribute("\\Subscribed")
+    }
+
+    /// The role given by a SPECIAL-USE attribute (RFC 6154) such as `\Sent`, if any.
+    pub fn special_use(&self) -> Option<MailboxRole> {
+        self.attributes
+            .iter()
+            .find_map(|a| MailboxRole::from_attribute(a))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct Envelope {
+    /// The Date header as sent, e.g. `Wed, 17 Jul 1996 02:23:25 -0700`.
+    pub date: Option<String>,
     pub subject: Option<String>,
+    pub from: Vec<Address>,
+    pub to: Vec<Address>,
+    pub cc: Vec<Address>,
+    pub bcc: Vec<Address>,
+    /// The Message-ID header, with its angle brackets.
+    pub message_id: Option<String>,
 }
 
+/// One address from an envelope address list (RFC 3501 section 7.4.2). Groups are
+/// flattened into their member addresses.
+///
+/// Displays as `Display Name <mailbox@host>`, or just `mailbox@host` without a name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Address {
+    pub name: Option<String>,
+    pub mailbox: Option<String>,
+    pub host: Option<String>,
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mailbox = self.mailbox.as_deref().unwrap_or_default();
+        let addr = match &self.host {
+            Some(host) => format!("{}@{}", mailbox, host),
+            None => mailbox.to_string(),
+        };
+        match &self.name {
+            Some(name) => write!(f, "{} <{}>", name, addr),
+            None => f.write_str(&addr),
+        }
+    }
+}
+
+/// A compact per-message listing entry, for folders too large to hold full envelopes.
+///
+/// Built from a single FETCH of UID, INTERNALDATE, RFC822.SIZE, FLAGS and ENVELOPE.
+/// Keywords are dropped; absent strings are empty.
 #[derive(Debug, Clone)]
+pub struct EnvelopeSummary {
+    pub uid: Uid,
+    /// INTERNALDATE as seconds since the Unix epoch, or 0 if missing or unparseable.
+    pub date: i64,
+    pub subject: Box<str>,
+    /// The first From address, formatted as `Name <mailbox@host>`.
+    pub from: Box<str>,
+    pub size: u32,
+    pub flags: SystemFlags,
+}
+
+#[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum FetchData {
     Envelope(Envelope),
-    Flags(Vec<Flag>),
+    Flags(MessageFlags),
     InternalDate(String),
     Rfc822Size(u32),
-    Uid(u32),
+    /// The whole message (`RFC822`, equivalent to `BODY[]`).
+    Rfc822(Option<Bytes>),
+    /// The header (`RFC822.HEADER`, equivalent to `BODY.PEEK[HEADER]`).
+    Rfc822Header(Option<Bytes>),
+    /// The body without the header (`RFC822.TEXT`, equivalent to `BODY[TEXT]`).
+    Rfc822Text(Option<Bytes>),
+    Uid(Uid),
+    BodySection {
+        section: String,
+        origin: Option<u32>,
+        data: Option<Bytes>,
+    },
+    /// `BINARY[section]` (RFC 3516): the section with its content transfer encoding removed.
+    BinarySection {
+        section: String,
+        origin: Option<u32>,
+        data: Option<Bytes>,
+    },
+    BodyStructure(BodyStructure),
+    /// An item this crate does not interpret, or could not parse, with its value as sent.
+    Other {
+        name: String,
+        value: Bytes,
+    },
+}
+
+/// An `* ESEARCH` reply (RFC 4731). Only the results that were asked for are set.
+#[derive(Debug, Clone, Default)]
+pub struct EsearchResult {
+    /// The tag of the command this replies to.
+    pub tag: Option<String>,
+    /// The numbers are UIDs.
+    pub uid: bool,
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+    pub count: Option<u32>,
+    pub all: Option<SequenceSet>,
+    /// Highest mod-sequence of the matches, when the search used MODSEQ (RFC 7162).
+    pub modseq: Option<u64>,
+}
+
+/// One identifier's rights from a `* ACL` response (RFC 4314).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AclEntry {
+    /// A user or group name; a leading `-` marks negative rights.
+    pub identifier: String,
+    pub rights: Rights,
+}
+
+/// The rights that can be granted to an identifier, from a `* LISTRIGHTS` response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListRights {
+    pub identifier: String,
+    /// Always granted to the identifier.
+    pub required: Rights,
+    /// Groups of rights that can be granted, each only as a whole.
+    pub optional: Vec<Rights>,
+}
+
+/// One resource of a quota root (RFC 9208).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaResource {
+    /// `STORAGE` (in units of 1024 octets), `MESSAGE`, `MAILBOX`, ...
+    pub name: String,
+    pub usage: u64,
+    pub limit: u64,
+}
+
+/// The limits of one quota root, from a `* QUOTA` response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quota {
+    pub root: String,
+    pub resources: Vec<QuotaResource>,
+}
+
+impl Quota {
+    /// Looks up a resource by name, case-insensitively.
+    pub fn resource(&self, name: &str) -> Option<&QuotaResource> {
+        self.resources
+            .iter()
+            .find(|r| r.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// The quota roots a mailbox counts against, with their limits (GETQUOTAROOT).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct QuotaRoot {
+    pub mailbox: String,
+    /// Empty when the mailbox is not subject to any quota.
+    pub roots: Vec<String>,
+    pub quotas: Vec<Quota>,
+}
+
+/// A message's MIME structure, from `BODYSTRUCTURE` or `BODY`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BodyStructure {
+    Single(BodyPart),
+    Multipart {
+        parts: Vec<BodyStructure>,
+        /// `mixed`, `alternative`, `related`, ...
+        subtype: String,
+        params: Vec<(String, String)>,
+        disposition: Option<Disposition>,
+    },
+}
+
+/// One non-multipart entity of a [`BodyStructure`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BodyPart {
+    pub media_type: String,
+    pub subtype: String,
+    pub params: Vec<(String, String)>,
+    pub id: Option<String>,
+    pub description: Option<String>,
+    /// Content-Transfer-Encoding, e.g. `7BIT` or `BASE64`.
+    pub encoding: String,
+    /// Size in octets, still transfer-encoded.
+    pub size: u32,
+    /// Line count, for `text/*` and `message/rfc822` parts.
+    pub lines: Option<u32>,
+    /// The structure of an attached `message/rfc822`.
+    pub message: Option<Box<BodyStructure>>,
+    /// Only sent for `BODYSTRUCTURE`, not `BODY`.
+    pub disposition: Option<Disposition>,
+}
+
+/// Content-Disposition (RFC 2183): `inline` or `attachment`, with parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disposition {
+    pub kind: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl BodyPart {
+    /// Case-insensitive media type check, e.g. `part.is("text", "plain")`.
+    pub fn is(&self, media_type: &str, subtype: &str) -> bool {
+        self.media_type.eq_ignore_ascii_case(media_type)
+            && self.subtype.eq_ignore_ascii_case(subtype)
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        param(&self.params, name)
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    /// Whether the part is marked `Content-Disposition: attachment`.
+    pub fn is_attachment(&self) -> bool {
+        self.disposition
+            .as_ref()
+            .is_some_and(|d| d.kind.eq_ignore_ascii_case("attachment"))
+    }
+
+    /// The attachment's file name, from the disposition or the legacy `name` parameter.
+    pub fn filename(&self) -> Option<&str> {
+        self.disposition
+            .as_ref()
+            .and_then(|d| param(&d.params, "filename"))
+            .or_else(|| self.param("name"))
+    }
+}
+
+fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    params
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Message header fields in wire order. Lookups are case-insensitive.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn insert(&mut self, name: &str, value: &str) {
+        self.entries.push((name.to_string(), value.to_string()));
+    }
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Internationalization level advertised via `I18NLEVEL=` (RFC 5255).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum I18nLevel {
+    /// No I18NLEVEL capability: SEARCH and SORT compare octets (`i;ascii-casemap` at best).
+    None,
+    /// Level 1: searching and sorting use a server-chosen Unicode-aware comparator.
+    Level1,
+    /// Level 2: like level 1, and the COMPARATOR command can change the comparator.
+    Level2,
+}
+
+/// How the server compares strings for SEARCH and SORT.
+#[derive(Debug, Clone)]
+pub struct Collation {
+    pub level: I18nLevel,
+    /// The server accepts the COMPARATOR command.
+    pub comparator: bool,
+}
+
+impl Collation {
+    /// Derives the collation support from a server's capability atoms.
+    pub fn from_capabilities<'a>(caps: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut level = I18nLevel::None;
+        let mut comparator = false;
+        for cap in caps {
+            let upper = cap.to_ascii_uppercase();
+            match upper.as_str() {
+                "I18NLEVEL=1" => level = level.max(I18nLevel::Level1),
+                "I18NLEVEL=2" => level = level.max(I18nLevel::Level2),
+                "COMPARATOR" => comparator = true,
+                _ if upper.starts_with("COMPARATOR=") => comparator = true,
+                _ => {}
+            }
+        }
+        Self { level, comparator }
+    }
+
+    /// True when results are only ordered octet-wise, so applications that need
+    /// locale-correct ordering should sort client-side.
+    pub fn is_octet_only(&self) -> bool {
+        self.level == I18nLevel::None
+    }
 }
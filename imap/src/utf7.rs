@@ -0,0 +1,59 @@
+//! Modified UTF-7 mailbox names (RFC 3501 section 5.1.3).
+
+/// Decodes a mailbox name as sent by the server, e.g. `"&AMk-l&AOk-ments envoy&AOk-s"`.
+///
+/// Returns `None` if the name is not valid modified UTF-7.
+pub fn decode(name: &str) -> Option<String> {
+    let mut out = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let shifted = &rest[start + 1..];
+        let end = shifted.find('-')?;
+        if end == 0 {
+            out.push('&');
+        } else {
+            let units = decode_base64_utf16(&shifted[..end])?;
+            out.extend(
+                char::decode_utf16(units)
+                    .collect::<Result<Vec<_>, _>>()
+                    .ok()?,
+            );
+        }
+        rest = &shifted[end + 1..];
+    }
+    out.push_str(rest);
+    Some(out)
+}
+
+/// Modified base64 (`,` in place of `/`, no padding) carrying UTF-16BE code units.
+fn decode_base64_utf16(s: &str) -> Option<Vec<u16>> {
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for b in s.bytes() {
+        let v = match b {
+            b'A'..=b'Z' => b - b'A',
+            b'a'..=b'z' => b - b'a' + 26,
+            b'0'..=b'9' => b - b'0' + 52,
+            b'+' => 62,
+            b',' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | u32::from(v);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+    Some(
+        bytes
+            .chunks(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect(),
+    )
+}
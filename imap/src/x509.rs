@@ -0,0 +1,201 @@
+//! Just enough X.509 (RFC 5280) DER parsing to describe a server certificate.
+
+use crate::parser::datetime::days_from_civil;
+
+/// The parts of a certificate worth showing to a user or checking in a custom policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateInfo {
+    /// Distinguished name, e.g. `CN=imap.example.com, O=Example`.
+    pub subject: String,
+    pub issuer: String,
+    /// DNS names and IP addresses from the subjectAltName extension.
+    pub subject_alt_names: Vec<String>,
+    /// Start of the validity period, in seconds since the Unix epoch.
+    pub not_before: i64,
+    /// End of the validity period, in seconds since the Unix epoch.
+    pub not_after: i64,
+}
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const OID: u8 = 0x06;
+const OCTET_STRING: u8 = 0x04;
+const UTC_TIME: u8 = 0x17;
+const GENERALIZED_TIME: u8 = 0x18;
+const VERSION: u8 = 0xa0;
+const EXTENSIONS: u8 = 0xa3;
+const DNS_NAME: u8 = 0x82;
+const IP_ADDRESS: u8 = 0x87;
+
+const SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+
+/// Parses a DER-encoded certificate. Returns `None` if it is malformed.
+pub fn parse_certificate(der: &[u8]) -> Option<CertificateInfo> {
+    let (cert, _) = expect(der, SEQUENCE)?;
+    let (tbs, _) = expect(cert, SEQUENCE)?;
+
+    let mut rest = tbs;
+    if rest.first() == Some(&VERSION) {
+        rest = read(rest)?.2;
+    }
+    let (_serial, _, rest) = read(rest)?;
+    let (_signature, rest) = expect(rest, SEQUENCE)?;
+    let (issuer, rest) = expect(rest, SEQUENCE)?;
+    let (validity, rest) = expect(rest, SEQUENCE)?;
+    let (subject, mut rest) = expect(rest, SEQUENCE)?;
+
+    let (not_before, validity) = parse_time(validity)?;
+    let (not_after, _) = parse_time(validity)?;
+
+    let mut subject_alt_names = Vec::new();
+    while !rest.is_empty() {
+        let (tag, content, next) = read(rest)?;
+        if tag == EXTENSIONS {
+            subject_alt_names = parse_alt_names(content)?;
+        }
+        rest = next;
+    }
+
+    Some(CertificateInfo {
+        subject: format_name(subject)?,
+        issuer: format_name(issuer)?,
+        subject_alt_names,
+        not_before,
+        not_after,
+    })
+}
+
+/// Splits one TLV off `buf`: tag, content and what follows.
+fn read(buf: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let tag = *buf.first()?;
+    let first = *buf.get(1)?;
+    let (len, header) = if first < 0x80 {
+        (usize::from(first), 2)
+    } else {
+        let n = usize::from(first & 0x7f);
+        if n == 0 || n > 4 {
+            return None;
+        }
+        let len = buf
+            .get(2..2 + n)?
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | usize::from(b));
+        (len, 2 + n)
+    };
+    let content = buf.get(header..header + len)?;
+    Some((tag, content, &buf[header + len..]))
+}
+
+fn expect(buf: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (found, content, rest) = read(buf)?;
+    (found == tag).then_some((content, rest))
+}
+
+/// Formats a Name as `CN=..., O=...`, in certificate order.
+fn format_name(mut rdns: &[u8]) -> Option<String> {
+    let mut parts = Vec::new();
+    while !rdns.is_empty() {
+        let (mut set, next) = expect(rdns, SET)?;
+        while !set.is_empty() {
+            let (attribute, more) = expect(set, SEQUENCE)?;
+            let (oid, value) = expect(attribute, OID)?;
+            let (_, value, _) = read(value)?;
+            let key = match oid {
+                [0x55, 0x04, 0x03] => "CN".to_string(),
+                [0x55, 0x04, 0x06] => "C".to_string(),
+                [0x55, 0x04, 0x07] => "L".to_string(),
+                [0x55, 0x04, 0x08] => "ST".to_string(),
+                [0x55, 0x04, 0x0a] => "O".to_string(),
+                [0x55, 0x04, 0x0b] => "OU".to_string(),
+                _ => format_oid(oid),
+            };
+            parts.push(format!("{}={}", key, String::from_utf8_lossy(value)));
+            set = more;
+        }
+        rdns = next;
+    }
+    Some(parts.join(", "))
+}
+
+fn format_oid(oid: &[u8]) -> String {
+    let mut arcs: Vec<u64> = Vec::new();
+    let mut value = 0u64;
+    for &b in oid {
+        value = (value << 7) | u64::from(b & 0x7f);
+        if b & 0x80 == 0 {
+            if arcs.is_empty() {
+                let first = (value / 40).min(2);
+                arcs.push(first);
+                arcs.push(value - first * 40);
+            } else {
+                arcs.push(value);
+            }
+            value = 0;
+        }
+    }
+    arcs.iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Reads the subjectAltName entries from the `[3]` extensions wrapper.
+fn parse_alt_names(extensions: &[u8]) -> Option<Vec<String>> {
+    let (mut list, _) = expect(extensions, SEQUENCE)?;
+    let mut names = Vec::new();
+    while !list.is_empty() {
+        let (extension, next) = expect(list, SEQUENCE)?;
+        let (oid, mut fields) = expect(extension, OID)?;
+        if oid == SUBJECT_ALT_NAME {
+            // Skip the optional `critical` BOOLEAN.
+            let value = loop {
+                let (tag, content, more) = read(fields)?;
+                if tag == OCTET_STRING {
+                    break content;
+                }
+                fields = more;
+            };
+            let (mut general_names, _) = expect(value, SEQUENCE)?;
+            while !general_names.is_empty() {
+                let (tag, content, more) = read(general_names)?;
+                match tag {
+                    DNS_NAME => names.push(String::from_utf8_lossy(content).into_owned()),
+                    IP_ADDRESS => names.extend(format_ip(content)),
+                    _ => {}
+                }
+                general_names = more;
+            }
+        }
+        list = next;
+    }
+    Some(names)
+}
+
+fn format_ip(bytes: &[u8]) -> Option<String> {
+    match bytes.len() {
+        4 => Some(std::net::Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string()),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            Some(std::net::Ipv6Addr::from(octets).to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Reads a UTCTime (`YYMMDDHHMMSSZ`) or GeneralizedTime (`YYYYMMDDHHMMSSZ`).
+fn parse_time(buf: &[u8]) -> Option<(i64, &[u8])> {
+    let (tag, content, rest) = read(buf)?;
+    let text = std::str::from_utf8(content).ok()?.strip_suffix('Z')?;
+    let (year, text) = match tag {
+        UTC_TIME => {
+            let yy: i64 = text.get(..2)?.parse().ok()?;
+            (if yy < 50 { 2000 + yy } else { 1900 + yy }, &text[2..])
+        }
+        GENERALIZED_TIME => (text.get(..4)?.parse().ok()?, &text[4..]),
+        _ => return None,
+    };
+    let field = |i: usize| -> Option<i64> { text.get(i..i + 2)?.parse().ok() };
+    let days = days_from_civil(year, field(0)?, field(2)?);
+    let seconds = days * 86_400 + field(4)? * 3600 + field(6)? * 60 + field(8)?;
+    Some((seconds, rest))
+}